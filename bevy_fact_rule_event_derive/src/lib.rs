@@ -0,0 +1,67 @@
+//! # bevy_fact_rule_event_derive
+//!
+//! Derive macros for `bevy_fact_rule_event`, enabled through its `derive` feature.
+//!
+//! `bevy_fact_rule_event` 的派生宏，通过其 `derive` 特性启用。
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+/// Derive `FromFacts` for a struct with named fields. Each field reads the fact named by its
+/// `#[fact("key")]` attribute, or by the field name when it has none. Fields are converted with
+/// `FromFactValue`; `Option` fields read a missing fact as `None`.
+///
+/// 为具名字段的结构体派生 `FromFacts`。每个字段读取其 `#[fact("key")]` 属性指定的事实，
+/// 没有该属性时读取与字段同名的事实。字段通过 `FromFactValue` 转换；`Option` 字段把缺失的事实读为 `None`。
+#[proc_macro_derive(FromFacts, attributes(fact))]
+pub fn derive_from_facts(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_facts(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn from_facts(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "FromFacts can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "FromFacts requires a struct with named fields",
+        ));
+    };
+
+    let mut reads = Vec::new();
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named fields have identifiers");
+        let mut key = LitStr::new(&ident.to_string(), ident.span());
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("fact"))
+        {
+            key = attr.parse_args()?;
+        }
+        reads.push(quote! {
+            #ident: ::bevy_fact_rule_event::read_fact(facts, #key)?
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::bevy_fact_rule_event::FromFacts for #name #ty_generics #where_clause {
+            fn from_facts(
+                facts: &dyn ::bevy_fact_rule_event::FactReader,
+            ) -> ::core::result::Result<Self, ::bevy_fact_rule_event::FromFactsError> {
+                ::core::result::Result::Ok(Self { #(#reads),* })
+            }
+        }
+    })
+}
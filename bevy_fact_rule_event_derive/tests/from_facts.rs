@@ -0,0 +1,64 @@
+use bevy_fact_rule_event::{FactType, FromFacts, FromFactsError, LayeredFactDatabase};
+
+#[derive(FromFacts, Debug, PartialEq)]
+struct PartyState {
+    #[fact("party:size")]
+    size: i64,
+    #[fact("party:leader")]
+    leader: String,
+    #[fact("party:in_combat")]
+    in_combat: bool,
+    #[fact("party:banner")]
+    banner: Option<String>,
+    gold: i64,
+}
+
+fn party() -> LayeredFactDatabase {
+    let mut db = LayeredFactDatabase::new();
+    db.set_global("party:leader", "Aria");
+    db.set("party:size", 3i64);
+    db.set("party:in_combat", false);
+    db.set("gold", 120i64);
+    db
+}
+
+#[test]
+fn derived_struct_reads_annotated_facts() {
+    let mut db = party();
+    assert_eq!(
+        PartyState::from_facts(&db),
+        Ok(PartyState {
+            size: 3,
+            leader: "Aria".to_string(),
+            in_combat: false,
+            banner: None,
+            gold: 120,
+        })
+    );
+
+    db.set("party:banner", "wolf");
+    let state = PartyState::from_facts(&db).unwrap();
+    assert_eq!(state.banner.as_deref(), Some("wolf"));
+}
+
+#[test]
+fn derived_struct_reports_missing_and_mistyped_facts() {
+    let mut db = party();
+    db.remove("party:size");
+    assert_eq!(
+        PartyState::from_facts(&db),
+        Err(FromFactsError::Missing {
+            key: "party:size".to_string()
+        })
+    );
+
+    db.set("party:size", "three");
+    assert_eq!(
+        PartyState::from_facts(&db),
+        Err(FromFactsError::WrongType {
+            key: "party:size".to_string(),
+            expected: FactType::Int,
+            found: FactType::String,
+        })
+    );
+}
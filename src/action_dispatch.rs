@@ -0,0 +1,239 @@
+//! # action_dispatch.rs
+//!
+//! Running a firing rule's actions. `process_rules_system` hands each action of a rule that
+//! fires to the `ActionHandlerRegistry`, which calls the handler registered for its action type
+//! with the facts and `Commands`. Whether actions run before or after the rule's modifications
+//! is the registry's `ActionPhase`; after is the default, so handlers see the facts the rule
+//! just wrote. Dry runs and plans never run actions.
+//!
+//! 执行触发规则的动作。`process_rules_system` 把触发规则的每个动作交给 `ActionHandlerRegistry`，
+//! 由它以事实和 `Commands` 调用为该动作类型注册的处理器。动作在规则的修改之前还是之后执行
+//! 由注册表的 `ActionPhase` 决定；默认在之后，因此处理器能看到规则刚写入的事实。
+//! 试运行和计划从不执行动作。
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::{ActionDef, ActionHandlerRegistry};
+use crate::event::FactEvent;
+use crate::layered::LayeredFactDatabase;
+use crate::rule::{FactModification, Rule};
+use crate::systems::RuleSink;
+
+/// When a firing rule's actions run relative to its modifications.
+///
+/// 触发规则的动作相对于其修改的执行时机。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "debug", derive(Reflect))]
+pub enum ActionPhase {
+    /// Actions see the facts as they were before the rule fired.
+    ///
+    /// 动作看到规则触发之前的事实。
+    BeforeModifications,
+
+    /// Actions see the facts the rule's modifications wrote.
+    ///
+    /// 动作看到规则的修改写入后的事实。
+    #[default]
+    AfterModifications,
+}
+
+/// Sink wrapper that runs firing rules' actions through the handler registry, if there is one.
+pub(crate) struct ActionSink<'a, 'w, 's, A: ActionDef, S> {
+    inner: S,
+    handlers: Option<&'a ActionHandlerRegistry<A>>,
+    commands: &'a mut Commands<'w, 's>,
+}
+
+impl<'a, 'w, 's, A: ActionDef, S> ActionSink<'a, 'w, 's, A, S> {
+    pub(crate) fn new(
+        inner: S,
+        handlers: Option<&'a ActionHandlerRegistry<A>>,
+        commands: &'a mut Commands<'w, 's>,
+    ) -> Self {
+        Self {
+            inner,
+            handlers,
+            commands,
+        }
+    }
+}
+
+impl<A: ActionDef, S: RuleSink<A>> RuleSink<A> for ActionSink<'_, '_, '_, A, S> {
+    fn facts(&self) -> &LayeredFactDatabase {
+        self.inner.facts()
+    }
+
+    fn can_fire(&self, rule: &Rule<A>) -> bool {
+        self.inner.can_fire(rule)
+    }
+
+    fn condition_checked(&mut self, rule: &Rule<A>, event: &FactEvent, passed: bool) {
+        self.inner.condition_checked(rule, event, passed);
+    }
+
+    fn rule_firing(&mut self, rule: &Rule<A>, event: &FactEvent) {
+        self.inner.rule_firing(rule, event);
+    }
+
+    fn run_actions(&mut self, rule: &Rule<A>, phase: ActionPhase) {
+        self.inner.run_actions(rule, phase);
+        let Some(handlers) = self.handlers else {
+            return;
+        };
+        if phase != handlers.action_phase() {
+            return;
+        }
+        for action in &rule.actions {
+            handlers.execute(action, self.inner.facts(), self.commands);
+        }
+    }
+
+    fn apply_modification(&mut self, rule: &Rule<A>, modification: &FactModification) {
+        self.inner.apply_modification(rule, modification);
+    }
+
+    fn apply_modifications(&mut self, rule: &Rule<A>) {
+        self.inner.apply_modifications(rule);
+    }
+
+    fn queue_output(&mut self, rule: &Rule<A>, event: FactEvent) {
+        self.inner.queue_output(rule, event);
+    }
+
+    fn rule_finished(&mut self, rule: &Rule<A>) {
+        self.inner.rule_finished(rule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry, FreAsset};
+    use crate::rule::{FreRng, LayeredRuleRegistry};
+    use crate::systems::{ConditionEvaluator, PendingFactEvents, process_rules_system};
+    use std::sync::{Arc, Mutex};
+
+    /// App with a rule that increments `hits` and logs, recording each logged message along
+    /// with the value of `hits` the handler saw.
+    fn action_app(phase: ActionPhase) -> (App, Arc<Mutex<Vec<(String, Option<i64>)>>>) {
+        let asset: FreAsset = ron::from_str(
+            r#"(
+                rules: [(
+                    id: "hit",
+                    event: Event("hit"),
+                    modifications: [Increment(key: "hits", amount: 1)],
+                    actions: [Log(message: "got hit")],
+                )],
+            )"#,
+        )
+        .unwrap();
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let mut handlers = ActionHandlerRegistry::<CoreActionDef>::default();
+        handlers.set_action_phase(phase);
+        let sink = logged.clone();
+        handlers.register("Log", move |action, db, _| {
+            if let CoreActionDef::Log { message } = action {
+                sink.lock()
+                    .unwrap()
+                    .push((message.clone(), db.get_int("hits")));
+            }
+        });
+
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<PendingFactEvents>()
+            .init_resource::<ConditionEvaluator>()
+            .init_resource::<EnumRegistry>()
+            .init_resource::<FreRng>()
+            .insert_resource(handlers)
+            .add_message::<FactEvent>()
+            .add_systems(Update, process_rules_system::<CoreActionDef>);
+        asset.register_rules_layered(
+            &mut app
+                .world_mut()
+                .resource_mut::<LayeredRuleRegistry<CoreActionDef>>(),
+        );
+        (app, logged)
+    }
+
+    #[test]
+    fn test_asset_actions_run_when_rule_fires() {
+        let (mut app, logged) = action_app(ActionPhase::AfterModifications);
+        app.world_mut().write_message(FactEvent::new("miss"));
+        app.update();
+        assert!(logged.lock().unwrap().is_empty());
+
+        app.world_mut().write_message(FactEvent::new("hit"));
+        app.update();
+        assert_eq!(*logged.lock().unwrap(), [("got hit".to_string(), Some(1))]);
+    }
+
+    #[test]
+    fn test_custom_action_runs_through_plugin() {
+        let asset: FreAsset = ron::from_str(
+            r#"(
+                rules: [(
+                    id: "open_chest",
+                    event: Event("interact"),
+                    actions: [Custom(action_type: "PlaySound", params: { "clip": "chest.ogg" })],
+                )],
+            )"#,
+        )
+        .unwrap();
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            crate::FREPlugin::<CoreActionDef>::default(),
+        ));
+        let played = Arc::new(Mutex::new(Vec::new()));
+        let sink = played.clone();
+        app.world_mut()
+            .resource_mut::<ActionHandlerRegistry<CoreActionDef>>()
+            .register("PlaySound", move |action, _, _| {
+                if let CoreActionDef::Custom { params, .. } = action {
+                    sink.lock().unwrap().push(params["clip"].clone());
+                }
+            });
+        asset.register_rules_layered(
+            &mut app
+                .world_mut()
+                .resource_mut::<LayeredRuleRegistry<CoreActionDef>>(),
+        );
+
+        app.world_mut().write_message(FactEvent::new("interact"));
+        app.update();
+        assert_eq!(*played.lock().unwrap(), ["chest.ogg"]);
+    }
+
+    #[test]
+    fn test_unhandled_action_type_warns_once() {
+        let (mut app, logged) = action_app(ActionPhase::AfterModifications);
+        app.insert_resource(ActionHandlerRegistry::<CoreActionDef>::default());
+        for _ in 0..2 {
+            app.world_mut().write_message(FactEvent::new("hit"));
+            app.update();
+        }
+        assert!(logged.lock().unwrap().is_empty());
+
+        // The first firing already recorded the warning for `Log`
+        let mut handlers = app
+            .world_mut()
+            .resource_mut::<ActionHandlerRegistry<CoreActionDef>>();
+        assert!(!handlers.first_unhandled("Log"));
+
+        // Registering a handler resets it
+        handlers.register("Log", |_, _, _| {});
+        assert!(handlers.first_unhandled("Log"));
+    }
+
+    #[test]
+    fn test_actions_can_run_before_modifications() {
+        let (mut app, logged) = action_app(ActionPhase::BeforeModifications);
+        app.world_mut().write_message(FactEvent::new("hit"));
+        app.update();
+        assert_eq!(*logged.lock().unwrap(), [("got hit".to_string(), None)]);
+    }
+}
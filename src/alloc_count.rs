@@ -0,0 +1,37 @@
+//! # alloc_count.rs
+//!
+//! Test-only counting allocator. It counts the allocations made by the current thread, so tests
+//! running in parallel do not interfere, and lets tests check that a hot path allocates nothing.
+//!
+//! 仅用于测试的计数分配器。它统计当前线程的分配次数，因此并行运行的测试互不干扰，
+//! 测试可以借此检查热路径不会分配内存。
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Number of allocations the current thread makes while running `f`.
+pub(crate) fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
@@ -4,16 +4,28 @@
 
 mod action_defs;
 mod enum_registry;
+mod export;
+mod fact_loading;
+mod handler_set;
+mod hot_reload;
 mod loader;
+mod migration;
 mod rule_defs;
+mod validation;
 mod value_defs;
 
 pub use action_defs::{ActionDef, CoreActionDef};
 pub use enum_registry::EnumRegistry;
+pub use fact_loading::{AssetFactLayer, apply_loaded_fre_asset_facts_system};
+pub use handler_set::ActionHandlerSet;
+pub use hot_reload::{FreAssetRules, reload_modified_fre_assets_system};
 pub use loader::{ActionHandler, ActionHandlerRegistry, FreAssetLoader};
+pub use migration::CURRENT_VERSION;
 pub use rule_defs::{FreAsset, RuleDef, RuleScopeDef};
+pub use validation::{FreLoadError, validate_fre_file, validate_fre_str};
 pub use value_defs::{
     ActionEventKind, FactModificationDef, FactValueDef, LocalFactValue, RuleEventDef,
+    UnrepresentableValue,
 };
 
 #[cfg(test)]
@@ -194,4 +206,361 @@ mod tests {
         assert_eq!(asset.rules[0].actions[1].action_type(), "SetLocalFact");
         assert_eq!(asset.rules[0].actions[2].action_type(), "EmitEvent");
     }
+
+    #[test]
+    fn test_validate_fre_file_good() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("good.fre.ron");
+        std::fs::write(
+            &path,
+            r#"
+(
+    enums: { "depth": ["main", "submenu"] },
+    facts: { "depth": Enum("main"), "selection": Int(0) },
+    rules: [
+        (
+            id: "wrap_selection",
+            event: Event("move"),
+            modifications: [Wrap(key: "selection", min: 0, max: 3)],
+        ),
+    ],
+)
+"#,
+        )
+        .unwrap();
+
+        let result = validate_fre_file::<CoreActionDef>(&path);
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[test]
+    fn test_validate_fre_str_syntax_error() {
+        let result = validate_fre_str::<CoreActionDef>("( rules: [ (id: \"broken\", ");
+        assert!(matches!(result, Err(FreLoadError::Parse(_))));
+    }
+
+    #[test]
+    fn test_validate_fre_str_semantic_errors() {
+        let fre_data = r#"
+(
+    facts: { "depth": Enum("main") },
+    rules: [
+        (id: "dup", event: Event("a")),
+        (id: "dup", event: Event("")),
+        (
+            id: "bad_clamp",
+            event: Event("b"),
+            modifications: [Clamp(key: "hp", min: 10.0, max: 0.0)],
+        ),
+        (id: "bark", event: Event("idle")),
+        (id: "bark_random", event: Event("idle"), selection: Random),
+        (id: "think", event: Event("idle"), selection: HighestScore),
+    ],
+)
+"#;
+        let Err(FreLoadError::Invalid(problems)) = validate_fre_str::<CoreActionDef>(fre_data)
+        else {
+            panic!("expected semantic validation to fail");
+        };
+        assert_eq!(problems.len(), 5, "{:?}", problems);
+        assert!(
+            problems
+                .iter()
+                .any(|p| p.contains("'bark_random' selects with Random"))
+        );
+        assert!(problems.iter().any(|p| p.contains("Enum(\"main\")")));
+        assert!(problems.iter().any(|p| p.contains("more than once")));
+        assert!(problems.iter().any(|p| p.contains("empty trigger")));
+        assert!(problems.iter().any(|p| p.contains("Clamp on 'hp'")));
+    }
+
+    #[test]
+    fn test_validate_fre_file_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.fre.ron");
+        let result = validate_fre_file::<CoreActionDef>(&path);
+        assert!(matches!(result, Err(FreLoadError::Io(_))));
+    }
+
+    #[test]
+    fn test_fre_asset_aliases() {
+        let fre_data = r#"
+(
+    aliases: { "player_hp": "stats:hp" },
+    rules: [],
+)
+"#;
+        let asset: FreAsset = ron::from_str(fre_data).unwrap();
+        let mut db = crate::LayeredFactDatabase::new();
+        asset.register_aliases(&mut db).unwrap();
+        db.set("stats:hp", 12i64);
+        assert_eq!(db.get_int("player_hp"), Some(12));
+
+        let cyclic = r#"(aliases: { "a": "b", "b": "a" })"#;
+        let Err(FreLoadError::Invalid(problems)) = validate_fre_str::<CoreActionDef>(cyclic) else {
+            panic!("expected alias cycle to be rejected");
+        };
+        assert!(problems.iter().any(|p| p.contains("cycle")));
+    }
+
+    #[test]
+    fn test_fre_asset_schema_violations() {
+        let fre_data = r#"
+(
+    schema: { "hp": Int, "name": String, "inventory": StringList },
+    facts: { "hp": Int(10), "name": Int(3) },
+    rules: [
+        (
+            id: "heal",
+            event: Event("potion"),
+            conditions: ["$hp < 5", "$name == 4"],
+            modifications: [
+                Set(key: "hp", value: String("full")),
+                Increment(key: "hp", amount: 1),
+                Toggle("inventory"),
+                Set(key: "mood", value: Float(0.5)),
+            ],
+        ),
+    ],
+)
+"#;
+        let asset: FreAsset = ron::from_str(fre_data).unwrap();
+        let problems = asset.validate();
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems.iter().any(|p| p.contains("fact 'name'")));
+        assert!(problems.iter().any(|p| p.contains("String(\"full\")")));
+        assert!(problems.iter().any(|p| p.contains("Toggle(\"inventory\")")));
+        assert!(problems.iter().any(|p| p.contains("'$name == 4'")));
+
+        let mut db = crate::LayeredFactDatabase::new();
+        asset.register_schema(&mut db);
+        assert_eq!(
+            db.schema().type_of("inventory"),
+            Some(crate::FactType::StringList)
+        );
+    }
+
+    #[test]
+    fn test_fre_asset_derived_facts() {
+        let asset: FreAsset =
+            ron::from_str(r#"(derived: { "hp_percent": "$hp / $max_hp * 100" })"#).unwrap();
+        let mut derived = crate::DerivedFacts::default();
+        asset.register_derived(&mut derived).unwrap();
+
+        let mut db = crate::LayeredFactDatabase::new();
+        db.set("hp", 3i64);
+        db.set("max_hp", 4i64);
+        derived.recompute(&mut db);
+        assert_eq!(db.get_int("hp_percent"), Some(75));
+
+        let cyclic = r#"(derived: { "a": "$b", "b": "$a" })"#;
+        let Err(FreLoadError::Invalid(problems)) = validate_fre_str::<CoreActionDef>(cyclic) else {
+            panic!("expected derived cycle to be rejected");
+        };
+        assert!(problems.iter().any(|p| p.contains("cycle")));
+    }
+
+    #[test]
+    fn test_vector_and_float_list_values_round_trip() {
+        let facts: std::collections::HashMap<String, FactValueDef> =
+            std::collections::HashMap::from([
+                ("pos".to_string(), FactValueDef::Vec2([1.5, -2.0])),
+                ("vel".to_string(), FactValueDef::Vec3([0.0, 1.0, 0.25])),
+                (
+                    "waypoints".to_string(),
+                    FactValueDef::FloatList(vec![2.5, 10.0, 0.125]),
+                ),
+            ]);
+        let text = ron::to_string(&facts).unwrap();
+        let parsed: std::collections::HashMap<String, FactValueDef> = ron::from_str(&text).unwrap();
+
+        let pos: FactValue = parsed["pos"].clone().into();
+        let vel: FactValue = parsed["vel"].clone().into();
+        assert_eq!(pos, FactValue::Vec2([1.5, -2.0]));
+        assert_eq!(vel.as_vec3(), Some(bevy::math::Vec3::new(0.0, 1.0, 0.25)));
+        let waypoints: FactValue = parsed["waypoints"].clone().into();
+        assert_eq!(waypoints.as_float_list(), Some(&[2.5, 10.0, 0.125][..]));
+
+        let authored: FactValueDef = ron::from_str("FloatList([1.5, 2])").unwrap();
+        assert_eq!(
+            FactValue::from(authored),
+            FactValue::FloatList(vec![1.5, 2.0])
+        );
+    }
+
+    #[test]
+    fn test_entity_ref_fact_drives_action_handler() {
+        use crate::rule::FactModification;
+        use bevy::ecs::system::RunSystemOnce;
+        use bevy::prelude::*;
+
+        #[derive(Component)]
+        struct Talking;
+
+        let mut world = World::new();
+        let npc = world.spawn_empty().id();
+        let mut db = crate::LayeredFactDatabase::new();
+        FactModification::Set("dialogue_target".to_string(), npc.into()).apply(&mut db);
+        assert_eq!(db.get_entity("dialogue_target"), Some(npc));
+        // Boolean lists have no asset form; entity references are written as their bits
+        let error = FactValueDef::try_from(FactValue::BoolList(vec![true])).unwrap_err();
+        assert_eq!(error.found, crate::FactType::BoolList);
+        assert!(FactValueDef::try_from(FactValue::from(npc)).is_ok());
+
+        let mut handlers = ActionHandlerRegistry::<CoreActionDef>::default();
+        handlers.register("StartTalking", |_, db, commands| {
+            if let Some(target) = db.get_entity("dialogue_target") {
+                commands.entity(target).insert(Talking);
+            }
+        });
+        let action = CoreActionDef::Custom {
+            action_type: "StartTalking".to_string(),
+            params: Default::default(),
+        };
+        world.insert_resource(db);
+        world
+            .run_system_once(
+                move |db: Res<crate::LayeredFactDatabase>, mut commands: Commands| {
+                    handlers.execute(&action, &db, &mut commands);
+                },
+            )
+            .unwrap();
+        assert!(world.entity(npc).contains::<Talking>());
+    }
+
+    #[test]
+    fn test_entity_def_round_trips_through_set() {
+        use crate::systems::ConditionEvaluator;
+        use bevy::prelude::World;
+
+        let mut world = World::new();
+        let npc = world.spawn_empty().id();
+        let def = FactValueDef::try_from(FactValue::from(npc)).unwrap();
+        let text = format!(
+            "Set(key: \"selected\", value: {})",
+            ron::to_string(&def).unwrap()
+        );
+        assert_eq!(
+            text,
+            format!("Set(key: \"selected\", value: Entity({}))", npc.to_bits())
+        );
+
+        let mut db = crate::LayeredFactDatabase::new();
+        let modification: FactModificationDef = ron::from_str(&text).unwrap();
+        crate::FactModification::from(modification).apply(&mut db);
+        assert_eq!(db.get_entity("selected"), Some(npc));
+
+        db.set("target", npc);
+        db.set("other", world.spawn_empty().id());
+        let evaluator = ConditionEvaluator::default();
+        let passes = |condition: &str| {
+            evaluator.evaluate_conditions(&[condition.to_string()], &db, &EnumRegistry::default())
+        };
+        assert!(passes("compare($selected == $target)"));
+        assert!(!passes("compare($selected == $other)"));
+
+        // Bits that are not a valid entity load as Null
+        let invalid: FactValueDef = ron::from_str("Entity(0)").unwrap();
+        assert_eq!(FactValue::from(invalid), FactValue::Null);
+    }
+
+    #[test]
+    fn test_multiply_divide_modulo_spellings() {
+        let apply = |db: &mut crate::LayeredFactDatabase, ron_text: &str| {
+            let def: FactModificationDef = ron::from_str(ron_text).unwrap();
+            crate::FactModification::from(def).apply(db);
+        };
+        let mut db = crate::LayeredFactDatabase::new();
+        db.set("gold", 10i64);
+        db.set("ammo", 17i64);
+        db.set("speed", 3.0);
+
+        apply(&mut db, r#"Multiply(key: "gold", factor: 2.5)"#);
+        assert_eq!(db.get_int("gold"), Some(25));
+        apply(&mut db, r#"Mul(key: "gold", value: 2)"#);
+        assert_eq!(db.get_int("gold"), Some(50));
+        apply(&mut db, r#"Divide(key: "gold", divisor: 4)"#);
+        assert_eq!(db.get_float("gold"), Some(12.5));
+        apply(&mut db, r#"Modulo(key: "ammo", divisor: 5)"#);
+        assert_eq!(db.get_int("ammo"), Some(2));
+
+        // Division by zero sets the fact to 0, as LayeredFactDatabase::div does
+        apply(&mut db, r#"Divide(key: "speed", divisor: 0)"#);
+        assert_eq!(db.get_int("speed"), Some(0));
+        // Modulo by zero leaves the fact unchanged
+        apply(&mut db, r#"Modulo(key: "ammo", divisor: 0)"#);
+        assert_eq!(db.get_int("ammo"), Some(2));
+    }
+
+    #[test]
+    fn test_scoped_modifications_from_ron() {
+        let parse = |ron_text: &str| {
+            let def: FactModificationDef = ron::from_str(ron_text).unwrap();
+            crate::FactModification::from(def)
+        };
+        assert_eq!(
+            parse(r#"Set(key: "deaths", value: Int(1), scope: Global)"#),
+            crate::FactModification::SetGlobal("deaths".to_string(), FactValue::Int(1))
+        );
+        assert_eq!(
+            parse(r#"Set(key: "deaths", value: Int(1))"#),
+            crate::FactModification::Set("deaths".to_string(), FactValue::Int(1))
+        );
+        assert_eq!(
+            parse(r#"Increment(key: "runs", amount: 1, scope: Global)"#),
+            crate::FactModification::IncrementGlobal("runs".to_string(), 1)
+        );
+        assert_eq!(
+            parse(r#"ToggleGlobal("hard_mode")"#),
+            crate::FactModification::ToggleGlobal("hard_mode".to_string())
+        );
+
+        let mut db = crate::LayeredFactDatabase::new();
+        parse(r#"Increment(key: "runs", amount: 1, scope: Global)"#).apply(&mut db);
+        parse(r#"Set(key: "room", value: String("cave"), scope: Local)"#).apply(&mut db);
+        parse(r#"RemoveGlobal("room")"#).apply(&mut db);
+        assert_eq!(db.global().get_int("runs"), Some(1));
+        assert_eq!(db.local().get_string("room"), Some("cave"));
+
+        // Exported rules keep the scope
+        let def = FactModificationDef::try_from(parse(
+            r#"Set(key: "deaths", value: Int(1), scope: Global)"#,
+        ))
+        .unwrap();
+        assert!(ron::to_string(&def).unwrap().contains("scope:Global"));
+    }
+
+    #[test]
+    fn test_rule_wraps_selection_and_clamps_health() {
+        let asset: FreAsset = ron::from_str(
+            r#"(rules: [
+                (id: "next", event: Event("menu_down"), modifications: [
+                    Increment(key: "selection", amount: 1),
+                    Wrap(key: "selection", min: 0, max: 3),
+                ]),
+                (id: "heal", event: Event("potion"), modifications: [
+                    Add(key: "hp", value: 50),
+                    Clamp(key: "hp", min: 0, max: 100),
+                ]),
+            ])"#,
+        )
+        .unwrap();
+        let rule_mods = |id: &str| -> Vec<crate::FactModification> {
+            let rule = asset.rules.iter().find(|rule| rule.id == id).unwrap();
+            rule.modifications.iter().cloned().map(Into::into).collect()
+        };
+        let mut db = crate::LayeredFactDatabase::new();
+        db.set("selection", 2i64);
+        db.set("hp", 80i64);
+
+        // Moving down from the last entry rolls back to the first
+        rule_mods("next").iter().for_each(|m| m.apply(&mut db));
+        assert_eq!(db.get_int("selection"), Some(0));
+        rule_mods("next").iter().for_each(|m| m.apply(&mut db));
+        assert_eq!(db.get_int("selection"), Some(1));
+
+        // Healing past the maximum is pinned to it
+        rule_mods("heal").iter().for_each(|m| m.apply(&mut db));
+        assert_eq!(db.get_int("hp"), Some(100));
+    }
 }
@@ -32,6 +32,13 @@ pub enum CoreActionDef {
     },
     SetLocalFact(String, LocalFactValue),
     EmitEvent(String),
+    /// Emit `event` on the `frames`-th frame after the rule fires.
+    ///
+    /// 在规则触发后的第 `frames` 帧发出 `event`。
+    EmitEventAfterFrames {
+        event: String,
+        frames: u32,
+    },
     Custom {
         action_type: String,
         params: HashMap<String, String>,
@@ -44,6 +51,7 @@ impl ActionDef for CoreActionDef {
             CoreActionDef::Log { .. } => "Log",
             CoreActionDef::SetLocalFact(_, _) => "SetLocalFact",
             CoreActionDef::EmitEvent(_) => "EmitEvent",
+            CoreActionDef::EmitEventAfterFrames { .. } => "EmitEventAfterFrames",
             CoreActionDef::Custom { action_type, .. } => action_type.as_str(),
         }
     }
@@ -0,0 +1,218 @@
+//! # export.rs
+//!
+//! # export.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Converting live rules back into their asset form, so rules built at runtime with
+//! `RuleBuilder` can be saved or inspected next to rules loaded from `.fre.ron` files. Each
+//! `Rule` becomes a `RuleDef` triggered by its event id, which reproduces action-event, interval
+//! and crossing triggers as well, since those are identified by their event ids too. Rules are
+//! written sorted by id. Facts, enums and the other asset sections are not part of a registry
+//! and are left empty. A rule setting a value without an asset form cannot be exported.
+//!
+//! 把实时规则转换回资源形式，使运行时用 `RuleBuilder` 构建的规则可以与从 `.fre.ron` 文件
+//! 加载的规则一起保存或检查。每条 `Rule` 变为由其事件 ID 触发的 `RuleDef`；由于动作事件、
+//! 间隔和穿越触发器同样由事件 ID 标识，它们也能被还原。规则按 ID 排序写出。
+//! 事实、枚举和资源的其他部分不属于注册表，因此保持为空。设置了没有资源形式的值的规则无法导出。
+
+use std::collections::HashMap;
+
+use super::action_defs::ActionDef;
+use super::migration::CURRENT_VERSION;
+use super::rule_defs::{FreAsset, RuleDef, RuleScopeDef};
+use super::value_defs::{RuleEventDef, UnrepresentableValue};
+use crate::rule::{LayeredRuleRegistry, Rule, RuleRegistry, RuleScope};
+
+impl From<RuleScope> for RuleScopeDef {
+    fn from(scope: RuleScope) -> Self {
+        match scope {
+            RuleScope::Global => RuleScopeDef::Global,
+            RuleScope::Local => RuleScopeDef::Local,
+            RuleScope::View => RuleScopeDef::View,
+        }
+    }
+}
+
+impl<A: ActionDef> TryFrom<&Rule<A>> for RuleDef<A> {
+    type Error = UnrepresentableValue;
+
+    fn try_from(rule: &Rule<A>) -> Result<Self, Self::Error> {
+        let modifications = rule
+            .modifications
+            .iter()
+            .cloned()
+            .map(TryInto::try_into)
+            .collect::<Result<_, _>>()?;
+        Ok(RuleDef {
+            id: rule.id.clone(),
+            event: RuleEventDef::Event(rule.trigger.to_string()),
+            conditions: rule.condition_expressions.clone(),
+            actions: rule.actions.clone(),
+            modifications,
+            transactional: rule.transactional,
+            outputs: rule.outputs.iter().map(ToString::to_string).collect(),
+            detached_outputs: rule
+                .outputs
+                .iter()
+                .filter(|output| rule.detached_outputs.contains(*output))
+                .map(ToString::to_string)
+                .collect(),
+            forward_data: rule.forward_data,
+            enabled: rule.enabled,
+            priority: rule.priority,
+            consume_event: rule.consume_event,
+            selection: rule.selection,
+            weight: rule.weight,
+            score: rule.score_expression.clone(),
+            cooldown: rule.cooldown.map(|cooldown| cooldown.as_secs_f32()),
+            tags: rule.tags.clone(),
+            owned_facts: rule.owned_facts.clone(),
+        })
+    }
+}
+
+/// Asset holding `rules` sorted by id, scoped like the rules if they all share a scope.
+fn asset_from_rules<'a, A: ActionDef>(
+    rules: impl Iterator<Item = &'a Rule<A>>,
+    scope: Option<RuleScopeDef>,
+) -> Result<FreAsset<A>, UnrepresentableValue> {
+    let mut rules: Vec<&Rule<A>> = rules.collect();
+    rules.sort_by(|a, b| a.id.cmp(&b.id));
+    let scope = scope.unwrap_or_else(|| match rules.split_first() {
+        Some((first, rest)) if rest.iter().all(|rule| rule.scope == first.scope) => {
+            first.scope.into()
+        }
+        _ => RuleScopeDef::default(),
+    });
+    Ok(FreAsset {
+        version: CURRENT_VERSION,
+        scope,
+        enums: HashMap::new(),
+        facts: HashMap::new(),
+        aliases: HashMap::new(),
+        queries: HashMap::new(),
+        named_conditions: HashMap::new(),
+        derived: HashMap::new(),
+        schema: HashMap::new(),
+        rules: rules
+            .into_iter()
+            .map(RuleDef::try_from)
+            .collect::<Result<_, _>>()?,
+        source: None,
+    })
+}
+
+impl<A: ActionDef> RuleRegistry<A> {
+    /// Export the registered rules as an asset, sorted by id. The asset takes the rules' scope
+    /// when they all share one. Fails if a rule sets a value that has no asset form.
+    ///
+    /// 把已注册的规则导出为资源，按 ID 排序。所有规则作用域相同时，资源采用该作用域。
+    /// 如果某条规则设置了没有资源形式的值则失败。
+    pub fn to_asset(&self) -> Result<FreAsset<A>, UnrepresentableValue> {
+        asset_from_rules(self.iter(), None)
+    }
+}
+
+impl<A: ActionDef> LayeredRuleRegistry<A> {
+    /// Export the global and local layers as two assets, in that order. View layers belong to
+    /// entities of the running world and are not exported.
+    ///
+    /// 把全局层和局部层导出为两个资源（按此顺序）。视图层属于运行中世界的实体，不会被导出。
+    pub fn to_assets(&self) -> Result<[FreAsset<A>; 2], UnrepresentableValue> {
+        Ok([
+            asset_from_rules(self.global_iter(), Some(RuleScopeDef::Global))?,
+            asset_from_rules(self.local_iter(), Some(RuleScopeDef::Local))?,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CoreActionDef;
+    use crate::database::FactValue;
+    use crate::rule::FactModification;
+
+    const RULESET: &str = r#"(
+    version: 1,
+    scope: Global,
+    rules: [
+        (
+            id: "chest_open",
+            event: Event("interact"),
+            conditions: ["$has_key == 1", "float($light) > 0.5"],
+            actions: [Log(message: "opened")],
+            modifications: [
+                Set(key: "opened", value: Bool(true)),
+                Increment(key: "chests", amount: 1),
+                Clamp(key: "light", min: 0.0, max: 1.0),
+                AppendToList(key: "loot", value: "gold"),
+            ],
+            outputs: ["chest_opened", "sfx"],
+            detached_outputs: ["sfx"],
+            priority: 5,
+            consume_event: false,
+            cooldown: Some(1.5),
+        ),
+        (
+            id: "hint",
+            event: Event("idle"),
+            enabled: false,
+            weight: 2.0,
+        ),
+    ],
+)"#;
+
+    fn to_ron(asset: &FreAsset) -> String {
+        ron::to_string(asset).unwrap()
+    }
+
+    #[test]
+    fn test_registry_round_trips_to_ron() {
+        let asset: FreAsset = ron::from_str(RULESET).unwrap();
+        let mut registry = RuleRegistry::<CoreActionDef>::new();
+        asset.register_rules(&mut registry);
+
+        let exported = registry.to_asset().unwrap();
+        assert_eq!(exported.scope, RuleScopeDef::Global);
+        assert_eq!(to_ron(&exported), to_ron(&asset));
+
+        // The exported asset loads back into the same rules
+        let reparsed: FreAsset = ron::from_str(&to_ron(&exported)).unwrap();
+        let mut reloaded = RuleRegistry::<CoreActionDef>::new();
+        reparsed.register_rules(&mut reloaded);
+        assert_eq!(
+            reloaded.get("chest_open").unwrap().modifications,
+            registry.get("chest_open").unwrap().modifications
+        );
+    }
+
+    #[test]
+    fn test_layered_export_and_unrepresentable_values() {
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(
+            Rule::builder("pause", "menu")
+                .scope(RuleScope::Global)
+                .build(),
+        );
+        registry.register(Rule::builder("step", "tick").build());
+        let [global, local] = registry.to_assets().unwrap();
+        assert_eq!(global.scope, RuleScopeDef::Global);
+        assert_eq!(global.rules[0].id, "pause");
+        assert_eq!(local.scope, RuleScopeDef::Local);
+        assert_eq!(local.rules[0].id, "step");
+
+        registry.register(
+            Rule::builder("flags", "tick")
+                .modify(FactModification::Set(
+                    "flags".to_string(),
+                    FactValue::BoolList(vec![true]),
+                ))
+                .build(),
+        );
+        assert!(registry.to_assets().is_err());
+    }
+}
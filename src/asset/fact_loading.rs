@@ -0,0 +1,114 @@
+//! # fact_loading.rs
+//!
+//! # fact_loading.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Writing an asset's declared `facts` into the database when the asset first loads. Loading
+//! is opt-in through `FREPlugin::asset_facts_layer`, because a game restoring a save usually
+//! wants the saved values rather than the authored defaults. The layer the facts go to is part
+//! of the setting: `Global` for values that outlive the current context, `Local` for values
+//! that are reset with it. Later modifications of the asset do not write the facts again.
+//!
+//! 在资源首次加载时把其声明的 `facts` 写入数据库。加载需通过 `FREPlugin::asset_facts_layer`
+//! 显式启用，因为恢复存档的游戏通常需要存档中的值而不是作者写下的默认值。
+//! 事实写入哪一层是该设置的一部分：`Global` 用于比当前上下文存活更久的值，
+//! `Local` 用于随上下文一起重置的值。之后对资源的修改不会再次写入这些事实。
+
+use bevy::prelude::*;
+
+use super::action_defs::ActionDef;
+use super::enum_registry::EnumRegistry;
+use super::rule_defs::FreAsset;
+use crate::database::FactLayer;
+use crate::layered::LayeredFactDatabase;
+
+/// The layer `apply_loaded_fre_asset_facts_system` writes asset facts to.
+///
+/// `apply_loaded_fre_asset_facts_system` 写入资源事实的层。
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetFactLayer(pub FactLayer);
+
+/// System that applies the declared facts of each newly added `FreAsset`.
+///
+/// 应用每个新添加的 `FreAsset` 所声明事实的系统。
+pub fn apply_loaded_fre_asset_facts_system<A: ActionDef>(
+    mut asset_events: MessageReader<AssetEvent<FreAsset<A>>>,
+    assets: Res<Assets<FreAsset<A>>>,
+    layer: Res<AssetFactLayer>,
+    mut db: ResMut<LayeredFactDatabase>,
+    enums: Res<EnumRegistry>,
+) {
+    for event in asset_events.read() {
+        let AssetEvent::Added { id } = *event else {
+            continue;
+        };
+        if let Some(asset) = assets.get(id) {
+            asset.apply_facts(&mut db, &enums, layer.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FREPlugin;
+    use crate::asset::CoreActionDef;
+
+    const COUNTER_ASSET: &str = r#"(facts: { "counter": Int(0), "mood": Enum("calm") })"#;
+
+    #[test]
+    fn test_apply_facts_to_either_layer() {
+        let asset: FreAsset = ron::from_str(COUNTER_ASSET).unwrap();
+        let mut enums = EnumRegistry::default();
+        enums.register("mood", &["calm".to_string(), "angry".to_string()]);
+
+        let mut db = LayeredFactDatabase::new();
+        asset.apply_facts(&mut db, &enums, FactLayer::Global);
+        assert_eq!(db.global().get_int("counter"), Some(0));
+        assert_eq!(db.global().get_int("mood"), Some(0));
+        assert!(!db.contains_local("counter"));
+
+        db.set("counter", 5);
+        asset.apply_facts(&mut db, &enums, FactLayer::Local);
+        assert_eq!(db.local().get_int("counter"), Some(0));
+    }
+
+    #[test]
+    fn test_loaded_asset_facts_reach_the_database() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            FREPlugin::<CoreActionDef> {
+                asset_facts_layer: Some(FactLayer::Local),
+                ..default()
+            },
+        ));
+        let asset: FreAsset = ron::from_str(COUNTER_ASSET).unwrap();
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<FreAsset>>()
+            .add(asset);
+        // Asset events are sent in `PostUpdate`, so the facts arrive on the next frame
+        app.update();
+        app.update();
+
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert_eq!(db.local().get_int("counter"), Some(0));
+
+        // Modifying the asset later does not reapply its facts
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("counter", 3);
+        app.world_mut()
+            .resource_mut::<Assets<FreAsset>>()
+            .get_mut(&handle)
+            .unwrap();
+        app.update();
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert_eq!(db.get_int("counter"), Some(3));
+    }
+}
@@ -0,0 +1,140 @@
+//! # handler_set.rs
+//!
+//! # handler_set.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Reusable sets of action handlers. A game with dozens of custom actions can assemble its
+//! handlers once with `ActionHandlerSet::with` and hand the set to
+//! `ActionHandlerRegistry::register_all` in every app or test that needs them. Handlers are
+//! shared rather than copied, so cloning a set is cheap and closures need not be `Clone`.
+//!
+//! 可复用的动作处理器集合。拥有数十个自定义动作的游戏可以用 `ActionHandlerSet::with`
+//! 一次性组装处理器，并在每个需要它们的应用或测试中把集合交给
+//! `ActionHandlerRegistry::register_all`。处理器是共享的而非复制的，
+//! 因此克隆集合开销很小，闭包也无需实现 `Clone`。
+
+use bevy::prelude::*;
+use std::sync::Arc;
+
+use super::action_defs::{ActionDef, CoreActionDef};
+use super::loader::ActionHandler;
+use crate::layered::LayeredFactDatabase;
+
+type SharedHandler<A> = Arc<dyn Fn(&A, &LayeredFactDatabase, &mut Commands) + Send + Sync>;
+
+/// Handlers by action type, ready to be registered together.
+///
+/// 按动作类型组织、可一起注册的处理器。
+pub struct ActionHandlerSet<A: ActionDef = CoreActionDef> {
+    handlers: Vec<(String, SharedHandler<A>)>,
+}
+
+impl<A: ActionDef> Default for ActionHandlerSet<A> {
+    fn default() -> Self {
+        Self {
+            handlers: Vec::new(),
+        }
+    }
+}
+
+impl<A: ActionDef> Clone for ActionHandlerSet<A> {
+    fn clone(&self) -> Self {
+        Self {
+            handlers: self.handlers.clone(),
+        }
+    }
+}
+
+impl<A: ActionDef> ActionHandlerSet<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the handler for `action_type`. A later handler for the same type wins on register.
+    ///
+    /// 添加 `action_type` 的处理器。注册时，同一类型后添加的处理器生效。
+    pub fn with<F>(mut self, action_type: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(&A, &LayeredFactDatabase, &mut Commands) + Send + Sync + 'static,
+    {
+        self.handlers.push((action_type.into(), Arc::new(handler)));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+impl<A: ActionDef> IntoIterator for ActionHandlerSet<A> {
+    type Item = (String, ActionHandler<A>);
+    type IntoIter = std::iter::Map<
+        std::vec::IntoIter<(String, SharedHandler<A>)>,
+        fn((String, SharedHandler<A>)) -> (String, ActionHandler<A>),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.handlers.into_iter().map(|(action_type, handler)| {
+            let boxed: ActionHandler<A> = Box::new(move |action, db, commands| {
+                handler(action, db, commands);
+            });
+            (action_type, boxed)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::ActionHandlerRegistry;
+    use bevy::ecs::world::CommandQueue;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    fn custom(action_type: &str) -> CoreActionDef {
+        CoreActionDef::Custom {
+            action_type: action_type.to_string(),
+            params: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_set_registers_every_handler_in_each_registry() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut set = ActionHandlerSet::<CoreActionDef>::new();
+        for action_type in ["PlaySound", "Shake", "Spawn"] {
+            let sink = calls.clone();
+            set = set.with(action_type, move |action, _, _| {
+                sink.lock().unwrap().push(action.action_type().to_string());
+            });
+        }
+        assert_eq!(set.len(), 3);
+
+        let world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        let db = LayeredFactDatabase::new();
+
+        // The same set can stock several registries
+        for _ in 0..2 {
+            let mut registry = ActionHandlerRegistry::<CoreActionDef>::default();
+            registry.register_all(set.clone());
+            assert!(registry.has_handler("Shake"));
+            assert!(!registry.has_handler("Log"));
+            for action_type in ["Spawn", "PlaySound", "Shake"] {
+                registry.execute(&custom(action_type), &db, &mut commands);
+            }
+        }
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["Spawn", "PlaySound", "Shake", "Spawn", "PlaySound", "Shake"]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! # hot_reload.rs
+//!
+//! # hot_reload.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Re-registering rules when a `.fre.ron` file changes at runtime. Assets registered through
+//! `FreAssetRules` remember the rule ids they contributed; when Bevy reports the asset as
+//! modified, `reload_modified_fre_assets_system` unregisters those rules, registers the new
+//! ruleset in their place, and records the asset's declared facts as starting values again.
+//! Rules registered directly with `FreAsset::register_rules_layered` are not tracked.
+//!
+//! 在运行时 `.fre.ron` 文件变化时重新注册规则。通过 `FreAssetRules` 注册的资源会记住它们
+//! 贡献的规则 ID；当 Bevy 报告资源已修改时，`reload_modified_fre_assets_system` 会注销这些规则，
+//! 在原处注册新的规则集，并重新记录资源声明的事实作为初始值。
+//! 直接通过 `FreAsset::register_rules_layered` 注册的规则不会被跟踪。
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use super::action_defs::{ActionDef, CoreActionDef};
+use super::enum_registry::EnumRegistry;
+use super::rule_defs::FreAsset;
+use crate::layered::LayeredFactDatabase;
+use crate::rule::LayeredRuleRegistry;
+
+/// Rule ids registered from each tracked `FreAsset`.
+///
+/// 从每个被跟踪的 `FreAsset` 注册的规则 ID。
+pub struct FreAssetRules<A: ActionDef = CoreActionDef> {
+    by_asset: HashMap<AssetId<FreAsset<A>>, Vec<String>>,
+}
+
+impl<A: ActionDef> Default for FreAssetRules<A> {
+    fn default() -> Self {
+        Self {
+            by_asset: HashMap::new(),
+        }
+    }
+}
+
+impl<A: ActionDef> Resource for FreAssetRules<A> {}
+
+impl<A: ActionDef> FreAssetRules<A> {
+    /// Register `asset`'s rules and track them under `id`, first unregistering the rules
+    /// previously registered from that asset.
+    ///
+    /// 注册 `asset` 的规则并以 `id` 跟踪它们；会先注销之前从该资源注册的规则。
+    pub fn register(
+        &mut self,
+        id: AssetId<FreAsset<A>>,
+        asset: &FreAsset<A>,
+        registry: &mut LayeredRuleRegistry<A>,
+    ) {
+        self.unregister(id, registry);
+        let rule_ids = asset.register_rules_layered(registry);
+        self.by_asset.insert(id, rule_ids);
+    }
+
+    /// Unregister the rules registered from `id` and stop tracking it.
+    /// Returns how many rules were removed.
+    ///
+    /// 注销从 `id` 注册的规则并停止跟踪它。返回移除的规则数量。
+    pub fn unregister(
+        &mut self,
+        id: AssetId<FreAsset<A>>,
+        registry: &mut LayeredRuleRegistry<A>,
+    ) -> usize {
+        let Some(rule_ids) = self.by_asset.remove(&id) else {
+            return 0;
+        };
+        rule_ids
+            .iter()
+            .filter(|rule_id| registry.unregister(rule_id).is_some())
+            .count()
+    }
+
+    /// Whether rules registered from `id` are tracked, so its modifications are hot-reloaded.
+    ///
+    /// 是否跟踪从 `id` 注册的规则，即其修改是否会被热重载。
+    pub fn is_tracked(&self, id: AssetId<FreAsset<A>>) -> bool {
+        self.by_asset.contains_key(&id)
+    }
+
+    /// Ids of the rules registered from `id`.
+    ///
+    /// 从 `id` 注册的规则 ID。
+    pub fn rule_ids(&self, id: AssetId<FreAsset<A>>) -> &[String] {
+        self.by_asset.get(&id).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// System that re-registers the rules and initial facts of tracked assets that were modified.
+///
+/// 重新注册已修改的被跟踪资源的规则和初始事实的系统。
+pub fn reload_modified_fre_assets_system<A: ActionDef>(
+    mut asset_events: MessageReader<AssetEvent<FreAsset<A>>>,
+    assets: Res<Assets<FreAsset<A>>>,
+    mut tracked: ResMut<FreAssetRules<A>>,
+    mut registry: ResMut<LayeredRuleRegistry<A>>,
+    mut db: ResMut<LayeredFactDatabase>,
+    enums: Res<EnumRegistry>,
+) {
+    for event in asset_events.read() {
+        let AssetEvent::Modified { id } = *event else {
+            continue;
+        };
+        if !tracked.is_tracked(id) {
+            continue;
+        }
+        let Some(asset) = assets.get(id) else {
+            continue;
+        };
+        info!(
+            "FRE: Reloading rules from modified asset {:?}",
+            asset.source
+        );
+        tracked.register(id, asset, &mut registry);
+        asset.register_initial_facts(&mut db, &enums);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FREPlugin;
+    use crate::event::FactEvent;
+
+    fn ruleset(rule_id: &str, counter: &str) -> FreAsset {
+        ron::from_str(&format!(
+            r#"(
+                facts: {{ "{counter}": Int(0) }},
+                rules: [(
+                    id: "{rule_id}",
+                    event: Event("tick"),
+                    modifications: [Increment(key: "{counter}", amount: 1)],
+                )],
+            )"#
+        ))
+        .unwrap()
+    }
+
+    fn tick(app: &mut App) {
+        app.world_mut().write_message(FactEvent::new("tick"));
+        app.update();
+    }
+
+    #[test]
+    fn test_modified_asset_replaces_its_rules() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            FREPlugin::<CoreActionDef>::default(),
+        ));
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<FreAsset>>()
+            .add(ruleset("old_rule", "old_hits"));
+        app.world_mut()
+            .resource_scope(|world, mut tracked: Mut<FreAssetRules>| {
+                let assets = world.resource::<Assets<FreAsset>>();
+                let asset = assets.get(&handle).unwrap().clone();
+                tracked.register(
+                    handle.id(),
+                    &asset,
+                    &mut world.resource_mut::<LayeredRuleRegistry<CoreActionDef>>(),
+                );
+            });
+        tick(&mut app);
+
+        // Swap the asset's contents, as a file reload would
+        *app.world_mut()
+            .resource_mut::<Assets<FreAsset>>()
+            .get_mut(&handle)
+            .unwrap() = ruleset("new_rule", "new_hits");
+        app.update();
+        tick(&mut app);
+
+        let registry = app.world().resource::<LayeredRuleRegistry<CoreActionDef>>();
+        assert!(registry.get("old_rule").is_none());
+        assert!(registry.get("new_rule").is_some());
+        let tracked = app.world().resource::<FreAssetRules>();
+        assert_eq!(tracked.rule_ids(handle.id()), ["new_rule".to_string()]);
+
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert_eq!(db.get_int("old_hits"), Some(1));
+        assert_eq!(db.get_int("new_hits"), Some(1));
+        assert!(db.initial_facts().get("new_hits").is_some());
+    }
+}
@@ -17,10 +17,12 @@ use bevy::asset::io::Reader;
 use bevy::asset::{AssetLoader, LoadContext};
 use bevy::prelude::*;
 use bevy::tasks::ConditionalSendFuture;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 
 use super::action_defs::{ActionDef, CoreActionDef};
 use super::rule_defs::FreAsset;
+use crate::action_dispatch::ActionPhase;
 
 pub struct FreAssetLoader<A: ActionDef = CoreActionDef>(std::marker::PhantomData<A>);
 
@@ -49,12 +51,14 @@ impl<A: ActionDef> AssetLoader for FreAssetLoader<A> {
         &self,
         reader: &mut dyn Reader,
         _settings: &Self::Settings,
-        _load_context: &mut LoadContext,
+        load_context: &mut LoadContext,
     ) -> impl ConditionalSendFuture<Output = Result<Self::Asset, Self::Error>> {
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
-            let asset = ron::de::from_bytes::<FreAsset<A>>(&bytes)?;
+            let mut asset = ron::de::from_bytes::<FreAsset<A>>(&bytes)?;
+            asset.source = Some(load_context.path().to_string());
+            asset.migrate();
             Ok(asset)
         })
     }
@@ -69,12 +73,17 @@ pub type ActionHandler<A> =
 
 pub struct ActionHandlerRegistry<A: ActionDef = CoreActionDef> {
     handlers: HashMap<String, ActionHandler<A>>,
+    phase: ActionPhase,
+    /// Action types already warned about for having no handler.
+    unhandled: Mutex<HashSet<String>>,
 }
 
 impl<A: ActionDef> Default for ActionHandlerRegistry<A> {
     fn default() -> Self {
         Self {
             handlers: HashMap::new(),
+            phase: ActionPhase::default(),
+            unhandled: Mutex::default(),
         }
     }
 }
@@ -91,22 +100,65 @@ impl<A: ActionDef> ActionHandlerRegistry<A> {
     {
         self.handlers
             .insert(action_type.to_string(), Box::new(handler));
+        self.unhandled
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(action_type);
+    }
+
+    /// Register several handlers at once, such as an `ActionHandlerSet`. Each replaces any
+    /// handler already registered for its action type.
+    ///
+    /// 一次注册多个处理器，例如 `ActionHandlerSet`。每个处理器都会替换其动作类型已注册的处理器。
+    pub fn register_all(&mut self, handlers: impl IntoIterator<Item = (String, ActionHandler<A>)>) {
+        let unhandled = self
+            .unhandled
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (action_type, handler) in handlers {
+            unhandled.remove(&action_type);
+            self.handlers.insert(action_type, handler);
+        }
+    }
+
+    pub fn action_phase(&self) -> ActionPhase {
+        self.phase
+    }
+
+    /// Set whether `process_rules_system` runs a firing rule's actions before or after its
+    /// modifications (after by default).
+    ///
+    /// 设置 `process_rules_system` 在触发规则的修改之前还是之后执行其动作（默认之后）。
+    pub fn set_action_phase(&mut self, phase: ActionPhase) {
+        self.phase = phase;
     }
 
     pub fn has_handler(&self, action_type: &str) -> bool {
         self.handlers.contains_key(action_type)
     }
 
+    /// Run the handler registered for the action's type. An action type without a handler is
+    /// warned about the first time it is executed, not on every firing.
+    ///
+    /// 运行为该动作类型注册的处理器。没有处理器的动作类型只在第一次执行时发出警告，而不是每次触发都警告。
     pub fn execute(&self, action: &A, db: &crate::LayeredFactDatabase, commands: &mut Commands) {
         let action_type = action.action_type();
 
         if let Some(handler) = self.handlers.get(action_type) {
             handler(action, db, commands);
-        } else {
+        } else if self.first_unhandled(action_type) {
             warn!(
                 "FRE: No handler registered for action type '{}'",
                 action_type
             );
         }
     }
+
+    /// Whether `action_type` is reported as unhandled for the first time.
+    pub(crate) fn first_unhandled(&self, action_type: &str) -> bool {
+        self.unhandled
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(action_type.to_string())
+    }
 }
@@ -0,0 +1,89 @@
+//! # migration.rs
+//!
+//! # migration.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Versioning for the `.fre.ron` format. Every asset carries a `version`; the loader calls
+//! `FreAsset::migrate` to upgrade older assets step by step to `CURRENT_VERSION`, so the rest of
+//! the crate only ever sees the current shape. Assets newer than this build are loaded as-is
+//! with a warning.
+//!
+//! `.fre.ron` 格式的版本管理。每个资源都带有 `version`；加载器会调用 `FreAsset::migrate`
+//! 把旧资源逐步升级到 `CURRENT_VERSION`，这样 crate 的其余部分只会看到当前的形状。
+//! 比当前构建更新的资源会按原样加载并发出警告。
+
+use bevy::prelude::*;
+
+use super::action_defs::ActionDef;
+use super::rule_defs::FreAsset;
+
+/// The `.fre.ron` format version produced and expected by this build.
+///
+/// 此构建生成并期望的 `.fre.ron` 格式版本。
+pub const CURRENT_VERSION: u32 = 1;
+
+impl<A: ActionDef> FreAsset<A> {
+    /// Upgrade the asset in place to `CURRENT_VERSION`.
+    /// Returns false if the asset declares a version newer than this build understands.
+    ///
+    /// 将资源原地升级到 `CURRENT_VERSION`。
+    /// 如果资源声明的版本比此构建能理解的更新，则返回 false。
+    pub fn migrate(&mut self) -> bool {
+        if self.version > CURRENT_VERSION {
+            warn!(
+                "FRE: Asset {} declares unknown format version {} (this build supports up to {})",
+                self.source.as_deref().unwrap_or("<unnamed>"),
+                self.version,
+                CURRENT_VERSION
+            );
+            return false;
+        }
+
+        while self.version < CURRENT_VERSION {
+            match self.version {
+                // Version 0 is the unversioned format. Its deprecated spellings (such as
+                // `trigger` for `event`) are accepted by serde aliases, so the shape is unchanged.
+                0 => {}
+                _ => unreachable!("every version below CURRENT_VERSION has a migration step"),
+            }
+            self.version += 1;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CoreActionDef;
+
+    fn load(fre_data: &str) -> FreAsset<CoreActionDef> {
+        let mut asset: FreAsset<CoreActionDef> = ron::from_str(fre_data).unwrap();
+        asset.migrate();
+        asset
+    }
+
+    #[test]
+    fn test_unversioned_asset_is_migrated() {
+        let absent = load(r#"(rules: [(trigger: Event("start"))])"#);
+        assert_eq!(absent.version, CURRENT_VERSION);
+        assert_eq!(absent.rules[0].event.to_event_id(), "start");
+
+        let zero = load(r#"(version: 0, rules: [(event: Event("start"))])"#);
+        assert_eq!(zero.version, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_current_and_future_versions() {
+        let current = load(r#"(version: 1, rules: [(event: Event("start"))])"#);
+        assert_eq!(current.version, CURRENT_VERSION);
+        assert_eq!(current.rules.len(), 1);
+
+        let mut future: FreAsset<CoreActionDef> = ron::from_str(r#"(version: 99)"#).unwrap();
+        assert!(!future.migrate());
+        assert_eq!(future.version, 99);
+    }
+}
@@ -16,10 +16,15 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
-use crate::database::FactValue;
+use crate::database::{FactLayer, FactValue};
+use crate::derived::{DerivedFact, DerivedFactCycleError, DerivedFacts};
 use crate::event::FactEventId;
-use crate::rule::{Rule, RuleRegistry, RuleScope};
+use crate::layered::{AliasCycleError, LayeredFactDatabase};
+use crate::named_condition::NamedConditions;
+use crate::rule::{Rule, RuleRegistry, RuleScope, SelectPolicy};
+use crate::schema::FactType;
 
 use super::action_defs::{ActionDef, CoreActionDef};
 use super::enum_registry::EnumRegistry;
@@ -38,14 +43,50 @@ pub struct RuleDef<A: ActionDef = CoreActionDef> {
     pub actions: Vec<A>,
     #[serde(default)]
     pub modifications: Vec<FactModificationDef>,
+    /// Whether the modifications are applied all or nothing.
+    ///
+    /// 修改是否以全有或全无的方式应用。
+    #[serde(default)]
+    pub transactional: bool,
     #[serde(default)]
     pub outputs: Vec<String>,
+    /// Outputs that do not inherit the triggering event's entity and data.
+    ///
+    /// 不继承触发事件实体和数据的输出。
+    #[serde(default)]
+    pub detached_outputs: Vec<String>,
+    #[serde(default)]
+    pub forward_data: bool,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
     #[serde(default)]
     pub priority: i32,
     #[serde(default = "default_consume_event")]
     pub consume_event: bool,
+    #[serde(default)]
+    pub selection: SelectPolicy,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    /// Score expression for `SelectPolicy::HighestScore`.
+    ///
+    /// `SelectPolicy::HighestScore` 使用的评分表达式。
+    #[serde(default)]
+    pub score: Option<String>,
+    /// Minimum seconds between firings.
+    ///
+    /// 两次触发之间的最短秒数。
+    #[serde(default)]
+    pub cooldown: Option<f32>,
+    /// Labels for toggling related rules together.
+    ///
+    /// 用于一起切换相关规则的标签。
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Local facts removed along with the rule when its layer is cleared.
+    ///
+    /// 规则所在层被清空时随规则一起移除的局部事实。
+    #[serde(default)]
+    pub owned_facts: Vec<String>,
 }
 
 fn default_enabled() -> bool {
@@ -56,6 +97,10 @@ fn default_consume_event() -> bool {
     true
 }
 
+fn default_weight() -> f32 {
+    1.0
+}
+
 impl<A: ActionDef> RuleDef<A> {
     pub fn to_rule(&self) -> Rule<A> {
         self.to_rule_with_index(0, RuleScope::default())
@@ -78,11 +123,22 @@ impl<A: ActionDef> RuleDef<A> {
             trigger: FactEventId::new(self.event.to_event_id()),
             condition_expressions: self.conditions.clone(),
             modifications: self.modifications.iter().cloned().map(Into::into).collect(),
+            transactional: self.transactional,
             outputs: self.outputs.iter().map(FactEventId::new).collect(),
+            detached_outputs: self.detached_outputs.iter().map(FactEventId::new).collect(),
+            forward_data: self.forward_data,
             enabled: self.enabled,
             priority: self.priority,
             consume_event: self.consume_event,
+            selection: self.selection,
+            weight: self.weight,
+            score_expression: self.score.clone(),
+            cooldown: self.cooldown.map(Duration::from_secs_f32),
             actions: self.actions.clone(),
+            source: None,
+            tags: self.tags.clone(),
+            owned_facts: self.owned_facts.clone(),
+            compiled: None,
         }
     }
 
@@ -102,14 +158,49 @@ impl<A: ActionDef> RuleDef<A> {
 #[derive(Asset, bevy::reflect::TypePath, Debug, Clone, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct FreAsset<A: ActionDef = CoreActionDef> {
+    /// Format version; absent means 0. Upgraded by `migrate` when loaded.
+    ///
+    /// 格式版本；缺省为 0。加载时由 `migrate` 升级。
+    #[serde(default)]
+    pub version: u32,
     #[serde(default)]
     pub scope: RuleScopeDef,
     #[serde(default)]
     pub enums: HashMap<String, Vec<String>>,
     #[serde(default)]
     pub facts: HashMap<String, FactValueDef>,
+    /// Renamed fact keys, mapping each old key to its new name.
+    ///
+    /// 重命名的事实键，把每个旧键映射到新名称。
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// Named condition lists that game code can evaluate through `FactQueryRegistry`.
+    ///
+    /// 游戏代码可以通过 `FactQueryRegistry` 评估的具名条件列表。
+    #[serde(default)]
+    pub queries: HashMap<String, Vec<String>>,
+    /// Condition lists that rule conditions can reference as `@name`.
+    ///
+    /// 规则条件可以通过 `@name` 引用的条件列表。
+    #[serde(default)]
+    pub named_conditions: HashMap<String, Vec<String>>,
+    /// Derived facts, mapping each key to the formula that computes it.
+    ///
+    /// 派生事实，把每个键映射到计算它的公式。
+    #[serde(default)]
+    pub derived: HashMap<String, String>,
+    /// Declared fact types, enforced by `LayeredFactDatabase` when its schema policy is enabled.
+    ///
+    /// 声明的事实类型；当 `LayeredFactDatabase` 启用模式策略时强制执行。
+    #[serde(default)]
+    pub schema: HashMap<String, FactType>,
     #[serde(default)]
     pub rules: Vec<RuleDef<A>>,
+    /// Path the asset was loaded from, filled in by the asset loader.
+    ///
+    /// 资源的加载路径，由资源加载器填写。
+    #[serde(skip)]
+    pub source: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -135,28 +226,45 @@ impl<A: ActionDef> FreAsset<A> {
         self.scope.into()
     }
 
+    fn build_rule(&self, idx: usize, rule_def: &RuleDef<A>) -> Rule<A> {
+        let mut rule = rule_def.to_rule_with_index(idx, self.scope());
+        rule.source = self.source.clone();
+        rule
+    }
+
     pub fn register_rules(&self, registry: &mut RuleRegistry<A>) {
         let scope = self.scope();
         for (idx, rule_def) in self.rules.iter().enumerate() {
-            let rule = rule_def.to_rule_with_index(idx, scope);
+            let rule = self.build_rule(idx, rule_def);
             info!(
                 "FRE: Registering rule '{}' from asset (scope: {:?})",
                 rule.id, scope
             );
             registry.register(rule);
         }
+        registry.compile_expressions();
     }
 
-    pub fn register_rules_layered(&self, registry: &mut crate::rule::LayeredRuleRegistry<A>) {
+    /// Register the asset's rules with a layered registry, returning their ids.
+    ///
+    /// 把资源的规则注册到分层注册表，并返回它们的 ID。
+    pub fn register_rules_layered(
+        &self,
+        registry: &mut crate::rule::LayeredRuleRegistry<A>,
+    ) -> Vec<String> {
         let scope = self.scope();
+        let mut rule_ids = Vec::with_capacity(self.rules.len());
         for (idx, rule_def) in self.rules.iter().enumerate() {
-            let rule = rule_def.to_rule_with_index(idx, scope);
+            let rule = self.build_rule(idx, rule_def);
             info!(
                 "FRE: Registering rule '{}' from asset to layered registry (scope: {:?})",
                 rule.id, scope
             );
+            rule_ids.push(rule.id.clone());
             registry.register(rule);
         }
+        registry.compile_expressions();
+        rule_ids
     }
 
     pub fn get_facts(&self) -> &HashMap<String, FactValueDef> {
@@ -173,6 +281,73 @@ impl<A: ActionDef> FreAsset<A> {
             .collect()
     }
 
+    /// Write the asset's declared facts into one layer of a database, replacing current values.
+    ///
+    /// 把资源中声明的事实写入数据库的某一层，替换当前值。
+    pub fn apply_facts(
+        &self,
+        db: &mut LayeredFactDatabase,
+        registry: &EnumRegistry,
+        layer: FactLayer,
+    ) {
+        for (key, value) in self.resolve_facts(registry) {
+            match layer {
+                FactLayer::Global => db.set_global(key, value),
+                FactLayer::Local => db.set(key, value),
+            }
+        }
+    }
+
+    /// Record the asset's declared facts as the starting values used by `reset_to_initial`.
+    ///
+    /// 把资源中声明的事实记录为 `reset_to_initial` 使用的初始值。
+    pub fn register_initial_facts(&self, db: &mut LayeredFactDatabase, registry: &EnumRegistry) {
+        let initial = db.initial_facts_mut();
+        for (key, value) in self.resolve_facts(registry) {
+            initial.record(key, value);
+        }
+    }
+
+    /// Add the asset's key aliases to a database. Stops at the first alias that forms a cycle.
+    ///
+    /// 把资源中的键别名添加到数据库。遇到第一个形成环的别名时停止。
+    pub fn register_aliases(&self, db: &mut LayeredFactDatabase) -> Result<(), AliasCycleError> {
+        self.aliases
+            .iter()
+            .try_for_each(|(alias, target)| db.add_alias(alias.clone(), target.clone()))
+    }
+
+    /// Register the asset's derived facts. Stops at the first one that forms a cycle.
+    ///
+    /// 注册资源中的派生事实。遇到第一个形成环的派生事实时停止。
+    pub fn register_derived(
+        &self,
+        derived: &mut DerivedFacts,
+    ) -> Result<(), DerivedFactCycleError> {
+        self.derived
+            .iter()
+            .try_for_each(|(key, expr)| derived.register(DerivedFact::new(key, expr)))
+    }
+
+    /// Define the asset's named conditions, replacing existing ones with the same name.
+    ///
+    /// 定义资源中的具名条件，替换同名的已有条件。
+    pub fn register_named_conditions(&self, named: &mut NamedConditions) {
+        for (name, conditions) in &self.named_conditions {
+            named.define(name.clone(), conditions.iter().cloned());
+        }
+    }
+
+    /// Declare the asset's fact types on a database's schema, keeping its policy.
+    ///
+    /// 在数据库的模式上声明资源中的事实类型，保留其策略。
+    pub fn register_schema(&self, db: &mut LayeredFactDatabase) {
+        let schema = db.schema_mut();
+        for (key, fact_type) in &self.schema {
+            schema.declare(key.clone(), *fact_type);
+        }
+    }
+
     pub fn get_rule_defs(&self) -> &[RuleDef<A>] {
         &self.rules
     }
@@ -0,0 +1,356 @@
+//! # validation.rs
+//!
+//! # validation.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Validates FRE content without a Bevy `App`. It parses `.fre.ron` text or files into
+//! `FreAsset`s and runs semantic checks over the result, so CI jobs and small tools can reject
+//! malformed or inconsistent content before the game ever loads it.
+//!
+//! 在没有 Bevy `App` 的情况下校验 FRE 内容。它把 `.fre.ron` 文本或文件解析为
+//! `FreAsset`，并对结果执行语义检查，这样 CI 任务和小工具可以在游戏加载之前拒绝格式错误或
+//! 不一致的内容。
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::derived::DerivedFacts;
+use crate::layered::LayeredFactDatabase;
+use crate::rule::conflicting_policy;
+use crate::schema::{FactSchema, FactType};
+
+use super::action_defs::ActionDef;
+use super::rule_defs::FreAsset;
+use super::value_defs::{FactModificationDef, FactValueDef, RuleEventDef};
+
+/// Error produced when reading, parsing, or validating FRE content.
+///
+/// 读取、解析或校验 FRE 内容时产生的错误。
+#[derive(Debug)]
+pub enum FreLoadError {
+    /// The file could not be read.
+    ///
+    /// 无法读取文件。
+    Io(std::io::Error),
+
+    /// The content is not valid RON for an `FreAsset`.
+    ///
+    /// 内容不是有效的 `FreAsset` RON。
+    Parse(ron::error::SpannedError),
+
+    /// The content parsed but failed semantic validation.
+    ///
+    /// 内容解析成功但未通过语义校验。
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for FreLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FreLoadError::Io(err) => write!(f, "failed to read FRE file: {}", err),
+            FreLoadError::Parse(err) => write!(f, "failed to parse FRE asset: {}", err),
+            FreLoadError::Invalid(problems) => {
+                write!(f, "invalid FRE asset: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for FreLoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FreLoadError::Io(err) => Some(err),
+            FreLoadError::Parse(err) => Some(err),
+            FreLoadError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for FreLoadError {
+    fn from(err: std::io::Error) -> Self {
+        FreLoadError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for FreLoadError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        FreLoadError::Parse(err)
+    }
+}
+
+impl<A: ActionDef> FreAsset<A> {
+    /// Run semantic checks over the asset, returning a description of each problem found.
+    ///
+    /// 对资源执行语义检查，返回发现的每个问题的描述。
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .facts
+            .iter()
+            .filter_map(|(key, def)| self.validate_fact(key, def))
+            .collect();
+
+        let mut aliases = LayeredFactDatabase::new();
+        problems.extend(
+            self.aliases
+                .iter()
+                .filter_map(|(alias, target)| aliases.add_alias(alias, target).err())
+                .map(|err| err.to_string()),
+        );
+        problems.extend(
+            self.register_derived(&mut DerivedFacts::default())
+                .err()
+                .map(|err| err.to_string()),
+        );
+
+        let mut seen_ids = HashSet::new();
+        for (idx, rule) in self.rules.iter().enumerate() {
+            let id = rule.generate_id(idx);
+            if rule.event.to_event_id().is_empty() {
+                problems.push(format!("rule '{}' has an empty trigger event", id));
+            }
+            if matches!(rule.event, RuleEventDef::Every { seconds } if seconds <= 0.0) {
+                problems.push(format!("rule '{}' has a non-positive Every interval", id));
+            }
+            if rule.weight < 0.0 {
+                problems.push(format!("rule '{}' has a negative weight", id));
+            }
+            if !rule.id.is_empty() && !seen_ids.insert(rule.id.as_str()) {
+                problems.push(format!("rule id '{}' is declared more than once", rule.id));
+            }
+            let trigger = rule.event.to_event_id();
+            let earlier = self.rules[..idx]
+                .iter()
+                .filter(|other| other.event.to_event_id() == trigger)
+                .map(|other| (other.selection, other.priority));
+            if let Some(other) = conflicting_policy(rule.selection, rule.priority, earlier) {
+                problems.push(format!(
+                    "rule '{}' selects with {:?} but shares its priority with a rule selecting \
+                    with {:?}",
+                    id, rule.selection, other
+                ));
+            }
+            problems.extend(
+                rule.detached_outputs
+                    .iter()
+                    .filter(|output| !rule.outputs.contains(output))
+                    .map(|output| {
+                        format!(
+                            "rule '{}' detaches '{}' which is not one of its outputs",
+                            id, output
+                        )
+                    }),
+            );
+            problems.extend(
+                rule.modifications
+                    .iter()
+                    .filter_map(validate_modification)
+                    .map(|problem| format!("rule '{}': {}", id, problem)),
+            );
+        }
+
+        let mut schema = FactSchema::default();
+        for (key, fact_type) in &self.schema {
+            schema.declare(key.clone(), *fact_type);
+        }
+        problems.extend(self.validate_against_schema(&schema));
+        problems
+    }
+
+    /// Check fact defaults, modification literals, and condition literals against a schema.
+    /// Use this with the combined schema of every loaded asset to catch cross-asset mismatches.
+    ///
+    /// 根据模式检查事实默认值、修改字面量和条件字面量。
+    /// 与所有已加载资源的合并模式一起使用，可以发现跨资源的类型不匹配。
+    pub fn validate_against_schema(&self, schema: &FactSchema) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .facts
+            .iter()
+            .filter(|(key, def)| !schema.type_of(key).is_none_or(|t| def_matches(def, t)))
+            .map(|(key, _)| format!("fact '{}' does not match its declared type", key))
+            .collect();
+
+        for (idx, rule) in self.rules.iter().enumerate() {
+            let id = rule.generate_id(idx);
+            let mismatches = rule
+                .modifications
+                .iter()
+                .filter_map(|modification| modification_mismatch(modification, schema))
+                .chain(
+                    rule.conditions
+                        .iter()
+                        .flat_map(|condition| condition_mismatches(condition, schema)),
+                );
+            problems.extend(mismatches.map(|problem| format!("rule '{}': {}", id, problem)));
+        }
+        problems
+    }
+
+    fn validate_fact(&self, key: &str, def: &FactValueDef) -> Option<String> {
+        let FactValueDef::Enum(variant) = def else {
+            return None;
+        };
+        let declared = self
+            .enums
+            .get(key)
+            .is_some_and(|variants| variants.contains(variant));
+        (!declared).then(|| {
+            format!(
+                "fact '{}' uses Enum(\"{}\") which is not declared in enums",
+                key, variant
+            )
+        })
+    }
+}
+
+fn validate_modification(modification: &FactModificationDef) -> Option<String> {
+    match modification {
+        FactModificationDef::Clamp { key, min, max } if min > max => Some(format!(
+            "Clamp on '{}' has min {} greater than max {}",
+            key, min, max
+        )),
+        FactModificationDef::Wrap { key, min, max } if max <= min => Some(format!(
+            "Wrap on '{}' has an empty range [{}, {})",
+            key, min, max
+        )),
+        FactModificationDef::Eval { key, expr } if expr.trim().is_empty() => {
+            Some(format!("Eval on '{}' has an empty expression", key))
+        }
+        _ => None,
+    }
+}
+
+/// Whether an authored literal can be stored in a fact of type `expected`.
+/// Enum variants resolve to integers, or to strings when the variant is unknown.
+fn def_matches(def: &FactValueDef, expected: FactType) -> bool {
+    match def {
+        FactValueDef::Int(_) => expected == FactType::Int,
+        FactValueDef::Float(_) => expected == FactType::Float,
+        FactValueDef::Bool(_) => expected == FactType::Bool,
+        FactValueDef::String(_) => expected == FactType::String,
+        FactValueDef::StringList(_) => expected == FactType::StringList,
+        FactValueDef::IntList(_) => expected == FactType::IntList,
+        FactValueDef::FloatList(_) => expected == FactType::FloatList,
+        FactValueDef::Vec2(_) => expected == FactType::Vec2,
+        FactValueDef::Vec3(_) => expected == FactType::Vec3,
+        FactValueDef::Entity(_) => expected == FactType::EntityRef,
+        FactValueDef::Enum(_) => matches!(expected, FactType::Int | FactType::String),
+        FactValueDef::Null => true,
+    }
+}
+
+fn modification_mismatch(
+    modification: &FactModificationDef,
+    schema: &FactSchema,
+) -> Option<String> {
+    use FactModificationDef as M;
+    let (key, fits) = match modification {
+        M::Set { key, value, .. } => (
+            key,
+            schema.type_of(key).is_none_or(|t| def_matches(value, t)),
+        ),
+        M::Increment { key, .. } | M::Mod { key, .. } | M::Wrap { key, .. } => {
+            (key, schema.type_of(key).is_none_or(|t| t == FactType::Int))
+        }
+        M::Add { key, .. }
+        | M::Sub { key, .. }
+        | M::Mul { key, .. }
+        | M::Div { key, .. }
+        | M::Clamp { key, .. }
+        | M::Eval { key, .. } => (key, schema.type_of(key).is_none_or(FactType::is_numeric)),
+        M::Toggle(key) | M::ToggleGlobal(key) => {
+            (key, schema.type_of(key).is_none_or(|t| t == FactType::Bool))
+        }
+        M::AppendToList { key, .. } | M::RemoveFromList { key, .. } => (
+            key,
+            schema
+                .type_of(key)
+                .is_none_or(|t| t == FactType::StringList),
+        ),
+        M::Remove(_) | M::RemoveGlobal(_) | M::SetNull(_) | M::ResetToInitial(_) => return None,
+    };
+    let expected = schema.type_of(key)?;
+    (!fits).then(|| {
+        format!(
+            "{:?} does not fit '{}' declared as {}",
+            modification, key, expected
+        )
+    })
+}
+
+/// Check `$key <op> literal` comparisons in a condition expression against the schema.
+fn condition_mismatches(condition: &str, schema: &FactSchema) -> Vec<String> {
+    condition
+        .split("&&")
+        .flat_map(|part| part.split("||"))
+        .filter_map(|comparison| {
+            let (key, literal) = split_comparison(comparison)?;
+            let expected = schema.type_of(key)?;
+            let fits = match literal {
+                Literal::Number => expected.is_numeric(),
+                Literal::Bool => expected == FactType::Bool,
+                Literal::String => expected == FactType::String,
+            };
+            (!fits).then(|| {
+                format!(
+                    "condition '{}' compares '{}' declared as {} with a {:?} literal",
+                    condition.trim(),
+                    key,
+                    expected,
+                    literal
+                )
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+enum Literal {
+    Number,
+    Bool,
+    String,
+}
+
+/// Split `$key <op> literal` (either side order) into the key and the literal kind.
+fn split_comparison(comparison: &str) -> Option<(&str, Literal)> {
+    let (left, right) = ["==", "!=", ">=", "<=", ">", "<"]
+        .iter()
+        .find_map(|op| comparison.split_once(op))?;
+    let (left, right) = (left.trim(), right.trim());
+    let (key, literal) = match left.strip_prefix('$') {
+        Some(key) => (key, right),
+        None => (right.strip_prefix('$')?, left),
+    };
+    let literal = match literal {
+        "true" | "false" => Literal::Bool,
+        _ if literal.starts_with(['"', '\'']) => Literal::String,
+        _ if literal.parse::<f64>().is_ok() => Literal::Number,
+        _ => return None,
+    };
+    Some((key, literal))
+}
+
+/// Parse and validate FRE content from a string, without a Bevy `App`.
+///
+/// 在没有 Bevy `App` 的情况下从字符串解析并校验 FRE 内容。
+pub fn validate_fre_str<A: ActionDef>(content: &str) -> Result<(), FreLoadError> {
+    let mut asset: FreAsset<A> = ron::from_str(content)?;
+    asset.migrate();
+    let problems = asset.validate();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(FreLoadError::Invalid(problems))
+    }
+}
+
+/// Read, parse, and validate an `.fre.ron` file, without a Bevy `App`.
+///
+/// 在没有 Bevy `App` 的情况下读取、解析并校验 `.fre.ron` 文件。
+pub fn validate_fre_file<A: ActionDef>(path: &Path) -> Result<(), FreLoadError> {
+    let content = std::fs::read_to_string(path)?;
+    validate_fre_str::<A>(&content)
+}
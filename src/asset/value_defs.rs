@@ -15,9 +15,13 @@
 
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-use crate::database::FactValue;
+use crate::crossing::{CrossDirection, crossing_event_id};
+use crate::database::{FactLayer, FactValue};
+use crate::interval::interval_event_id;
 use crate::rule::FactModification;
+use crate::schema::FactType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FactValueDef {
@@ -27,7 +31,17 @@ pub enum FactValueDef {
     String(String),
     StringList(Vec<String>),
     IntList(Vec<i64>),
+    FloatList(Vec<f64>),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    /// An entity reference, as the bits of `Entity::to_bits`. Entity ids are only meaningful
+    /// within the running world, so this is for saves and debug dumps rather than authored assets.
+    ///
+    /// 实体引用，以 `Entity::to_bits` 的位表示。实体 ID 仅在运行中的世界内有意义，
+    /// 因此它用于存档和调试转储，而非手写的资源。
+    Entity(u64),
     Enum(String),
+    Null,
 }
 
 impl From<FactValueDef> for FactValue {
@@ -39,6 +53,20 @@ impl From<FactValueDef> for FactValue {
             FactValueDef::String(value) => FactValue::String(value),
             FactValueDef::StringList(value) => FactValue::StringList(value),
             FactValueDef::IntList(value) => FactValue::IntList(value),
+            FactValueDef::FloatList(value) => FactValue::FloatList(value),
+            FactValueDef::Vec2(value) => FactValue::Vec2(value),
+            FactValueDef::Vec3(value) => FactValue::Vec3(value),
+            FactValueDef::Null => FactValue::Null,
+            FactValueDef::Entity(bits) => match Entity::try_from_bits(bits) {
+                Some(entity) => FactValue::EntityRef(entity),
+                None => {
+                    warn!(
+                        "FactValueDef::Entity({}) is not a valid entity — stored as Null",
+                        bits
+                    );
+                    FactValue::Null
+                }
+            },
             FactValueDef::Enum(variant) => {
                 warn!(
                     "FactValueDef::Enum('{}') converted without EnumRegistry — stored as String",
@@ -50,29 +78,132 @@ impl From<FactValueDef> for FactValue {
     }
 }
 
+/// A runtime value that has no asset form, such as a boolean list.
+///
+/// 没有资源形式的运行时值，例如布尔列表。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnrepresentableValue {
+    pub found: FactType,
+}
+
+impl fmt::Display for UnrepresentableValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} values cannot be written to an asset", self.found)
+    }
+}
+
+impl std::error::Error for UnrepresentableValue {}
+
+impl TryFrom<FactValue> for FactValueDef {
+    type Error = UnrepresentableValue;
+
+    fn try_from(value: FactValue) -> Result<Self, Self::Error> {
+        match value {
+            FactValue::Int(value) => Ok(FactValueDef::Int(value)),
+            FactValue::Float(value) => Ok(FactValueDef::Float(value)),
+            FactValue::Bool(value) => Ok(FactValueDef::Bool(value)),
+            FactValue::String(value) => Ok(FactValueDef::String(value)),
+            FactValue::StringList(value) => Ok(FactValueDef::StringList(value)),
+            FactValue::IntList(value) => Ok(FactValueDef::IntList(value)),
+            FactValue::FloatList(value) => Ok(FactValueDef::FloatList(value)),
+            FactValue::Vec2(value) => Ok(FactValueDef::Vec2(value)),
+            FactValue::Vec3(value) => Ok(FactValueDef::Vec3(value)),
+            FactValue::Null => Ok(FactValueDef::Null),
+            FactValue::EntityRef(entity) => Ok(FactValueDef::Entity(entity.to_bits())),
+            other @ FactValue::BoolList(_) => Err(UnrepresentableValue {
+                found: FactType::of(&other),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FactModificationDef {
-    Set { key: String, value: FactValueDef },
-    Increment { key: String, amount: i64 },
-    Add { key: String, value: f64 },
-    Sub { key: String, value: f64 },
-    Mul { key: String, value: f64 },
-    Div { key: String, value: f64 },
-    Mod { key: String, value: i64 },
-    Clamp { key: String, min: f64, max: f64 },
-    Wrap { key: String, min: i64, max: i64 },
-    Eval { key: String, expr: String },
+    /// `scope: Global` writes to the global layer; the local layer is the default.
+    ///
+    /// `scope: Global` 写入全局层；默认写入局部层。
+    Set {
+        key: String,
+        value: FactValueDef,
+        #[serde(default)]
+        scope: FactLayer,
+    },
+    Increment {
+        key: String,
+        amount: i64,
+        #[serde(default)]
+        scope: FactLayer,
+    },
+    Add {
+        key: String,
+        value: f64,
+    },
+    Sub {
+        key: String,
+        value: f64,
+    },
+    #[serde(alias = "Multiply")]
+    Mul {
+        key: String,
+        #[serde(alias = "factor")]
+        value: f64,
+    },
+    /// Division by zero sets the fact to 0.
+    ///
+    /// 除以零时将事实设为 0。
+    #[serde(alias = "Divide")]
+    Div {
+        key: String,
+        #[serde(alias = "divisor")]
+        value: f64,
+    },
+    #[serde(alias = "Modulo")]
+    Mod {
+        key: String,
+        #[serde(alias = "divisor")]
+        value: i64,
+    },
+    Clamp {
+        key: String,
+        min: f64,
+        max: f64,
+    },
+    Wrap {
+        key: String,
+        min: i64,
+        max: i64,
+    },
+    Eval {
+        key: String,
+        expr: String,
+    },
     Remove(String),
+    RemoveGlobal(String),
     Toggle(String),
+    ToggleGlobal(String),
+    SetNull(String),
+    ResetToInitial(String),
+    AppendToList {
+        key: String,
+        value: String,
+    },
+    RemoveFromList {
+        key: String,
+        value: String,
+    },
 }
 
 impl From<FactModificationDef> for FactModification {
     fn from(def: FactModificationDef) -> Self {
         match def {
-            FactModificationDef::Set { key, value } => FactModification::Set(key, value.into()),
-            FactModificationDef::Increment { key, amount } => {
-                FactModification::Increment(key, amount)
-            }
+            FactModificationDef::Set { key, value, scope } => match scope {
+                FactLayer::Local => FactModification::Set(key, value.into()),
+                FactLayer::Global => FactModification::SetGlobal(key, value.into()),
+            },
+            FactModificationDef::Increment { key, amount, scope } => match scope {
+                FactLayer::Local => FactModification::Increment(key, amount),
+                FactLayer::Global => FactModification::IncrementGlobal(key, amount),
+            },
             FactModificationDef::Add { key, value } => FactModification::Add(key, value),
             FactModificationDef::Sub { key, value } => FactModification::Sub(key, value),
             FactModificationDef::Mul { key, value } => FactModification::Mul(key, value),
@@ -82,11 +213,70 @@ impl From<FactModificationDef> for FactModification {
             FactModificationDef::Wrap { key, min, max } => FactModification::Wrap(key, min, max),
             FactModificationDef::Eval { key, expr } => FactModification::Eval(key, expr),
             FactModificationDef::Remove(key) => FactModification::Remove(key),
+            FactModificationDef::RemoveGlobal(key) => FactModification::RemoveGlobal(key),
             FactModificationDef::Toggle(key) => FactModification::Toggle(key),
+            FactModificationDef::ToggleGlobal(key) => FactModification::ToggleGlobal(key),
+            FactModificationDef::SetNull(key) => FactModification::SetNull(key),
+            FactModificationDef::ResetToInitial(key) => FactModification::ResetToInitial(key),
+            FactModificationDef::AppendToList { key, value } => {
+                FactModification::AppendToList(key, value)
+            }
+            FactModificationDef::RemoveFromList { key, value } => {
+                FactModification::RemoveFromList(key, value)
+            }
         }
     }
 }
 
+impl TryFrom<FactModification> for FactModificationDef {
+    type Error = UnrepresentableValue;
+
+    fn try_from(modification: FactModification) -> Result<Self, Self::Error> {
+        Ok(match modification {
+            FactModification::Set(key, value) => FactModificationDef::Set {
+                key,
+                value: value.try_into()?,
+                scope: FactLayer::Local,
+            },
+            FactModification::SetGlobal(key, value) => FactModificationDef::Set {
+                key,
+                value: value.try_into()?,
+                scope: FactLayer::Global,
+            },
+            FactModification::Increment(key, amount) => FactModificationDef::Increment {
+                key,
+                amount,
+                scope: FactLayer::Local,
+            },
+            FactModification::IncrementGlobal(key, amount) => FactModificationDef::Increment {
+                key,
+                amount,
+                scope: FactLayer::Global,
+            },
+            FactModification::Add(key, value) => FactModificationDef::Add { key, value },
+            FactModification::Sub(key, value) => FactModificationDef::Sub { key, value },
+            FactModification::Mul(key, value) => FactModificationDef::Mul { key, value },
+            FactModification::Div(key, value) => FactModificationDef::Div { key, value },
+            FactModification::Mod(key, value) => FactModificationDef::Mod { key, value },
+            FactModification::Clamp(key, min, max) => FactModificationDef::Clamp { key, min, max },
+            FactModification::Wrap(key, min, max) => FactModificationDef::Wrap { key, min, max },
+            FactModification::Eval(key, expr) => FactModificationDef::Eval { key, expr },
+            FactModification::Remove(key) => FactModificationDef::Remove(key),
+            FactModification::RemoveGlobal(key) => FactModificationDef::RemoveGlobal(key),
+            FactModification::Toggle(key) => FactModificationDef::Toggle(key),
+            FactModification::ToggleGlobal(key) => FactModificationDef::ToggleGlobal(key),
+            FactModification::SetNull(key) => FactModificationDef::SetNull(key),
+            FactModification::ResetToInitial(key) => FactModificationDef::ResetToInitial(key),
+            FactModification::AppendToList(key, value) => {
+                FactModificationDef::AppendToList { key, value }
+            }
+            FactModification::RemoveFromList(key, value) => {
+                FactModificationDef::RemoveFromList { key, value }
+            }
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ActionEventKind {
     JustPressed,
@@ -101,6 +291,20 @@ pub enum RuleEventDef {
         action: String,
         kind: ActionEventKind,
     },
+    /// Repeating trigger fired every `seconds` by the plugin's interval ticker.
+    ///
+    /// 由插件的间隔计时器每 `seconds` 秒触发一次的重复触发器。
+    Every {
+        seconds: f32,
+    },
+    /// Trigger fired on the frame `key` passes through `threshold` in `direction`.
+    ///
+    /// 在 `key` 以 `direction` 方向穿过 `threshold` 的那一帧触发的触发器。
+    TriggerOnCross {
+        key: String,
+        threshold: f64,
+        direction: CrossDirection,
+    },
 }
 
 impl Default for RuleEventDef {
@@ -121,6 +325,12 @@ impl RuleEventDef {
                 };
                 format!("action:{}:{}", action.to_lowercase(), kind_str)
             }
+            RuleEventDef::Every { seconds } => interval_event_id(*seconds),
+            RuleEventDef::TriggerOnCross {
+                key,
+                threshold,
+                direction,
+            } => crossing_event_id(key, *threshold, *direction),
         }
     }
 }
@@ -0,0 +1,80 @@
+//! # autosave.rs
+//!
+//! Saving the global layer when the app exits (`autosave` feature). Inserting a
+//! `GlobalAutosave` resource names the save file; `FREPlugin` then runs
+//! `save_global_on_exit_system` in `Last`, which writes the global layer with
+//! `LayeredFactDatabase::save_global_to` in the frame an `AppExit` message is sent.
+//! A failed save is logged rather than keeping the app from exiting.
+//!
+//! 在应用退出时保存全局层（`autosave` 特性）。插入 `GlobalAutosave` 资源即可指定存档文件；
+//! 之后 `FREPlugin` 会在 `Last` 中运行 `save_global_on_exit_system`，在发送 `AppExit` 消息的那一帧
+//! 用 `LayeredFactDatabase::save_global_to` 写出全局层。保存失败只会被记录，不会阻止应用退出。
+
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+use crate::layered::LayeredFactDatabase;
+
+/// File the global layer is saved to when the app exits.
+///
+/// 应用退出时保存全局层的文件。
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct GlobalAutosave {
+    pub path: PathBuf,
+}
+
+impl GlobalAutosave {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+/// Save the global layer to the `GlobalAutosave` path once an `AppExit` message is sent.
+///
+/// 发送 `AppExit` 消息后，把全局层保存到 `GlobalAutosave` 指定的路径。
+pub fn save_global_on_exit_system(
+    mut exits: MessageReader<AppExit>,
+    autosave: Option<Res<GlobalAutosave>>,
+    db: Res<LayeredFactDatabase>,
+) {
+    if exits.read().count() == 0 {
+        return;
+    }
+    let Some(autosave) = autosave else {
+        return;
+    };
+    match db.save_global_to(&autosave.path) {
+        Ok(()) => info!("FRE: Saved global facts to {}", autosave.path.display()),
+        Err(err) => error!("FRE: Could not save global facts on exit: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layered::GlobalLoadMode;
+
+    #[test]
+    fn test_global_layer_saved_on_exit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("save.ron");
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .insert_resource(GlobalAutosave::new(&path))
+            .add_systems(Last, save_global_on_exit_system);
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set_global("chapter", 3i64);
+
+        app.update();
+        assert!(!path.exists());
+
+        app.world_mut().write_message(AppExit::Success);
+        app.update();
+        let mut loaded = LayeredFactDatabase::new();
+        loaded
+            .load_global_from(&path, GlobalLoadMode::Replace)
+            .unwrap();
+        assert_eq!(loaded.global().get_int("chapter"), Some(3));
+    }
+}
@@ -0,0 +1,114 @@
+//! # containment.rs
+//!
+//! Built-in membership conditions on list and string facts.
+//! `list_contains($inventory, "potion")` passes when the `StringList` fact `inventory` holds the
+//! element, and `string_contains($tags, "fire")` passes when the `String` fact `tags` contains
+//! the substring. A missing fact or a fact of another type fails the condition. Like the other
+//! built-ins, they are checked by `ConditionEvaluator` before the game's evaluator runs.
+//!
+//! 针对列表和字符串事实的内置成员条件。
+//! 当 `StringList` 事实 `inventory` 包含该元素时，`list_contains($inventory, "potion")` 通过；
+//! 当 `String` 事实 `tags` 包含该子串时，`string_contains($tags, "fire")` 通过。
+//! 缺失的事实或其他类型的事实会使条件失败。与其他内置条件一样，它们由 `ConditionEvaluator` 在游戏评估器之前检查。
+
+use crate::database::FactReader;
+
+/// A parsed built-in membership condition.
+///
+/// 已解析的内置成员条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainsCondition<'a> {
+    /// `list_contains($key, "element")`: a string list holds the element.
+    ///
+    /// `list_contains($key, "element")`：字符串列表包含该元素。
+    ListContains(&'a str, &'a str),
+
+    /// `string_contains($key, "part")`: a string contains the substring.
+    ///
+    /// `string_contains($key, "part")`：字符串包含该子串。
+    StringContains(&'a str, &'a str),
+}
+
+impl<'a> ContainsCondition<'a> {
+    /// Parse a condition expression, returning None if it is not a membership condition.
+    /// Values may be written with or without double quotes.
+    ///
+    /// 解析条件表达式；如果不是成员条件则返回 None。值可以带或不带双引号。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let condition = condition.trim();
+        if let Some(args) = condition.strip_prefix("list_contains(") {
+            let (key, value) = arguments(args)?;
+            return Some(ContainsCondition::ListContains(key, value));
+        }
+        let (key, value) = arguments(condition.strip_prefix("string_contains(")?)?;
+        Some(ContainsCondition::StringContains(key, value))
+    }
+
+    /// Whether the fact exists with the right type and contains the value.
+    ///
+    /// 事实是否以正确的类型存在并包含该值。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        match *self {
+            ContainsCondition::ListContains(key, element) => facts
+                .get_string_list(key)
+                .is_some_and(|list| list.iter().any(|item| item == element)),
+            ContainsCondition::StringContains(key, part) => facts
+                .get_string(key)
+                .is_some_and(|value| value.contains(part)),
+        }
+    }
+}
+
+/// Split `$key, value)` into the key and the unquoted value.
+fn arguments(args: &str) -> Option<(&str, &str)> {
+    let (key, value) = args.strip_suffix(')')?.split_once(',')?;
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    Some((key.trim().strip_prefix('$')?, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layered::LayeredFactDatabase;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ContainsCondition::parse(r#"list_contains($inventory, "potion")"#),
+            Some(ContainsCondition::ListContains("inventory", "potion"))
+        );
+        assert_eq!(
+            ContainsCondition::parse("string_contains($tags, fire)"),
+            Some(ContainsCondition::StringContains("tags", "fire"))
+        );
+        assert_eq!(
+            ContainsCondition::parse("list_contains(inventory, potion)"),
+            None
+        );
+        assert_eq!(ContainsCondition::parse("$inventory == potion"), None);
+    }
+
+    #[test]
+    fn test_present_absent_and_wrong_type() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("inventory", vec!["potion", "key"]);
+        db.set("tags", "fire,undead");
+        db.set("gold", 10i64);
+        let passes = |condition: &str| ContainsCondition::parse(condition).unwrap().matches(&db);
+
+        assert!(passes(r#"list_contains($inventory, "key")"#));
+        assert!(!passes(r#"list_contains($inventory, "sword")"#));
+        assert!(passes(r#"string_contains($tags, "undead")"#));
+        assert!(!passes(r#"string_contains($tags, "ice")"#));
+
+        // Missing facts and wrong types never match
+        assert!(!passes(r#"list_contains($missing, "key")"#));
+        assert!(!passes(r#"list_contains($tags, "fire,undead")"#));
+        assert!(!passes(r#"string_contains($inventory, "potion")"#));
+        assert!(!passes(r#"string_contains($gold, "1")"#));
+    }
+}
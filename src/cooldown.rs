@@ -0,0 +1,288 @@
+//! # cooldown.rs
+//!
+//! Minimum re-trigger intervals for rules. A rule built with a `cooldown` is skipped, as if its
+//! conditions had failed, until that much time has passed since it last fired, so a rule
+//! triggered every frame while a button is held fires once per window instead of every frame.
+//! Fire times are tracked by `RuleCooldownState` using `Time::elapsed`; without a `Time`
+//! resource cooldowns are not enforced. Simulations read the state without recording anything,
+//! and `prune_rule_cooldowns_system` forgets the fire times of unregistered rules.
+//!
+//! 规则的最小重复触发间隔。带有 `cooldown` 的规则在距上次触发未满该时长之前会被跳过，
+//! 如同其条件未通过，因此按住按钮时每帧触发的规则在每个时间窗口内只触发一次。
+//! 触发时间由 `RuleCooldownState` 使用 `Time::elapsed` 记录；没有 `Time` 资源时不执行冷却。
+//! 模拟只读取该状态而不记录任何内容，`prune_rule_cooldowns_system` 会忘记已注销规则的触发时间。
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::action_dispatch::ActionPhase;
+use crate::asset::ActionDef;
+use crate::event::FactEvent;
+use crate::layered::LayeredFactDatabase;
+use crate::rule::{FactModification, Rule, RuleRegistryChangeKind, RuleRegistryChanged};
+use crate::systems::RuleSink;
+
+/// When each rule with a cooldown last fired.
+///
+/// 每条带冷却的规则上次触发的时间。
+#[derive(Resource, Debug, Default, Clone)]
+pub struct RuleCooldownState {
+    last_fired: HashMap<String, Duration>,
+}
+
+impl RuleCooldownState {
+    /// Time elapsed (per `Time::elapsed`) when `rule_id` last fired, if tracked.
+    ///
+    /// `rule_id` 上次触发时的已用时间（按 `Time::elapsed`），如果有记录。
+    pub fn last_fired(&self, rule_id: &str) -> Option<Duration> {
+        self.last_fired.get(rule_id).copied()
+    }
+
+    /// Whether `rule` may fire at time `now`.
+    ///
+    /// `rule` 在时间 `now` 是否可以触发。
+    pub fn is_ready<A: ActionDef>(&self, rule: &Rule<A>, now: Duration) -> bool {
+        let (Some(cooldown), Some(last)) = (rule.cooldown, self.last_fired(&rule.id)) else {
+            return true;
+        };
+        now.saturating_sub(last) >= cooldown
+    }
+
+    /// Forget when `rule_id` fired, making it ready immediately.
+    ///
+    /// 忘记 `rule_id` 的触发时间，使其立即就绪。
+    pub fn reset(&mut self, rule_id: &str) {
+        self.last_fired.remove(rule_id);
+    }
+
+    pub fn clear(&mut self) {
+        self.last_fired.clear();
+    }
+}
+
+/// System that forgets the fire times of rules removed from the registry.
+///
+/// 忘记已从注册表中删除的规则的触发时间的系统。
+pub fn prune_rule_cooldowns_system(
+    mut changes: MessageReader<RuleRegistryChanged>,
+    mut state: ResMut<RuleCooldownState>,
+) {
+    for change in changes.read() {
+        if !matches!(
+            change.kind,
+            RuleRegistryChangeKind::Unregistered | RuleRegistryChangeKind::LayerCleared { .. }
+        ) {
+            continue;
+        }
+        for rule_id in &change.rule_ids {
+            if state.last_fired.contains_key(rule_id) {
+                state.reset(rule_id);
+            }
+        }
+    }
+}
+
+/// Cooldown state as seen by a `CooldownSink`: recorded into for real runs, only read by
+/// simulations.
+enum CooldownAccess<'a> {
+    Record(&'a mut RuleCooldownState),
+    Read(&'a RuleCooldownState),
+}
+
+/// Sink wrapper that skips rules on cooldown and records when rules with a cooldown fire.
+pub(crate) struct CooldownSink<'a, S> {
+    inner: S,
+    state: CooldownAccess<'a>,
+    now: Duration,
+}
+
+impl<'a, S> CooldownSink<'a, S> {
+    pub(crate) fn new(inner: S, state: &'a mut RuleCooldownState, now: Duration) -> Self {
+        Self {
+            inner,
+            state: CooldownAccess::Record(state),
+            now,
+        }
+    }
+
+    /// Skip rules on cooldown without recording any fire times.
+    pub(crate) fn read_only(inner: S, state: &'a RuleCooldownState, now: Duration) -> Self {
+        Self {
+            inner,
+            state: CooldownAccess::Read(state),
+            now,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn state(&self) -> &RuleCooldownState {
+        match &self.state {
+            CooldownAccess::Record(state) => state,
+            CooldownAccess::Read(state) => state,
+        }
+    }
+}
+
+impl<A: ActionDef, S: RuleSink<A>> RuleSink<A> for CooldownSink<'_, S> {
+    fn facts(&self) -> &LayeredFactDatabase {
+        self.inner.facts()
+    }
+
+    fn can_fire(&self, rule: &Rule<A>) -> bool {
+        self.state().is_ready(rule, self.now) && self.inner.can_fire(rule)
+    }
+
+    fn condition_checked(&mut self, rule: &Rule<A>, event: &FactEvent, passed: bool) {
+        self.inner.condition_checked(rule, event, passed);
+    }
+
+    fn rule_firing(&mut self, rule: &Rule<A>, event: &FactEvent) {
+        if let CooldownAccess::Record(state) = &mut self.state
+            && rule.cooldown.is_some()
+        {
+            state.last_fired.insert(rule.id.clone(), self.now);
+        }
+        self.inner.rule_firing(rule, event);
+    }
+
+    fn run_actions(&mut self, rule: &Rule<A>, phase: ActionPhase) {
+        self.inner.run_actions(rule, phase);
+    }
+
+    fn apply_modification(&mut self, rule: &Rule<A>, modification: &FactModification) {
+        self.inner.apply_modification(rule, modification);
+    }
+
+    fn apply_modifications(&mut self, rule: &Rule<A>) {
+        self.inner.apply_modifications(rule);
+    }
+
+    fn queue_output(&mut self, rule: &Rule<A>, event: FactEvent) {
+        self.inner.queue_output(rule, event);
+    }
+
+    fn rule_finished(&mut self, rule: &Rule<A>) {
+        self.inner.rule_finished(rule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry, FreAsset};
+    use crate::rule::{FreRng, LayeredRuleRegistry};
+    use crate::systems::{ConditionEvaluator, PendingFactEvents, process_rules_system};
+
+    fn cooldown_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<PendingFactEvents>()
+            .init_resource::<ConditionEvaluator>()
+            .init_resource::<EnumRegistry>()
+            .init_resource::<FreRng>()
+            .init_resource::<RuleCooldownState>()
+            .add_message::<FactEvent>()
+            .add_systems(Update, process_rules_system::<CoreActionDef>);
+        app
+    }
+
+    /// Advance simulated time by `seconds`, press the button, and return the play count.
+    fn press(app: &mut App, seconds: f32) -> Option<i64> {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        app.world_mut().write_message(FactEvent::new("button_held"));
+        app.update();
+        app.world()
+            .resource::<LayeredFactDatabase>()
+            .get_int("plays")
+    }
+
+    #[test]
+    fn test_rule_is_suppressed_during_cooldown() {
+        let mut app = cooldown_app();
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+            .register(
+                Rule::builder("play_click", "button_held")
+                    .modify(FactModification::Increment("plays".to_string(), 1))
+                    .cooldown(Duration::from_millis(500))
+                    .build(),
+            );
+
+        assert_eq!(press(&mut app, 0.0), Some(1));
+        assert_eq!(press(&mut app, 0.2), Some(1));
+        assert_eq!(press(&mut app, 0.2), Some(1));
+        assert_eq!(press(&mut app, 0.2), Some(2));
+        assert_eq!(press(&mut app, 0.1), Some(2));
+
+        app.world_mut()
+            .resource_mut::<RuleCooldownState>()
+            .reset("play_click");
+        assert_eq!(press(&mut app, 0.0), Some(3));
+    }
+
+    #[test]
+    fn test_cooldown_from_asset_lets_fallback_fire() {
+        let mut app = cooldown_app();
+        let asset: FreAsset = ron::from_str(
+            r#"(rules: [
+                (id: "play_click", event: Event("button_held"), priority: 1, cooldown: Some(1.0),
+                 modifications: [Increment(key: "plays", amount: 1)]),
+                (id: "count_skips", event: Event("button_held"),
+                 modifications: [Increment(key: "skips", amount: 1)]),
+            ])"#,
+        )
+        .unwrap();
+        asset.register_rules_layered(
+            &mut app
+                .world_mut()
+                .resource_mut::<LayeredRuleRegistry<CoreActionDef>>(),
+        );
+
+        assert_eq!(press(&mut app, 0.0), Some(1));
+        assert_eq!(press(&mut app, 0.5), Some(1));
+        assert_eq!(press(&mut app, 0.6), Some(2));
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert_eq!(db.get_int("skips"), Some(1));
+    }
+
+    #[test]
+    fn test_unregistered_rules_are_pruned() {
+        let mut app = cooldown_app();
+        app.add_message::<RuleRegistryChanged>().add_systems(
+            Update,
+            (
+                crate::systems::emit_rule_registry_changes_system::<CoreActionDef>,
+                prune_rule_cooldowns_system,
+            )
+                .chain()
+                .after(process_rules_system::<CoreActionDef>),
+        );
+        let mut registry = app
+            .world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>();
+        registry.set_change_tracking(true);
+        registry.register(
+            Rule::builder("play_click", "button_held")
+                .cooldown(Duration::from_secs(1))
+                .build(),
+        );
+        press(&mut app, 0.0);
+        let state = app.world().resource::<RuleCooldownState>();
+        assert!(state.last_fired("play_click").is_some());
+
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+            .unregister("play_click");
+        app.update();
+        let state = app.world().resource::<RuleCooldownState>();
+        assert_eq!(state.last_fired("play_click"), None);
+    }
+}
@@ -0,0 +1,251 @@
+//! # crossing.rs
+//!
+//! Threshold crossing triggers.
+//! A rule declared with `TriggerOnCross(key: "hp", threshold: 20.0, direction: Below)` is
+//! triggered by the internal event `fre:cross:below:20:hp`. `track_threshold_crossings_system`
+//! remembers the value of every watched key from the previous frame and emits the event only on
+//! the frame the value passes through the threshold, not while it merely stays on the far side.
+//!
+//! 阈值穿越触发器。
+//! 以 `TriggerOnCross(key: "hp", threshold: 20.0, direction: Below)` 声明的规则由内部事件
+//! `fre:cross:below:20:hp` 触发。`track_threshold_crossings_system` 记住每个被监视键在上一帧的值，
+//! 并且只在值穿过阈值的那一帧发出事件，而不是在值停留在另一侧期间持续发出。
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::asset::ActionDef;
+use crate::database::FactReader;
+use crate::event::FactEvent;
+use crate::layered::LayeredFactDatabase;
+use crate::rule::LayeredRuleRegistry;
+
+/// Prefix of the internal event ids used by crossing triggers.
+///
+/// 穿越触发器所用内部事件 ID 的前缀。
+pub const CROSSING_EVENT_PREFIX: &str = "fre:cross:";
+
+/// Which way a fact must pass through its threshold.
+///
+/// 事实必须以哪个方向穿过阈值。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrossDirection {
+    /// From at or above the threshold to below it.
+    ///
+    /// 从阈值及以上降到阈值以下。
+    Below,
+
+    /// From at or below the threshold to above it.
+    ///
+    /// 从阈值及以下升到阈值以上。
+    Above,
+}
+
+impl CrossDirection {
+    fn as_str(self) -> &'static str {
+        match self {
+            CrossDirection::Below => "below",
+            CrossDirection::Above => "above",
+        }
+    }
+
+    fn parse(direction: &str) -> Option<Self> {
+        match direction {
+            "below" => Some(CrossDirection::Below),
+            "above" => Some(CrossDirection::Above),
+            _ => None,
+        }
+    }
+
+    /// Whether a change from `previous` to `current` crosses `threshold` in this direction.
+    ///
+    /// 从 `previous` 到 `current` 的变化是否以此方向穿过 `threshold`。
+    pub fn crossed(self, previous: f64, current: f64, threshold: f64) -> bool {
+        match self {
+            CrossDirection::Below => previous >= threshold && current < threshold,
+            CrossDirection::Above => previous <= threshold && current > threshold,
+        }
+    }
+}
+
+/// Event id that triggers rules watching `key` cross `threshold` in `direction`.
+/// Rules watching the same crossing share the same id.
+///
+/// 触发监视 `key` 以 `direction` 方向穿过 `threshold` 的规则的事件 ID。
+/// 监视同一穿越的规则共享同一个 ID。
+pub fn crossing_event_id(key: &str, threshold: f64, direction: CrossDirection) -> String {
+    format!(
+        "{}{}:{}:{}",
+        CROSSING_EVENT_PREFIX,
+        direction.as_str(),
+        threshold,
+        key
+    )
+}
+
+/// Parse the key, threshold and direction of a crossing trigger id, if it is one.
+fn parse_crossing(event_id: &str) -> Option<(&str, f64, CrossDirection)> {
+    let rest = event_id.strip_prefix(CROSSING_EVENT_PREFIX)?;
+    let (direction, rest) = rest.split_once(':')?;
+    let (threshold, key) = rest.split_once(':')?;
+    Some((
+        key,
+        threshold.parse().ok()?,
+        CrossDirection::parse(direction)?,
+    ))
+}
+
+/// A watched crossing: the key, threshold and direction parsed from its trigger id.
+#[derive(Debug, Clone)]
+struct WatchedCrossing {
+    key: String,
+    threshold: f64,
+    direction: CrossDirection,
+}
+
+/// Value of each watched crossing as of the previous frame.
+///
+/// 每个被监视穿越在上一帧时的值。
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ThresholdTracker {
+    previous: HashMap<String, Option<f64>>,
+    /// Crossings of the enabled rules by trigger id, refreshed when the registry changes.
+    watched: HashMap<String, WatchedCrossing>,
+}
+
+impl ThresholdTracker {
+    /// Number of crossing triggers currently being tracked.
+    ///
+    /// 当前正在跟踪的穿越触发器数量。
+    pub fn active_crossings(&self) -> usize {
+        self.previous.len()
+    }
+}
+
+/// System that emits crossing trigger events for the enabled crossing rules in the registry.
+/// A crossing is only detected between two frames in which the key held a number (read as by
+/// `FactReader::get_number`); the first frame a trigger is watched only records the value. The registry is only rescanned in frames
+/// it was modified in.
+///
+/// 为注册表中启用的穿越规则发出穿越触发事件的系统。
+/// 只有在键于前后两帧都持有数值（按 `FactReader::get_number` 读取）时才会检测穿越；触发器被监视的第一帧只记录值。
+/// 只有在注册表被修改的帧中才会重新扫描注册表。
+pub fn track_threshold_crossings_system<A: ActionDef>(
+    registry: Res<LayeredRuleRegistry<A>>,
+    db: Res<LayeredFactDatabase>,
+    mut tracker: ResMut<ThresholdTracker>,
+    mut writer: MessageWriter<FactEvent>,
+) {
+    let refresh = registry.is_changed() || tracker.is_added();
+    let tracker = &mut *tracker;
+    if refresh {
+        tracker.watched = registry
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                let (key, threshold, direction) = parse_crossing(rule.trigger.as_str())?;
+                let crossing = WatchedCrossing {
+                    key: key.to_string(),
+                    threshold,
+                    direction,
+                };
+                Some((rule.trigger.to_string(), crossing))
+            })
+            .collect();
+        let watched = &tracker.watched;
+        tracker
+            .previous
+            .retain(|event_id, _| watched.contains_key(event_id));
+    }
+
+    for (event_id, crossing) in &tracker.watched {
+        let current = db.get_number(&crossing.key);
+        let previous = tracker.previous.insert(event_id.clone(), current);
+        let (Some(Some(previous)), Some(current)) = (previous, current) else {
+            continue;
+        };
+        if crossing
+            .direction
+            .crossed(previous, current, crossing.threshold)
+        {
+            writer.write(FactEvent::new(event_id.as_str()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, FreAsset};
+
+    fn crossing_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<ThresholdTracker>()
+            .add_message::<FactEvent>()
+            .add_systems(Update, track_threshold_crossings_system::<CoreActionDef>);
+        let asset: FreAsset = ron::from_str(
+            r#"(rules: [(id: "low_hp", event: TriggerOnCross(key: "hp", threshold: 20.0, direction: Below))])"#,
+        )
+        .unwrap();
+        asset.register_rules_layered(
+            &mut app
+                .world_mut()
+                .resource_mut::<LayeredRuleRegistry<CoreActionDef>>(),
+        );
+        app
+    }
+
+    /// Set hp, run one frame, and return the emitted event ids.
+    fn step(app: &mut App, hp: i64) -> Vec<String> {
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("hp", hp);
+        app.update();
+        app.world()
+            .resource::<Messages<FactEvent>>()
+            .iter_current_update_messages()
+            .map(|event| event.id.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_fires_once_on_crossing() {
+        let mut app = crossing_app();
+        assert!(step(&mut app, 50).is_empty());
+        assert!(step(&mut app, 30).is_empty());
+        assert_eq!(step(&mut app, 10), vec!["fre:cross:below:20:hp"]);
+        // Still below: no further events
+        assert!(step(&mut app, 5).is_empty());
+        assert!(step(&mut app, 15).is_empty());
+        // Recovering and dropping again crosses again
+        assert!(step(&mut app, 25).is_empty());
+        assert_eq!(step(&mut app, 19), vec!["fre:cross:below:20:hp"]);
+    }
+
+    #[test]
+    fn test_starting_below_does_not_fire() {
+        let mut app = crossing_app();
+        assert!(step(&mut app, 10).is_empty());
+        assert!(step(&mut app, 5).is_empty());
+        assert_eq!(
+            app.world()
+                .resource::<ThresholdTracker>()
+                .active_crossings(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_direction() {
+        assert!(CrossDirection::Above.crossed(20.0, 21.0, 20.0));
+        assert!(!CrossDirection::Above.crossed(21.0, 25.0, 20.0));
+        assert!(CrossDirection::Below.crossed(20.0, 19.5, 20.0));
+        assert_eq!(
+            parse_crossing(&crossing_event_id("global:hp", 0.5, CrossDirection::Above)),
+            Some(("global:hp", 0.5, CrossDirection::Above))
+        );
+    }
+}
@@ -5,199 +5,55 @@
 //! 集中式事实数据库，用于将游戏状态存储为键值对。
 
 use bevy::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::BuildHasher;
+
+use crate::key::FactKey;
 
 #[cfg(feature = "debug")]
 use bevy::reflect::Reflect;
 
-/// Value types supported by the fact database.
-///
-/// 事实数据库支持的值类型。
-#[derive(Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "debug", derive(Reflect))]
-pub enum FactValue {
-    Int(i64),
-    Float(f64),
-    Bool(bool),
-    String(String),
-    /// List of strings - useful for inventories, tags, etc.
-    /// 字符串列表 - 适用于物品栏、标签等。
-    StringList(Vec<String>),
-    /// List of integers - useful for HP values, stats arrays, etc.
-    /// 整数列表 - 适用于 HP 值、属性数组等。
-    IntList(Vec<i64>),
-    /// List of floats - useful for coordinates, multipliers, etc.
-    /// 浮点数列表 - 适用于坐标、乘数等。
-    FloatList(Vec<f64>),
-    /// List of booleans - useful for flags, toggles, etc.
-    /// 布尔列表 - 适用于标志、开关等。
-    BoolList(Vec<bool>),
-}
-
-impl FactValue {
-    /// Get the value as an integer, if it is one.
-    ///
-    /// 如果值是整数，则获取该值。
-    pub fn as_int(&self) -> Option<i64> {
-        match self {
-            FactValue::Int(v) => Some(*v),
-            _ => None,
-        }
-    }
-
-    /// Get the value as a float, if it is one.
-    ///
-    /// 如果值是浮点数，则获取该值。
-    pub fn as_float(&self) -> Option<f64> {
-        match self {
-            FactValue::Float(v) => Some(*v),
-            _ => None,
-        }
-    }
-
-    /// Get the value as a boolean, if it is one.
-    ///
-    /// 如果值是布尔值，则获取该值。
-    pub fn as_bool(&self) -> Option<bool> {
-        match self {
-            FactValue::Bool(v) => Some(*v),
-            _ => None,
-        }
-    }
-
-    /// Get the value as a string, if it is one.
-    ///
-    /// 如果值是字符串，则获取该值。
-    pub fn as_string(&self) -> Option<&str> {
-        match self {
-            FactValue::String(v) => Some(v),
-            _ => None,
-        }
-    }
-
-    /// Get the value as a string list, if it is one.
-    ///
-    /// 如果值是字符串列表，则获取该值。
-    pub fn as_string_list(&self) -> Option<&[String]> {
-        match self {
-            FactValue::StringList(v) => Some(v),
-            _ => None,
-        }
-    }
-
-    /// Get the value as an integer list, if it is one.
-    ///
-    /// 如果值是整数列表，则获取该值。
-    pub fn as_int_list(&self) -> Option<&[i64]> {
-        match self {
-            FactValue::IntList(v) => Some(v),
-            _ => None,
-        }
-    }
+mod absent;
+mod aggregate;
+mod changes;
+mod handle;
+mod lists;
+mod merge;
+mod overflow;
+mod persist;
+mod prefix;
+mod retain;
+mod snapshot;
+mod value;
+
+pub use handle::FactHandle;
+use handle::Slot;
+pub(crate) use lists::{list_with_item, list_without_item};
+pub(crate) use merge::plan_merge;
+pub use merge::{MergeConflict, MergeError, MergePolicy};
+pub use overflow::IntOverflowPolicy;
+pub(crate) use overflow::{add_or_warn, float_to_fact, whole_to_int};
+pub use snapshot::{FactChange, FactSnapshot};
+pub use value::FactValue;
+#[cfg(test)]
+pub(crate) use value::every_variant;
 
-    /// Get the value as a float list, if it is one.
+/// A storage layer of a layered fact reader.
+///
+/// 分层事实读取器的存储层。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum FactLayer {
+    /// Persistent data across game states.
     ///
-    /// 如果值是浮点数列表，则获取该值。
-    pub fn as_float_list(&self) -> Option<&[f64]> {
-        match self {
-            FactValue::FloatList(v) => Some(v),
-            _ => None,
-        }
-    }
+    /// 跨游戏状态的持久数据。
+    Global,
 
-    /// Get the value as a boolean list, if it is one.
+    /// Temporary data for the current context, the default write target.
     ///
-    /// 如果值是布尔列表，则获取该值。
-    pub fn as_bool_list(&self) -> Option<&[bool]> {
-        match self {
-            FactValue::BoolList(v) => Some(v),
-            _ => None,
-        }
-    }
-}
-
-impl From<i64> for FactValue {
-    fn from(v: i64) -> Self {
-        FactValue::Int(v)
-    }
-}
-
-impl From<i32> for FactValue {
-    fn from(v: i32) -> Self {
-        FactValue::Int(v as i64)
-    }
-}
-
-impl From<f64> for FactValue {
-    fn from(v: f64) -> Self {
-        FactValue::Float(v)
-    }
-}
-
-impl From<f32> for FactValue {
-    fn from(v: f32) -> Self {
-        FactValue::Float(v as f64)
-    }
-}
-
-impl From<bool> for FactValue {
-    fn from(v: bool) -> Self {
-        FactValue::Bool(v)
-    }
-}
-
-impl From<String> for FactValue {
-    fn from(v: String) -> Self {
-        FactValue::String(v)
-    }
-}
-
-impl From<&str> for FactValue {
-    fn from(v: &str) -> Self {
-        FactValue::String(v.to_string())
-    }
-}
-
-impl From<Vec<String>> for FactValue {
-    fn from(v: Vec<String>) -> Self {
-        FactValue::StringList(v)
-    }
-}
-
-impl From<Vec<&str>> for FactValue {
-    fn from(v: Vec<&str>) -> Self {
-        FactValue::StringList(v.into_iter().map(|s| s.to_string()).collect())
-    }
-}
-
-impl From<Vec<i64>> for FactValue {
-    fn from(v: Vec<i64>) -> Self {
-        FactValue::IntList(v)
-    }
-}
-
-impl From<Vec<i32>> for FactValue {
-    fn from(v: Vec<i32>) -> Self {
-        FactValue::IntList(v.into_iter().map(|i| i as i64).collect())
-    }
-}
-
-impl From<Vec<f64>> for FactValue {
-    fn from(v: Vec<f64>) -> Self {
-        FactValue::FloatList(v)
-    }
-}
-
-impl From<Vec<f32>> for FactValue {
-    fn from(v: Vec<f32>) -> Self {
-        FactValue::FloatList(v.into_iter().map(|f| f as f64).collect())
-    }
-}
-
-impl From<Vec<bool>> for FactValue {
-    fn from(v: Vec<bool>) -> Self {
-        FactValue::BoolList(v)
-    }
+    /// 当前上下文的临时数据，默认的写入目标。
+    #[default]
+    Local,
 }
 
 /// Trait for read-only fact database access.
@@ -209,6 +65,11 @@ pub trait FactReader {
     /// Get a fact value by string key.
     fn get_by_str(&self, key: &str) -> Option<&FactValue>;
 
+    /// Get a fact value by interned key.
+    fn get_by_key(&self, key: FactKey) -> Option<&FactValue> {
+        self.get_by_str(key.as_str())
+    }
+
     /// Get an integer fact value.
     fn get_int(&self, key: &str) -> Option<i64> {
         self.get_by_str(key).and_then(|v| v.as_int())
@@ -224,6 +85,17 @@ pub trait FactReader {
         self.get_by_str(key).and_then(|v| v.as_float())
     }
 
+    /// Get a numeric fact value, accepting Int, Float, and Bool facts (see `FactValue::as_number`).
+    fn get_number(&self, key: &str) -> Option<f64> {
+        self.get_by_str(key).and_then(|v| v.as_number())
+    }
+
+    /// Get an integer fact value, truncating Float facts and reading Bool facts as 1 or 0
+    /// (see `FactValue::as_int_lossy`).
+    fn get_int_lossy(&self, key: &str) -> Option<i64> {
+        self.get_by_str(key).and_then(|v| v.as_int_lossy())
+    }
+
     /// Get a boolean fact value.
     fn get_bool(&self, key: &str) -> Option<bool> {
         self.get_by_str(key).and_then(|v| v.as_bool())
@@ -254,17 +126,69 @@ pub trait FactReader {
         self.get_by_str(key).and_then(|v| v.as_bool_list())
     }
 
+    /// Get an entity reference fact value.
+    fn get_entity(&self, key: &str) -> Option<Entity> {
+        self.get_by_str(key).and_then(|v| v.as_entity())
+    }
+
     /// Check if a fact exists.
     fn contains(&self, key: &str) -> bool;
+
+    /// Access a single storage layer, bypassing the layered fallback.
+    /// Returns None for readers that have no layers.
+    fn layer(&self, _layer: FactLayer) -> Option<&dyn FactReader> {
+        None
+    }
+
+    /// Get a fact value from one specific layer only.
+    fn get_in_layer(&self, layer: FactLayer, key: &str) -> Option<&FactValue> {
+        self.layer(layer)?.get_by_str(key)
+    }
+
+    /// Check if a fact exists in one specific layer only.
+    fn contains_in_layer(&self, layer: FactLayer, key: &str) -> bool {
+        self.layer(layer).is_some_and(|reader| reader.contains(key))
+    }
+
+    /// Whether a fact's value differs from its value when the frame started.
+    /// Returns None for readers that keep no frame snapshot, or keys it does not track.
+    fn changed_since_last_frame(&self, _key: &str) -> Option<bool> {
+        None
+    }
 }
 
 /// Centralized database for storing facts (game state).
+/// Keys are stored as `String`s, so lookups by `&str` borrow the key and never allocate.
 ///
 /// 用于存储事实（游戏状态）的集中式数据库。
+/// 键以 `String` 存储，因此按 `&str` 查找时借用键，从不分配内存。
 #[derive(Resource, Default, Debug, Clone)]
-#[cfg_attr(feature = "debug", derive(Reflect))]
+#[cfg_attr(feature = "debug", derive(Reflect), reflect(Resource))]
 pub struct FactDatabase {
-    facts: HashMap<String, FactValue>,
+    /// Maps each key to its slot index.
+    keys: HashMap<String, usize>,
+    /// Fact values by slot. Slots freed by `remove` and `clear` are reused.
+    slots: Vec<Slot>,
+    /// Freed slots, reused before the slot list grows.
+    free: Vec<usize>,
+    /// Number of slots holding a value.
+    len: usize,
+    /// How `increment` handles results outside the `i64` range.
+    int_overflow_policy: IntOverflowPolicy,
+    /// Keys set or removed since the last `clear_changes`.
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    changed: HashSet<String>,
+    /// Bumped on every write; see `generation`.
+    generation: u64,
+    /// Generation of each key's last write, including removals not yet forgotten.
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    stamps: HashMap<String, u64>,
+    /// The keys of `stamps` ordered by generation, so `iter_changed_since` reads only the tail.
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    log: BTreeMap<u64, String>,
+    /// Generations in `log` that record a removal.
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    removals: BTreeSet<u64>,
 }
 
 impl FactDatabase {
@@ -272,16 +196,19 @@ impl FactDatabase {
     ///
     /// 创建一个新的空事实数据库。
     pub fn new() -> Self {
-        Self {
-            facts: HashMap::new(),
-        }
+        Self::default()
     }
 
     /// Set a fact value in the database.
     ///
     /// 在数据库中设置一个事实值。
     pub fn set(&mut self, key: impl Into<String>, value: impl Into<FactValue>) {
-        self.facts.insert(key.into(), value.into());
+        let key = key.into();
+        self.record_change(&key);
+        let slot = self.slot_for(key);
+        if self.slots[slot].value.replace(value.into()).is_none() {
+            self.len += 1;
+        }
     }
 
     /// Set a fact value only if it's different from the current value.
@@ -292,8 +219,8 @@ impl FactDatabase {
     pub fn set_if_changed(&mut self, key: impl Into<String>, value: impl Into<FactValue>) -> bool {
         let key = key.into();
         let value = value.into();
-        if self.facts.get(&key) != Some(&value) {
-            self.facts.insert(key, value);
+        if self.get_by_str(&key) != Some(&value) {
+            self.set(key, value);
             true
         } else {
             false
@@ -304,7 +231,7 @@ impl FactDatabase {
     ///
     /// 通过字符串键获取事实值。
     pub fn get_by_str(&self, key: &str) -> Option<&FactValue> {
-        self.facts.get(key)
+        self.slots.get(*self.keys.get(key)?)?.value.as_ref()
     }
 
     /// Get an integer fact value, returning a default if not found or wrong type.
@@ -346,14 +273,21 @@ impl FactDatabase {
     ///
     /// 检查数据库中是否存在某个事实。
     pub fn contains(&self, key: &str) -> bool {
-        self.facts.contains_key(key)
+        self.get_by_str(key).is_some()
     }
 
     /// Remove a fact from the database.
     ///
     /// 从数据库中移除一个事实。
     pub fn remove(&mut self, key: &str) -> Option<FactValue> {
-        self.facts.remove(key)
+        let slot = self.keys.remove(key)?;
+        let removed = self.slots[slot].value.take();
+        self.free_slot(slot);
+        if removed.is_some() {
+            self.len -= 1;
+            self.record_removal(key);
+        }
+        removed
     }
 
     /// Increment an integer fact by a given amount.
@@ -363,45 +297,81 @@ impl FactDatabase {
     /// 如果事实不存在，将使用增量值创建。
     pub fn increment(&mut self, key: &str, amount: i64) {
         let current = self.get_int(key).unwrap_or(0);
-        self.set(key, current + amount);
+        if let Some(sum) = add_or_warn(self.int_overflow_policy, key, current, amount) {
+            self.set(key, sum);
+        }
+    }
+
+    pub fn int_overflow_policy(&self) -> IntOverflowPolicy {
+        self.int_overflow_policy
+    }
+
+    /// Set how `increment` handles results outside the `i64` range (saturating by default).
+    ///
+    /// 设置 `increment` 如何处理超出 `i64` 范围的结果（默认饱和）。
+    pub fn set_int_overflow_policy(&mut self, policy: IntOverflowPolicy) {
+        self.int_overflow_policy = policy;
     }
 
     /// Get all facts as an iterator.
     ///
     /// 获取所有事实的迭代器。
     pub fn iter(&self) -> impl Iterator<Item = (&String, &FactValue)> {
-        self.facts.iter()
+        self.keys
+            .iter()
+            .filter_map(|(key, &slot)| Some((key, self.slots[slot].value.as_ref()?)))
     }
 
     /// Get the number of facts in the database.
     ///
     /// 获取数据库中事实的数量。
     pub fn len(&self) -> usize {
-        self.facts.len()
+        self.len
     }
 
     /// Check if the database is empty.
     ///
     /// 检查数据库是否为空。
     pub fn is_empty(&self) -> bool {
-        self.facts.is_empty()
+        self.len == 0
     }
 
     /// Clear all facts from the database.
     ///
     /// 清除数据库中的所有事实。
     pub fn clear(&mut self) {
-        self.facts.clear();
+        let keys = std::mem::take(&mut self.keys);
+        for (key, slot) in keys {
+            if self.slots[slot].value.is_some() {
+                self.record_removal(&key);
+            }
+            self.free_slot(slot);
+        }
+        self.len = 0;
     }
 }
 
 impl FactReader for FactDatabase {
     fn get_by_str(&self, key: &str) -> Option<&FactValue> {
-        self.facts.get(key)
+        FactDatabase::get_by_str(self, key)
     }
 
     fn contains(&self, key: &str) -> bool {
-        self.facts.contains_key(key)
+        FactDatabase::contains(self, key)
+    }
+}
+
+/// Plain maps are readers too, so conditions can be evaluated against ad-hoc facts,
+/// e.g. in unit tests, without building a `FactDatabase`.
+///
+/// 普通映射也是读取器，因此无需构建 `FactDatabase` 即可针对临时事实评估条件（例如在单元测试中）。
+impl<S: BuildHasher> FactReader for HashMap<String, FactValue, S> {
+    fn get_by_str(&self, key: &str) -> Option<&FactValue> {
+        self.get(key)
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.contains_key(key)
     }
 }
 
@@ -431,11 +401,25 @@ impl FactReader for CombinedFactReader<'_> {
     fn contains(&self, key: &str) -> bool {
         self.primary.contains(key) || self.secondary.contains(key)
     }
+
+    fn layer(&self, layer: FactLayer) -> Option<&dyn FactReader> {
+        self.primary
+            .layer(layer)
+            .or_else(|| self.secondary.layer(layer))
+    }
+
+    fn changed_since_last_frame(&self, key: &str) -> Option<bool> {
+        self.primary
+            .changed_since_last_frame(key)
+            .or_else(|| self.secondary.changed_since_last_frame(key))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::alloc_count::allocations_during;
+    use crate::layered::LayeredFactDatabase;
 
     #[test]
     fn test_fact_database_basic_operations() {
@@ -463,45 +447,6 @@ mod tests {
         assert_eq!(db.get_int("counter"), Some(6));
     }
 
-    #[test]
-    fn test_fact_value_type_accessors() {
-        let int_val = FactValue::Int(42);
-        let float_val = FactValue::Float(2.71);
-        let bool_val = FactValue::Bool(true);
-        let string_val = FactValue::String("test".to_string());
-
-        // Test correct type accessors
-        assert_eq!(int_val.as_int(), Some(42));
-        assert_eq!(float_val.as_float(), Some(2.71));
-        assert_eq!(bool_val.as_bool(), Some(true));
-        assert_eq!(string_val.as_string(), Some("test"));
-
-        // Test wrong type accessors return None
-        assert_eq!(int_val.as_float(), None);
-        assert_eq!(float_val.as_int(), None);
-        assert_eq!(bool_val.as_string(), None);
-        assert_eq!(string_val.as_bool(), None);
-    }
-
-    #[test]
-    fn test_fact_value_from_implementations() {
-        let from_i64: FactValue = 42i64.into();
-        let from_i32: FactValue = 42i32.into();
-        let from_f64: FactValue = 2.71f64.into();
-        let from_f32: FactValue = 2.71f32.into();
-        let from_bool: FactValue = true.into();
-        let from_string: FactValue = String::from("test").into();
-        let from_str: FactValue = "test".into();
-
-        assert_eq!(from_i64.as_int(), Some(42));
-        assert_eq!(from_i32.as_int(), Some(42));
-        assert!(from_f64.as_float().is_some());
-        assert!(from_f32.as_float().is_some());
-        assert_eq!(from_bool.as_bool(), Some(true));
-        assert_eq!(from_string.as_string(), Some("test"));
-        assert_eq!(from_str.as_string(), Some("test"));
-    }
-
     #[test]
     fn test_fact_database_remove() {
         let mut db = FactDatabase::new();
@@ -572,6 +517,9 @@ mod tests {
             assert_eq!(reader.get_string("name"), Some("Hero"));
             assert_eq!(reader.get_bool("alive"), Some(true));
             assert_eq!(reader.get_float("speed"), Some(2.5));
+            assert_eq!(reader.get_int("speed"), None);
+            assert_eq!(reader.get_int_lossy("speed"), Some(2));
+            assert_eq!(reader.get_int_lossy("alive"), Some(1));
             assert!(reader.contains("health"));
             assert!(!reader.contains("missing"));
         }
@@ -593,4 +541,119 @@ mod tests {
         assert_eq!(db.get_string("key"), Some("string_value"));
         assert_eq!(db.get_int("key"), None);
     }
+
+    #[test]
+    fn test_conditions_against_a_plain_map() {
+        use crate::asset::EnumRegistry;
+        use crate::systems::ConditionEvaluator;
+
+        let facts = HashMap::from([
+            ("hp".to_string(), FactValue::Float(2.5)),
+            ("level".to_string(), FactValue::Int(3)),
+            ("bag".to_string(), FactValue::from(vec!["potion", "key"])),
+            ("pos".to_string(), FactValue::Vec2([3.0, 4.0])),
+            ("origin".to_string(), FactValue::Vec2([0.0, 0.0])),
+        ]);
+        let evaluator = ConditionEvaluator::default();
+        let passes = |condition: &str| {
+            evaluator.evaluate_conditions(
+                &[condition.to_string()],
+                &facts,
+                &EnumRegistry::default(),
+            )
+        };
+
+        assert!(passes("float($hp) > 2.0"));
+        assert!(!passes("float($level) < 3"));
+        assert!(passes(r#"list_contains($bag, "key")"#));
+        assert!(!passes(r#"list_contains($bag, "sword")"#));
+        assert!(passes("distance($pos, $origin) < 5.5"));
+        assert!(!passes("float($missing) > 0"));
+        assert_eq!(
+            crate::expr::evaluate_expr("$level * 2 + $hp", &facts),
+            Some(8.5)
+        );
+    }
+
+    #[test]
+    fn test_lookups_do_not_allocate() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("hp", 10i64);
+        db.set_global("name", "hero");
+        let key = FactKey::new("hp");
+        let allocations = allocations_during(|| {
+            for _ in 0..1_000 {
+                assert!(db.get_by_str("hp").is_some());
+                assert!(db.contains("name"));
+                assert!(!db.contains("lookup:missing"));
+                assert_eq!(db.get_int("hp"), Some(10));
+                assert!(FactReader::get_by_key(&db, key).is_some());
+                assert!(db.is_changed("hp"));
+                assert!(!db.is_changed("lookup:missing"));
+            }
+        });
+        assert_eq!(allocations, 0);
+        // Checking an unknown key for changes does not intern it
+        assert_eq!(FactKey::lookup("lookup:missing"), None);
+    }
+
+    #[test]
+    fn test_layer_pinned_reads_through_combined_reader() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("mode", "global");
+        db.set_local("mode", "local");
+        let mut view = FactDatabase::new();
+        view.set("mode", "view");
+
+        let combined = CombinedFactReader::new(&view, &db);
+        assert_eq!(combined.get_string("mode"), Some("view"));
+        assert_eq!(
+            combined.get_in_layer(FactLayer::Global, "mode"),
+            Some(&FactValue::String("global".to_string()))
+        );
+
+        // Plain databases have no layers
+        assert_eq!(view.get_in_layer(FactLayer::Global, "mode"), None);
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn test_databases_reflect_through_the_type_registry() {
+        use crate::event::{FactEvent, FactEventId};
+        use crate::rule::RuleScope;
+        use bevy::reflect::serde::ReflectSerializer;
+        use bevy::reflect::{PartialReflect, ReflectRef, TypeRegistry};
+        use std::any::TypeId;
+
+        let mut registry = TypeRegistry::default();
+        registry.register::<LayeredFactDatabase>();
+        registry.register::<FactEvent>();
+        registry.register::<RuleScope>();
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("name", "hero");
+        db.set("hp", 10i64);
+        db.set("position", Vec2::new(1.0, 2.0));
+        db.set("inventory", vec!["key"]);
+
+        let resource =
+            registry.get_type_data::<ReflectResource>(TypeId::of::<LayeredFactDatabase>());
+        assert!(resource.is_some());
+        let ReflectRef::Struct(layers) = db.reflect_ref() else {
+            panic!("LayeredFactDatabase reflects as a struct");
+        };
+        assert!(layers.field("local").is_some());
+        assert_eq!(
+            FactValue::Int(10).reflect_partial_eq(&FactValue::Int(10)),
+            Some(true)
+        );
+
+        let text = ron::to_string(&ReflectSerializer::new(&db, &registry)).unwrap();
+        assert!(text.contains("hero") && text.contains("inventory"));
+        let event = FactEvent::new("door_opened").with_data("door", "north");
+        let ReflectRef::Struct(event) = event.reflect_ref() else {
+            panic!("FactEvent reflects as a struct");
+        };
+        let id = event.field("id").unwrap().try_downcast_ref::<FactEventId>();
+        assert_eq!(id, Some(&FactEventId::from("door_opened")));
+    }
 }
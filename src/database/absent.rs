@@ -0,0 +1,77 @@
+//! # absent.rs
+//!
+//! # absent.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Insert-if-missing helpers for `FactDatabase`, replacing the `if !db.contains(key) { db.set(..) }`
+//! pattern of initialization code. Both leave a fact that already exists untouched, including one
+//! stored as `FactValue::Null`.
+//!
+//! `FactDatabase` 的缺失时插入辅助方法，用于替代初始化代码中 `if !db.contains(key) { db.set(..) }`
+//! 的写法。两者都不会改动已存在的事实，包括存储为 `FactValue::Null` 的事实。
+
+use super::{FactDatabase, FactValue};
+
+impl FactDatabase {
+    /// Get the fact `key`, first setting it to `f()` if it is absent.
+    ///
+    /// 获取事实 `key`；如果不存在，先将其设置为 `f()`。
+    pub fn get_or_insert_with(&mut self, key: &str, f: impl FnOnce() -> FactValue) -> &FactValue {
+        let slot = match self.keys.get(key) {
+            Some(&slot) => slot,
+            None => self.slot_for(key.to_string()),
+        };
+        if self.slots[slot].value.is_none() {
+            self.record_change(key);
+            self.len += 1;
+        }
+        self.slots[slot].value.get_or_insert_with(f)
+    }
+
+    /// Set the fact `key` only if it is absent. Returns whether it was set.
+    ///
+    /// 仅当事实 `key` 不存在时才设置。返回是否进行了设置。
+    pub fn set_if_absent(&mut self, key: &str, value: impl Into<FactValue>) -> bool {
+        if self.contains(key) {
+            return false;
+        }
+        self.set(key, value);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_only_when_absent() {
+        let mut db = FactDatabase::new();
+        assert_eq!(
+            db.get_or_insert_with("gold", || 10.into()),
+            &FactValue::Int(10)
+        );
+        assert_eq!(
+            db.get_or_insert_with("gold", || unreachable!()),
+            &FactValue::Int(10)
+        );
+        assert_eq!(db.len(), 1);
+        assert!(db.changed_keys().any(|key| key == "gold"));
+
+        assert!(!db.set_if_absent("gold", 0));
+        assert!(db.set_if_absent("name", "hero"));
+        db.set("cleared", FactValue::Null);
+        assert!(!db.set_if_absent("cleared", 1));
+        assert_eq!(db.get_int("gold"), Some(10));
+        assert_eq!(db.get_string("name"), Some("hero"));
+
+        // A reserved handle slot is filled in place
+        let handle = db.handle("hp");
+        db.get_or_insert_with("hp", || 3.into());
+        assert_eq!(db.get_by_handle(handle), Some(&FactValue::Int(3)));
+        assert_eq!(db.len(), 4);
+    }
+}
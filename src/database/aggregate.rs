@@ -0,0 +1,85 @@
+//! # aggregate.rs
+//!
+//! # aggregate.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Extremes across a set of keys, for rules that pick among related facts, such as the
+//! scarcest of several resources. Only keys holding an integer take part; missing keys and
+//! facts of other types are skipped. When several keys share the extreme value, the first of
+//! them in the given order wins, so the result does not depend on storage order.
+//!
+//! 一组键之间的极值，用于在相关事实中进行挑选的规则，例如几种资源中最稀缺的一种。
+//! 只有持有整数的键参与比较；缺失的键和其他类型的事实会被跳过。当多个键同为极值时，
+//! 按给定顺序的第一个键胜出，因此结果不依赖于存储顺序。
+
+use super::FactDatabase;
+
+impl FactDatabase {
+    /// The key and value of the smallest integer fact among `keys`, or None if none of them
+    /// holds an integer.
+    ///
+    /// `keys` 中最小整数事实的键和值；如果都不持有整数则返回 None。
+    pub fn min_int_among(&self, keys: &[&str]) -> Option<(String, i64)> {
+        self.int_extreme_among(keys, |value, best| value < best)
+    }
+
+    /// The key and value of the largest integer fact among `keys`, or None if none of them
+    /// holds an integer.
+    ///
+    /// `keys` 中最大整数事实的键和值；如果都不持有整数则返回 None。
+    pub fn max_int_among(&self, keys: &[&str]) -> Option<(String, i64)> {
+        self.int_extreme_among(keys, |value, best| value > best)
+    }
+
+    /// The first integer fact among `keys` that no later one `beats`.
+    fn int_extreme_among(
+        &self,
+        keys: &[&str],
+        beats: impl Fn(i64, i64) -> bool,
+    ) -> Option<(String, i64)> {
+        let mut best: Option<(&str, i64)> = None;
+        for key in keys {
+            let Some(value) = self.get_int(key) else {
+                continue;
+            };
+            if best.is_none_or(|(_, best)| beats(value, best)) {
+                best = Some((key, value));
+            }
+        }
+        best.map(|(key, value)| (key.to_string(), value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_int_extremes_among_keys() {
+        let mut db = FactDatabase::new();
+        db.set("wood", 12i64);
+        db.set("stone", 3i64);
+        db.set("iron", 3i64);
+        db.set("gold", 40i64);
+        db.set("name", "camp");
+
+        let resources = ["wood", "stone", "iron", "gold", "name", "food"];
+        assert_eq!(db.min_int_among(&resources), Some(("stone".to_string(), 3)));
+        assert_eq!(db.max_int_among(&resources), Some(("gold".to_string(), 40)));
+        // Ties go to the first listed key
+        assert_eq!(
+            db.min_int_among(&["iron", "stone"]),
+            Some(("iron".to_string(), 3))
+        );
+        assert_eq!(
+            db.min_int_among(&["food", "wood"]),
+            Some(("wood".to_string(), 12))
+        );
+
+        assert_eq!(db.min_int_among(&["food", "name"]), None);
+        assert_eq!(db.max_int_among(&[]), None);
+    }
+}
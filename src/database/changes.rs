@@ -0,0 +1,221 @@
+//! # changes.rs
+//!
+//! # changes.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Per-key change tracking for `FactDatabase`. Every `set`, `remove`, and `clear` records the
+//! affected keys, so rules and UI code can ask which facts changed this frame without diffing
+//! the whole database. Tracking accumulates until `clear_changes`, which `FREPlugin` calls once
+//! per frame in `Last`.
+//!
+//! Independently of that per-frame set, every write bumps the database's generation and stamps
+//! the key with it. `iter_changed_since` yields the keys written after a generation the caller
+//! saved earlier, so secondary indexes can be updated incrementally instead of rebuilt. Stamps
+//! are kept in generation order, so that only reads the writes after the generation. A removed
+//! key keeps its stamp until `forget_removals_through` drops it.
+//!
+//! `FactDatabase` 的按键变更跟踪。每次 `set`、`remove` 和 `clear` 都会记录受影响的键，
+//! 因此规则和 UI 代码无需对比整个数据库即可知道本帧哪些事实发生了变化。
+//! 跟踪会一直累积到 `clear_changes` 被调用，`FREPlugin` 每帧在 `Last` 中调用一次。
+//!
+//! 与按帧的集合无关，每次写入都会递增数据库的代数并用它标记该键。`iter_changed_since`
+//! 产出调用方先前保存的代数之后被写入的键，因此二级索引可以增量更新，而无需重建。
+//! 标记按代数顺序保存，因此只会读取该代数之后的写入。被移除的键会保留其标记，
+//! 直到 `forget_removals_through` 将其丢弃。
+
+use std::ops::Bound;
+
+use super::{FactDatabase, FactValue};
+
+impl FactDatabase {
+    /// Keys set or removed since changes were last cleared, in no particular order.
+    /// Setting a key to the value it already holds still counts as a change; use
+    /// `set_if_changed` to avoid that.
+    ///
+    /// 自上次清除变更以来被设置或移除的键，无特定顺序。
+    /// 把键设置为它已持有的值仍算作变更；如需避免，请使用 `set_if_changed`。
+    pub fn changed_keys(&self) -> impl Iterator<Item = &str> {
+        self.changed.iter().map(String::as_str)
+    }
+
+    /// Whether `key` was set or removed since changes were last cleared.
+    ///
+    /// `key` 自上次清除变更以来是否被设置或移除。
+    pub fn is_changed(&self, key: &str) -> bool {
+        self.changed.contains(key)
+    }
+
+    /// Forget all recorded changes.
+    ///
+    /// 清除所有已记录的变更。
+    pub fn clear_changes(&mut self) {
+        self.changed.clear();
+    }
+
+    /// Generation of the most recent write. Starts at 0 and increases with every `set`,
+    /// `remove`, and `clear`; `clear_changes` does not reset it.
+    ///
+    /// 最近一次写入的代数。从 0 开始，每次 `set`、`remove` 和 `clear` 都会增加；
+    /// `clear_changes` 不会重置它。
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Keys written after `generation`, each once and oldest write first, with their current
+    /// value (None if removed).
+    ///
+    /// 在 `generation` 之后被写入的键（每个键一次，最早的写入在前）及其当前值（已移除则为 None）。
+    pub fn iter_changed_since(
+        &self,
+        generation: u64,
+    ) -> impl Iterator<Item = (&str, Option<&FactValue>)> {
+        self.log
+            .range((Bound::Excluded(generation), Bound::Unbounded))
+            .map(|(_, key)| (key.as_str(), self.get_by_str(key)))
+    }
+
+    /// Drop the stamps of keys removed at or before `generation`, once every reader of
+    /// `iter_changed_since` has seen those removals. Keys still set keep their stamps.
+    ///
+    /// 丢弃在 `generation` 及之前被移除的键的标记，前提是 `iter_changed_since` 的所有读取方都已
+    /// 看到这些移除。仍被设置的键保留其标记。
+    pub fn forget_removals_through(&mut self, generation: u64) {
+        let kept = match generation.checked_add(1) {
+            Some(next) => self.removals.split_off(&next),
+            None => Default::default(),
+        };
+        for stamp in std::mem::replace(&mut self.removals, kept) {
+            if let Some(key) = self.log.remove(&stamp) {
+                self.stamps.remove(&key);
+            }
+        }
+    }
+
+    /// Record `key` as changed without writing it, for when the value readers see changes
+    /// because of a layer above or below this database.
+    pub(crate) fn mark_changed(&mut self, key: &str) {
+        self.record_change(key);
+    }
+
+    pub(super) fn record_change(&mut self, key: &str) {
+        self.generation += 1;
+        let generation = self.generation;
+        match self.stamps.get_mut(key) {
+            Some(stamp) => {
+                let previous = std::mem::replace(stamp, generation);
+                self.removals.remove(&previous);
+                if let Some(key) = self.log.remove(&previous) {
+                    self.log.insert(generation, key);
+                }
+            }
+            None => {
+                self.stamps.insert(key.to_owned(), generation);
+                self.log.insert(generation, key.to_owned());
+            }
+        }
+        if !self.changed.contains(key) {
+            self.changed.insert(key.to_owned());
+        }
+    }
+
+    pub(super) fn record_removal(&mut self, key: &str) {
+        self.record_change(key);
+        self.removals.insert(self.generation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changed(db: &FactDatabase) -> Vec<&str> {
+        let mut keys: Vec<&str> = db.changed_keys().collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_set_remove_and_overwrite_are_tracked() {
+        let mut db = FactDatabase::new();
+        db.set("hp", 10i64);
+        db.set("gold", 5i64);
+        assert_eq!(changed(&db), ["gold", "hp"]);
+        assert!(db.is_changed("hp"));
+
+        db.clear_changes();
+        assert!(changed(&db).is_empty());
+        assert!(!db.is_changed("hp"));
+
+        // Overwrites and removals are changes; removing a missing key is not
+        db.set("hp", 7i64);
+        db.remove("gold");
+        db.remove("missing");
+        assert_eq!(changed(&db), ["gold", "hp"]);
+
+        // set_if_changed only records real changes
+        db.clear_changes();
+        assert!(!db.set_if_changed("hp", 7i64));
+        assert!(!db.is_changed("hp"));
+
+        db.clear();
+        assert_eq!(changed(&db), ["hp"]);
+    }
+
+    #[test]
+    fn test_iter_changed_since_a_generation() {
+        let mut db = FactDatabase::new();
+        db.set("hp", 10i64);
+        db.set("gold", 5i64);
+        db.set("mp", 3i64);
+        let before = db.generation();
+
+        db.set("xp", 1i64);
+        db.set("hp", 9i64);
+        let since: Vec<_> = db.iter_changed_since(before).collect();
+        assert_eq!(
+            since,
+            [
+                ("xp", Some(&FactValue::Int(1))),
+                ("hp", Some(&FactValue::Int(9)))
+            ]
+        );
+
+        // Removals are reported without a value, and the per-frame reset does not affect stamps
+        let before = db.generation();
+        db.clear_changes();
+        db.remove("gold");
+        let since: Vec<_> = db.iter_changed_since(before).collect();
+        assert_eq!(since, [("gold", None)]);
+        assert_eq!(db.iter_changed_since(db.generation()).count(), 0);
+    }
+
+    #[test]
+    fn test_rewrites_and_forgotten_removals_do_not_grow_the_log() {
+        let mut db = FactDatabase::new();
+        for turn in 0..100i64 {
+            db.set("hp", turn);
+            db.set("buff", turn);
+            db.remove("buff");
+        }
+        assert_eq!(db.log.len(), 2);
+        assert_eq!(db.iter_changed_since(0).count(), 2);
+
+        // A removal written again is not forgotten
+        let removed = db.generation();
+        db.set("buff", 1i64);
+        db.remove("mp");
+        db.forget_removals_through(removed);
+        assert_eq!(db.log.len(), 2);
+
+        db.remove("buff");
+        db.forget_removals_through(db.generation());
+        assert_eq!(
+            db.iter_changed_since(0).collect::<Vec<_>>(),
+            [("hp", Some(&FactValue::Int(99)))]
+        );
+        assert_eq!(db.stamps.len(), 1);
+    }
+}
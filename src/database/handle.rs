@@ -0,0 +1,177 @@
+//! # handle.rs
+//!
+//! # handle.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Slot storage behind `FactDatabase` and the `FactHandle`s that index it. Each key maps to a
+//! slot; `remove` and `clear` return slots to a free list for reuse, and bump the slot's stamp so
+//! handles taken for the previous occupant stop matching.
+//!
+//! `FactDatabase` 背后的槽位存储，以及索引这些槽位的 `FactHandle`。每个键映射到一个槽位；
+//! `remove` 和 `clear` 会把槽位归还到空闲列表以便复用，并递增槽位的标记，
+//! 使为先前占用者获取的句柄不再匹配。
+
+#[cfg(feature = "debug")]
+use bevy::reflect::Reflect;
+
+use super::{FactDatabase, FactValue};
+
+/// Stable reference to a fact slot in a `FactDatabase`, for repeated access without rehashing.
+///
+/// A handle stays valid across any number of `set` calls on its key. It becomes invalid once
+/// the key is removed or the database is cleared; reads through an invalid handle return None,
+/// even if the key is set again later or its slot is reused for another key (obtain a fresh
+/// handle in that case).
+///
+/// 指向 `FactDatabase` 中事实槽位的稳定引用，用于无需重新哈希的重复访问。
+///
+/// 句柄在其键上任意次数的 `set` 调用之后仍然有效。一旦键被移除或数据库被清空，
+/// 句柄即失效；通过失效句柄读取会返回 None，即使之后再次设置该键或其槽位被其他键复用
+/// （此时请重新获取句柄）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FactHandle {
+    slot: usize,
+    stamp: u32,
+}
+
+/// Storage for one fact. `stamp` counts how often the slot was freed, so a handle taken for an
+/// earlier occupant of the slot no longer matches.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "debug", derive(Reflect))]
+pub(super) struct Slot {
+    pub(super) value: Option<FactValue>,
+    stamp: u32,
+}
+
+impl FactDatabase {
+    /// Get the handle for a key, reserving a slot if the key is not set yet.
+    /// The handle reads None until a value is set for the key.
+    ///
+    /// 获取键的句柄，如果键尚未设置则预留一个槽位。
+    /// 在为该键设置值之前，通过句柄读取会返回 None。
+    pub fn handle(&mut self, key: &str) -> FactHandle {
+        let slot = match self.keys.get(key) {
+            Some(&slot) => slot,
+            None => self.slot_for(key.to_string()),
+        };
+        FactHandle {
+            slot,
+            stamp: self.slots[slot].stamp,
+        }
+    }
+
+    /// Get a fact value through a handle, without hashing the key.
+    /// Returns None if the fact is unset or the handle has been invalidated.
+    ///
+    /// 通过句柄获取事实值，无需对键进行哈希。
+    /// 如果事实未设置或句柄已失效则返回 None。
+    pub fn get_by_handle(&self, handle: FactHandle) -> Option<&FactValue> {
+        let slot = self.slots.get(handle.slot)?;
+        if slot.stamp != handle.stamp {
+            return None;
+        }
+        slot.value.as_ref()
+    }
+
+    pub(super) fn slot_for(&mut self, key: String) -> usize {
+        if let Some(&slot) = self.keys.get(&key) {
+            return slot;
+        }
+        let slot = self.free.pop().unwrap_or_else(|| {
+            self.slots.push(Slot::default());
+            self.slots.len() - 1
+        });
+        self.keys.insert(key, slot);
+        slot
+    }
+
+    /// Return an emptied slot to the free list, invalidating handles to it.
+    pub(super) fn free_slot(&mut self, slot: usize) {
+        let freed = &mut self.slots[slot];
+        freed.value = None;
+        freed.stamp = freed.stamp.wrapping_add(1);
+        self.free.push(slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fact_handle_reads_current_value() {
+        let mut db = FactDatabase::new();
+        db.set("hp", 10i64);
+        let hp = db.handle("hp");
+        assert_eq!(db.get_by_handle(hp), Some(&FactValue::Int(10)));
+
+        for value in [7i64, 3, 12] {
+            db.set("hp", value);
+            assert_eq!(db.get_by_handle(hp), Some(&FactValue::Int(value)));
+        }
+
+        // Other keys do not disturb existing handles
+        db.set("mp", 5i64);
+        assert_eq!(db.get_by_handle(hp), Some(&FactValue::Int(12)));
+        assert_eq!(db.handle("hp"), hp);
+    }
+
+    #[test]
+    fn test_fact_handle_reserved_before_set() {
+        let mut db = FactDatabase::new();
+        let gold = db.handle("gold");
+        assert_eq!(db.get_by_handle(gold), None);
+        assert!(!db.contains("gold"));
+        assert_eq!(db.len(), 0);
+
+        db.set("gold", 50i64);
+        assert_eq!(db.get_by_handle(gold), Some(&FactValue::Int(50)));
+        assert_eq!(db.len(), 1);
+    }
+
+    #[test]
+    fn test_fact_handle_invalidation() {
+        let mut db = FactDatabase::new();
+        db.set("key", 1i64);
+        let handle = db.handle("key");
+
+        db.remove("key");
+        assert_eq!(db.get_by_handle(handle), None);
+
+        // Re-setting the key does not revive the old handle
+        db.set("key", 2i64);
+        assert_eq!(db.get_by_handle(handle), None);
+        let fresh = db.handle("key");
+        assert_eq!(db.get_by_handle(fresh), Some(&FactValue::Int(2)));
+
+        db.clear();
+        db.set("key", 3i64);
+        assert_eq!(db.get_by_handle(fresh), None);
+        assert_eq!(db.len(), 1);
+    }
+
+    #[test]
+    fn test_freed_slots_are_reused() {
+        let mut db = FactDatabase::new();
+        db.set("hp", 10i64);
+        db.set("mp", 5i64);
+        let hp = db.handle("hp");
+        for turn in 0..100i64 {
+            db.set("buff", turn);
+            db.remove("buff");
+            db.set(format!("effect_{}", turn), true);
+            db.remove(&format!("effect_{}", turn));
+        }
+        db.clear();
+        db.set("hp", 1i64);
+        db.set("mp", 2i64);
+        assert_eq!(db.slots.len(), 3);
+        assert_eq!(db.len(), 2);
+
+        // The handle's slot now holds a later occupant
+        assert_eq!(db.get_by_handle(hp), None);
+    }
+}
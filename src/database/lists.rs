@@ -0,0 +1,204 @@
+//! # lists.rs
+//!
+//! # lists.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Element-wise helpers for list facts, so an inventory stored as a `StringList` or a stat
+//! array stored as an `IntList` can change one element at a time. An element only goes into a
+//! list of its own type (a `String` into a `StringList`, an `Int` into an `IntList`, and so on);
+//! pushing onto a fact of another type leaves the fact unchanged and logs a warning instead of
+//! replacing it.
+//!
+//! 列表事实的逐元素辅助方法，使以 `StringList` 存储的物品栏或以 `IntList` 存储的属性数组
+//! 可以逐个元素修改。元素只能进入同类型的列表（`String` 进入 `StringList`，`Int` 进入
+//! `IntList`，以此类推）；向其他类型的事实追加时，事实保持不变并记录警告，而不是被替换。
+
+use bevy::prelude::*;
+
+use super::{FactDatabase, FactValue};
+
+impl FactValue {
+    /// Whether this is a list holding `item`. Always false for non-lists.
+    ///
+    /// 是否为包含 `item` 的列表。非列表始终返回 false。
+    pub fn list_contains(&self, item: &FactValue) -> bool {
+        match (self, item) {
+            (FactValue::StringList(list), FactValue::String(item)) => list.contains(item),
+            (FactValue::IntList(list), FactValue::Int(item)) => list.contains(item),
+            (FactValue::FloatList(list), FactValue::Float(item)) => list.contains(item),
+            (FactValue::BoolList(list), FactValue::Bool(item)) => list.contains(item),
+            _ => false,
+        }
+    }
+
+    /// Empty list that elements like `item` go into.
+    fn empty_list_for(item: &FactValue) -> Option<FactValue> {
+        match item {
+            FactValue::String(_) => Some(FactValue::StringList(Vec::new())),
+            FactValue::Int(_) => Some(FactValue::IntList(Vec::new())),
+            FactValue::Float(_) => Some(FactValue::FloatList(Vec::new())),
+            FactValue::Bool(_) => Some(FactValue::BoolList(Vec::new())),
+            _ => None,
+        }
+    }
+
+    /// Append `item`, handing it back if this is not a list of its type.
+    fn push_item(&mut self, item: FactValue) -> Result<(), FactValue> {
+        match (self, item) {
+            (FactValue::StringList(list), FactValue::String(item)) => list.push(item),
+            (FactValue::IntList(list), FactValue::Int(item)) => list.push(item),
+            (FactValue::FloatList(list), FactValue::Float(item)) => list.push(item),
+            (FactValue::BoolList(list), FactValue::Bool(item)) => list.push(item),
+            (_, item) => return Err(item),
+        }
+        Ok(())
+    }
+
+    /// Remove the first element equal to `item`, returning whether one was found.
+    fn remove_item(&mut self, item: &FactValue) -> bool {
+        fn remove_first<T: PartialEq>(list: &mut Vec<T>, item: &T) -> bool {
+            let Some(index) = list.iter().position(|element| element == item) else {
+                return false;
+            };
+            list.remove(index);
+            true
+        }
+        match (self, item) {
+            (FactValue::StringList(list), FactValue::String(item)) => remove_first(list, item),
+            (FactValue::IntList(list), FactValue::Int(item)) => remove_first(list, item),
+            (FactValue::FloatList(list), FactValue::Float(item)) => remove_first(list, item),
+            (FactValue::BoolList(list), FactValue::Bool(item)) => remove_first(list, item),
+            _ => false,
+        }
+    }
+}
+
+/// The list `key` becomes after pushing `item` onto its current value, or None with a warning
+/// if the current value is not a list of the item's type.
+pub(crate) fn list_with_item(
+    key: &str,
+    current: Option<&FactValue>,
+    item: FactValue,
+) -> Option<FactValue> {
+    let Some(mut list) = current
+        .cloned()
+        .or_else(|| FactValue::empty_list_for(&item))
+    else {
+        warn!(
+            "FRE: Cannot push {:?} to '{}': not a list element",
+            item, key
+        );
+        return None;
+    };
+    match list.push_item(item) {
+        Ok(()) => Some(list),
+        Err(item) => {
+            warn!(
+                "FRE: Cannot push {:?} to '{}': {:?} is not a list of that type",
+                item, key, list
+            );
+            None
+        }
+    }
+}
+
+/// The list after removing the first element equal to `item`, or None if there is none.
+pub(crate) fn list_without_item(
+    current: Option<&FactValue>,
+    item: &FactValue,
+) -> Option<FactValue> {
+    let mut list = current?.clone();
+    list.remove_item(item).then_some(list)
+}
+
+impl FactDatabase {
+    /// Push `value` onto the list `key`, creating the list if the fact is absent.
+    /// A fact that is not a list of the value's type is left unchanged with a warning.
+    ///
+    /// 把 `value` 追加到列表 `key`；如果事实不存在则创建列表。
+    /// 不是该值类型列表的事实保持不变并记录警告。
+    pub fn push_to_list(&mut self, key: &str, value: impl Into<FactValue>) {
+        if let Some(list) = list_with_item(key, self.get_by_str(key), value.into()) {
+            self.set(key, list);
+        }
+    }
+
+    /// Remove the first element equal to `value` from the list `key`.
+    /// Returns false if the fact is missing, not a list of the value's type, or lacks the value.
+    ///
+    /// 从列表 `key` 中移除第一个等于 `value` 的元素。
+    /// 如果事实缺失、不是该值类型的列表或不包含该值，则返回 false。
+    pub fn remove_from_list(&mut self, key: &str, value: impl Into<FactValue>) -> bool {
+        let Some(list) = list_without_item(self.get_by_str(key), &value.into()) else {
+            return false;
+        };
+        self.set(key, list);
+        true
+    }
+
+    /// Whether the list `key` holds `value`.
+    ///
+    /// 列表 `key` 是否包含 `value`。
+    pub fn list_contains(&self, key: &str, value: impl Into<FactValue>) -> bool {
+        self.get_by_str(key)
+            .is_some_and(|list| list.list_contains(&value.into()))
+    }
+
+    /// Number of elements of the list `key`, or None if the fact is missing or not a list.
+    ///
+    /// 列表 `key` 的元素数量；如果事实缺失或不是列表则返回 None。
+    pub fn list_len(&self, key: &str) -> Option<usize> {
+        self.get_by_str(key).and_then(FactValue::list_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_and_int_list_helpers() {
+        let mut db = FactDatabase::new();
+        db.push_to_list("inventory", "potion");
+        db.push_to_list("inventory", "sword");
+        db.push_to_list("rolls", 6);
+        db.push_to_list("rolls", 2);
+        assert_eq!(
+            db.get_by_str("inventory"),
+            Some(&vec!["potion", "sword"].into())
+        );
+        assert_eq!(db.get_by_str("rolls"), Some(&vec![6i64, 2].into()));
+        assert_eq!(db.list_len("inventory"), Some(2));
+
+        assert!(db.list_contains("inventory", "sword"));
+        assert!(db.list_contains("rolls", 2));
+        assert!(!db.list_contains("rolls", "2"));
+        assert!(!db.list_contains("missing", 2));
+
+        assert!(db.remove_from_list("inventory", "potion"));
+        assert!(!db.remove_from_list("inventory", "potion"));
+        assert!(db.remove_from_list("rolls", 6));
+        assert!(!db.remove_from_list("missing", 6));
+        assert_eq!(db.get_by_str("inventory"), Some(&vec!["sword"].into()));
+        assert_eq!(db.get_by_str("rolls"), Some(&vec![2i64].into()));
+    }
+
+    #[test]
+    fn test_push_of_mismatched_type_leaves_fact_unchanged() {
+        let mut db = FactDatabase::new();
+        db.set("gold", 10);
+        db.push_to_list("gold", 5);
+        assert_eq!(db.get_int("gold"), Some(10));
+        assert_eq!(db.list_len("gold"), None);
+
+        db.push_to_list("inventory", "potion");
+        db.push_to_list("inventory", 3);
+        assert_eq!(db.get_by_str("inventory"), Some(&vec!["potion"].into()));
+
+        db.push_to_list("position", FactValue::Vec2([0.0, 1.0]));
+        assert!(!db.contains("position"));
+    }
+}
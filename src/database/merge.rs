@@ -0,0 +1,201 @@
+//! # merge.rs
+//!
+//! # merge.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Merging one fact database into another, e.g. the initial facts of several assets.
+//! A key present in both databases with different values is a conflict; `MergePolicy` decides
+//! whether the incoming value wins, the existing one is kept, or the merge is refused. Every
+//! conflict is reported with its key and both values, so callers can log what was resolved.
+//!
+//! 把一个事实数据库合并到另一个中，例如多个资源的初始事实。两个数据库中都存在且值不同的键
+//! 即为冲突；`MergePolicy` 决定由传入值覆盖、保留现有值，还是拒绝合并。每个冲突都会连同其键
+//! 和两个值一起报告，以便调用方记录解决了哪些冲突。
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::{FactDatabase, FactValue};
+
+/// How a merge resolves keys present in both databases with different values.
+///
+/// 合并时如何处理两个数据库中都存在且值不同的键。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "debug", derive(Reflect))]
+pub enum MergePolicy {
+    /// The incoming value replaces the existing one.
+    ///
+    /// 传入值替换现有值。
+    #[default]
+    Overwrite,
+
+    /// The existing value is kept.
+    ///
+    /// 保留现有值。
+    KeepExisting,
+
+    /// Nothing is merged if any key conflicts.
+    ///
+    /// 只要有任何键冲突，就不合并任何内容。
+    Error,
+}
+
+/// A key present in both databases with different values.
+///
+/// 在两个数据库中都存在且值不同的键。
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    pub key: String,
+    pub existing: FactValue,
+    pub incoming: FactValue,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' (existing {:?}, incoming {:?})",
+            self.key, self.existing, self.incoming
+        )
+    }
+}
+
+/// Error returned by a `MergePolicy::Error` merge that found conflicts.
+///
+/// `MergePolicy::Error` 合并发现冲突时返回的错误。
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeError {
+    /// Conflicts sorted by key.
+    ///
+    /// 按键排序的冲突。
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} conflicting facts:", self.conflicts.len())?;
+        for conflict in &self.conflicts {
+            write!(f, " {}", conflict)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Writes a merge performs, and the conflicts it resolved.
+pub(crate) struct MergePlan {
+    pub(crate) writes: Vec<(String, FactValue)>,
+    pub(crate) conflicts: Vec<MergeConflict>,
+}
+
+/// Work out how merging `other` into `target` under `policy` changes `target`.
+pub(crate) fn plan_merge(
+    target: &FactDatabase,
+    other: &FactDatabase,
+    policy: MergePolicy,
+) -> Result<MergePlan, MergeError> {
+    let mut plan = MergePlan {
+        writes: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    for (key, incoming) in other.iter() {
+        match target.get_by_str(key) {
+            None => plan.writes.push((key.clone(), incoming.clone())),
+            Some(existing) if existing == incoming => {}
+            Some(existing) => {
+                plan.conflicts.push(MergeConflict {
+                    key: key.clone(),
+                    existing: existing.clone(),
+                    incoming: incoming.clone(),
+                });
+                if policy == MergePolicy::Overwrite {
+                    plan.writes.push((key.clone(), incoming.clone()));
+                }
+            }
+        }
+    }
+    plan.conflicts.sort_by(|a, b| a.key.cmp(&b.key));
+    if policy == MergePolicy::Error && !plan.conflicts.is_empty() {
+        return Err(MergeError {
+            conflicts: plan.conflicts,
+        });
+    }
+    Ok(plan)
+}
+
+impl FactDatabase {
+    /// Copy every fact of `other` into this database, resolving conflicting keys by `policy`.
+    /// Returns the conflicts that were resolved, sorted by key; under `MergePolicy::Error`,
+    /// any conflict leaves this database unchanged and is returned as an error instead.
+    ///
+    /// 把 `other` 的所有事实复制到此数据库，按 `policy` 解决冲突的键。返回已解决的冲突
+    /// （按键排序）；在 `MergePolicy::Error` 下，任何冲突都会使此数据库保持不变并作为错误返回。
+    pub fn merge(
+        &mut self,
+        other: &FactDatabase,
+        policy: MergePolicy,
+    ) -> Result<Vec<MergeConflict>, MergeError> {
+        let plan = plan_merge(self, other, policy)?;
+        for (key, value) in plan.writes {
+            self.set(key, value);
+        }
+        Ok(plan.conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn databases() -> (FactDatabase, FactDatabase) {
+        let mut existing = FactDatabase::new();
+        existing.set("hp", 10i64);
+        existing.set("name", "hero");
+        let mut incoming = FactDatabase::new();
+        incoming.set("hp", 20i64);
+        incoming.set("name", "hero");
+        incoming.set("gold", 5i64);
+        (existing, incoming)
+    }
+
+    #[test]
+    fn test_merge_under_each_policy() {
+        let conflict = MergeConflict {
+            key: "hp".to_string(),
+            existing: FactValue::Int(10),
+            incoming: FactValue::Int(20),
+        };
+
+        let (mut db, incoming) = databases();
+        assert_eq!(
+            db.merge(&incoming, MergePolicy::Overwrite),
+            Ok(vec![conflict.clone()])
+        );
+        assert_eq!(db.get_int("hp"), Some(20));
+        assert_eq!(db.get_int("gold"), Some(5));
+
+        let (mut db, incoming) = databases();
+        assert_eq!(
+            db.merge(&incoming, MergePolicy::KeepExisting),
+            Ok(vec![conflict.clone()])
+        );
+        assert_eq!(db.get_int("hp"), Some(10));
+        assert_eq!(db.get_int("gold"), Some(5));
+
+        let (mut db, incoming) = databases();
+        let error = db.merge(&incoming, MergePolicy::Error).unwrap_err();
+        assert_eq!(error.conflicts, vec![conflict]);
+        assert_eq!(
+            error.to_string(),
+            "1 conflicting facts: 'hp' (existing Int(10), incoming Int(20))"
+        );
+        // A refused merge writes nothing
+        assert_eq!(db.get_int("hp"), Some(10));
+        assert!(!db.contains("gold"));
+    }
+}
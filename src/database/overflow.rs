@@ -0,0 +1,169 @@
+//! # overflow.rs
+//!
+//! # overflow.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Overflow-safe integer arithmetic for fact writes. `IntOverflowPolicy` decides what an
+//! increment does when the result would leave the `i64` range, and `float_to_fact` stores a
+//! whole float result as an integer, clamped to the `i64` range, so no write ever panics in debug
+//! builds or silently wraps in release builds.
+//!
+//! 事实写入的溢出安全整数运算。`IntOverflowPolicy` 决定当结果超出 `i64` 范围时增量操作的行为，
+//! `float_to_fact` 把为整数的浮点结果存储为整数并限制在 `i64` 范围内，
+//! 因此任何写入都不会在调试构建中 panic，也不会在发布构建中静默回绕。
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::FactValue;
+
+/// What an integer increment does when the result overflows `i64`.
+///
+/// 整数增量结果溢出 `i64` 时的处理方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "debug", derive(Reflect))]
+pub enum IntOverflowPolicy {
+    /// Clamp to `i64::MIN` or `i64::MAX`.
+    ///
+    /// 限制在 `i64::MIN` 或 `i64::MAX`。
+    #[default]
+    Saturating,
+
+    /// Wrap around in two's complement.
+    ///
+    /// 按二进制补码回绕。
+    Wrapping,
+
+    /// Leave the fact unchanged and log a warning.
+    ///
+    /// 保持事实不变并记录警告。
+    Checked,
+}
+
+impl IntOverflowPolicy {
+    /// Add two integers under this policy. Returns None only for `Checked` on overflow.
+    ///
+    /// 按此策略相加两个整数。仅当策略为 `Checked` 且溢出时返回 None。
+    pub fn add(self, a: i64, b: i64) -> Option<i64> {
+        match self {
+            IntOverflowPolicy::Saturating => Some(a.saturating_add(b)),
+            IntOverflowPolicy::Wrapping => Some(a.wrapping_add(b)),
+            IntOverflowPolicy::Checked => a.checked_add(b),
+        }
+    }
+}
+
+/// Add `amount` to the value of `key`, warning when a checked addition overflows.
+pub(crate) fn add_or_warn(
+    policy: IntOverflowPolicy,
+    key: &str,
+    current: i64,
+    amount: i64,
+) -> Option<i64> {
+    let sum = policy.add(current, amount);
+    if sum.is_none() {
+        warn!(
+            "FRE: Adding {} to '{}' ({}) overflows; value left unchanged",
+            amount, key, current
+        );
+    }
+    sum
+}
+
+/// Convert a float to an integer if it is whole and inside the `i64` range.
+pub(crate) fn whole_to_int(value: f64) -> Option<i64> {
+    // i64::MIN is exactly representable; i64::MAX rounds up to 2^63, which is out of range
+    (value.fract() == 0.0 && value >= i64::MIN as f64 && value < i64::MAX as f64)
+        .then_some(value as i64)
+}
+
+/// An integer when the result is whole, clamped to the `i64` range, otherwise a float.
+/// Infinities clamp too; NaN stays a float.
+pub(crate) fn float_to_fact(value: f64) -> FactValue {
+    if value.is_infinite() || value.fract() == 0.0 {
+        // `as` saturates at i64::MIN and i64::MAX
+        FactValue::Int(value as i64)
+    } else {
+        FactValue::Float(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::FactDatabase;
+    use crate::expr::evaluate_expr_to_fact;
+    use crate::layered::LayeredFactDatabase;
+
+    #[test]
+    fn test_increment_at_max() {
+        let mut db = FactDatabase::new();
+        db.set("score", i64::MAX);
+        db.increment("score", 1);
+        assert_eq!(db.get_int("score"), Some(i64::MAX));
+
+        db.set_int_overflow_policy(IntOverflowPolicy::Wrapping);
+        db.increment("score", 1);
+        assert_eq!(db.get_int("score"), Some(i64::MIN));
+
+        db.set_int_overflow_policy(IntOverflowPolicy::Checked);
+        db.increment("score", -1);
+        assert_eq!(db.get_int("score"), Some(i64::MIN));
+
+        let mut layered = LayeredFactDatabase::new();
+        layered.set_global("gold", i64::MAX - 1);
+        layered.increment("gold", 5);
+        assert_eq!(layered.get_int("gold"), Some(i64::MAX));
+        layered.add("gold", 3.0);
+        assert_eq!(layered.get_int("gold"), Some(i64::MAX));
+
+        // The layered database follows the policy of the layer it writes to
+        layered.set_int_overflow_policy(IntOverflowPolicy::Checked);
+        layered.push_layer("battle");
+        layered.increment("gold", 1);
+        assert_eq!(layered.get_int("gold"), Some(i64::MAX));
+        layered
+            .global_mut()
+            .set_int_overflow_policy(IntOverflowPolicy::Wrapping);
+        layered.increment_global("gold", 1);
+        assert_eq!(layered.global().get_int("gold"), Some(i64::MIN));
+
+        // Whole results beyond the range clamp instead of becoming floats
+        layered.set("gold", i64::MAX / 2);
+        layered.mul("gold", 4.0);
+        assert_eq!(layered.get_int("gold"), Some(i64::MAX));
+        layered.add("gold", -1e30);
+        assert_eq!(layered.get_int("gold"), Some(i64::MIN));
+    }
+
+    #[test]
+    fn test_expression_beyond_int_range() {
+        let db = FactDatabase::new();
+        assert_eq!(
+            evaluate_expr_to_fact("9223372036854775807 * 4", &db),
+            Some(FactValue::Int(i64::MAX))
+        );
+        assert_eq!(
+            evaluate_expr_to_fact("0 - 9223372036854775807 * 4", &db),
+            Some(FactValue::Int(i64::MIN))
+        );
+        assert_eq!(
+            evaluate_expr_to_fact("9223372036854775807 * 4 + 0.5", &db),
+            Some(FactValue::Int(i64::MAX))
+        );
+        assert!(matches!(float_to_fact(f64::NAN), FactValue::Float(value) if value.is_nan()));
+        assert_eq!(float_to_fact(f64::NEG_INFINITY), FactValue::Int(i64::MIN));
+        assert_eq!(float_to_fact(2.5), FactValue::Float(2.5));
+        assert_eq!(
+            evaluate_expr_to_fact("0 - 9223372036854775807 - 1", &db),
+            Some(FactValue::Int(i64::MIN))
+        );
+        assert_eq!(whole_to_int(i64::MAX as f64), None);
+        assert_eq!(whole_to_int(f64::NAN), None);
+        assert_eq!(whole_to_int(f64::INFINITY), None);
+        assert_eq!(whole_to_int(-3.0), Some(-3));
+    }
+}
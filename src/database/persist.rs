@@ -0,0 +1,116 @@
+//! # persist.rs
+//!
+//! # persist.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Serde support for `FactDatabase`, for writing fact layers into save games. A database
+//! serializes as a map from key to tagged `FactValue`, sorted by key so the output is stable
+//! between runs. Only the facts are saved: policies and change tracking belong to the running
+//! game, and a loaded database starts with the default policies and no recorded changes.
+//!
+//! `FactDatabase` 的 serde 支持，用于把事实层写入存档。数据库序列化为从键到带标签
+//! `FactValue` 的映射，并按键排序，使输出在多次运行之间保持稳定。只保存事实：
+//! 策略和变更跟踪属于运行中的游戏，加载的数据库使用默认策略且没有已记录的变更。
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{FactDatabase, FactValue};
+
+impl Serialize for FactDatabase {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sorted: BTreeMap<&String, &FactValue> = self.iter().collect();
+        sorted.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FactDatabase {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let facts = BTreeMap::<String, FactValue>::deserialize(deserializer)?;
+        let mut db = FactDatabase::new();
+        for (key, value) in facts {
+            db.set(key, value);
+        }
+        db.clear_changes();
+        Ok(db)
+    }
+}
+
+impl FactDatabase {
+    /// Render the facts as pretty-printed RON.
+    ///
+    /// 将事实渲染为格式化的 RON。
+    pub fn to_ron_string(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    /// Load facts written by `to_ron_string`.
+    ///
+    /// 加载由 `to_ron_string` 写出的事实。
+    pub fn from_ron_str(ron_str: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(ron_str)
+    }
+}
+
+/// Serializes an `Entity` as its `u64` bits. The bits only identify the entity within the
+/// world that spawned it, so saved entity references must be remapped after loading.
+pub(super) mod entity_bits {
+    use bevy::ecs::entity::Entity;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(entity: &Entity, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(entity.to_bits())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Entity, D::Error> {
+        let bits = u64::deserialize(deserializer)?;
+        Entity::try_from_bits(bits).ok_or_else(|| D::Error::custom("invalid entity bits"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::FactKey;
+    use bevy::ecs::entity::Entity;
+
+    #[test]
+    fn test_every_variant_round_trips() {
+        let values = [
+            FactValue::Int(-3),
+            FactValue::Float(0.25),
+            FactValue::Bool(true),
+            FactValue::from("hero"),
+            FactValue::StringList(vec!["sword".into(), "shield".into()]),
+            FactValue::IntList(vec![1, 2, 3]),
+            FactValue::FloatList(vec![0.5, -1.5]),
+            FactValue::BoolList(vec![true, false]),
+            FactValue::Vec2([1.0, 2.0]),
+            FactValue::Vec3([1.0, 2.0, 3.0]),
+            FactValue::EntityRef(Entity::from_raw_u32(42).unwrap()),
+            FactValue::Null,
+        ];
+        let mut db = FactDatabase::new();
+        for (i, value) in values.iter().enumerate() {
+            db.set(format!("fact_{i:02}"), value.clone());
+        }
+
+        let text = db.to_ron_string().unwrap();
+        assert!(text.find("fact_00").unwrap() < text.find("fact_11").unwrap());
+        let loaded = FactDatabase::from_ron_str(&text).unwrap();
+        assert_eq!(loaded.len(), values.len());
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(loaded.get_by_str(&format!("fact_{i:02}")), Some(value));
+        }
+        assert_eq!(loaded.changed_keys().count(), 0);
+        assert_eq!(text, loaded.to_ron_string().unwrap());
+
+        let key: FactKey = ron::from_str(&ron::to_string(&FactKey::new("hp")).unwrap()).unwrap();
+        assert_eq!(key.as_str(), "hp");
+    }
+}
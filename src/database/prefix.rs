@@ -0,0 +1,50 @@
+//! # prefix.rs
+//!
+//! # prefix.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Namespace queries for `FactDatabase`. Keys conventionally share a `namespace:` prefix
+//! (`battle:turn`, `battle:phase`), so these helpers iterate, test, and remove everything under
+//! one prefix. Matching compares the stored key strings directly, without interning a
+//! `FactKey` per comparison.
+//!
+//! `FactDatabase` 的命名空间查询。键通常共享一个 `namespace:` 前缀（`battle:turn`、
+//! `battle:phase`），因此这些辅助方法可以迭代、检测和移除某个前缀下的所有事实。
+//! 匹配直接比较存储的键字符串，不会为每次比较驻留一个 `FactKey`。
+
+use super::{FactDatabase, FactValue};
+
+impl FactDatabase {
+    /// Iterate over the facts whose key starts with `prefix`, in no particular order.
+    ///
+    /// 迭代键以 `prefix` 开头的事实，无特定顺序。
+    pub fn iter_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a String, &'a FactValue)> {
+        self.iter().filter(move |(key, _)| key.starts_with(prefix))
+    }
+
+    /// Whether any fact's key starts with `prefix`.
+    ///
+    /// 是否有事实的键以 `prefix` 开头。
+    pub fn contains_prefix(&self, prefix: &str) -> bool {
+        self.iter_prefix(prefix).next().is_some()
+    }
+
+    /// Remove every fact whose key starts with `prefix`, returning how many were removed.
+    ///
+    /// 移除键以 `prefix` 开头的所有事实，并返回移除的数量。
+    pub fn remove_prefix(&mut self, prefix: &str) -> usize {
+        let keys: Vec<String> = self
+            .keys
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        keys.iter().filter(|key| self.remove(key).is_some()).count()
+    }
+}
@@ -0,0 +1,85 @@
+//! # retain.rs
+//!
+//! # retain.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Bulk removal for `FactDatabase`. `retain` keeps the facts a predicate accepts, like
+//! `HashMap::retain`, and `remove_many` drops a known list of keys. Both go through `remove`,
+//! so every dropped fact is recorded as a change and its handles are invalidated, and both
+//! return how many facts were actually removed.
+//!
+//! `FactDatabase` 的批量移除。`retain` 保留谓词接受的事实（与 `HashMap::retain` 相同），
+//! `remove_many` 移除一组已知的键。两者都通过 `remove` 进行，因此每个被移除的事实都会记录为
+//! 变更，其句柄也会失效；两者都返回实际移除的事实数量。
+
+use super::{FactDatabase, FactValue};
+
+impl FactDatabase {
+    /// Keep only the facts for which `keep` returns true, returning how many were removed.
+    ///
+    /// 仅保留 `keep` 返回 true 的事实，并返回移除的数量。
+    pub fn retain(&mut self, mut keep: impl FnMut(&str, &FactValue) -> bool) -> usize {
+        let doomed: Vec<String> = self
+            .iter()
+            .filter(|(key, value)| !keep(key, value))
+            .map(|(key, _)| key.clone())
+            .collect();
+        self.remove_many(doomed.iter().map(String::as_str))
+    }
+
+    /// Remove every listed key, returning how many facts were removed.
+    /// Keys that are not set, or listed twice, are not counted again.
+    ///
+    /// 移除列出的每个键，并返回移除的事实数量。未设置或重复列出的键不会重复计数。
+    pub fn remove_many<'a>(&mut self, keys: impl IntoIterator<Item = &'a str>) -> usize {
+        keys.into_iter()
+            .filter(|key| self.remove(key).is_some())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retain_by_prefix_and_type() {
+        let mut db = FactDatabase::new();
+        db.set("room:door_open", true);
+        db.set("room:visits", 3i64);
+        db.set("room:name", "cellar");
+        db.set("player:hp", 10i64);
+        let hp = db.handle("room:visits");
+        db.clear_changes();
+
+        // Leaving the room: drop its facts except the string ones
+        let removed = db.retain(|key, value| {
+            !key.starts_with("room:") || matches!(value, FactValue::String(_))
+        });
+        assert_eq!(removed, 2);
+        assert_eq!(db.len(), 2);
+        assert_eq!(db.get_string("room:name"), Some("cellar"));
+        assert_eq!(db.get_int("player:hp"), Some(10));
+        assert!(db.is_changed("room:door_open"));
+        assert!(!db.is_changed("room:name"));
+        assert_eq!(db.get_by_handle(hp), None);
+
+        assert_eq!(db.retain(|_, _| true), 0);
+    }
+
+    #[test]
+    fn test_remove_many_counts_removed_facts() {
+        let mut db = FactDatabase::new();
+        db.set("a", 1i64);
+        db.set("b", 2i64);
+        db.set("c", 3i64);
+
+        assert_eq!(db.remove_many(["a", "b", "a", "missing"]), 2);
+        assert_eq!(db.len(), 1);
+        assert_eq!(db.get_int("c"), Some(3));
+        assert_eq!(db.remove_many(Vec::<&str>::new()), 0);
+    }
+}
@@ -0,0 +1,258 @@
+//! # snapshot.rs
+//!
+//! # snapshot.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Point-in-time copies of a `FactDatabase` and the differences between them, for tracking down
+//! desyncs. `snapshot` copies the facts out of the database; `diff` later compares the database
+//! against that copy and reports every key that was added, removed, or given a different value,
+//! sorted by key so two diffs of the same change read the same. `restore` rolls the database
+//! back to a snapshot, and snapshots serialize so they can be stored in save files.
+//!
+//! `FactDatabase` 的时间点副本以及它们之间的差异，用于排查不同步问题。`snapshot` 从数据库
+//! 复制出事实；之后 `diff` 将数据库与该副本比较，报告每个被添加、移除或改变值的键，
+//! 并按键排序，使同一变化的两次差异读起来相同。`restore` 把数据库回滚到快照，
+//! 快照可以序列化，因此也可以存入存档文件。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{FactDatabase, FactValue};
+
+/// Copy of a fact database's facts at one point in time, ordered by key.
+///
+/// 事实数据库在某一时间点的事实副本，按键排序。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FactSnapshot {
+    facts: BTreeMap<String, FactValue>,
+}
+
+impl FactSnapshot {
+    pub fn get(&self, key: &str) -> Option<&FactValue> {
+        self.facts.get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &FactValue)> {
+        self.facts.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.facts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.facts.is_empty()
+    }
+
+    /// Changes that turn `self` into `newer`, sorted by key.
+    ///
+    /// 把 `self` 变为 `newer` 的变化，按键排序。
+    pub fn diff(&self, newer: &FactSnapshot) -> Vec<FactChange> {
+        let mut changes: Vec<FactChange> = self
+            .facts
+            .iter()
+            .filter_map(|(key, old)| match newer.facts.get(key) {
+                None => Some(FactChange::Removed {
+                    key: key.clone(),
+                    value: old.clone(),
+                }),
+                Some(new) if new != old => Some(FactChange::Modified {
+                    key: key.clone(),
+                    old: old.clone(),
+                    new: new.clone(),
+                }),
+                Some(_) => None,
+            })
+            .collect();
+        changes.extend(
+            newer
+                .facts
+                .iter()
+                .filter(|(key, _)| !self.facts.contains_key(*key))
+                .map(|(key, value)| FactChange::Added {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+        );
+        changes.sort_by(|a, b| a.key().cmp(b.key()));
+        changes
+    }
+}
+
+/// How one fact differs between a snapshot and a later state.
+///
+/// 一个事实在快照与之后状态之间的差异。
+#[derive(Debug, Clone, PartialEq)]
+pub enum FactChange {
+    /// The fact did not exist in the snapshot.
+    ///
+    /// 快照中不存在该事实。
+    Added { key: String, value: FactValue },
+
+    /// The fact existed in the snapshot and is gone now.
+    ///
+    /// 快照中存在该事实，现在已不存在。
+    Removed { key: String, value: FactValue },
+
+    /// The fact holds a different value than in the snapshot.
+    ///
+    /// 事实的值与快照中不同。
+    Modified {
+        key: String,
+        old: FactValue,
+        new: FactValue,
+    },
+}
+
+impl FactChange {
+    pub fn key(&self) -> &str {
+        match self {
+            FactChange::Added { key, .. }
+            | FactChange::Removed { key, .. }
+            | FactChange::Modified { key, .. } => key,
+        }
+    }
+}
+
+impl fmt::Display for FactChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactChange::Added { key, value } => write!(f, "+ '{}' = {:?}", key, value),
+            FactChange::Removed { key, value } => write!(f, "- '{}' (was {:?})", key, value),
+            FactChange::Modified { key, old, new } => {
+                write!(f, "~ '{}': {:?} -> {:?}", key, old, new)
+            }
+        }
+    }
+}
+
+impl FactDatabase {
+    /// Copy the current facts into a snapshot for a later `diff`.
+    ///
+    /// 把当前事实复制到快照中，供之后的 `diff` 使用。
+    pub fn snapshot(&self) -> FactSnapshot {
+        FactSnapshot {
+            facts: self
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// Changes made since `snapshot` was taken, sorted by key.
+    ///
+    /// 自 `snapshot` 拍摄以来的变化，按键排序。
+    pub fn diff(&self, snapshot: &FactSnapshot) -> Vec<FactChange> {
+        snapshot.diff(&self.snapshot())
+    }
+
+    /// Replace the facts with those of `snapshot`. Only keys whose value differs from the
+    /// snapshot are written, so change tracking sees exactly what the rollback changed.
+    ///
+    /// 用 `snapshot` 中的事实替换当前事实。只有值与快照不同的键会被写入，
+    /// 因此变更跟踪看到的正是回滚所改变的内容。
+    pub fn restore(&mut self, snapshot: &FactSnapshot) {
+        let stale: Vec<String> = self
+            .iter()
+            .filter(|(key, _)| snapshot.get(key).is_none())
+            .map(|(key, _)| key.clone())
+            .collect();
+        self.remove_many(stale.iter().map(String::as_str));
+        for (key, value) in snapshot.iter() {
+            self.set_if_changed(key.clone(), value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::every_variant;
+
+    #[test]
+    fn test_diff_reports_changes_of_every_variant() {
+        let mut db = FactDatabase::new();
+        for (key, value) in every_variant() {
+            db.set(key, value);
+        }
+        let before = db.snapshot();
+        assert_eq!(before.len(), 12);
+        assert!(db.diff(&before).is_empty());
+
+        // Rewrite every fact with a value of the next variant, so each one is modified
+        let variants = every_variant();
+        for (i, (key, old)) in variants.iter().enumerate() {
+            let new = variants[(i + 1) % variants.len()].1.clone();
+            db.set(*key, new.clone());
+            let changes = db.diff(&before);
+            let change = changes.iter().find(|c| c.key() == *key).unwrap();
+            assert_eq!(
+                *change,
+                FactChange::Modified {
+                    key: key.to_string(),
+                    old: old.clone(),
+                    new,
+                }
+            );
+        }
+        assert_eq!(db.diff(&before).len(), 12);
+
+        // Setting the same value again is not a change
+        db.set("int", variants[1].1.clone());
+        assert_eq!(db.diff(&before).len(), 12);
+    }
+
+    #[test]
+    fn test_diff_added_and_removed_sorted_by_key() {
+        let mut db = FactDatabase::new();
+        db.set("b", 1);
+        db.set("c", "kept");
+        let before = db.snapshot();
+
+        db.remove("b");
+        db.set("a", FactValue::Null);
+        db.set("d", vec![true, false]);
+
+        let changes = db.diff(&before);
+        assert_eq!(
+            changes,
+            vec![
+                FactChange::Added {
+                    key: "a".to_string(),
+                    value: FactValue::Null,
+                },
+                FactChange::Removed {
+                    key: "b".to_string(),
+                    value: FactValue::Int(1),
+                },
+                FactChange::Added {
+                    key: "d".to_string(),
+                    value: vec![true, false].into(),
+                },
+            ]
+        );
+        let lines: Vec<String> = changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "+ 'a' = Null",
+                "- 'b' (was Int(1))",
+                "+ 'd' = BoolList([true, false])",
+            ]
+        );
+        assert_eq!(
+            FactChange::Modified {
+                key: "hp".to_string(),
+                old: FactValue::Int(3),
+                new: FactValue::Int(2),
+            }
+            .to_string(),
+            "~ 'hp': Int(3) -> Int(2)"
+        );
+    }
+}
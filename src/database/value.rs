@@ -0,0 +1,596 @@
+//! # value.rs
+//!
+//! # value.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Defines `FactValue`, the dynamically typed value stored in fact databases, together with
+//! its typed accessors, list aggregates, and conversions from plain Rust types.
+//!
+//! 定义 `FactValue`，即存储在事实数据库中的动态类型值，以及它的类型化访问器、
+//! 列表聚合和从普通 Rust 类型的转换。
+
+use bevy::ecs::entity::Entity;
+use bevy::math::{Vec2, Vec3};
+#[cfg(feature = "debug")]
+use bevy::reflect::Reflect;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Value types supported by the fact database.
+///
+/// 事实数据库支持的值类型。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "debug", derive(Reflect), reflect(PartialEq))]
+pub enum FactValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// List of strings - useful for inventories, tags, etc.
+    /// 字符串列表 - 适用于物品栏、标签等。
+    StringList(Vec<String>),
+    /// List of integers - useful for HP values, stats arrays, etc.
+    /// 整数列表 - 适用于 HP 值、属性数组等。
+    IntList(Vec<i64>),
+    /// List of floats - useful for coordinates, multipliers, etc.
+    /// 浮点数列表 - 适用于坐标、乘数等。
+    FloatList(Vec<f64>),
+    /// List of booleans - useful for flags, toggles, etc.
+    /// 布尔列表 - 适用于标志、开关等。
+    BoolList(Vec<bool>),
+    /// 2D vector - useful for positions and velocities.
+    /// 二维向量 - 适用于位置和速度。
+    Vec2([f32; 2]),
+    /// 3D vector - useful for positions and velocities.
+    /// 三维向量 - 适用于位置和速度。
+    Vec3([f32; 3]),
+    /// Reference to a spawned entity - runtime only, it has no asset form.
+    /// Serialized as raw entity bits, which are only meaningful in the same world.
+    /// 对已生成实体的引用 - 仅限运行时，没有资源形式。
+    /// 序列化为原始实体位，仅在同一个世界中有意义。
+    EntityRef(#[serde(with = "super::persist::entity_bits")] Entity),
+    /// A fact that exists but was explicitly cleared, as opposed to one never set.
+    /// 存在但被显式清空的事实，区别于从未设置的事实。
+    Null,
+}
+
+impl FactValue {
+    /// Get the value as an integer, if it is one.
+    ///
+    /// 如果值是整数，则获取该值。
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            FactValue::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a float, if it is one.
+    ///
+    /// 如果值是浮点数，则获取该值。
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            FactValue::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a boolean, if it is one.
+    ///
+    /// 如果值是布尔值，则获取该值。
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            FactValue::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is `Null`.
+    ///
+    /// 值是否为 `Null`。
+    pub fn is_null(&self) -> bool {
+        matches!(self, FactValue::Null)
+    }
+
+    /// Get the value as a number: integers and floats as they are, booleans as 1.0 or 0.0.
+    /// Use this instead of `as_int` / `as_float` when a numeric comparison should not care
+    /// whether the fact was stored as `Int` or `Float`.
+    ///
+    /// 以数值形式获取值：整数和浮点数保持原值，布尔值为 1.0 或 0.0。
+    /// 当数值比较不应关心事实存储为 `Int` 还是 `Float` 时，使用它代替 `as_int` / `as_float`。
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            FactValue::Int(v) => Some(*v as f64),
+            FactValue::Float(v) => Some(*v),
+            FactValue::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an integer, truncating floats toward zero and reading booleans as 1 or
+    /// 0. Floats that are not finite or do not fit in an `i64` return None.
+    ///
+    /// 以整数形式获取值：浮点数向零截断，布尔值为 1 或 0。
+    /// 非有限值或超出 `i64` 范围的浮点数返回 None。
+    pub fn as_int_lossy(&self) -> Option<i64> {
+        match self {
+            FactValue::Int(v) => Some(*v),
+            FactValue::Float(v) => {
+                let truncated = v.trunc();
+                // i64::MAX as f64 rounds up to 2^63, which is already out of range
+                (truncated >= i64::MIN as f64 && truncated < i64::MAX as f64)
+                    .then_some(truncated as i64)
+            }
+            FactValue::Bool(v) => Some(i64::from(*v)),
+            _ => None,
+        }
+    }
+
+    /// Compare two numeric values, treating `Int` and `Float` alike. Two integers compare
+    /// exactly; any other pair, including booleans, returns None.
+    ///
+    /// 比较两个数值，`Int` 和 `Float` 同等对待。两个整数精确比较；
+    /// 其他任何组合（包括布尔值）返回 None。
+    pub fn partial_cmp_numeric(&self, other: &FactValue) -> Option<Ordering> {
+        match (self, other) {
+            (FactValue::Int(a), FactValue::Int(b)) => Some(a.cmp(b)),
+            (FactValue::Int(a), FactValue::Float(b)) => (*a as f64).partial_cmp(b),
+            (FactValue::Float(a), FactValue::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (FactValue::Float(a), FactValue::Float(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a string, if it is one.
+    ///
+    /// 如果值是字符串，则获取该值。
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            FactValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a string list, if it is one.
+    ///
+    /// 如果值是字符串列表，则获取该值。
+    pub fn as_string_list(&self) -> Option<&[String]> {
+        match self {
+            FactValue::StringList(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an integer list, if it is one.
+    ///
+    /// 如果值是整数列表，则获取该值。
+    pub fn as_int_list(&self) -> Option<&[i64]> {
+        match self {
+            FactValue::IntList(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a float list, if it is one.
+    ///
+    /// 如果值是浮点数列表，则获取该值。
+    pub fn as_float_list(&self) -> Option<&[f64]> {
+        match self {
+            FactValue::FloatList(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a boolean list, if it is one.
+    ///
+    /// 如果值是布尔列表，则获取该值。
+    pub fn as_bool_list(&self) -> Option<&[bool]> {
+        match self {
+            FactValue::BoolList(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a 2D vector, if it is one.
+    ///
+    /// 如果值是二维向量，则获取该值。
+    pub fn as_vec2(&self) -> Option<Vec2> {
+        match self {
+            FactValue::Vec2(v) => Some(Vec2::from_array(*v)),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a 3D vector, if it is one.
+    ///
+    /// 如果值是三维向量，则获取该值。
+    pub fn as_vec3(&self) -> Option<Vec3> {
+        match self {
+            FactValue::Vec3(v) => Some(Vec3::from_array(*v)),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an entity, if it is an entity reference.
+    ///
+    /// 如果值是实体引用，则获取该实体。
+    pub fn as_entity(&self) -> Option<Entity> {
+        match self {
+            FactValue::EntityRef(entity) => Some(*entity),
+            _ => None,
+        }
+    }
+
+    /// Distance between two vectors of the same dimension, or None otherwise.
+    ///
+    /// 两个相同维度向量之间的距离；否则返回 None。
+    pub fn distance(&self, other: &FactValue) -> Option<f32> {
+        match (self, other) {
+            (FactValue::Vec2(a), FactValue::Vec2(b)) => {
+                Some(Vec2::from_array(*a).distance(Vec2::from_array(*b)))
+            }
+            (FactValue::Vec3(a), FactValue::Vec3(b)) => {
+                Some(Vec3::from_array(*a).distance(Vec3::from_array(*b)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Number of elements of any list value, or None if the value is not a list.
+    ///
+    /// 任意列表值的元素数量；如果值不是列表则返回 None。
+    pub fn list_len(&self) -> Option<usize> {
+        match self {
+            FactValue::StringList(v) => Some(v.len()),
+            FactValue::IntList(v) => Some(v.len()),
+            FactValue::FloatList(v) => Some(v.len()),
+            FactValue::BoolList(v) => Some(v.len()),
+            _ => None,
+        }
+    }
+
+    /// Sum of an integer list (0 for an empty list), or None if not an integer list.
+    ///
+    /// 整数列表之和（空列表为 0），如果不是整数列表则返回 None。
+    pub fn int_list_sum(&self) -> Option<i64> {
+        self.as_int_list()
+            .map(|list| list.iter().fold(0i64, |acc, v| acc.saturating_add(*v)))
+    }
+
+    /// Maximum of an integer list, or None if empty or not an integer list.
+    ///
+    /// 整数列表的最大值，如果为空或不是整数列表则返回 None。
+    pub fn int_list_max(&self) -> Option<i64> {
+        self.as_int_list()
+            .and_then(|list| list.iter().copied().max())
+    }
+
+    /// Sum of a float list (0.0 for an empty list), or None if not a float list.
+    ///
+    /// 浮点数列表之和（空列表为 0.0），如果不是浮点数列表则返回 None。
+    pub fn float_list_sum(&self) -> Option<f64> {
+        self.as_float_list().map(|list| list.iter().sum())
+    }
+
+    /// Maximum of a float list, ignoring NaN, or None if empty or not a float list.
+    ///
+    /// 浮点数列表的最大值（忽略 NaN），如果为空或不是浮点数列表则返回 None。
+    pub fn float_list_max(&self) -> Option<f64> {
+        self.as_float_list()?
+            .iter()
+            .copied()
+            .filter(|v| !v.is_nan())
+            .reduce(f64::max)
+    }
+}
+
+impl From<i64> for FactValue {
+    fn from(v: i64) -> Self {
+        FactValue::Int(v)
+    }
+}
+
+impl From<i32> for FactValue {
+    fn from(v: i32) -> Self {
+        FactValue::Int(v as i64)
+    }
+}
+
+impl From<f64> for FactValue {
+    fn from(v: f64) -> Self {
+        FactValue::Float(v)
+    }
+}
+
+impl From<f32> for FactValue {
+    fn from(v: f32) -> Self {
+        FactValue::Float(v as f64)
+    }
+}
+
+impl From<bool> for FactValue {
+    fn from(v: bool) -> Self {
+        FactValue::Bool(v)
+    }
+}
+
+impl From<String> for FactValue {
+    fn from(v: String) -> Self {
+        FactValue::String(v)
+    }
+}
+
+impl From<&str> for FactValue {
+    fn from(v: &str) -> Self {
+        FactValue::String(v.to_string())
+    }
+}
+
+impl From<Vec<String>> for FactValue {
+    fn from(v: Vec<String>) -> Self {
+        FactValue::StringList(v)
+    }
+}
+
+impl From<Vec<&str>> for FactValue {
+    fn from(v: Vec<&str>) -> Self {
+        FactValue::StringList(v.into_iter().map(|s| s.to_string()).collect())
+    }
+}
+
+impl From<Vec<i64>> for FactValue {
+    fn from(v: Vec<i64>) -> Self {
+        FactValue::IntList(v)
+    }
+}
+
+impl From<Vec<i32>> for FactValue {
+    fn from(v: Vec<i32>) -> Self {
+        FactValue::IntList(v.into_iter().map(|i| i as i64).collect())
+    }
+}
+
+impl From<Vec<f64>> for FactValue {
+    fn from(v: Vec<f64>) -> Self {
+        FactValue::FloatList(v)
+    }
+}
+
+impl From<Vec<f32>> for FactValue {
+    fn from(v: Vec<f32>) -> Self {
+        FactValue::FloatList(v.into_iter().map(|f| f as f64).collect())
+    }
+}
+
+impl From<Vec<bool>> for FactValue {
+    fn from(v: Vec<bool>) -> Self {
+        FactValue::BoolList(v)
+    }
+}
+
+impl From<Vec2> for FactValue {
+    fn from(v: Vec2) -> Self {
+        FactValue::Vec2(v.to_array())
+    }
+}
+
+impl From<Vec3> for FactValue {
+    fn from(v: Vec3) -> Self {
+        FactValue::Vec3(v.to_array())
+    }
+}
+
+impl From<Entity> for FactValue {
+    fn from(entity: Entity) -> Self {
+        FactValue::EntityRef(entity)
+    }
+}
+
+/// Write `items` as `[a, b, c]`.
+fn write_list<T>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    write_item: impl Fn(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    f.write_str("[")?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write_item(f, item)?;
+    }
+    f.write_str("]")
+}
+
+/// Human-readable form for overlays and logs: strings unquoted, floats always with a decimal
+/// point, lists as `[a, b, c]`, vectors as `(x, y)`, and `Null` as `null`.
+///
+/// 用于叠加层和日志的可读形式：字符串不加引号，浮点数总带小数点，列表为 `[a, b, c]`，
+/// 向量为 `(x, y)`，`Null` 为 `null`。
+impl fmt::Display for FactValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactValue::Int(v) => write!(f, "{}", v),
+            FactValue::Float(v) => write!(f, "{:?}", v),
+            FactValue::Bool(v) => write!(f, "{}", v),
+            FactValue::String(v) => f.write_str(v),
+            FactValue::StringList(v) => write_list(f, v, |f, item| f.write_str(item)),
+            FactValue::IntList(v) => write_list(f, v, |f, item| write!(f, "{}", item)),
+            FactValue::FloatList(v) => write_list(f, v, |f, item| write!(f, "{:?}", item)),
+            FactValue::BoolList(v) => write_list(f, v, |f, item| write!(f, "{}", item)),
+            FactValue::Vec2([x, y]) => write!(f, "({:?}, {:?})", x, y),
+            FactValue::Vec3([x, y, z]) => write!(f, "({:?}, {:?}, {:?})", x, y, z),
+            FactValue::EntityRef(entity) => write!(f, "{}", entity),
+            FactValue::Null => f.write_str("null"),
+        }
+    }
+}
+
+/// One fact of every value variant, for tests that must cover them all.
+#[cfg(test)]
+pub(crate) fn every_variant() -> Vec<(&'static str, FactValue)> {
+    vec![
+        ("int", FactValue::Int(-42)),
+        ("float", FactValue::Float(2.5)),
+        ("bool", FactValue::Bool(true)),
+        ("string", FactValue::from("Hero \"the brave\"")),
+        ("string_list", vec!["sword", "shield"].into()),
+        ("int_list", vec![1i64, 2, 3].into()),
+        ("float_list", FactValue::FloatList(vec![0.5, -1.25])),
+        ("bool_list", FactValue::BoolList(vec![true, false])),
+        ("vec2", FactValue::Vec2([1.0, -2.0])),
+        ("vec3", FactValue::Vec3([1.0, 2.0, 3.5])),
+        ("entity", FactValue::EntityRef(Entity::from_bits(7))),
+        ("null", FactValue::Null),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_fact_value_type_accessors() {
+        let int_val = FactValue::Int(42);
+        let float_val = FactValue::Float(2.71);
+        let bool_val = FactValue::Bool(true);
+        let string_val = FactValue::String("test".to_string());
+
+        // Test correct type accessors
+        assert_eq!(int_val.as_int(), Some(42));
+        assert_eq!(float_val.as_float(), Some(2.71));
+        assert_eq!(bool_val.as_bool(), Some(true));
+        assert_eq!(string_val.as_string(), Some("test"));
+
+        // Test wrong type accessors return None
+        assert_eq!(int_val.as_float(), None);
+        assert_eq!(float_val.as_int(), None);
+        assert_eq!(bool_val.as_string(), None);
+        assert_eq!(string_val.as_bool(), None);
+    }
+
+    #[test]
+    fn test_fact_value_from_implementations() {
+        let from_i64: FactValue = 42i64.into();
+        let from_i32: FactValue = 42i32.into();
+        let from_f64: FactValue = 2.71f64.into();
+        let from_f32: FactValue = 2.71f32.into();
+        let from_bool: FactValue = true.into();
+        let from_string: FactValue = String::from("test").into();
+        let from_str: FactValue = "test".into();
+
+        assert_eq!(from_i64.as_int(), Some(42));
+        assert_eq!(from_i32.as_int(), Some(42));
+        assert!(from_f64.as_float().is_some());
+        assert!(from_f32.as_float().is_some());
+        assert_eq!(from_bool.as_bool(), Some(true));
+        assert_eq!(from_string.as_string(), Some("test"));
+        assert_eq!(from_str.as_string(), Some("test"));
+    }
+    #[test]
+    fn test_as_number_coerces_mixed_numeric_types() {
+        assert_eq!(FactValue::Int(3).as_number(), Some(3.0));
+        assert_eq!(FactValue::Float(3.0).as_number(), Some(3.0));
+        assert_eq!(FactValue::Bool(true).as_number(), Some(1.0));
+        assert_eq!(FactValue::Bool(false).as_number(), Some(0.0));
+        assert_eq!(FactValue::String("3".to_string()).as_number(), None);
+        assert_eq!(FactValue::IntList(vec![3]).as_number(), None);
+
+        // Strict accessors are unchanged
+        assert_eq!(FactValue::Float(3.0).as_int(), None);
+        assert_eq!(FactValue::Int(3).as_float(), None);
+    }
+
+    #[test]
+    fn test_as_int_lossy_truncates_floats() {
+        assert_eq!(FactValue::Int(i64::MAX).as_int_lossy(), Some(i64::MAX));
+        assert_eq!(FactValue::Float(5.0).as_int_lossy(), Some(5));
+        assert_eq!(FactValue::Float(5.9).as_int_lossy(), Some(5));
+        assert_eq!(FactValue::Float(-5.9).as_int_lossy(), Some(-5));
+        assert_eq!(FactValue::Bool(true).as_int_lossy(), Some(1));
+        assert_eq!(FactValue::Bool(false).as_int_lossy(), Some(0));
+        assert_eq!(FactValue::Float(f64::NAN).as_int_lossy(), None);
+        assert_eq!(FactValue::Float(1e19).as_int_lossy(), None);
+        assert_eq!(FactValue::String("5".to_string()).as_int_lossy(), None);
+    }
+
+    #[test]
+    fn test_null_has_no_typed_value() {
+        let null = FactValue::Null;
+        assert!(null.is_null());
+        assert!(!FactValue::Int(0).is_null());
+        assert_eq!(null.as_int(), None);
+        assert_eq!(null.as_bool(), None);
+        assert_eq!(null.as_string(), None);
+        assert_eq!(null.as_number(), None);
+        assert_eq!(null.list_len(), None);
+    }
+
+    #[test]
+    fn test_int_list_aggregates() {
+        let stats = FactValue::IntList(vec![40, 25, 50]);
+        assert_eq!(stats.int_list_sum(), Some(115));
+        assert_eq!(stats.int_list_max(), Some(50));
+        assert!(stats.int_list_sum().is_some_and(|sum| sum > 100));
+
+        // Empty list: sum is 0, max is None
+        let empty = FactValue::IntList(Vec::new());
+        assert_eq!(empty.int_list_sum(), Some(0));
+        assert_eq!(empty.int_list_max(), None);
+
+        // Non-list values have no aggregate
+        assert_eq!(FactValue::Int(5).int_list_sum(), None);
+        assert_eq!(FactValue::Int(5).int_list_max(), None);
+    }
+
+    #[test]
+    fn test_vector_values() {
+        let a: FactValue = Vec2::new(0.0, 0.0).into();
+        let b: FactValue = Vec2::new(3.0, 4.0).into();
+        assert_eq!(b.as_vec2(), Some(Vec2::new(3.0, 4.0)));
+        assert_eq!(b.as_vec3(), None);
+        assert_eq!(a.distance(&b), Some(5.0));
+        assert_eq!(b, FactValue::Vec2([3.0, 4.0]));
+
+        let c: FactValue = Vec3::new(1.0, 2.0, 2.0).into();
+        assert_eq!(c.distance(&Vec3::ZERO.into()), Some(3.0));
+        // Mismatched dimensions have no distance
+        assert_eq!(a.distance(&c), None);
+    }
+
+    #[test]
+    fn test_float_list_helpers_match_int_list() {
+        let multipliers: FactValue = vec![1.5f64, 0.5, 2.0].into();
+        assert_eq!(multipliers.as_float_list(), Some(&[1.5, 0.5, 2.0][..]));
+        assert_eq!(multipliers.float_list_sum(), Some(4.0));
+        assert_eq!(multipliers.float_list_max(), Some(2.0));
+        assert_eq!(multipliers.list_len(), Some(3));
+        assert_eq!(FactValue::from(vec![1i64, 2, 3]).list_len(), Some(3));
+        assert_eq!(multipliers, FactValue::from(vec![1.5f32, 0.5, 2.0]));
+
+        let empty = FactValue::FloatList(Vec::new());
+        assert_eq!(empty.float_list_sum(), Some(0.0));
+        assert_eq!(empty.float_list_max(), None);
+        assert_eq!(FactValue::Float(1.0).list_len(), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let shown = |value: FactValue| value.to_string();
+        assert_eq!(shown(FactValue::Int(-3)), "-3");
+        assert_eq!(shown(FactValue::Float(1.0)), "1.0");
+        assert_eq!(shown(FactValue::Bool(true)), "true");
+        assert_eq!(shown("hero".into()), "hero");
+        assert_eq!(shown(vec!["a", "b", "c"].into()), "[a, b, c]");
+        assert_eq!(shown(vec![1i64, 2].into()), "[1, 2]");
+        assert_eq!(shown(vec![0.5f64, 2.0].into()), "[0.5, 2.0]");
+        assert_eq!(shown(vec![true, false].into()), "[true, false]");
+        assert_eq!(shown(FactValue::StringList(Vec::new())), "[]");
+        assert_eq!(shown(Vec2::new(1.0, 2.5).into()), "(1.0, 2.5)");
+        assert_eq!(shown(Vec3::new(0.0, 1.0, 2.0).into()), "(0.0, 1.0, 2.0)");
+        assert_eq!(shown(FactValue::Null), "null");
+    }
+}
@@ -0,0 +1,236 @@
+//! # dead_condition.rs
+//!
+//! Best-effort static analysis of condition lists, for linting content.
+//! A rule passes only when every condition in its list passes, so a list is dead when one
+//! condition can never pass on its own (`0`, `1 == 2`, `compare($a > $a)`) or when several
+//! conditions contradict each other on one fact (`$hp > 10` with `float($hp) < 5`,
+//! `$flag == 1` with `$flag == 0`, a numeric bound with `is_null($hp)`). Remaining conditions
+//! are read as `expr` expressions, as `ExprConditionEvaluator` does. Anything the analysis does
+//! not understand, including host syntax and `@name` references, is assumed satisfiable, so a
+//! list not reported as dead may still never pass.
+//!
+//! 针对条件列表的尽力而为的静态分析，用于内容检查。
+//! 只有列表中的每个条件都通过时规则才会通过，因此当某个条件本身永远无法通过
+//! （`0`、`1 == 2`、`compare($a > $a)`），或多个条件在同一事实上相互矛盾
+//! （`$hp > 10` 与 `float($hp) < 5`、`$flag == 1` 与 `$flag == 0`、数值约束与 `is_null($hp)`）时，
+//! 该列表即为死条件。其余条件按 `expr` 表达式读取，与 `ExprConditionEvaluator` 相同。
+//! 分析无法理解的内容（包括宿主语法和 `@name` 引用）都被视为可满足，
+//! 因此未被报告为死条件的列表仍可能永远无法通过。
+
+use std::collections::{HashMap, HashSet};
+
+use crate::expr::Expr;
+use crate::expr_condition::ExprLessThan;
+use crate::fact_comparison::FactComparison;
+use crate::float_condition::FloatCondition;
+use crate::presence::PresenceCondition;
+
+/// A bound a condition places on a numeric fact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Bound {
+    Above(f64),
+    AtLeast(f64),
+    Below(f64),
+    AtMost(f64),
+}
+
+/// The values a numeric fact may still hold, as an interval.
+#[derive(Debug, Clone, Copy)]
+struct Interval {
+    lower: f64,
+    lower_inclusive: bool,
+    upper: f64,
+    upper_inclusive: bool,
+}
+
+impl Default for Interval {
+    fn default() -> Self {
+        Self {
+            lower: f64::NEG_INFINITY,
+            lower_inclusive: true,
+            upper: f64::INFINITY,
+            upper_inclusive: true,
+        }
+    }
+}
+
+impl Interval {
+    fn narrow(&mut self, bound: Bound) {
+        let (value, inclusive, is_lower) = match bound {
+            Bound::Above(value) => (value, false, true),
+            Bound::AtLeast(value) => (value, true, true),
+            Bound::Below(value) => (value, false, false),
+            Bound::AtMost(value) => (value, true, false),
+        };
+        if is_lower && (value > self.lower || (value == self.lower && !inclusive)) {
+            self.lower = value;
+            self.lower_inclusive = inclusive;
+        }
+        if !is_lower && (value < self.upper || (value == self.upper && !inclusive)) {
+            self.upper = value;
+            self.upper_inclusive = inclusive;
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lower > self.upper
+            || (self.lower == self.upper && !(self.lower_inclusive && self.upper_inclusive))
+    }
+}
+
+/// Whether the condition list can never pass, as far as static analysis can tell.
+///
+/// 就静态分析所能判断的范围而言，条件列表是否永远无法通过。
+pub fn is_trivially_false(conditions: &[String]) -> bool {
+    if conditions
+        .iter()
+        .any(|c| constant_verdict(c) == Some(false))
+    {
+        return true;
+    }
+    let mut intervals: HashMap<String, Interval> = HashMap::new();
+    let mut nulls = HashSet::new();
+    for condition in conditions {
+        if let Some(PresenceCondition::IsNull(key)) = PresenceCondition::parse(condition) {
+            nulls.insert(key);
+        }
+        for (key, bound) in fact_bounds(condition) {
+            intervals.entry(key).or_default().narrow(bound);
+        }
+    }
+    // `Null` is not a number, so any numeric bound on a null fact fails
+    intervals
+        .iter()
+        .any(|(key, interval)| interval.is_empty() || nulls.contains(key.as_str()))
+}
+
+/// Whether the condition list always passes, whatever the facts. An empty list always passes.
+///
+/// 无论事实如何，条件列表是否总是通过。空列表总是通过。
+pub fn is_trivially_true(conditions: &[String]) -> bool {
+    conditions.iter().all(|c| constant_verdict(c) == Some(true))
+}
+
+/// The result of a condition that does not depend on any fact.
+fn constant_verdict(condition: &str) -> Option<bool> {
+    if let Some(expr_condition) = ExprLessThan::parse(condition) {
+        let value = expr_condition.expr.constant()?;
+        return Some(value < expr_condition.threshold);
+    }
+    if let Some(FactComparison::GreaterThan(a, b)) = FactComparison::parse(condition) {
+        return (a == b).then_some(false);
+    }
+    Expr::parse(condition)?.constant_bool()
+}
+
+/// Numeric bounds the condition places on facts when it passes.
+fn fact_bounds(condition: &str) -> Vec<(String, Bound)> {
+    let Some(float) = FloatCondition::parse(condition) else {
+        return Expr::parse(condition)
+            .map(|expr| {
+                let bounds = expr.fact_bounds();
+                bounds
+                    .into_iter()
+                    .map(|(k, b)| (k.to_string(), b))
+                    .collect()
+            })
+            .unwrap_or_default();
+    };
+    let (key, bounds) = match float {
+        FloatCondition::GreaterThan(key, value) => (key, vec![Bound::Above(value)]),
+        FloatCondition::GreaterOrEqual(key, value) => (key, vec![Bound::AtLeast(value)]),
+        FloatCondition::LessThan(key, value) => (key, vec![Bound::Below(value)]),
+        FloatCondition::LessOrEqual(key, value) => (key, vec![Bound::AtMost(value)]),
+        FloatCondition::InRange(key, min, max) => {
+            (key, vec![Bound::AtLeast(min), Bound::AtMost(max)])
+        }
+    };
+    bounds.into_iter().map(|b| (key.to_string(), b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{LayeredRuleRegistry, Rule, RuleScope};
+
+    fn conditions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_contradictions_are_flagged() {
+        let dead = [
+            &["$door_open == 1", "$door_open == 0"][..],
+            &["$hp > 10 && $hp < 5"],
+            &["$hp > 10", "float($hp) <= 10"],
+            &["float($hp) in 5..1"],
+            &["float($hp) >= 3", "is_null($hp)"],
+            &["1 == 2"],
+            &["0 && $hp > 0"],
+            &["compare($gold > $gold)"],
+            &["expr(2 * 3) < 1"],
+        ];
+        for list in dead {
+            assert!(is_trivially_false(&conditions(list)), "{:?}", list);
+            assert!(!is_trivially_true(&conditions(list)), "{:?}", list);
+        }
+    }
+
+    #[test]
+    fn test_satisfiable_conditions_are_not_flagged() {
+        let live = [
+            &["$door_open == 1", "$key_count > 0"][..],
+            &["$hp >= 10", "float($hp) <= 10"],
+            &["10 < $hp", "$hp < 11"],
+            &["$hp > 10 || $hp < 5"],
+            &["float($hp) in 1..5", "exists($hp)"],
+            &["compare($gold > $debt)"],
+            &["@can_act", "player_is_ready"],
+        ];
+        for list in live {
+            assert!(!is_trivially_false(&conditions(list)), "{:?}", list);
+        }
+    }
+
+    #[test]
+    fn test_tautologies() {
+        assert!(is_trivially_true(&[]));
+        assert!(is_trivially_true(&conditions(&["1", "1 || $hp > 0"])));
+        assert!(is_trivially_true(&conditions(&["expr(max(1, 2)) < 3"])));
+        assert!(!is_trivially_true(&conditions(&["1", "$hp > 0"])));
+        assert!(!is_trivially_true(&conditions(&["player_is_ready"])));
+    }
+
+    #[test]
+    fn test_registry_reports_dead_rules() {
+        let mut registry: LayeredRuleRegistry = LayeredRuleRegistry::new();
+        registry.register(
+            Rule::builder("locked_and_open", "door_touched")
+                .condition_expr("$door_open == 1")
+                .condition_expr("$door_open == 0")
+                .build(),
+        );
+        registry.register(
+            Rule::builder("open_door", "door_touched")
+                .scope(RuleScope::Global)
+                .condition_expr("$door_open == 0")
+                .condition_expr("$keys > 0")
+                .build(),
+        );
+        registry.register_view_rule(
+            bevy::prelude::Entity::from_bits(1),
+            Rule::builder("never", "menu_opened")
+                .condition_expr("float($hp) > 1")
+                .condition_expr("float($hp) < 1")
+                .build(),
+        );
+
+        let mut dead: Vec<&str> = registry
+            .find_dead_conditions()
+            .iter()
+            .map(|rule| rule.id.as_str())
+            .collect();
+        dead.sort();
+        assert_eq!(dead, ["locked_and_open", "never"]);
+    }
+}
@@ -0,0 +1,440 @@
+//! # debug_hooks.rs
+//!
+//! Per-rule debug hooks for chasing a single misbehaving rule.
+//! `RuleDebugHooks` maps rule ids to `DebugAction`s. While it is empty, rule processing takes
+//! its normal path; otherwise each considered rule costs one map lookup, and watched rules collect
+//! a `RuleDebugInfo` (condition breakdown, modification before/after values, outputs) that is
+//! handed to their actions every time the rule is considered, not only when it fires.
+//! Hooks are runtime-only: they are never serialized, and watches on rules whose registry layer
+//! is cleared (or that are unregistered) are dropped.
+//!
+//! 用于追踪单条异常规则的逐规则调试钩子。
+//! `RuleDebugHooks` 把规则 ID 映射到 `DebugAction`。为空时规则处理走常规路径；否则每条被考虑的
+//! 规则只需一次映射查找，被监视的规则会收集 `RuleDebugInfo`（条件明细、修改前后的值、输出），
+//! 并在每次被考虑时（而不仅是触发时）交给其动作。
+//! 钩子仅存在于运行时：它们永远不会被序列化，规则所在注册表层被清空（或规则被注销）时，
+//! 对这些规则的监视会被移除。
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::asset::{ActionDef, EnumRegistry};
+use crate::event::{FactEvent, FactEventId};
+use crate::layered::LayeredFactDatabase;
+use crate::query::ConditionResult;
+use crate::rule::{FactModification, Rule, RuleRegistryChangeKind, RuleRegistryChanged};
+use crate::simulation::ModificationOutcome;
+use crate::systems::{ConditionEvaluator, LiveSink, RuleSink};
+
+/// Everything recorded about one consideration of a watched rule.
+///
+/// 被监视规则一次被考虑时记录的全部信息。
+#[derive(Debug, Clone)]
+pub struct RuleDebugInfo {
+    pub rule_id: String,
+    pub event: FactEvent,
+
+    /// Whether the rule's conditions passed.
+    ///
+    /// 规则条件是否通过。
+    pub passed: bool,
+
+    /// Whether the rule fired. A passing rule in a random or `HighestScore` group only fires if
+    /// it is the one selected.
+    ///
+    /// 规则是否已触发。随机或 `HighestScore` 组中通过的规则只有被选中时才会触发。
+    pub fired: bool,
+
+    /// Each condition evaluated on its own.
+    ///
+    /// 单独评估的每个条件。
+    pub conditions: Vec<ConditionResult>,
+
+    /// Applied modifications with the targeted fact's value before and after.
+    ///
+    /// 已应用的修改，以及目标事实修改前后的值。
+    pub modifications: Vec<ModificationOutcome>,
+
+    /// Output events queued by the rule.
+    ///
+    /// 规则排队的输出事件。
+    pub outputs: Vec<FactEventId>,
+}
+
+type DebugCallback = Arc<dyn Fn(&RuleDebugInfo) + Send + Sync>;
+
+/// What to do each time a watched rule is considered.
+///
+/// 每次考虑被监视的规则时要执行的操作。
+#[derive(Clone)]
+pub enum DebugAction {
+    /// Log the full `RuleDebugInfo` at info level.
+    ///
+    /// 以 info 级别记录完整的 `RuleDebugInfo`。
+    VerboseLog,
+
+    /// Call a function with the `RuleDebugInfo`.
+    ///
+    /// 使用 `RuleDebugInfo` 调用函数。
+    Callback(DebugCallback),
+
+    /// Raise a named flag for the game to interpret (e.g. pausing a virtual clock).
+    /// Raised flags are collected with `RuleDebugHooks::take_raised_flags`.
+    ///
+    /// 抛出一个由游戏解释的具名标志（例如暂停虚拟时钟）。
+    /// 通过 `RuleDebugHooks::take_raised_flags` 收集已抛出的标志。
+    Flag(String),
+}
+
+impl DebugAction {
+    /// Convenience constructor for `DebugAction::Callback`.
+    ///
+    /// `DebugAction::Callback` 的便捷构造函数。
+    pub fn callback(f: impl Fn(&RuleDebugInfo) + Send + Sync + 'static) -> Self {
+        DebugAction::Callback(Arc::new(f))
+    }
+}
+
+impl std::fmt::Debug for DebugAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DebugAction::VerboseLog => write!(f, "VerboseLog"),
+            DebugAction::Callback(_) => write!(f, "Callback(..)"),
+            DebugAction::Flag(flag) => f.debug_tuple("Flag").field(flag).finish(),
+        }
+    }
+}
+
+/// Debug actions attached to individual rules.
+///
+/// 附加到单条规则上的调试操作。
+#[derive(Resource, Debug, Default)]
+pub struct RuleDebugHooks {
+    watched: HashMap<String, Vec<DebugAction>>,
+    raised: Vec<(String, String)>,
+}
+
+impl RuleDebugHooks {
+    /// Attach an action to a rule. A rule may have several actions.
+    ///
+    /// 为规则附加一个操作。一条规则可以有多个操作。
+    pub fn watch(&mut self, rule_id: impl Into<String>, action: DebugAction) {
+        self.watched.entry(rule_id.into()).or_default().push(action);
+    }
+
+    /// Remove every action attached to a rule.
+    ///
+    /// 移除附加到规则上的所有操作。
+    pub fn unwatch(&mut self, rule_id: &str) -> bool {
+        self.watched.remove(rule_id).is_some()
+    }
+
+    pub fn is_watched(&self, rule_id: &str) -> bool {
+        self.watched.contains_key(rule_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watched.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.watched.clear();
+    }
+
+    /// Take the `(rule_id, flag)` pairs raised by `DebugAction::Flag` since the last call.
+    ///
+    /// 取出自上次调用以来由 `DebugAction::Flag` 抛出的 `(rule_id, flag)` 对。
+    pub fn take_raised_flags(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.raised)
+    }
+
+    fn dispatch(&mut self, info: &RuleDebugInfo) {
+        let Some(actions) = self.watched.get(&info.rule_id) else {
+            return;
+        };
+        for action in actions {
+            match action {
+                DebugAction::VerboseLog => info!("FRE debug: {:#?}", info),
+                DebugAction::Callback(callback) => callback(info),
+                DebugAction::Flag(flag) => self.raised.push((info.rule_id.clone(), flag.clone())),
+            }
+        }
+    }
+}
+
+/// System that drops watches on rules removed from the registry.
+///
+/// 移除对已从注册表中删除的规则的监视的系统。
+pub fn prune_rule_debug_hooks_system(
+    mut changes: MessageReader<RuleRegistryChanged>,
+    hooks: Option<ResMut<RuleDebugHooks>>,
+) {
+    let Some(mut hooks) = hooks else {
+        return;
+    };
+    for change in changes.read() {
+        if !matches!(
+            change.kind,
+            RuleRegistryChangeKind::Unregistered | RuleRegistryChangeKind::LayerCleared { .. }
+        ) {
+            continue;
+        }
+        for rule_id in &change.rule_ids {
+            hooks.unwatch(rule_id);
+        }
+    }
+}
+
+/// Live sink that records watched rules and otherwise behaves exactly like `LiveSink`.
+pub(crate) struct DebugSink<'a> {
+    live: LiveSink<'a>,
+    hooks: &'a mut RuleDebugHooks,
+    evaluator: &'a ConditionEvaluator,
+    enums: &'a EnumRegistry,
+    current: Option<RuleDebugInfo>,
+}
+
+impl<'a> DebugSink<'a> {
+    pub(crate) fn new(
+        live: LiveSink<'a>,
+        hooks: &'a mut RuleDebugHooks,
+        evaluator: &'a ConditionEvaluator,
+        enums: &'a EnumRegistry,
+    ) -> Self {
+        Self {
+            live,
+            hooks,
+            evaluator,
+            enums,
+            current: None,
+        }
+    }
+
+    fn explain(&self, conditions: &[String], event: &FactEvent) -> Vec<ConditionResult> {
+        conditions
+            .iter()
+            .map(|expression| ConditionResult {
+                expression: expression.clone(),
+                passed: self.evaluator.evaluate_conditions_for_event(
+                    std::slice::from_ref(expression),
+                    event,
+                    &self.live.db.for_view(event.entity),
+                    self.enums,
+                ),
+            })
+            .collect()
+    }
+}
+
+impl<A: ActionDef> RuleSink<A> for DebugSink<'_> {
+    fn facts(&self) -> &LayeredFactDatabase {
+        self.live.db
+    }
+
+    fn condition_checked(&mut self, rule: &Rule<A>, event: &FactEvent, passed: bool) {
+        RuleSink::<A>::condition_checked(&mut self.live, rule, event, passed);
+        if !self.hooks.is_watched(&rule.id) {
+            return;
+        }
+        self.current = Some(RuleDebugInfo {
+            rule_id: rule.id.clone(),
+            event: event.clone(),
+            passed,
+            fired: false,
+            conditions: self.explain(&rule.condition_expressions, event),
+            modifications: Vec::new(),
+            outputs: Vec::new(),
+        });
+    }
+
+    fn rule_firing(&mut self, rule: &Rule<A>, event: &FactEvent) {
+        RuleSink::<A>::rule_firing(&mut self.live, rule, event);
+        if let Some(current) = self.current.as_mut() {
+            current.fired = true;
+        }
+    }
+
+    fn apply_modification(&mut self, _rule: &Rule<A>, modification: &FactModification) {
+        let db = &mut *self.live.db;
+        let Some(current) = self.current.as_mut() else {
+            modification.apply(db);
+            return;
+        };
+        let before = db.get_by_str(modification.key()).cloned();
+        modification.apply(db);
+        current.modifications.push(ModificationOutcome {
+            modification: modification.clone(),
+            before,
+            after: db.get_by_str(modification.key()).cloned(),
+        });
+    }
+
+    fn apply_modifications(&mut self, rule: &Rule<A>) {
+        if !rule.transactional {
+            for modification in &rule.modifications {
+                self.apply_modification(rule, modification);
+            }
+            return;
+        }
+        // The batch is applied as a whole, so each outcome spans all of it
+        let before: Vec<_> = rule
+            .modifications
+            .iter()
+            .map(|modification| self.live.db.get_by_str(modification.key()).cloned())
+            .collect();
+        RuleSink::<A>::apply_modifications(&mut self.live, rule);
+        let Some(current) = self.current.as_mut() else {
+            return;
+        };
+        for (modification, before) in rule.modifications.iter().zip(before) {
+            current.modifications.push(ModificationOutcome {
+                modification: modification.clone(),
+                before,
+                after: self.live.db.get_by_str(modification.key()).cloned(),
+            });
+        }
+    }
+
+    fn queue_output(&mut self, rule: &Rule<A>, event: FactEvent) {
+        if let Some(current) = self.current.as_mut() {
+            current.outputs.push(event.id.clone());
+        }
+        RuleSink::<A>::queue_output(&mut self.live, rule, event);
+    }
+
+    fn rule_finished(&mut self, _rule: &Rule<A>) {
+        if let Some(info) = self.current.take() {
+            self.hooks.dispatch(&info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CoreActionDef;
+    use crate::database::FactValue;
+    use crate::rule::{FreRng, LayeredRuleRegistry, SelectPolicy};
+    use crate::systems::{
+        PendingFactEvents, emit_rule_registry_changes_system, process_rules_system,
+    };
+    use std::sync::Mutex;
+
+    fn debug_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<PendingFactEvents>()
+            .init_resource::<EnumRegistry>()
+            .init_resource::<ConditionEvaluator>()
+            .init_resource::<FreRng>()
+            .init_resource::<RuleDebugHooks>()
+            .add_message::<FactEvent>()
+            .add_message::<RuleRegistryChanged>()
+            .add_systems(
+                Update,
+                (
+                    emit_rule_registry_changes_system::<CoreActionDef>,
+                    prune_rule_debug_hooks_system,
+                    process_rules_system::<CoreActionDef>,
+                )
+                    .chain(),
+            );
+        let mut registry = app
+            .world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>();
+        for id in ["take_damage", "log_damage"] {
+            registry.register(
+                Rule::builder(id, "hit")
+                    .consume_event(false)
+                    .modify(FactModification::Increment("hp".to_string(), -3))
+                    .output("hurt")
+                    .build(),
+            );
+        }
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("hp", 10i64);
+        app
+    }
+
+    #[test]
+    fn test_callback_receives_before_and_after() {
+        let mut app = debug_app();
+        let seen: Arc<Mutex<Vec<RuleDebugInfo>>> = Arc::default();
+        let sink = seen.clone();
+        {
+            let mut hooks = app.world_mut().resource_mut::<RuleDebugHooks>();
+            hooks.watch(
+                "take_damage",
+                DebugAction::callback(move |info| sink.lock().unwrap().push(info.clone())),
+            );
+            hooks.watch("take_damage", DebugAction::Flag("pause".to_string()));
+        }
+
+        app.world_mut().write_message(FactEvent::new("hit"));
+        app.update();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        let info = &seen[0];
+        assert_eq!(info.rule_id, "take_damage");
+        assert!(info.passed && info.fired);
+        let outcome = &info.modifications[0];
+        // Both rules decrement hp; only the watched one is recorded
+        let before = outcome.before.as_ref().and_then(FactValue::as_int).unwrap();
+        assert_eq!(outcome.after, Some(FactValue::Int(before - 3)));
+        assert_eq!(info.outputs, vec![FactEventId::new("hurt")]);
+        assert_eq!(
+            app.world_mut()
+                .resource_mut::<RuleDebugHooks>()
+                .take_raised_flags(),
+            vec![("take_damage".to_string(), "pause".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_unselected_candidates_are_reported() {
+        let mut app = debug_app();
+        let seen: Arc<Mutex<Vec<RuleDebugInfo>>> = Arc::default();
+        for id in ["bark_a", "bark_b"] {
+            app.world_mut()
+                .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+                .register(
+                    Rule::builder(id, "idle")
+                        .selection(SelectPolicy::Random)
+                        .build(),
+                );
+            let sink = seen.clone();
+            app.world_mut().resource_mut::<RuleDebugHooks>().watch(
+                id,
+                DebugAction::callback(move |info| sink.lock().unwrap().push(info.clone())),
+            );
+        }
+
+        app.world_mut().write_message(FactEvent::new("idle"));
+        app.update();
+
+        // Both candidates passed and are reported; only the drawn one fired
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert!(seen.iter().all(|info| info.passed));
+        assert_eq!(seen.iter().filter(|info| info.fired).count(), 1);
+    }
+
+    #[test]
+    fn test_hooks_dropped_when_layer_cleared() {
+        let mut app = debug_app();
+        app.world_mut()
+            .resource_mut::<RuleDebugHooks>()
+            .watch("take_damage", DebugAction::VerboseLog);
+        let mut registry = app
+            .world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>();
+        registry.set_change_tracking(true);
+        registry.clear_local();
+        app.update();
+        assert!(app.world().resource::<RuleDebugHooks>().is_empty());
+    }
+}
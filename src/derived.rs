@@ -0,0 +1,339 @@
+//! # derived.rs
+//!
+//! Derived facts computed from formulas over other facts.
+//! A `DerivedFact` such as `hp_percent = $hp / $max_hp * 100` is registered in the
+//! `DerivedFacts` resource; `recompute_derived_facts_system` runs after rule processing and
+//! rewrites every derived fact whose dependencies changed to the local layer. Derived facts may
+//! depend on other derived facts; they are recomputed in dependency order and cycles are rejected
+//! at registration. Readers see a value that is at most one system pass stale: a write made after
+//! the recompute system (or by a rule in the same pass) is reflected on its next run.
+//!
+//! 由其他事实的公式计算出的派生事实。
+//! 诸如 `hp_percent = $hp / $max_hp * 100` 的 `DerivedFact` 注册在 `DerivedFacts` 资源中；
+//! `recompute_derived_facts_system` 在规则处理之后运行，把依赖发生变化的每个派生事实重新写入局部层。
+//! 派生事实可以依赖其他派生事实；它们按依赖顺序重新计算，并在注册时拒绝环。
+//! 读取方看到的值最多落后一次系统运行：在重新计算系统之后（或同一轮中由规则）进行的写入，
+//! 会在它下一次运行时反映出来。
+
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::database::FactValue;
+use crate::expr::{evaluate_expr_to_fact, expr_variables};
+use crate::layered::LayeredFactDatabase;
+
+/// A fact whose value is an expression over other facts.
+///
+/// 值为其他事实表达式的事实。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedFact {
+    pub key: String,
+    pub expr: String,
+}
+
+impl DerivedFact {
+    pub fn new(key: impl Into<String>, expr: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            expr: expr.into(),
+        }
+    }
+
+    /// Fact keys the formula reads.
+    ///
+    /// 公式读取的事实键。
+    pub fn dependencies(&self) -> Vec<&str> {
+        expr_variables(&self.expr)
+    }
+}
+
+/// Error returned when registering a derived fact would make it depend on itself.
+///
+/// 注册派生事实会使其依赖自身时返回的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedFactCycleError {
+    pub key: String,
+    pub dependency: String,
+}
+
+impl fmt::Display for DerivedFactCycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "derived fact '{}' depends on '{}', which would create a cycle",
+            self.key, self.dependency
+        )
+    }
+}
+
+impl std::error::Error for DerivedFactCycleError {}
+
+#[derive(Debug, Clone)]
+struct DerivedEntry {
+    fact: DerivedFact,
+    dependencies: Vec<String>,
+    /// Dependency values seen by the last recompute, or None before the first one.
+    last_inputs: Option<Vec<Option<FactValue>>>,
+}
+
+/// Registered derived facts, kept in dependency order.
+///
+/// 已注册的派生事实，按依赖顺序保存。
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DerivedFacts {
+    entries: HashMap<String, DerivedEntry>,
+    order: Vec<String>,
+}
+
+impl DerivedFacts {
+    /// Register (or replace) a derived fact, rejecting it if it would form a cycle.
+    ///
+    /// 注册（或替换）派生事实；如果会形成环则拒绝。
+    pub fn register(&mut self, fact: DerivedFact) -> Result<(), DerivedFactCycleError> {
+        let dependencies: Vec<String> = fact.dependencies().into_iter().map(String::from).collect();
+        if let Some(dependency) = dependencies
+            .iter()
+            .find(|dependency| self.reaches(dependency, &fact.key))
+        {
+            return Err(DerivedFactCycleError {
+                key: fact.key,
+                dependency: dependency.clone(),
+            });
+        }
+        self.entries.insert(
+            fact.key.clone(),
+            DerivedEntry {
+                fact,
+                dependencies,
+                last_inputs: None,
+            },
+        );
+        self.rebuild_order();
+        Ok(())
+    }
+
+    /// Stop deriving a fact. Its last written value stays in the database.
+    ///
+    /// 停止派生某个事实。其最后写入的值保留在数据库中。
+    pub fn unregister(&mut self, key: &str) -> bool {
+        let removed = self.entries.remove(key).is_some();
+        self.order.retain(|entry| entry != key);
+        removed
+    }
+
+    pub fn get(&self, key: &str) -> Option<&DerivedFact> {
+        self.entries.get(key).map(|entry| &entry.fact)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `target` is `key` or one of its transitive derived dependencies.
+    fn reaches(&self, key: &str, target: &str) -> bool {
+        let mut stack = vec![key];
+        let mut visited = HashSet::new();
+        while let Some(key) = stack.pop() {
+            if key == target {
+                return true;
+            }
+            if !visited.insert(key) {
+                continue;
+            }
+            if let Some(entry) = self.entries.get(key) {
+                stack.extend(entry.dependencies.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    /// Order derived facts so every fact comes after the derived facts it reads.
+    fn rebuild_order(&mut self) {
+        let mut keys: Vec<&String> = self.entries.keys().collect();
+        keys.sort();
+        let mut order = Vec::with_capacity(keys.len());
+        let mut placed = HashSet::new();
+        for key in keys {
+            self.place(key, &mut placed, &mut order);
+        }
+        self.order = order;
+    }
+
+    fn place<'a>(&'a self, key: &'a str, placed: &mut HashSet<&'a str>, order: &mut Vec<String>) {
+        let Some(entry) = self.entries.get(key) else {
+            return;
+        };
+        if !placed.insert(key) {
+            return;
+        }
+        for dependency in &entry.dependencies {
+            self.place(dependency, placed, order);
+        }
+        order.push(key.to_string());
+    }
+
+    /// Recompute derived facts whose dependency values changed since the last recompute, or
+    /// that are missing from the database, e.g. after `clear_local` removed them while their
+    /// global inputs stayed the same. Facts whose formula cannot be evaluated (e.g. a missing
+    /// dependency) keep their value. Returns the number of facts whose value changed.
+    ///
+    /// 重新计算自上次计算以来依赖值发生变化的派生事实，或数据库中缺失的派生事实
+    /// （例如 `clear_local` 移除了它们而其全局输入保持不变）。
+    /// 公式无法求值（例如缺少依赖）的事实保持原值。返回值发生变化的事实数量。
+    pub fn recompute(&mut self, db: &mut LayeredFactDatabase) -> usize {
+        let mut changed = 0;
+        for key in &self.order {
+            let Some(entry) = self.entries.get_mut(key) else {
+                continue;
+            };
+            let inputs: Vec<Option<FactValue>> = entry
+                .dependencies
+                .iter()
+                .map(|dependency| db.get_by_str(dependency).cloned())
+                .collect();
+            if entry.last_inputs.as_ref() == Some(&inputs) && db.contains(key) {
+                continue;
+            }
+            entry.last_inputs = Some(inputs);
+            let Some(value) = evaluate_expr_to_fact(&entry.fact.expr, db) else {
+                continue;
+            };
+            if db.set_if_changed(key.clone(), value) {
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+/// System that recomputes derived facts after the database changed.
+///
+/// 在数据库发生变化后重新计算派生事实的系统。
+pub fn recompute_derived_facts_system(
+    mut derived: ResMut<DerivedFacts>,
+    mut db: ResMut<LayeredFactDatabase>,
+) {
+    if derived.is_empty() || !(db.is_changed() || derived.is_changed()) {
+        return;
+    }
+    // Only flag the database as changed when a derived value actually moved
+    if derived
+        .bypass_change_detection()
+        .recompute(db.bypass_change_detection())
+        > 0
+    {
+        db.set_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derived_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .init_resource::<DerivedFacts>()
+            .add_systems(Update, recompute_derived_facts_system);
+        app.world_mut()
+            .resource_mut::<DerivedFacts>()
+            .register(DerivedFact::new("hp_percent", "$hp / $max_hp * 100"))
+            .unwrap();
+        app
+    }
+
+    fn set_hp(app: &mut App, hp: i64) -> Option<i64> {
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("hp", hp);
+        app.update();
+        app.world()
+            .resource::<LayeredFactDatabase>()
+            .get_int("hp_percent")
+    }
+
+    #[test]
+    fn test_recompute_on_dependency_change() {
+        let mut app = derived_app();
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("max_hp", 200i64);
+
+        assert_eq!(set_hp(&mut app, 50), Some(25));
+        assert_eq!(set_hp(&mut app, 200), Some(100));
+
+        // Unrelated writes leave the derived fact alone
+        let mut db = app.world_mut().resource_mut::<LayeredFactDatabase>();
+        db.set("hp_percent", 7i64);
+        db.set("gold", 10i64);
+        app.update();
+        assert_eq!(
+            app.world()
+                .resource::<LayeredFactDatabase>()
+                .get_int("hp_percent"),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn test_cleared_derived_fact_is_restored() {
+        let mut app = derived_app();
+        let mut db = app.world_mut().resource_mut::<LayeredFactDatabase>();
+        db.set_global("hp", 30i64);
+        db.set_global("max_hp", 60i64);
+        app.update();
+
+        // Leaving the scene drops the derived fact, though none of its inputs changed
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .clear_local();
+        app.update();
+        assert_eq!(
+            app.world()
+                .resource::<LayeredFactDatabase>()
+                .get_int("hp_percent"),
+            Some(50)
+        );
+    }
+
+    #[test]
+    fn test_derived_chain_recomputes_in_order() {
+        let mut derived = DerivedFacts::default();
+        // Registered before the derived fact it reads
+        derived
+            .register(DerivedFact::new("hp_bar", "$hp_percent / 10"))
+            .unwrap();
+        derived
+            .register(DerivedFact::new("hp_percent", "$hp / $max_hp * 100"))
+            .unwrap();
+
+        let mut db = LayeredFactDatabase::new();
+        db.set("hp", 30i64);
+        db.set("max_hp", 60i64);
+        assert_eq!(derived.recompute(&mut db), 2);
+        assert_eq!(db.get_int("hp_bar"), Some(5));
+        assert_eq!(derived.recompute(&mut db), 0);
+    }
+
+    #[test]
+    fn test_cycles_are_rejected() {
+        let mut derived = DerivedFacts::default();
+        derived.register(DerivedFact::new("a", "$b + 1")).unwrap();
+        derived.register(DerivedFact::new("b", "$c * 2")).unwrap();
+
+        let err = derived
+            .register(DerivedFact::new("c", "$a - 1"))
+            .unwrap_err();
+        assert_eq!(err.key, "c");
+        assert_eq!(err.dependency, "a");
+        assert!(derived.get("c").is_none());
+
+        assert!(derived.register(DerivedFact::new("d", "$d + 1")).is_err());
+        assert_eq!(derived.len(), 2);
+    }
+}
@@ -0,0 +1,257 @@
+//! # entity_count.rs
+//!
+//! Entity-count providers that expose ECS world state to rule conditions.
+//! Game code registers named `Fn(&World) -> usize` providers; each frame before rules are
+//! processed, their counts are written to the local fact `entity_count:<name>`, so condition
+//! expressions can compare them like any other fact. The built-in condition
+//! `entity_count(enemies) < 5` reads that fact; if no count was written for the name, because no
+//! provider is registered under it, the condition fails with a warning.
+//!
+//! 将 ECS 世界状态暴露给规则条件的实体计数提供者。
+//! 游戏代码注册具名的 `Fn(&World) -> usize` 提供者；每帧在处理规则之前，
+//! 它们的计数会写入局部事实 `entity_count:<name>`，因此条件表达式可以像比较其他事实一样比较它们。
+//! 内置条件 `entity_count(enemies) < 5` 读取该事实；如果该名称没有写入计数（因为没有以此名称
+//! 注册提供者），条件失败并发出警告。
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::database::FactReader;
+use crate::layered::LayeredFactDatabase;
+
+/// Prefix of the facts written by entity-count providers.
+///
+/// 实体计数提供者写入的事实的前缀。
+pub const ENTITY_COUNT_PREFIX: &str = "entity_count:";
+
+/// Fact key holding the count of the provider `name`.
+///
+/// 保存提供者 `name` 计数的事实键。
+pub fn entity_count_key(name: &str) -> String {
+    format!("{}{}", ENTITY_COUNT_PREFIX, name)
+}
+
+type CountProvider = Arc<dyn Fn(&World) -> usize + Send + Sync>;
+
+/// Named providers counting entities in the world.
+///
+/// 统计世界中实体数量的具名提供者。
+#[derive(Resource, Default, Clone)]
+pub struct EntityCountProviders {
+    providers: HashMap<String, CountProvider>,
+}
+
+impl EntityCountProviders {
+    /// Register (or replace) a provider under `name`.
+    ///
+    /// 以 `name` 注册（或替换）一个提供者。
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        provider: impl Fn(&World) -> usize + Send + Sync + 'static,
+    ) {
+        self.providers.insert(name.into(), Arc::new(provider));
+    }
+
+    /// Remove a provider. Its fact is left as last written.
+    ///
+    /// 移除一个提供者。其事实保留最后一次写入的值。
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.providers.remove(name).is_some()
+    }
+
+    /// Evaluate a single provider, or None if no provider is registered under `name`.
+    ///
+    /// 评估单个提供者；如果 `name` 下没有注册提供者则返回 None。
+    pub fn count(&self, name: &str, world: &World) -> Option<usize> {
+        self.providers.get(name).map(|provider| provider(world))
+    }
+
+    /// Whether no provider is registered.
+    ///
+    /// 是否没有注册任何提供者。
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+/// A parsed `entity_count(name) < limit` condition.
+///
+/// 已解析的 `entity_count(name) < limit` 条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityCountLessThan<'a> {
+    pub name: &'a str,
+    pub limit: usize,
+}
+
+impl<'a> EntityCountLessThan<'a> {
+    /// Parse a condition expression, returning None if it is not an entity count condition.
+    ///
+    /// 解析条件表达式；如果不是实体计数条件则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let rest = condition.trim().strip_prefix("entity_count(")?;
+        let (name, comparison) = rest.split_once(')')?;
+        let limit = comparison.trim().strip_prefix('<')?.trim().parse().ok()?;
+        Some(Self {
+            name: name.trim(),
+            limit,
+        })
+    }
+
+    /// Whether the provider's last count is below the limit. Fails with a warning if no count
+    /// was written for the provider.
+    ///
+    /// 提供者最近一次的计数是否低于上限。如果没有为该提供者写入计数，则条件失败并发出警告。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        let Some(count) = facts.get_int(&entity_count_key(self.name)) else {
+            warn!(
+                "FRE: No entity count provider registered as '{}'",
+                self.name
+            );
+            return false;
+        };
+        count < self.limit as i64
+    }
+}
+
+/// Exclusive system that writes every provider's count into the local fact layer.
+///
+/// 将每个提供者的计数写入局部事实层的独占系统。
+pub fn refresh_entity_counts_system(world: &mut World) {
+    let Some(providers) = world.get_resource::<EntityCountProviders>() else {
+        return;
+    };
+    if providers.is_empty() {
+        return;
+    }
+    let counts: Vec<(String, i64)> = providers
+        .providers
+        .iter()
+        .map(|(name, provider)| (entity_count_key(name), provider(world) as i64))
+        .collect();
+
+    let mut db = world.resource_mut::<LayeredFactDatabase>();
+    for (key, count) in counts {
+        db.set_if_changed(key, count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry};
+    use crate::event::FactEvent;
+    use crate::rule::{FactModification, FreRng, LayeredRuleRegistry, Rule};
+    use crate::systems::{ConditionEvaluator, PendingFactEvents, process_rules_system};
+
+    #[derive(Component)]
+    struct Enemy;
+
+    fn spawn_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<PendingFactEvents>()
+            .init_resource::<EnumRegistry>()
+            .init_resource::<FreRng>()
+            .init_resource::<EntityCountProviders>()
+            .init_resource::<ConditionEvaluator>()
+            .add_message::<FactEvent>()
+            .add_systems(
+                Update,
+                (
+                    refresh_entity_counts_system,
+                    process_rules_system::<CoreActionDef>,
+                )
+                    .chain(),
+            );
+        app.world_mut()
+            .resource_mut::<EntityCountProviders>()
+            .register("enemies", |world: &World| {
+                world
+                    .try_query_filtered::<(), With<Enemy>>()
+                    .map_or(0, |query| query.iter_manual(world).count())
+            });
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+            .register(
+                Rule::builder("spawn_wave", "wave_timer")
+                    .condition_expr("entity_count(enemies) < 5")
+                    .modify(FactModification::Increment("waves".to_string(), 1))
+                    .build(),
+            );
+        app
+    }
+
+    fn send_wave_timer(app: &mut App) -> i64 {
+        app.world_mut().write_message(FactEvent::new("wave_timer"));
+        app.update();
+        app.world()
+            .resource::<LayeredFactDatabase>()
+            .get_int_or("waves", 0)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            EntityCountLessThan::parse("entity_count(enemies) < 5"),
+            Some(EntityCountLessThan {
+                name: "enemies",
+                limit: 5
+            })
+        );
+        assert_eq!(
+            EntityCountLessThan::parse("entity_count(enemies) > 5"),
+            None
+        );
+        assert_eq!(
+            EntityCountLessThan::parse("entity_count(enemies) < -1"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rule_gated_on_entity_count() {
+        let mut app = spawn_app();
+        for _ in 0..3 {
+            app.world_mut().spawn(Enemy);
+        }
+        assert_eq!(send_wave_timer(&mut app), 1);
+        assert_eq!(
+            app.world()
+                .resource::<LayeredFactDatabase>()
+                .get_int("entity_count:enemies"),
+            Some(3)
+        );
+
+        for _ in 0..3 {
+            app.world_mut().spawn(Enemy);
+        }
+        // Six enemies: the rule no longer fires
+        assert_eq!(send_wave_timer(&mut app), 1);
+    }
+
+    #[test]
+    fn test_unknown_provider_fails_the_rule() {
+        let mut app = spawn_app();
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+            .register(
+                Rule::builder("summon_boss", "wave_timer")
+                    .condition_expr("entity_count(bosses) < 1")
+                    .modify(FactModification::Increment(
+                        "bosses_summoned".to_string(),
+                        1,
+                    ))
+                    .build(),
+            );
+        assert_eq!(send_wave_timer(&mut app), 1);
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert!(!db.contains("bosses_summoned"));
+
+        let providers = app.world().resource::<EntityCountProviders>();
+        assert_eq!(providers.count("bosses", app.world()), None);
+        assert_eq!(providers.count("enemies", app.world()), Some(0));
+    }
+}
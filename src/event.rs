@@ -5,30 +5,19 @@
 //! FRE 的事件系统 - 事件是不包含逻辑的纯信号。
 
 use bevy::prelude::*;
+use std::collections::HashMap;
 
-/// Unique identifier for an event type.
-///
-/// 事件类型的唯一标识符。
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct FactEventId(pub String);
+use crate::database::FactValue;
 
-impl FactEventId {
-    pub fn new(id: impl Into<String>) -> Self {
-        Self(id.into())
-    }
-}
+mod data_condition;
+mod id;
+mod payload_condition;
 
-impl From<&str> for FactEventId {
-    fn from(s: &str) -> Self {
-        Self(s.to_string())
-    }
-}
-
-impl From<String> for FactEventId {
-    fn from(s: String) -> Self {
-        Self(s)
-    }
-}
+pub use data_condition::{EVENT_DATA_PREFIX, EventDataCondition};
+pub use id::FactEventId;
+#[doc(hidden)]
+pub use id::distinct_ids;
+pub use payload_condition::{EVENT_PAYLOAD_PREFIX, PayloadListLenAtLeast};
 
 /// A fact event - a signal that can trigger rules.
 /// Events are pure data carriers with no logic.
@@ -36,6 +25,7 @@ impl From<String> for FactEventId {
 /// 事实事件 - 可以触发规则的信号。
 /// 事件是不包含逻辑的纯数据载体。
 #[derive(Message, Debug, Clone)]
+#[cfg_attr(feature = "debug", derive(Reflect))]
 pub struct FactEvent {
     /// The unique identifier for this event type.
     ///
@@ -47,10 +37,28 @@ pub struct FactEvent {
     /// 与此事件关联的可选实体。
     pub entity: Option<Entity>,
 
-    /// Optional additional data as key-value pairs.
+    /// Optional additional data as key-value pairs, read through `get_data` and `data`.
+    /// The map is only created by the first `with_data` insert, so events without data
+    /// carry (and clone) no heap allocation.
+    ///
+    /// 作为键值对的可选附加数据，通过 `get_data` 和 `data` 读取。
+    /// 该映射仅在第一次 `with_data` 插入时创建，因此没有数据的事件在创建和克隆时都没有堆分配。
+    data: Option<HashMap<String, String>>,
+
+    /// Optional typed payload, read through `get_fact_data` and `typed_data`. It is separate
+    /// from the string data: a key set with `with_fact_data` is not visible to `get_data`.
+    /// Like `data`, the map is only created by the first insert.
+    ///
+    /// 可选的类型化负载，通过 `get_fact_data` 和 `typed_data` 读取。它与字符串数据相互独立：
+    /// 通过 `with_fact_data` 设置的键对 `get_data` 不可见。与 `data` 相同，该映射仅在第一次插入时创建。
+    typed_data: Option<HashMap<String, FactValue>>,
+
+    /// Number of rule outputs between this event and the event that started the chain.
+    /// Events sent directly have depth 0; `PendingFactEvents::queue_output` adds one.
     ///
-    /// 作为键值对的可选附加数据。
-    pub data: std::collections::HashMap<String, String>,
+    /// 此事件与引发事件链的初始事件之间经过的规则输出数量。
+    /// 直接发送的事件深度为 0；`PendingFactEvents::queue_output` 会加一。
+    pub depth: u32,
 }
 
 impl FactEvent {
@@ -61,7 +69,9 @@ impl FactEvent {
         Self {
             id: id.into(),
             entity: None,
-            data: std::collections::HashMap::new(),
+            data: None,
+            typed_data: None,
+            depth: 0,
         }
     }
 
@@ -72,7 +82,9 @@ impl FactEvent {
         Self {
             id: id.into(),
             entity: Some(entity),
-            data: std::collections::HashMap::new(),
+            data: None,
+            typed_data: None,
+            depth: 0,
         }
     }
 
@@ -80,7 +92,9 @@ impl FactEvent {
     ///
     /// 向事件添加数据。
     pub fn with_data(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
-        self.data.insert(key.into(), value.into());
+        self.data
+            .get_or_insert_default()
+            .insert(key.into(), value.into());
         self
     }
 
@@ -88,6 +102,128 @@ impl FactEvent {
     ///
     /// 从事件获取数据。
     pub fn get_data(&self, key: &str) -> Option<&String> {
-        self.data.get(key)
+        self.data.as_ref()?.get(key)
+    }
+
+    /// All data carried by the event, or None if it carries none.
+    ///
+    /// 事件携带的全部数据；未携带任何数据时返回 None。
+    pub fn data(&self) -> Option<&HashMap<String, String>> {
+        self.data.as_ref()
+    }
+
+    /// Add a typed value to the event's payload.
+    ///
+    /// 向事件负载添加类型化的值。
+    pub fn with_fact_data(mut self, key: impl Into<String>, value: impl Into<FactValue>) -> Self {
+        self.typed_data
+            .get_or_insert_default()
+            .insert(key.into(), value.into());
+        self
+    }
+
+    /// Get a typed value from the event's payload.
+    ///
+    /// 从事件负载获取类型化的值。
+    pub fn get_fact_data(&self, key: &str) -> Option<&FactValue> {
+        self.typed_data.as_ref()?.get(key)
+    }
+
+    /// The whole typed payload, or None if the event carries none.
+    ///
+    /// 事件的全部类型化负载；未携带任何负载时返回 None。
+    pub fn typed_data(&self) -> Option<&HashMap<String, FactValue>> {
+        self.typed_data.as_ref()
+    }
+
+    /// Copy of this event under a different id, keeping its entity, depth, and optionally its
+    /// string and typed data.
+    ///
+    /// 以不同 ID 复制此事件，保留其实体和深度，并可选择保留其字符串数据和类型化数据。
+    pub fn derive(&self, id: FactEventId, keep_data: bool) -> Self {
+        Self {
+            id,
+            entity: self.entity,
+            data: if keep_data { self.data.clone() } else { None },
+            typed_data: if keep_data {
+                self.typed_data.clone()
+            } else {
+                None
+            },
+            depth: self.depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc_count::allocations_during;
+
+    #[test]
+    fn test_event_data_accessors() {
+        let event = FactEvent::new("item_picked")
+            .with_data("item", "potion")
+            .with_data("count", "3");
+
+        assert_eq!(event.get_data("item"), Some(&"potion".to_string()));
+        assert_eq!(event.get_data("count"), Some(&"3".to_string()));
+        assert_eq!(event.get_data("missing"), None);
+
+        // Later values overwrite earlier ones
+        let event = event.with_data("item", "ether");
+        assert_eq!(event.get_data("item"), Some(&"ether".to_string()));
+    }
+
+    #[test]
+    fn test_typed_data_round_trips() {
+        let event = FactEvent::new("damaged")
+            .with_data("source", "trap")
+            .with_fact_data("amount", 12i64)
+            .with_fact_data("critical", true);
+
+        assert_eq!(event.get_fact_data("amount"), Some(&FactValue::Int(12)));
+        assert_eq!(
+            event.get_fact_data("amount").and_then(FactValue::as_int),
+            Some(12)
+        );
+        assert_eq!(
+            event.get_fact_data("critical").and_then(FactValue::as_bool),
+            Some(true)
+        );
+        assert_eq!(event.get_fact_data("source"), None);
+
+        // Legacy string keys read as before, and the two payloads stay apart
+        assert_eq!(event.get_data("source"), Some(&"trap".to_string()));
+        assert_eq!(event.get_data("amount"), None);
+        assert_eq!(event.data().map(HashMap::len), Some(1));
+
+        let derived = event.derive(FactEventId::new("hurt"), true);
+        assert_eq!(
+            derived.get_fact_data("critical"),
+            Some(&FactValue::Bool(true))
+        );
+        let bare = event.derive(FactEventId::new("hurt"), false);
+        assert!(bare.typed_data().is_none());
+    }
+
+    #[test]
+    fn test_empty_events_do_not_allocate_data() {
+        let id = FactEventId::new("tick");
+        let mut events = Vec::with_capacity(20_000);
+        let allocations = allocations_during(|| {
+            for _ in 0..10_000 {
+                events.push(FactEvent::new(id.clone()));
+                events.push(FactEvent::with_entity(id.clone(), Entity::PLACEHOLDER));
+            }
+            let _ = events[0].clone();
+        });
+        assert_eq!(allocations, 0);
+        assert!(events.iter().all(|event| event.data().is_none()));
+        assert!(events.iter().all(|event| event.get_data("any").is_none()));
+
+        // Only the first insert allocates the map
+        let event = FactEvent::new(id).with_data("a", "1");
+        assert_eq!(event.data().map(HashMap::len), Some(1));
     }
 }
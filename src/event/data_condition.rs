@@ -0,0 +1,158 @@
+//! # data_condition.rs
+//!
+//! # data_condition.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Built-in conditions on the triggering event's `data` map. A rule condition written as
+//! `event.data.<key>` passes when the event carries `<key>`, and `event.data.<key> == "value"`
+//! passes when it carries exactly that value. These are checked by `ConditionEvaluator` itself,
+//! so RON rules can filter on event data without a custom evaluator; all other conditions are
+//! still handed to the game's `ConditionEvaluatorTrait`. Outside of an event, such as in a
+//! `FactQuery`, data conditions always fail.
+//!
+//! 针对触发事件 `data` 映射的内置条件。写作 `event.data.<key>` 的规则条件在事件携带 `<key>`
+//! 时通过，`event.data.<key> == "value"` 在事件携带恰好该值时通过。这些条件由
+//! `ConditionEvaluator` 自身检查，因此 RON 规则无需自定义评估器即可按事件数据过滤；
+//! 其他所有条件仍交给游戏的 `ConditionEvaluatorTrait`。在事件之外（例如 `FactQuery` 中），
+//! 数据条件总是失败。
+
+use super::FactEvent;
+
+/// Prefix of the built-in event data conditions.
+///
+/// 内置事件数据条件的前缀。
+pub const EVENT_DATA_PREFIX: &str = "event.data.";
+
+/// A parsed built-in condition on the event's data map.
+///
+/// 已解析的针对事件数据映射的内置条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventDataCondition<'a> {
+    /// `event.data.<key>`: the key is present.
+    ///
+    /// `event.data.<key>`：键存在。
+    Exists(&'a str),
+
+    /// `event.data.<key> == "value"`: the key is present with this value.
+    ///
+    /// `event.data.<key> == "value"`：键存在且值相等。
+    Equals(&'a str, &'a str),
+}
+
+impl<'a> EventDataCondition<'a> {
+    /// Parse a condition expression, returning None if it is not an event data condition.
+    /// Values may be written with or without double quotes.
+    ///
+    /// 解析条件表达式；如果不是事件数据条件则返回 None。值可以带或不带双引号。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let rest = condition.trim().strip_prefix(EVENT_DATA_PREFIX)?;
+        let Some((key, value)) = rest.split_once("==") else {
+            return Some(EventDataCondition::Exists(rest.trim()));
+        };
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        Some(EventDataCondition::Equals(key.trim(), value))
+    }
+
+    /// Whether the event satisfies the condition.
+    ///
+    /// 事件是否满足该条件。
+    pub fn matches(&self, event: &FactEvent) -> bool {
+        match self {
+            EventDataCondition::Exists(key) => event.get_data(key).is_some(),
+            EventDataCondition::Equals(key, value) => {
+                event.get_data(key).is_some_and(|data| data == value)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry, FreAsset};
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::{FreRng, LayeredRuleRegistry};
+    use crate::systems::{ConditionEvaluator, LiveSink, PendingFactEvents, process_event_rules};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            EventDataCondition::parse("event.data.door"),
+            Some(EventDataCondition::Exists("door"))
+        );
+        assert_eq!(
+            EventDataCondition::parse(r#"event.data.door == "north""#),
+            Some(EventDataCondition::Equals("door", "north"))
+        );
+        assert_eq!(
+            EventDataCondition::parse("event.data.count==3"),
+            Some(EventDataCondition::Equals("count", "3"))
+        );
+        assert_eq!(EventDataCondition::parse("$hp > 3"), None);
+    }
+
+    /// Send `event` through a rule filtering on event data and return whether it fired.
+    fn fires(condition: &str, event: FactEvent) -> bool {
+        let asset: FreAsset = ron::from_str(&format!(
+            r#"(rules: [(id: "open", event: Event("use_door"), conditions: [{:?}], outputs: ["opened"])])"#,
+            condition
+        ))
+        .unwrap();
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        asset.register_rules_layered(&mut registry);
+
+        let mut pending = PendingFactEvents::default();
+        process_event_rules(
+            &event,
+            registry.get_matching_rules_grouped(&event),
+            &mut LiveSink {
+                pending: &mut pending,
+                db: &mut LayeredFactDatabase::new(),
+            },
+            &ConditionEvaluator::default(),
+            &EnumRegistry::default(),
+            &mut FreRng::seeded(0),
+        );
+        !pending.events.is_empty()
+    }
+
+    #[test]
+    fn test_rules_filter_on_event_data() {
+        let north = || FactEvent::new("use_door").with_data("door", "north");
+        let south = FactEvent::new("use_door").with_data("door", "south");
+        let equals = r#"event.data.door == "north""#;
+
+        assert!(fires(equals, north()));
+        assert!(!fires(equals, south));
+        assert!(!fires(equals, FactEvent::new("use_door")));
+
+        assert!(fires("event.data.door", north()));
+        assert!(!fires("event.data.door", FactEvent::new("use_door")));
+    }
+
+    #[test]
+    fn test_builtins_without_an_event() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("hp", 2.5);
+        let evaluator = ConditionEvaluator::default();
+        let passes = |conditions: &[&str]| {
+            let conditions: Vec<String> = conditions.iter().map(|c| c.to_string()).collect();
+            evaluator.evaluate_conditions(&conditions, &db, &EnumRegistry::default())
+        };
+
+        assert!(passes(&["float($hp) > 1.0"]));
+        assert!(!passes(&["float($hp) > 3.0"]));
+        assert!(!passes(&["event.data.door"]));
+        assert!(!passes(&[
+            "float($hp) > 1.0",
+            r#"event.data.door == "north""#
+        ]));
+    }
+}
@@ -0,0 +1,235 @@
+//! # id.rs
+//!
+//! # id.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Event ids and the `event_ids!` macro for declaring them once. Ids stay strings at runtime,
+//! but a game that names every event through constants gets a compile error for a misspelled
+//! constant instead of a rule that silently never fires. `FactEventId::from_static` builds an
+//! id in a `const`, and `event_ids!` declares a module of such constants, rejecting two
+//! constants with the same id at compile time.
+//!
+//! 事件 ID 以及用于集中声明它们的 `event_ids!` 宏。ID 在运行时仍是字符串，
+//! 但通过常量命名所有事件的游戏在常量拼写错误时会得到编译错误，而不是一条静默地永不触发的规则。
+//! `FactEventId::from_static` 可在 `const` 中构建 ID，`event_ids!` 声明由此类常量组成的模块，
+//! 并在编译时拒绝 ID 相同的两个常量。
+
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Unique identifier for an event type.
+/// The name is either a `'static` string or shared behind an `Arc`, so cloning an id is at most
+/// a reference count bump. Ids compare, order and hash by name.
+///
+/// 事件类型的唯一标识符。
+/// 名称是 `'static` 字符串或通过 `Arc` 共享，因此克隆 ID 至多增加引用计数。
+/// ID 按名称比较、排序和哈希。
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "debug",
+    derive(Reflect),
+    reflect(opaque, Clone, Debug, PartialEq, Hash)
+)]
+pub struct FactEventId(Name);
+
+#[derive(Clone)]
+enum Name {
+    Static(&'static str),
+    Shared(Arc<str>),
+}
+
+impl FactEventId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(Name::Shared(Arc::from(id.into())))
+    }
+
+    /// Id usable in a `const`, such as the constants declared by `event_ids!`.
+    ///
+    /// 可在 `const` 中使用的 ID，例如 `event_ids!` 声明的常量。
+    pub const fn from_static(id: &'static str) -> Self {
+        Self(Name::Static(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Name::Static(id) => id,
+            Name::Shared(id) => id,
+        }
+    }
+}
+
+impl From<&str> for FactEventId {
+    fn from(s: &str) -> Self {
+        Self(Name::Shared(Arc::from(s)))
+    }
+}
+
+impl From<String> for FactEventId {
+    fn from(s: String) -> Self {
+        Self(Name::Shared(Arc::from(s)))
+    }
+}
+
+impl PartialEq for FactEventId {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for FactEventId {}
+
+impl PartialOrd for FactEventId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FactEventId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for FactEventId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialEq<str> for FactEventId {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for FactEventId {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for FactEventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FactEventId").field(&self.as_str()).finish()
+    }
+}
+
+impl fmt::Display for FactEventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether no two of `ids` are equal; evaluated at compile time by `event_ids!`.
+#[doc(hidden)]
+pub const fn distinct_ids(ids: &[&str]) -> bool {
+    let mut i = 0;
+    while i < ids.len() {
+        let mut j = i + 1;
+        while j < ids.len() {
+            if same_str(ids[i], ids[j]) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn same_str(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Declare a module of `FactEventId` constants. Two constants with the same id fail to compile.
+///
+/// 声明由 `FactEventId` 常量组成的模块。ID 相同的两个常量会导致编译失败。
+///
+/// ```
+/// bevy_fact_rule_event::event_ids! {
+///     pub mod events {
+///         DOOR_OPENED = "door_opened",
+///         DOOR_LOCKED = "door_locked",
+///     }
+/// }
+/// assert_eq!(events::DOOR_OPENED, "door_opened");
+/// ```
+#[macro_export]
+macro_rules! event_ids {
+    ($vis:vis mod $module:ident { $($name:ident = $id:literal),* $(,)? }) => {
+        $vis mod $module {
+            $(
+                pub const $name: $crate::FactEventId = $crate::FactEventId::from_static($id);
+            )*
+
+            const _: () = assert!(
+                $crate::__distinct_event_ids(&[$($id),*]),
+                "event_ids!: two constants share an id"
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CoreActionDef;
+    use crate::event::FactEvent;
+    use crate::rule::Rule;
+    use std::collections::HashSet;
+
+    crate::event_ids! {
+        mod events {
+            CHEST_OPENED = "chest_opened",
+            LOOT_DROPPED = "loot_dropped",
+        }
+    }
+
+    #[test]
+    fn test_static_and_shared_ids_agree() {
+        assert_eq!(events::CHEST_OPENED, FactEventId::new("chest_opened"));
+        assert_eq!(events::CHEST_OPENED.as_str(), "chest_opened");
+        assert_eq!(
+            format!("{:?}", events::LOOT_DROPPED),
+            r#"FactEventId("loot_dropped")"#
+        );
+        let ids: HashSet<FactEventId> = [events::CHEST_OPENED, FactEventId::from("chest_opened")]
+            .into_iter()
+            .collect();
+        assert_eq!(ids.len(), 1);
+        assert!(!distinct_ids(&["a", "b", "a"]));
+        assert!(distinct_ids(&["a", "ab", "b"]));
+    }
+
+    #[test]
+    fn test_constants_in_rules_and_events() {
+        let rule = Rule::<CoreActionDef>::builder("drop_loot", events::CHEST_OPENED)
+            .output(events::LOOT_DROPPED)
+            .build();
+        assert!(rule.matches_event(&FactEvent::new(events::CHEST_OPENED)));
+        assert!(rule.matches_event(&FactEvent::new("chest_opened")));
+        assert_eq!(rule.outputs, vec![events::LOOT_DROPPED]);
+        assert_eq!(
+            rule.output_event(&events::LOOT_DROPPED, &FactEvent::new("chest_opened"))
+                .id,
+            "loot_dropped"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! # payload_condition.rs
+//!
+//! # payload_condition.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Built-in condition on the length of a list in the triggering event's typed payload, for
+//! multi-target effects. `event.payload.<key>.len() >= N` passes when the payload entry `<key>`
+//! is a list of any element type with at least `N` elements. A missing entry or a scalar value
+//! fails the condition, as does evaluating it outside of an event.
+//!
+//! 针对触发事件类型化负载中列表长度的内置条件，用于多目标效果。
+//! 当负载条目 `<key>` 是元素数量至少为 `N` 的任意类型列表时，`event.payload.<key>.len() >= N` 通过。
+//! 条目缺失或为标量值时条件不通过，在事件之外评估时同样不通过。
+
+use super::FactEvent;
+use crate::database::FactValue;
+
+/// Prefix of the built-in event payload conditions.
+///
+/// 内置事件负载条件的前缀。
+pub const EVENT_PAYLOAD_PREFIX: &str = "event.payload.";
+
+/// A parsed `event.payload.<key>.len() >= N` condition.
+///
+/// 已解析的 `event.payload.<key>.len() >= N` 条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadListLenAtLeast<'a>(pub &'a str, pub usize);
+
+impl<'a> PayloadListLenAtLeast<'a> {
+    /// Parse a condition expression, returning None if it is not a payload length condition.
+    ///
+    /// 解析条件表达式；如果不是负载长度条件则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let rest = condition.trim().strip_prefix(EVENT_PAYLOAD_PREFIX)?;
+        let (key, min) = rest.split_once(">=")?;
+        let key = key.trim().strip_suffix(".len()")?;
+        Some(PayloadListLenAtLeast(key, min.trim().parse().ok()?))
+    }
+
+    /// Whether the event's payload holds a list under the key with at least the minimum length.
+    ///
+    /// 事件负载在该键下是否持有长度不小于最小值的列表。
+    pub fn matches(&self, event: &FactEvent) -> bool {
+        event
+            .get_fact_data(self.0)
+            .and_then(FactValue::list_len)
+            .is_some_and(|len| len >= self.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry};
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::Rule;
+    use crate::systems::ConditionEvaluator;
+
+    const CONDITION: &str = "event.payload.targets.len() >= 2";
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            PayloadListLenAtLeast::parse(" event.payload.targets.len()>=3 "),
+            Some(PayloadListLenAtLeast("targets", 3))
+        );
+        assert_eq!(PayloadListLenAtLeast::parse("event.payload.targets"), None);
+        assert_eq!(
+            PayloadListLenAtLeast::parse("event.payload.targets.len() >= -1"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_list_length_threshold() {
+        let evaluator = ConditionEvaluator::default();
+        let db = LayeredFactDatabase::new();
+        let mut rule = Rule::<CoreActionDef>::builder("cleave", "attack")
+            .condition_expr(CONDITION)
+            .build();
+        let attack = |payload: Option<FactValue>| match payload {
+            Some(value) => FactEvent::new("attack").with_fact_data("targets", value),
+            None => FactEvent::new("attack"),
+        };
+        let cases = [
+            (Some(vec!["orc"].into()), false),
+            (Some(vec!["orc", "goblin"].into()), true),
+            (Some(vec![1i64, 2, 3].into()), true),
+            (Some(Vec::<i64>::new().into()), false),
+            (Some(FactValue::Int(5)), false),
+            (None, false),
+        ];
+
+        for (payload, expected) in cases.clone() {
+            let event = attack(payload);
+            let passed = evaluator.evaluate_conditions_for_event(
+                &[CONDITION.to_string()],
+                &event,
+                &db,
+                &EnumRegistry::default(),
+            );
+            assert_eq!(passed, expected);
+        }
+        assert!(rule.compile_conditions());
+        for (payload, expected) in cases {
+            let event = attack(payload);
+            let passed = evaluator.evaluate_for_event(&rule, &event, &db, &EnumRegistry::default());
+            assert_eq!(passed, expected);
+        }
+        // Outside of an event the condition fails
+        assert!(!evaluator.evaluate_conditions(
+            &[CONDITION.to_string()],
+            &db,
+            &EnumRegistry::default()
+        ));
+    }
+}
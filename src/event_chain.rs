@@ -0,0 +1,124 @@
+//! # event_chain.rs
+//!
+//! Guard against rules that keep triggering each other. Every queued rule output is one level
+//! deeper than the event that fired the rule, so two rules that emit each other's trigger build
+//! an ever deeper chain across frames. `process_rules_system` drops events deeper than
+//! `EventChainLimit::max_depth` (32 unless `FREPlugin::max_event_depth` says otherwise) and
+//! warns once per event id, which ends the loop instead of running it forever.
+//!
+//! 防止规则相互无限触发的保护。每个排队的规则输出都比触发该规则的事件深一层，
+//! 因此相互发出对方触发器的两条规则会跨帧形成越来越深的事件链。`process_rules_system`
+//! 会丢弃深度超过 `EventChainLimit::max_depth`（除非 `FREPlugin::max_event_depth` 另有设置，
+//! 否则为 32）的事件，并对每个事件 ID 只警告一次，从而终止循环而不是永远运行。
+
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::event::{FactEvent, FactEventId};
+
+/// Default maximum depth of an event chain.
+///
+/// 事件链的默认最大深度。
+pub const DEFAULT_MAX_EVENT_DEPTH: u32 = 32;
+
+/// Maximum depth of events that rules are evaluated against.
+///
+/// 规则评估所接受事件的最大深度。
+#[derive(Resource, Debug, Clone)]
+pub struct EventChainLimit {
+    pub max_depth: u32,
+    overflowed: HashSet<FactEventId>,
+}
+
+impl Default for EventChainLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_EVENT_DEPTH)
+    }
+}
+
+impl EventChainLimit {
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+            overflowed: HashSet::new(),
+        }
+    }
+
+    /// Ids of the events dropped for exceeding the limit.
+    ///
+    /// 因超出限制而被丢弃的事件 ID。
+    pub fn overflowed(&self) -> impl Iterator<Item = &FactEventId> {
+        self.overflowed.iter()
+    }
+
+    /// Whether rules may process `event`. Warns the first time an event id is dropped.
+    pub(crate) fn admits(&mut self, event: &FactEvent) -> bool {
+        if event.depth <= self.max_depth {
+            return true;
+        }
+        if self.overflowed.insert(event.id.clone()) {
+            warn!(
+                "FRE: Dropping event '{}' at chain depth {} (limit {}); rules may be triggering each other in a loop",
+                event.id, event.depth, self.max_depth
+            );
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::{FactModification, LayeredRuleRegistry, Rule};
+    use crate::systems::PendingFactEvents;
+    use crate::{CoreActionDef, FREPlugin};
+
+    #[test]
+    fn test_ping_pong_rules_terminate() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            FREPlugin::<CoreActionDef> {
+                max_event_depth: 5,
+                ..Default::default()
+            },
+        ));
+        {
+            let mut registry = app
+                .world_mut()
+                .resource_mut::<LayeredRuleRegistry<CoreActionDef>>();
+            for (id, trigger, output) in [("ping", "ping", "pong"), ("pong", "pong", "ping")] {
+                registry.register(
+                    Rule::builder(id, trigger)
+                        .modify(FactModification::Increment("volleys".to_string(), 1))
+                        .output(output)
+                        .build(),
+                );
+            }
+        }
+
+        app.world_mut().write_message(FactEvent::new("ping"));
+        for _ in 0..20 {
+            app.update();
+        }
+
+        // Depths 0 through 5 are processed, the depth 6 event is dropped
+        assert_eq!(
+            app.world()
+                .resource::<LayeredFactDatabase>()
+                .get_int("volleys"),
+            Some(6)
+        );
+        assert!(
+            app.world()
+                .resource::<PendingFactEvents>()
+                .events
+                .is_empty()
+        );
+        let limit = app.world().resource::<EventChainLimit>();
+        let overflowed: Vec<&FactEventId> = limit.overflowed().collect();
+        assert_eq!(overflowed, vec![&FactEventId::new("ping")]);
+    }
+}
@@ -1,12 +1,20 @@
 //! # expr.rs
 //!
-//! Simple expression evaluation for FRE modifications.
-//! Supports arithmetic operations on fact values.
+//! Simple expression evaluation for FRE modifications and conditions.
+//! Supports arithmetic operations on fact values, and comparisons and boolean logic that
+//! evaluate to 1.0 (true) or 0.0 (false). Strings take part only in `==` and `!=`.
 //!
-//! FRE 修改器的简单表达式求值。
-//! 支持对 fact 值进行算术运算。
+//! FRE 修改器和条件的简单表达式求值。
+//! 支持对 fact 值进行算术运算，以及求值为 1.0（真）或 0.0（假）的比较和布尔逻辑。
+//! 字符串只参与 `==` 和 `!=`。
 
-use crate::database::{FactReader, FactValue};
+use crate::database::{FactLayer, FactReader, FactValue, float_to_fact};
+
+mod bounds;
+mod function;
+mod text;
+
+use function::Function;
 
 /// Evaluate a simple arithmetic expression.
 ///
@@ -14,26 +22,176 @@ use crate::database::{FactReader, FactValue};
 ///
 /// Supported syntax:
 /// - `$key` - Reference to a fact value
+/// - `fact('key')` or `global('key')` - Reference to a fact in the global layer only
 /// - Numbers (integers and floats)
+/// - Quoted strings (`'battle'` or `"battle"`), only as operands of `==` and `!=`
 /// - Operators: `+`, `-`, `*`, `/`, `%`
+/// - Comparisons: `==`, `!=`, `<`, `>`, `<=`, `>=`, giving 1.0 or 0.0. When either side of
+///   `==` or `!=` is a string fact or literal, the sides compare as strings, and a string never
+///   equals a number
+/// - Logic: `&&`, `||`, treating any non-zero value as true and giving 1.0 or 0.0
+/// - Functions: `min(a, b, ...)`, `max(a, b, ...)`, `abs(x)`, `clamp(x, min, max)`,
+///   `floor(x)`, `ceil(x)`
 /// - Parentheses for grouping
 ///
 /// 支持的语法：
 /// - `$key` - 引用 fact 值
+/// - `fact('key')` 或 `global('key')` - 仅引用全局层中的 fact 值
 /// - 数字（整数和浮点数）
+/// - 带引号的字符串（`'battle'` 或 `"battle"`），仅作为 `==` 和 `!=` 的操作数
 /// - 运算符：`+`、`-`、`*`、`/`、`%`
+/// - 比较：`==`、`!=`、`<`、`>`、`<=`、`>=`，结果为 1.0 或 0.0。当 `==` 或 `!=`
+///   任一侧是字符串事实或字面量时按字符串比较，字符串永远不等于数字
+/// - 逻辑：`&&`、`||`，任何非零值视为真，结果为 1.0 或 0.0
+/// - 函数：`min(a, b, ...)`、`max(a, b, ...)`、`abs(x)`、`clamp(x, min, max)`、
+///   `floor(x)`、`ceil(x)`
 /// - 括号用于分组
 ///
 /// Returns the result as f64, or None if evaluation fails.
 pub fn evaluate_expr(expr: &str, db: &dyn FactReader) -> Option<f64> {
-    let expr = expr.trim();
-    if expr.is_empty() {
-        return None;
+    Expr::parse(expr)?.evaluate(db)
+}
+
+/// Evaluate an expression as a condition: true if it evaluates to a non-zero value.
+/// Returns None if the expression is malformed or a referenced fact is missing.
+///
+/// 把表达式作为条件求值：结果非零时为 true。
+/// 如果表达式格式错误或引用的事实缺失，则返回 None。
+pub fn evaluate_bool(expr: &str, db: &dyn FactReader) -> Option<bool> {
+    Expr::parse(expr)?.evaluate_bool(db)
+}
+
+/// An expression parsed once, for evaluating repeatedly against changing facts.
+///
+/// 只解析一次的表达式，用于针对不断变化的事实重复求值。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    root: Node,
+}
+
+impl Expr {
+    /// Parse an expression in the syntax of `evaluate_expr`, returning None if it is malformed.
+    /// Facts are not looked up until `evaluate`.
+    ///
+    /// 按 `evaluate_expr` 的语法解析表达式；格式错误时返回 None。
+    /// 事实直到 `evaluate` 时才会被查找。
+    pub fn parse(expr: &str) -> Option<Self> {
+        let expr = expr.trim();
+        if expr.is_empty() {
+            return None;
+        }
+        let tokens = tokenize(expr)?;
+        parse_expr(&tokens, 0).map(|(root, _)| Expr { root })
+    }
+
+    /// Evaluate against the facts, or None if a referenced fact is missing or not a number.
+    ///
+    /// 针对事实求值；如果引用的事实缺失或不是数字则返回 None。
+    pub fn evaluate(&self, db: &dyn FactReader) -> Option<f64> {
+        self.root.evaluate(db)
+    }
+
+    /// Evaluate as a condition: true if the value is non-zero.
+    ///
+    /// 作为条件求值：值非零时为 true。
+    pub fn evaluate_bool(&self, db: &dyn FactReader) -> Option<bool> {
+        self.evaluate(db).map(truthy)
+    }
+}
+
+/// Whether a value counts as true in `&&`, `||` and conditions.
+fn truthy(value: f64) -> bool {
+    value != 0.0
+}
+
+fn from_bool(value: bool) -> f64 {
+    if value { 1.0 } else { 0.0 }
+}
+
+/// A comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessOrEqual,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::Greater => left > right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+/// A node of a parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Str(String),
+    Fact(String),
+    GlobalFact(String),
+    Negate(Box<Node>),
+    Binary(char, Box<Node>, Box<Node>),
+    Compare(Comparison, Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    fn binary(op: char, left: Node, right: Node) -> Node {
+        Node::Binary(op, Box::new(left), Box::new(right))
     }
 
-    // Tokenize and parse the expression
-    let tokens = tokenize(expr, db)?;
-    parse_expr(&tokens, 0).map(|(result, _)| result)
+    fn evaluate(&self, db: &dyn FactReader) -> Option<f64> {
+        match self {
+            Node::Number(n) => Some(*n),
+            // Text is only meaningful as an operand of `==` and `!=`
+            Node::Str(_) => None,
+            Node::Fact(key) => db.get_number(key),
+            // Readers without layers have no global layer to read from
+            Node::GlobalFact(key) => db
+                .get_in_layer(FactLayer::Global, key)
+                .and_then(FactValue::as_number),
+            Node::Negate(node) => node.evaluate(db).map(|value| -value),
+            Node::Binary(op, left, right) => {
+                let (left, right) = (left.evaluate(db)?, right.evaluate(db)?);
+                Some(match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    // Division and modulo by zero evaluate to 0
+                    '/' if right != 0.0 => left / right,
+                    '%' if right != 0.0 => left % right,
+                    _ => 0.0,
+                })
+            }
+            Node::Compare(comparison, left, right) => Node::compare(*comparison, left, right, db),
+            // `&&` and `||` short-circuit, so the right side may reference a missing fact
+            // when the left side already decides the result
+            Node::And(left, right) if !truthy(left.evaluate(db)?) => Some(0.0),
+            Node::Or(left, right) if truthy(left.evaluate(db)?) => Some(1.0),
+            Node::And(_, right) | Node::Or(_, right) => {
+                right.evaluate(db).map(|value| from_bool(truthy(value)))
+            }
+            Node::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(db))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(function.apply(&args))
+            }
+        }
+    }
 }
 
 /// Evaluate an expression and return as FactValue.
@@ -42,30 +200,95 @@ pub fn evaluate_expr(expr: &str, db: &dyn FactReader) -> Option<f64> {
 pub fn evaluate_expr_to_fact(expr: &str, db: &dyn FactReader) -> Option<FactValue> {
     let result = evaluate_expr(expr, db)?;
 
-    // Return as Int if the result is a whole number, otherwise Float
-    if result.fract() == 0.0 && result.abs() < i64::MAX as f64 {
-        Some(FactValue::Int(result as i64))
-    } else {
-        Some(FactValue::Float(result))
+    // Return as Int (clamped to the i64 range) if the result is a whole number, otherwise Float
+    Some(float_to_fact(result))
+}
+
+/// List the fact keys an expression references as `$key` or `fact('key')`, in order of first
+/// appearance.
+///
+/// 按首次出现的顺序列出表达式中以 `$key` 或 `fact('key')` 形式引用的事实键。
+pub fn expr_variables(expr: &str) -> Vec<&str> {
+    let mut found: Vec<(usize, &str)> = Vec::new();
+    for (pos, _) in expr.match_indices('$') {
+        let rest = &expr[pos + 1..];
+        let end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .unwrap_or(rest.len());
+        found.push((pos, &rest[..end]));
+    }
+    for name in ["fact(", "global("] {
+        for (pos, _) in expr.match_indices(name) {
+            // Skip longer names that merely end the same way, like `artifact(`
+            let longer = expr[..pos].ends_with(|c: char| c.is_alphanumeric() || c == '_');
+            if let Some(key) = quoted_key(&expr[pos + name.len()..]).filter(|_| !longer) {
+                found.push((pos, key));
+            }
+        }
+    }
+    found.sort_by_key(|(pos, _)| *pos);
+
+    let mut keys = Vec::new();
+    for (_, key) in found {
+        if !key.is_empty() && !keys.contains(&key) {
+            keys.push(key);
+        }
     }
+    keys
+}
+
+/// The quoted key at the start of `args`, as in `'key')`.
+fn quoted_key(args: &str) -> Option<&str> {
+    let args = args.trim_start();
+    let quote = args.chars().next().filter(|&c| c == '\'' || c == '"')?;
+    let rest = &args[1..];
+    rest.find(quote).map(|end| &rest[..end])
 }
 
 #[derive(Debug, Clone)]
 enum Token {
     Number(f64),
+    Fact(String),
+    Ident(String),
+    Str(String),
     Op(char),
+    Compare(Comparison),
+    And,
+    Or,
     LParen,
     RParen,
+    Comma,
 }
 
-/// Convert a FactValue to f64 for expression evaluation.
-fn fact_value_to_f64(value: &FactValue) -> Option<f64> {
-    match value {
-        FactValue::Int(v) => Some(*v as f64),
-        FactValue::Float(v) => Some(*v),
-        FactValue::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
-        _ => None,
-    }
+/// Whether the next token starts an operand, so a `-` there is a sign rather than a subtraction.
+fn expects_operand(tokens: &[Token]) -> bool {
+    matches!(
+        tokens.last(),
+        None | Some(
+            Token::Op(_)
+                | Token::Compare(_)
+                | Token::And
+                | Token::Or
+                | Token::LParen
+                | Token::Comma
+        )
+    )
+}
+
+/// Comparison or logic operator at the start of `chars`, with its length in characters.
+fn logic_operator(chars: &[char]) -> Option<(Token, usize)> {
+    let next = chars.get(1).copied();
+    Some(match (chars.first()?, next) {
+        ('=', Some('=')) => (Token::Compare(Comparison::Equal), 2),
+        ('!', Some('=')) => (Token::Compare(Comparison::NotEqual), 2),
+        ('<', Some('=')) => (Token::Compare(Comparison::LessOrEqual), 2),
+        ('>', Some('=')) => (Token::Compare(Comparison::GreaterOrEqual), 2),
+        ('<', _) => (Token::Compare(Comparison::Less), 1),
+        ('>', _) => (Token::Compare(Comparison::Greater), 1),
+        ('&', Some('&')) => (Token::And, 2),
+        ('|', Some('|')) => (Token::Or, 2),
+        _ => return None,
+    })
 }
 
 /// Try to parse a unary minus followed by digits at `start`. Returns (number, new_index).
@@ -79,7 +302,7 @@ fn try_parse_unary_minus(
     if c != '-' {
         return None;
     }
-    if !tokens.is_empty() && !matches!(tokens.last(), Some(Token::Op(_)) | Some(Token::LParen)) {
+    if !expects_operand(tokens) {
         return None;
     }
     let mut i = start + 1;
@@ -93,8 +316,8 @@ fn try_parse_unary_minus(
     Some((num, i))
 }
 
-/// Tokenize an expression string, resolving $variables to their values.
-fn tokenize(expr: &str, db: &dyn FactReader) -> Option<Vec<Token>> {
+/// Tokenize an expression string.
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
     let mut tokens = Vec::new();
     let chars: Vec<char> = expr.chars().collect();
     let mut i = 0;
@@ -116,10 +339,26 @@ fn tokenize(expr: &str, db: &dyn FactReader) -> Option<Vec<Token>> {
             {
                 i += 1;
             }
-            let key = &expr[start..i];
+            tokens.push(Token::Fact(expr[start..i].to_string()));
+            continue;
+        }
+
+        if c.is_alphabetic() {
+            // Function name
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(expr[start..i].to_string()));
+            continue;
+        }
 
-            let value = db.get_by_str(key).and_then(fact_value_to_f64)?;
-            tokens.push(Token::Number(value));
+        if c == '\'' || c == '"' {
+            // String literal, or the quoted key of `fact('key')`
+            let start = i + 1;
+            let len = chars[start..].iter().position(|&q| q == c)?;
+            i = start + len + 1;
+            tokens.push(Token::Str(chars[start..start + len].iter().collect()));
             continue;
         }
 
@@ -127,8 +366,7 @@ fn tokenize(expr: &str, db: &dyn FactReader) -> Option<Vec<Token>> {
             || (c == '-'
                 && i + 1 < chars.len()
                 && chars[i + 1].is_ascii_digit()
-                && (tokens.is_empty()
-                    || matches!(tokens.last(), Some(Token::Op(_)) | Some(Token::LParen))))
+                && expects_operand(&tokens))
         {
             // Number literal
             let start = i;
@@ -162,9 +400,15 @@ fn tokenize(expr: &str, db: &dyn FactReader) -> Option<Vec<Token>> {
                 tokens.push(Token::RParen);
                 i += 1;
             }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
             _ => {
-                // Unknown character
-                return None;
+                // Comparison or logic operator, or an unknown character
+                let (token, len) = logic_operator(&chars[i..])?;
+                tokens.push(token);
+                i += len;
             }
         }
     }
@@ -172,94 +416,135 @@ fn tokenize(expr: &str, db: &dyn FactReader) -> Option<Vec<Token>> {
     Some(tokens)
 }
 
-/// Parse expression with operator precedence.
-/// Returns (result, next_index).
-fn parse_expr(tokens: &[Token], start: usize) -> Option<(f64, usize)> {
-    parse_additive(tokens, start)
+/// Parse expression with operator precedence, loosest first: `||`, `&&`, comparisons,
+/// `+ -`, `* / %`.
+/// Returns (node, next_index).
+fn parse_expr(tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    parse_or(tokens, start)
 }
 
-fn parse_additive(tokens: &[Token], start: usize) -> Option<(f64, usize)> {
-    let (mut left, mut idx) = parse_multiplicative(tokens, start)?;
+fn parse_or(tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    let (mut left, mut idx) = parse_and(tokens, start)?;
 
-    while idx < tokens.len() {
-        match &tokens[idx] {
-            Token::Op('+') => {
-                let (right, next) = parse_multiplicative(tokens, idx + 1)?;
-                left += right;
-                idx = next;
-            }
-            Token::Op('-') => {
-                let (right, next) = parse_multiplicative(tokens, idx + 1)?;
-                left -= right;
-                idx = next;
-            }
-            _ => break,
-        }
+    while let Some(Token::Or) = tokens.get(idx) {
+        let (right, next) = parse_and(tokens, idx + 1)?;
+        left = Node::Or(Box::new(left), Box::new(right));
+        idx = next;
     }
 
     Some((left, idx))
 }
 
-fn parse_multiplicative(tokens: &[Token], start: usize) -> Option<(f64, usize)> {
-    let (mut left, mut idx) = parse_primary(tokens, start)?;
+fn parse_and(tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    let (mut left, mut idx) = parse_comparison(tokens, start)?;
 
-    while idx < tokens.len() {
-        match &tokens[idx] {
-            Token::Op('*') => {
-                let (right, next) = parse_primary(tokens, idx + 1)?;
-                left *= right;
-                idx = next;
-            }
-            Token::Op('/') => {
-                let (right, next) = parse_primary(tokens, idx + 1)?;
-                if right != 0.0 {
-                    left /= right;
-                } else {
-                    left = 0.0; // Division by zero = 0
-                }
-                idx = next;
-            }
-            Token::Op('%') => {
-                let (right, next) = parse_primary(tokens, idx + 1)?;
-                if right != 0.0 {
-                    left %= right;
-                } else {
-                    left = 0.0; // Mod by zero = 0
-                }
-                idx = next;
-            }
-            _ => break,
-        }
+    while let Some(Token::And) = tokens.get(idx) {
+        let (right, next) = parse_comparison(tokens, idx + 1)?;
+        left = Node::And(Box::new(left), Box::new(right));
+        idx = next;
     }
 
     Some((left, idx))
 }
 
-fn parse_primary(tokens: &[Token], start: usize) -> Option<(f64, usize)> {
-    if start >= tokens.len() {
-        return None;
+fn parse_comparison(tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    let (mut left, mut idx) = parse_additive(tokens, start)?;
+
+    while let Some(Token::Compare(comparison)) = tokens.get(idx) {
+        let (right, next) = parse_additive(tokens, idx + 1)?;
+        left = Node::Compare(*comparison, Box::new(left), Box::new(right));
+        idx = next;
     }
 
-    match &tokens[start] {
-        Token::Number(n) => Some((*n, start + 1)),
+    Some((left, idx))
+}
+
+fn parse_additive(tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    let (mut left, mut idx) = parse_multiplicative(tokens, start)?;
+
+    while let Some(Token::Op(op @ ('+' | '-'))) = tokens.get(idx) {
+        let (right, next) = parse_multiplicative(tokens, idx + 1)?;
+        left = Node::binary(*op, left, right);
+        idx = next;
+    }
+
+    Some((left, idx))
+}
+
+fn parse_multiplicative(tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    let (mut left, mut idx) = parse_primary(tokens, start)?;
+
+    while let Some(Token::Op(op @ ('*' | '/' | '%'))) = tokens.get(idx) {
+        let (right, next) = parse_primary(tokens, idx + 1)?;
+        left = Node::binary(*op, left, right);
+        idx = next;
+    }
+
+    Some((left, idx))
+}
+
+fn parse_primary(tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    match tokens.get(start)? {
+        Token::Number(n) => Some((Node::Number(*n), start + 1)),
+        Token::Fact(key) => Some((Node::Fact(key.clone()), start + 1)),
+        Token::Str(text) => Some((Node::Str(text.clone()), start + 1)),
         Token::LParen => {
-            let (result, idx) = parse_expr(tokens, start + 1)?;
+            let (node, idx) = parse_expr(tokens, start + 1)?;
             // Expect closing paren
-            if idx < tokens.len() && matches!(&tokens[idx], Token::RParen) {
-                Some((result, idx + 1))
-            } else {
-                None // Missing closing paren
+            match tokens.get(idx) {
+                Some(Token::RParen) => Some((node, idx + 1)),
+                _ => None, // Missing closing paren
             }
         }
         Token::Op('-') => {
             // Unary minus
-            let (val, idx) = parse_primary(tokens, start + 1)?;
-            Some((-val, idx))
+            let (node, idx) = parse_primary(tokens, start + 1)?;
+            Some((Node::Negate(Box::new(node)), idx))
+        }
+        Token::Ident(name) if matches!(name.as_str(), "fact" | "global") => {
+            parse_global_fact(tokens, start + 1)
+        }
+        Token::Ident(name) => parse_call(name, tokens, start + 1),
+        _ => None,
+    }
+}
+
+/// Parse the `('key')` of a global fact reference starting at `start`.
+fn parse_global_fact(tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    match tokens.get(start..start + 3)? {
+        [Token::LParen, Token::Str(key), Token::RParen] => {
+            Some((Node::GlobalFact(key.clone()), start + 3))
         }
         _ => None,
     }
 }
 
+/// Parse the parenthesized arguments of a call to `name` starting at `start`.
+/// Unknown functions and wrong argument counts fail.
+fn parse_call(name: &str, tokens: &[Token], start: usize) -> Option<(Node, usize)> {
+    let function = Function::from_name(name)?;
+    let Some(Token::LParen) = tokens.get(start) else {
+        return None;
+    };
+    let mut args = Vec::new();
+    let mut idx = start;
+    loop {
+        let (arg, next) = parse_expr(tokens, idx + 1)?;
+        args.push(arg);
+        match tokens.get(next)? {
+            Token::Comma => idx = next,
+            Token::RParen => {
+                idx = next + 1;
+                break;
+            }
+            _ => return None,
+        }
+    }
+    function
+        .accepts(args.len())
+        .then_some((Node::Call(function, args), idx))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,4 +595,119 @@ mod tests {
         assert_eq!(evaluate_expr("$menu:selection", &db), Some(3.0));
         assert_eq!(evaluate_expr("$menu:selection - 1", &db), Some(2.0));
     }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        let mut db = LayeredFactDatabase::default();
+        db.set_local("x", 3i64);
+        db.set_local("y", 2.0);
+
+        assert_eq!(evaluate_expr("$x > 2", &db), Some(1.0));
+        assert_eq!(evaluate_expr("$x <= 2", &db), Some(0.0));
+        assert_eq!(evaluate_expr("$x - 1 == $y", &db), Some(1.0));
+        assert_eq!(evaluate_expr("$x != 3 || $y >= 2", &db), Some(1.0));
+        assert_eq!(evaluate_expr("($x > 0) + ($y > 0)", &db), Some(2.0));
+        assert_eq!(evaluate_bool("$x < -1", &db), Some(false));
+        // `&&` binds tighter than `||`
+        assert_eq!(evaluate_bool("1 || 0 && 0", &db), Some(true));
+
+        let both = "$x > 0 && $y == 2";
+        assert_eq!(evaluate_bool(both, &db), Some(true));
+        db.set_local("y", 2i64);
+        assert_eq!(evaluate_bool(both, &db), Some(true));
+        db.set_local("x", 0i64);
+        assert_eq!(evaluate_bool(both, &db), Some(false));
+        db.set_local("x", 1i64);
+        db.set_local("y", 2.5);
+        assert_eq!(evaluate_bool(both, &db), Some(false));
+        db.remove("y");
+        assert_eq!(evaluate_bool(both, &db), None);
+        // Short-circuiting skips the missing fact
+        db.set_local("x", 0i64);
+        assert_eq!(evaluate_bool(both, &db), Some(false));
+
+        assert_eq!(evaluate_bool("$x = 1", &db), None);
+        assert_eq!(evaluate_bool("$x & 1", &db), None);
+        assert_eq!(evaluate_bool("$x >", &db), None);
+    }
+
+    #[test]
+    fn test_function_calls() {
+        let mut db = LayeredFactDatabase::default();
+        db.set_local("hp", 95i64);
+        db.set_local("maxhp", 100i64);
+        db.set_local("x", -12.5);
+
+        assert_eq!(evaluate_expr("min($hp + 10, $maxhp)", &db), Some(100.0));
+        assert_eq!(evaluate_expr("max($hp, $maxhp, 120)", &db), Some(120.0));
+        assert_eq!(evaluate_expr("abs($x)", &db), Some(12.5));
+        assert_eq!(evaluate_expr("floor($x) + ceil(0.2)", &db), Some(-12.0));
+        assert_eq!(evaluate_expr("clamp($x, 0, 100)", &db), Some(0.0));
+        assert_eq!(
+            evaluate_expr("clamp($hp * 2, -5, $maxhp)", &db),
+            Some(100.0)
+        );
+        assert_eq!(
+            evaluate_expr("clamp(min($hp, abs($x) * 4), -1, max(10, 60))", &db),
+            Some(50.0)
+        );
+        assert_eq!(
+            evaluate_bool("max($x, 0) == 0 && abs(-3) > 2", &db),
+            Some(true)
+        );
+
+        // Unknown functions, wrong argument counts and malformed calls fail
+        assert_eq!(evaluate_expr("sqrt(4)", &db), None);
+        assert_eq!(evaluate_expr("min($hp)", &db), None);
+        assert_eq!(evaluate_expr("abs(1, 2)", &db), None);
+        assert_eq!(evaluate_expr("clamp($x, 0)", &db), None);
+        assert_eq!(evaluate_expr("min($hp, )", &db), None);
+        assert_eq!(evaluate_expr("abs 3", &db), None);
+        assert_eq!(evaluate_expr("min($hp, $missing)", &db), None);
+    }
+
+    #[test]
+    fn test_expr_variables() {
+        assert_eq!(
+            expr_variables("$hp / $max_hp * 100 + $hp"),
+            vec!["hp", "max_hp"]
+        );
+        assert_eq!(expr_variables("$menu:selection-1"), vec!["menu:selection"]);
+        assert!(expr_variables("2 * 3").is_empty());
+        assert_eq!(
+            expr_variables("fact('bonus') + $hp * global(\"bonus\") + artifact('x')"),
+            vec!["bonus", "hp"]
+        );
+    }
+
+    #[test]
+    fn test_global_fact_reads() {
+        let mut db = LayeredFactDatabase::default();
+        db.set_global("k", 10i64);
+        db.set_local("k", 3i64);
+        db.set_global("bonus", 2.5);
+
+        assert_eq!(evaluate_expr("$k", &db), Some(3.0));
+        assert_eq!(evaluate_expr("fact('k')", &db), Some(10.0));
+        assert_eq!(evaluate_expr("global(\"k\") - $k", &db), Some(7.0));
+        assert_eq!(
+            evaluate_expr("max(fact( 'k' ), $bonus) * 2", &db),
+            Some(20.0)
+        );
+        assert_eq!(evaluate_bool("fact('k') > $k", &db), Some(true));
+
+        // Local-only facts are missing from the global layer
+        db.set_local("temp", 1i64);
+        assert_eq!(evaluate_expr("fact('temp')", &db), None);
+        // Plain readers have no global layer
+        let mut plain = crate::database::FactDatabase::new();
+        plain.set("k", 1i64);
+        assert_eq!(evaluate_expr("fact('k')", &plain), None);
+
+        // Malformed references fail
+        assert_eq!(evaluate_expr("fact(k)", &db), None);
+        assert_eq!(evaluate_expr("fact('k)", &db), None);
+        assert_eq!(evaluate_expr("fact('k', 'bonus')", &db), None);
+        assert_eq!(evaluate_expr("'k' + 1", &db), None);
+    }
 }
@@ -0,0 +1,98 @@
+//! # bounds.rs
+//!
+//! # bounds.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Static facts about a parsed expression, for linting conditions without any fact values.
+//! A subexpression that evaluates against an empty reader references no fact on the path it
+//! takes, so its value is the same for every database. Comparisons of a fact with such a
+//! constant, joined by `&&`, bound the values the fact can hold while the expression is true.
+//!
+//! 关于已解析表达式的静态信息，用于在没有任何事实值的情况下检查条件。
+//! 能针对空读取器求值的子表达式在其求值路径上不引用任何事实，因此对任何数据库其值都相同。
+//! 由 `&&` 连接的事实与此类常量的比较，界定了表达式为真时该事实可取的值。
+
+use std::collections::HashMap;
+
+use super::{Comparison, Expr, Node, truthy};
+use crate::database::FactValue;
+use crate::dead_condition::Bound;
+
+impl Expr {
+    /// The value of the expression if it does not depend on any fact.
+    pub(crate) fn constant(&self) -> Option<f64> {
+        self.root.constant()
+    }
+
+    /// The truth of the expression as a condition if it does not depend on any fact.
+    pub(crate) fn constant_bool(&self) -> Option<bool> {
+        self.constant().map(truthy)
+    }
+
+    /// Bounds the expression places on facts when it is true, from the comparisons of a fact
+    /// with a constant on its `&&` spine. Other parts of the expression add no bounds.
+    pub(crate) fn fact_bounds(&self) -> Vec<(&str, Bound)> {
+        let mut bounds = Vec::new();
+        self.root.collect_bounds(&mut bounds);
+        bounds
+    }
+}
+
+impl Node {
+    fn constant(&self) -> Option<f64> {
+        self.evaluate(&HashMap::<String, FactValue>::new())
+    }
+
+    fn collect_bounds<'a>(&'a self, bounds: &mut Vec<(&'a str, Bound)>) {
+        match self {
+            Node::And(left, right) => {
+                left.collect_bounds(bounds);
+                right.collect_bounds(bounds);
+            }
+            Node::Compare(comparison, left, right) => match (&**left, &**right) {
+                (Node::Fact(key), value) => push_bounds(bounds, key, *comparison, value),
+                (value, Node::Fact(key)) => push_bounds(bounds, key, comparison.flipped(), value),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Comparison {
+    /// The comparison with its operands swapped, so `1 < $x` reads as `$x > 1`.
+    fn flipped(self) -> Self {
+        match self {
+            Comparison::Less => Comparison::Greater,
+            Comparison::Greater => Comparison::Less,
+            Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+            Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+            Comparison::Equal | Comparison::NotEqual => self,
+        }
+    }
+}
+
+fn push_bounds<'a>(
+    bounds: &mut Vec<(&'a str, Bound)>,
+    key: &'a str,
+    comparison: Comparison,
+    value: &Node,
+) {
+    let Some(value) = value.constant() else {
+        return;
+    };
+    match comparison {
+        Comparison::Less => bounds.push((key, Bound::Below(value))),
+        Comparison::Greater => bounds.push((key, Bound::Above(value))),
+        Comparison::LessOrEqual => bounds.push((key, Bound::AtMost(value))),
+        Comparison::GreaterOrEqual => bounds.push((key, Bound::AtLeast(value))),
+        Comparison::Equal => {
+            bounds.push((key, Bound::AtLeast(value)));
+            bounds.push((key, Bound::AtMost(value)));
+        }
+        Comparison::NotEqual => {}
+    }
+}
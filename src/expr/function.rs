@@ -0,0 +1,62 @@
+//! # function.rs
+//!
+//! # function.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! The functions expressions can call, such as `min($hp + 10, $maxhp)`. Names and argument
+//! counts are checked when the expression is parsed, so a call to an unknown function or with
+//! the wrong number of arguments makes the whole expression malformed.
+//!
+//! 表达式可以调用的函数，例如 `min($hp + 10, $maxhp)`。名称和参数数量在解析表达式时检查，
+//! 因此调用未知函数或参数数量错误会使整个表达式格式错误。
+
+/// A built-in function callable from expressions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Function {
+    Min,
+    Max,
+    Abs,
+    Clamp,
+    Floor,
+    Ceil,
+}
+
+impl Function {
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "abs" => Function::Abs,
+            "clamp" => Function::Clamp,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            _ => return None,
+        })
+    }
+
+    /// Whether the function takes `count` arguments.
+    pub(super) fn accepts(self, count: usize) -> bool {
+        match self {
+            Function::Min | Function::Max => count >= 2,
+            Function::Abs | Function::Floor | Function::Ceil => count == 1,
+            Function::Clamp => count == 3,
+        }
+    }
+
+    /// Apply to arguments whose count `accepts` allowed.
+    pub(super) fn apply(self, args: &[f64]) -> f64 {
+        match (self, args) {
+            (Function::Min, _) => args.iter().copied().fold(f64::INFINITY, f64::min),
+            (Function::Max, _) => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            (Function::Abs, [x]) => x.abs(),
+            (Function::Floor, [x]) => x.floor(),
+            (Function::Ceil, [x]) => x.ceil(),
+            // Reversed bounds clamp to `min` instead of panicking like `f64::clamp`
+            (Function::Clamp, [x, min, max]) => x.min(*max).max(*min),
+            _ => unreachable!("argument count is checked when parsing"),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! # text.rs
+//!
+//! # text.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Comparisons that may involve strings. String facts and quoted literals are kept as text
+//! instead of failing numeric evaluation, so `$mode == 'battle'` can gate a rule. Text only
+//! supports `==` and `!=`, and a string never equals a number, so mixing the two gives
+//! `!=` true and `==` false rather than an error. Ordering comparisons with text fail.
+//!
+//! 可能涉及字符串的比较。字符串事实和带引号的字面量保留为文本，而不会使数值求值失败，
+//! 因此 `$mode == 'battle'` 可以控制规则。文本只支持 `==` 和 `!=`，且字符串永远不等于数字，
+//! 所以二者混合比较时 `!=` 为真、`==` 为假，而不是报错。涉及文本的大小比较会失败。
+
+use super::{Comparison, Node, from_bool};
+use crate::database::{FactLayer, FactReader, FactValue};
+
+/// A comparison operand after evaluation.
+enum Operand<'a> {
+    Number(f64),
+    Text(&'a str),
+}
+
+impl Node {
+    /// Evaluate a comparison, comparing as strings when either side is text.
+    pub(super) fn compare(
+        comparison: Comparison,
+        left: &Node,
+        right: &Node,
+        db: &dyn FactReader,
+    ) -> Option<f64> {
+        let (left, right) = (left.operand(db)?, right.operand(db)?);
+        if let (Operand::Number(left), Operand::Number(right)) = (&left, &right) {
+            return Some(from_bool(comparison.holds(*left, *right)));
+        }
+        let equal =
+            matches!((left, right), (Operand::Text(left), Operand::Text(right)) if left == right);
+        match comparison {
+            Comparison::Equal => Some(from_bool(equal)),
+            Comparison::NotEqual => Some(from_bool(!equal)),
+            _ => None,
+        }
+    }
+
+    /// The node as a comparison operand, keeping string facts and literals as text.
+    fn operand<'a>(&'a self, db: &'a dyn FactReader) -> Option<Operand<'a>> {
+        let value = match self {
+            Node::Str(text) => return Some(Operand::Text(text)),
+            Node::Fact(key) => db.get_by_str(key),
+            Node::GlobalFact(key) => db.get_in_layer(FactLayer::Global, key),
+            _ => return self.evaluate(db).map(Operand::Number),
+        };
+        match value? {
+            FactValue::String(text) => Some(Operand::Text(text)),
+            value => value.as_number().map(Operand::Number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::FactDatabase;
+    use crate::expr::{evaluate_bool, evaluate_expr};
+
+    #[test]
+    fn test_string_equality() {
+        let mut db = FactDatabase::new();
+        db.set("mode", "battle");
+        db.set("previous_mode", "battle");
+
+        assert_eq!(evaluate_bool("$mode == 'battle'", &db), Some(true));
+        assert_eq!(evaluate_bool("$mode != \"menu\"", &db), Some(true));
+        assert_eq!(evaluate_bool("'menu' == $mode", &db), Some(false));
+        assert_eq!(evaluate_bool("$mode == $previous_mode", &db), Some(true));
+        assert_eq!(
+            evaluate_bool("$mode == 'battle' && $mode != 'menu'", &db),
+            Some(true)
+        );
+        // Text has no order, and is not a number outside comparisons
+        assert_eq!(evaluate_bool("$mode < 'menu'", &db), None);
+        assert_eq!(evaluate_expr("'battle' + 1", &db), None);
+        assert_eq!(evaluate_bool("$missing == 'battle'", &db), None);
+    }
+
+    #[test]
+    fn test_string_never_equals_number() {
+        let mut db = FactDatabase::new();
+        db.set("mode", "1");
+        db.set("lives", 1i64);
+
+        assert_eq!(evaluate_bool("$mode == 1", &db), Some(false));
+        assert_eq!(evaluate_bool("$mode != 1", &db), Some(true));
+        assert_eq!(evaluate_bool("$lives == '1'", &db), Some(false));
+        assert_eq!(evaluate_bool("$lives + 1 != $mode", &db), Some(true));
+        assert_eq!(evaluate_bool("$lives > '0'", &db), None);
+    }
+}
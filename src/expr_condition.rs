@@ -0,0 +1,146 @@
+//! # expr_condition.rs
+//!
+//! Built-in comparison of an arithmetic expression against a constant.
+//! `expr($hp / $max_hp) < 0.3` evaluates the expression with the syntax of `expr::evaluate_expr`
+//! and passes when the result is below the threshold, so derived values such as ratios can gate
+//! rules without a custom evaluator. A missing or non-numeric fact in the expression fails the
+//! condition. Compiled rules keep the parsed expression, so conditions evaluated every frame
+//! are not parsed again.
+//! Like the other built-ins, it is checked by `ConditionEvaluator` before the game's evaluator.
+//!
+//! Games without an expression syntax of their own can install `ExprConditionEvaluator` as that
+//! evaluator, which reads every remaining condition as an `expr` expression such as
+//! `$x > 0 && $y == 2`.
+//!
+//! 算术表达式与常量比较的内置条件。
+//! `expr($hp / $max_hp) < 0.3` 按 `expr::evaluate_expr` 的语法对表达式求值，结果低于阈值时通过，
+//! 因此比率等派生值无需自定义评估器即可控制规则。表达式中缺失或非数值的事实会使条件失败。
+//! 编译后的规则保存已解析的表达式，因此每帧评估的条件不会被再次解析。
+//! 与其他内置条件一样，它由 `ConditionEvaluator` 在游戏评估器之前检查。
+//!
+//! 没有自己表达式语法的游戏可以把 `ExprConditionEvaluator` 安装为该评估器，
+//! 它把其余每个条件都当作 `expr` 表达式读取，例如 `$x > 0 && $y == 2`。
+
+use crate::asset::EnumRegistry;
+use crate::database::FactReader;
+use crate::expr::{Expr, evaluate_bool};
+use crate::systems::ConditionEvaluatorTrait;
+
+/// A parsed `expr(...) < value` condition.
+///
+/// 已解析的 `expr(...) < value` 条件。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprLessThan {
+    pub expr: Expr,
+    pub threshold: f64,
+}
+
+impl ExprLessThan {
+    /// Parse a condition expression, returning None if it is not an expression comparison.
+    ///
+    /// 解析条件表达式；如果不是表达式比较则返回 None。
+    pub fn parse(condition: &str) -> Option<Self> {
+        let rest = condition.trim().strip_prefix("expr(")?;
+        let (expr, comparison) = rest.rsplit_once(')')?;
+        let threshold = comparison.trim().strip_prefix('<')?.trim().parse().ok()?;
+        Some(ExprLessThan {
+            expr: Expr::parse(expr)?,
+            threshold,
+        })
+    }
+
+    /// Whether the expression evaluates below the threshold.
+    ///
+    /// 表达式的值是否低于阈值。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        self.expr
+            .evaluate(facts)
+            .is_some_and(|value| value < self.threshold)
+    }
+}
+
+/// Condition evaluator that passes when every condition is an expression evaluating to a
+/// non-zero value. A malformed expression or a missing fact fails its condition.
+///
+/// 当每个条件都是求值为非零值的表达式时通过的条件评估器。
+/// 格式错误的表达式或缺失的事实会使其条件失败。
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExprConditionEvaluator;
+
+impl ConditionEvaluatorTrait for ExprConditionEvaluator {
+    fn evaluate(
+        &self,
+        conditions: &[String],
+        facts: &dyn FactReader,
+        _enums: &EnumRegistry,
+    ) -> bool {
+        conditions
+            .iter()
+            .all(|condition| evaluate_bool(condition, facts) == Some(true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layered::LayeredFactDatabase;
+    use crate::systems::ConditionEvaluator;
+
+    #[test]
+    fn test_parse() {
+        let parsed = ExprLessThan::parse("expr(($hp + 1) / $max_hp) < 0.3").unwrap();
+        assert_eq!(parsed.expr, Expr::parse("($hp + 1) / $max_hp").unwrap());
+        assert_eq!(parsed.threshold, 0.3);
+        assert_eq!(ExprLessThan::parse("expr($hp) > 0.3"), None);
+        assert_eq!(ExprLessThan::parse("expr($hp +) < 0.3"), None);
+    }
+
+    #[test]
+    fn test_ratio_crossing_threshold() {
+        let condition = ["expr($hp / $max_hp) < 0.3".to_string()];
+        let evaluator = ConditionEvaluator::default();
+        let enums = EnumRegistry::default();
+        let mut db = LayeredFactDatabase::new();
+        db.set("max_hp", 100);
+
+        db.set("hp", 31);
+        assert!(!evaluator.evaluate_conditions(&condition, &db, &enums));
+        db.set("hp", 29.5);
+        assert!(evaluator.evaluate_conditions(&condition, &db, &enums));
+
+        // A missing fact fails the condition instead of reading as zero
+        db.remove("max_hp");
+        assert!(!evaluator.evaluate_conditions(&condition, &db, &enums));
+        db.set("max_hp", "lots");
+        assert!(!evaluator.evaluate_conditions(&condition, &db, &enums));
+    }
+
+    #[test]
+    fn test_expr_condition_evaluator_ands_conditions() {
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let conditions = ["$x > 0 && $y == 2".to_string(), "$z != 1".to_string()];
+        let mut db = LayeredFactDatabase::new();
+        db.set("x", 1i64);
+        db.set("y", 2.0);
+        db.set("z", 0i64);
+        assert!(evaluator.evaluate_conditions(&conditions, &db, &enums));
+
+        db.set("z", true);
+        assert!(!evaluator.evaluate_conditions(&conditions, &db, &enums));
+        db.set("z", 5i64);
+        db.set_global("x", 3i64);
+        db.set("x", -1i64);
+        assert!(!evaluator.evaluate_conditions(&conditions, &db, &enums));
+        db.remove("x");
+        assert!(evaluator.evaluate_conditions(&conditions, &db, &enums));
+        db.remove("y");
+        assert!(!evaluator.evaluate_conditions(&conditions, &db, &enums));
+
+        // Built-ins are still resolved before the expression evaluator sees the rest
+        db.set("y", 2i64);
+        let mixed = ["float($x) >= 3".to_string(), "$y * 2 == 4".to_string()];
+        assert!(evaluator.evaluate_conditions(&mixed, &db, &enums));
+        assert!(!evaluator.evaluate_conditions(&["$x >".to_string()], &db, &enums));
+    }
+}
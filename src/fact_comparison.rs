@@ -0,0 +1,125 @@
+//! # fact_comparison.rs
+//!
+//! Built-in conditions comparing two facts with each other.
+//! `compare($score > $highscore)` passes when both facts are numbers and the first is greater;
+//! `compare($a == $b)` passes when they are equal. Numbers are compared with
+//! `FactValue::partial_cmp_numeric`, so an `Int` and a `Float` compare by value. Non-numeric
+//! facts are equal only to a value of the same type; mismatched types and missing facts fail.
+//! Like the other built-ins, they are checked by `ConditionEvaluator` before the game's evaluator.
+//!
+//! 比较两个事实的内置条件。
+//! 当两个事实都是数值且第一个更大时，`compare($score > $highscore)` 通过；
+//! 二者相等时 `compare($a == $b)` 通过。数值通过 `FactValue::partial_cmp_numeric` 比较，
+//! 因此 `Int` 与 `Float` 按值比较。非数值事实只与同类型的值相等；类型不匹配或事实缺失时条件失败。
+//! 与其他内置条件一样，它们由 `ConditionEvaluator` 在游戏评估器之前检查。
+
+use std::cmp::Ordering;
+
+use crate::database::FactReader;
+
+/// A parsed built-in comparison between two facts.
+///
+/// 已解析的两个事实之间的内置比较。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FactComparison<'a> {
+    /// `compare($a > $b)`
+    GreaterThan(&'a str, &'a str),
+
+    /// `compare($a == $b)`
+    Equals(&'a str, &'a str),
+}
+
+impl<'a> FactComparison<'a> {
+    /// Parse a condition expression, returning None if it is not a fact comparison.
+    ///
+    /// 解析条件表达式；如果不是事实比较则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let args = condition
+            .trim()
+            .strip_prefix("compare(")?
+            .strip_suffix(')')?;
+        if let Some((a, b)) = args.split_once("==") {
+            return Some(FactComparison::Equals(key(a)?, key(b)?));
+        }
+        let (a, b) = args.split_once('>')?;
+        Some(FactComparison::GreaterThan(key(a)?, key(b)?))
+    }
+
+    /// Whether both facts are present and satisfy the comparison.
+    ///
+    /// 两个事实是否都存在并满足比较。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        let (FactComparison::GreaterThan(a, b) | FactComparison::Equals(a, b)) = *self;
+        let (Some(a), Some(b)) = (facts.get_by_str(a), facts.get_by_str(b)) else {
+            return false;
+        };
+        let ordering = a.partial_cmp_numeric(b);
+        match self {
+            FactComparison::GreaterThan(..) => ordering == Some(Ordering::Greater),
+            FactComparison::Equals(..) => match ordering {
+                Some(ordering) => ordering == Ordering::Equal,
+                None => a == b,
+            },
+        }
+    }
+}
+
+/// Key of `$key`.
+fn key(arg: &str) -> Option<&str> {
+    arg.trim().strip_prefix('$')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::EnumRegistry;
+    use crate::layered::LayeredFactDatabase;
+    use crate::systems::ConditionEvaluator;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            FactComparison::parse("compare($score > $highscore)"),
+            Some(FactComparison::GreaterThan("score", "highscore"))
+        );
+        assert_eq!(
+            FactComparison::parse("compare( $a==$b )"),
+            Some(FactComparison::Equals("a", "b"))
+        );
+        assert_eq!(FactComparison::parse("compare($a > 3)"), None);
+        assert_eq!(FactComparison::parse("$a > $b"), None);
+    }
+
+    #[test]
+    fn test_compare_facts() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("score", 120i64);
+        db.set("highscore", 100.5);
+        db.set("level", 3i64);
+        db.set("level_f", 3.0);
+        db.set("name", "hero");
+        db.set("rival", "hero");
+        db.set("count", "3");
+        db.set("armed", true);
+        db.set("one", 1i64);
+        let evaluator = ConditionEvaluator::default();
+        let passes = |condition: &str| {
+            evaluator.evaluate_conditions(&[condition.to_string()], &db, &EnumRegistry::default())
+        };
+
+        // Int against float, both ways
+        assert!(passes("compare($score > $highscore)"));
+        assert!(!passes("compare($highscore > $score)"));
+        assert!(passes("compare($level == $level_f)"));
+        assert!(!passes("compare($level > $level_f)"));
+        // Non-numeric facts are equal only to the same type
+        assert!(passes("compare($name == $rival)"));
+        assert!(!passes("compare($name > $rival)"));
+        // Mismatched types and missing facts never match
+        assert!(!passes("compare($count == $level)"));
+        assert!(!passes("compare($level > $count)"));
+        assert!(!passes("compare($armed == $one)"));
+        assert!(!passes("compare($score > $missing)"));
+        assert!(!passes("compare($missing == $missing)"));
+    }
+}
@@ -0,0 +1,194 @@
+//! # float_condition.rs
+//!
+//! Built-in comparisons on floating point facts.
+//! Conditions written as `float($timer) > 2.5`, `float($timer) >= 2.5`, `float($timer) < 2.5`,
+//! `float($timer) <= 2.5` or `float($timer) in 1.0..3.0` read the fact as a float, so HP and timers stored as
+//! `FactValue::Float` can gate rules without the game's evaluator knowing about floats.
+//! Integer and boolean facts are converted as by `FactValue::as_number`; any other value, or a
+//! missing fact, fails the condition.
+//! Like the other built-ins, they are checked by `ConditionEvaluator` before the game's evaluator.
+//!
+//! 针对浮点事实的内置比较。
+//! 写作 `float($timer) > 2.5`、`float($timer) >= 2.5`、`float($timer) < 2.5`、`float($timer) <= 2.5`
+//! 或 `float($timer) in 1.0..3.0` 的条件
+//! 以浮点数读取事实，因此存储为 `FactValue::Float` 的 HP 和计时器无需游戏评估器理解浮点数即可控制规则。
+//! 整数和布尔事实按 `FactValue::as_number` 的规则转换；其他任何值或缺失的事实都会使条件失败。
+//! 与其他内置条件一样，它们由 `ConditionEvaluator` 在游戏评估器之前检查。
+
+use crate::database::FactReader;
+
+/// A parsed built-in float comparison.
+///
+/// 已解析的内置浮点比较。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FloatCondition<'a> {
+    /// `float($key) > value`
+    GreaterThan(&'a str, f64),
+
+    /// `float($key) >= value`
+    GreaterOrEqual(&'a str, f64),
+
+    /// `float($key) < value`
+    LessThan(&'a str, f64),
+
+    /// `float($key) <= value`
+    LessOrEqual(&'a str, f64),
+
+    /// `float($key) in min..max`, inclusive at both ends.
+    ///
+    /// `float($key) in min..max`，两端均包含。
+    InRange(&'a str, f64, f64),
+}
+
+impl<'a> FloatCondition<'a> {
+    /// Parse a condition expression, returning None if it is not a float comparison.
+    ///
+    /// 解析条件表达式；如果不是浮点比较则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let rest = condition.trim().strip_prefix("float($")?;
+        let (key, comparison) = rest.split_once(')')?;
+        let key = key.trim();
+        let comparison = comparison.trim();
+        if let Some(value) = comparison.strip_prefix(">=") {
+            return Some(FloatCondition::GreaterOrEqual(
+                key,
+                value.trim().parse().ok()?,
+            ));
+        }
+        if let Some(value) = comparison.strip_prefix("<=") {
+            return Some(FloatCondition::LessOrEqual(key, value.trim().parse().ok()?));
+        }
+        if let Some(value) = comparison.strip_prefix('>') {
+            return Some(FloatCondition::GreaterThan(key, value.trim().parse().ok()?));
+        }
+        if let Some(value) = comparison.strip_prefix('<') {
+            return Some(FloatCondition::LessThan(key, value.trim().parse().ok()?));
+        }
+        let (min, max) = comparison.strip_prefix("in ")?.split_once("..")?;
+        Some(FloatCondition::InRange(
+            key,
+            min.trim().parse().ok()?,
+            max.trim().parse().ok()?,
+        ))
+    }
+
+    /// Whether the fact holds a number satisfying the comparison.
+    ///
+    /// 事实是否持有满足比较的数值。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        let key = match self {
+            FloatCondition::GreaterThan(key, _)
+            | FloatCondition::GreaterOrEqual(key, _)
+            | FloatCondition::LessThan(key, _)
+            | FloatCondition::LessOrEqual(key, _)
+            | FloatCondition::InRange(key, _, _) => key,
+        };
+        let Some(value) = facts.get_number(key) else {
+            return false;
+        };
+        match *self {
+            FloatCondition::GreaterThan(_, threshold) => value > threshold,
+            FloatCondition::GreaterOrEqual(_, threshold) => value >= threshold,
+            FloatCondition::LessThan(_, threshold) => value < threshold,
+            FloatCondition::LessOrEqual(_, threshold) => value <= threshold,
+            FloatCondition::InRange(_, min, max) => (min..=max).contains(&value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::EnumRegistry;
+    use crate::event::FactEvent;
+    use crate::layered::LayeredFactDatabase;
+    use crate::systems::ConditionEvaluator;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            FloatCondition::parse("float($timer) > 2.5"),
+            Some(FloatCondition::GreaterThan("timer", 2.5))
+        );
+        assert_eq!(
+            FloatCondition::parse("float($timer)<1"),
+            Some(FloatCondition::LessThan("timer", 1.0))
+        );
+        assert_eq!(
+            FloatCondition::parse("float($timer) in 1.0..3.0"),
+            Some(FloatCondition::InRange("timer", 1.0, 3.0))
+        );
+        assert_eq!(
+            FloatCondition::parse("float($timer) >= 2.5"),
+            Some(FloatCondition::GreaterOrEqual("timer", 2.5))
+        );
+        assert_eq!(
+            FloatCondition::parse("float($timer)<=-1"),
+            Some(FloatCondition::LessOrEqual("timer", -1.0))
+        );
+        assert_eq!(FloatCondition::parse("$timer > 2.5"), None);
+        assert_eq!(FloatCondition::parse("float($timer) => 2.5"), None);
+        assert_eq!(FloatCondition::parse("float($timer) > soon"), None);
+    }
+
+    #[test]
+    fn test_float_fact_gates_conditions() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("hp", 2.5);
+        db.set("level", 3i64);
+        db.set("name", "hero");
+        db.set("rank", 3.0);
+        db.set("armed", true);
+        let evaluator = ConditionEvaluator::default();
+        let passes = |condition: &str| {
+            evaluator.evaluate_conditions_for_event(
+                &[condition.to_string()],
+                &FactEvent::new("tick"),
+                &db,
+                &EnumRegistry::default(),
+            )
+        };
+
+        assert!(passes("float($hp) > 2.0"));
+        assert!(!passes("float($hp) > 2.5"));
+        assert!(passes("float($hp) < 3"));
+        assert!(passes("float($hp) in 2.5..4.0"));
+        assert!(!passes("float($hp) in 0.0..2.4"));
+        // Integer facts are widened; other values and missing facts fail
+        assert!(passes("float($level) in 2.5..3.5"));
+        assert!(passes("float($level) > 2"));
+        assert!(!passes("float($level) < 3"));
+        // Int thresholds still match a whole number stored as a float, and booleans count as 0/1
+        assert!(passes("float($rank) > 2"));
+        assert!(passes("float($rank) in 3..3"));
+        assert!(passes("float($armed) > 0"));
+        assert!(!passes("float($name) > 0.0"));
+        assert!(!passes("float($missing) < 100.0"));
+    }
+
+    #[test]
+    fn test_inclusive_bounds_on_both_sides_of_the_threshold() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("hp", 0.5);
+        db.set("lives", 1i64);
+        let passes = |condition: &str| {
+            ConditionEvaluator::default().evaluate_conditions(
+                &[condition.to_string()],
+                &db,
+                &EnumRegistry::default(),
+            )
+        };
+
+        assert!(passes("float($hp) >= 0.5"));
+        assert!(passes("float($hp) >= 0.49"));
+        assert!(!passes("float($hp) >= 0.51"));
+        assert!(passes("float($hp) <= 0.5"));
+        assert!(!passes("float($hp) <= 0.49"));
+        // Integer facts are coerced to floats
+        assert!(passes("float($lives) >= 1.0"));
+        assert!(!passes("float($lives) >= 1.5"));
+        assert!(passes("float($lives) <= 1.0"));
+        assert!(!passes("float($lives) <= 0.5"));
+        assert!(!passes("float($missing) <= 0.5"));
+    }
+}
@@ -0,0 +1,198 @@
+//! # frame_change.rs
+//!
+//! Built-in condition on whether a fact changed since the previous frame.
+//! `changed_since_last_frame($position)` passes when the value of `position` differs from its
+//! value when the frame started, which suits "is moving" checks that generic change tracking
+//! cannot express: a value written back unchanged does not count, and the answer only covers
+//! the current frame. `snapshot_frame_start_system` runs in `First` and records the keys that
+//! enabled rules reference this way, so a write made at any point of the frame is seen by
+//! rules evaluated after it. Keys not referenced by an enabled rule are untracked, and the
+//! condition fails for them.
+//!
+//! 关于事实自上一帧以来是否变化的内置条件。
+//! 当 `position` 的值与帧开始时的值不同时，`changed_since_last_frame($position)` 通过，
+//! 适用于通用变更跟踪无法表达的"是否在移动"检查：写回相同的值不算变化，结果也只覆盖当前帧。
+//! `snapshot_frame_start_system` 在 `First` 中运行，记录启用的规则以此方式引用的键，
+//! 因此帧内任何时刻的写入都会被之后评估的规则看到。未被启用规则引用的键不被跟踪，其条件不通过。
+
+use bevy::prelude::*;
+
+use crate::asset::ActionDef;
+use crate::database::FactReader;
+use crate::layered::LayeredFactDatabase;
+use crate::rule::LayeredRuleRegistry;
+use crate::systems::ConditionEvaluator;
+
+/// A parsed `changed_since_last_frame($key)` condition.
+///
+/// 已解析的 `changed_since_last_frame($key)` 条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedSinceLastFrame<'a>(pub &'a str);
+
+impl<'a> ChangedSinceLastFrame<'a> {
+    /// Parse a condition expression, returning None if it is not a frame change condition.
+    ///
+    /// 解析条件表达式；如果不是帧变化条件则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let args = condition.trim().strip_prefix("changed_since_last_frame(")?;
+        let key = args.strip_suffix(')')?.trim().strip_prefix('$')?;
+        Some(ChangedSinceLastFrame(key))
+    }
+
+    /// Whether the fact changed since the frame started. Fails for untracked keys.
+    ///
+    /// 事实自帧开始以来是否变化。未被跟踪的键不通过。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        facts.changed_since_last_frame(self.0) == Some(true)
+    }
+}
+
+/// System that snapshots the facts referenced by `changed_since_last_frame` conditions of
+/// enabled rules, including those inside `@name` conditions. The referenced keys are only
+/// collected again in frames the registry or the named conditions were modified in, and frames
+/// without any are skipped.
+///
+/// 为启用规则的 `changed_since_last_frame` 条件（包括 `@name` 条件中的）所引用的事实拍摄快照的系统。
+/// 只有在注册表或命名条件被修改的帧中才会重新收集被引用的键，没有被引用键的帧会被跳过。
+pub fn snapshot_frame_start_system<A: ActionDef>(
+    registry: Res<LayeredRuleRegistry<A>>,
+    evaluator: Res<ConditionEvaluator>,
+    mut db: ResMut<LayeredFactDatabase>,
+    mut keys: Local<Vec<String>>,
+) {
+    if registry.is_changed() || evaluator.is_changed() {
+        keys.clear();
+        for rule in registry.iter().filter(|rule| rule.enabled) {
+            let Some(conditions) = evaluator
+                .named_conditions()
+                .expand(&rule.condition_expressions)
+            else {
+                continue;
+            };
+            keys.extend(
+                conditions
+                    .iter()
+                    .filter_map(|condition| ChangedSinceLastFrame::parse(condition))
+                    .map(|condition| condition.0.to_string()),
+            );
+        }
+    } else if keys.is_empty() {
+        return;
+    }
+    // The snapshot is bookkeeping, not a change to the facts
+    db.bypass_change_detection()
+        .snapshot_frame_start(keys.iter().map(String::as_str));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry};
+    use crate::rule::Rule;
+
+    const CONDITION: &str = "changed_since_last_frame($position)";
+
+    fn frame_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<ConditionEvaluator>()
+            .add_systems(First, snapshot_frame_start_system::<CoreActionDef>);
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+            .register(
+                Rule::builder("moving", "tick")
+                    .condition_expr(CONDITION)
+                    .build(),
+            );
+        app
+    }
+
+    fn moved(app: &App) -> bool {
+        let world = app.world();
+        world.resource::<ConditionEvaluator>().evaluate_conditions(
+            &[CONDITION.to_string()],
+            world.resource::<LayeredFactDatabase>(),
+            &EnumRegistry::default(),
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ChangedSinceLastFrame::parse(" changed_since_last_frame( $pos ) "),
+            Some(ChangedSinceLastFrame("pos"))
+        );
+        assert_eq!(
+            ChangedSinceLastFrame::parse("changed_since_last_frame(pos)"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_changes_are_frame_granular() {
+        let mut app = frame_app();
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("position", 1.0);
+        app.update();
+        assert!(!moved(&app));
+
+        // Set mid-frame, after the snapshot
+        app.add_systems(Update, |mut db: ResMut<LayeredFactDatabase>| {
+            if db.get_float("position") == Some(1.0) {
+                db.set("position", 2.0);
+            }
+        });
+        app.update();
+        assert!(moved(&app));
+
+        // Nothing written this frame
+        app.update();
+        assert!(!moved(&app));
+        assert_eq!(
+            app.world()
+                .resource::<LayeredFactDatabase>()
+                .get_float("position"),
+            Some(2.0)
+        );
+    }
+
+    #[test]
+    fn test_unchanged_write_and_untracked_keys() {
+        let mut app = frame_app();
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("position", 1.0);
+        app.update();
+        let mut db = app.world_mut().resource_mut::<LayeredFactDatabase>();
+        db.set("position", 1.0);
+        db.set("speed", 3.0);
+        assert!(!moved(&app));
+
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert!(!ChangedSinceLastFrame("speed").matches(db));
+        assert_eq!(db.changed_since_last_frame("speed"), None);
+    }
+
+    #[test]
+    fn test_disabling_the_rule_stops_tracking() {
+        let mut app = frame_app();
+        app.update();
+        let tracked = |app: &App| {
+            app.world()
+                .resource::<LayeredFactDatabase>()
+                .changed_since_last_frame("position")
+                .is_some()
+        };
+        assert!(tracked(&app));
+
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+            .set_enabled("moving", false);
+        app.update();
+        assert!(!tracked(&app));
+        app.update();
+        assert!(!tracked(&app));
+    }
+}
@@ -0,0 +1,155 @@
+//! # frame_delay.rs
+//!
+//! Events delayed by a number of frames, for frame-timed gameplay such as input windows.
+//! `FrameScheduledEvents` holds events together with the number of frames left before they are
+//! emitted; `tick_frame_scheduled_events_system` counts them down once per frame and emits each
+//! event on the frame its counter reaches zero. Bridge layers schedule the events of
+//! `CoreActionDef::EmitEventAfterFrames` actions, either themselves or through the handler
+//! installed by `ActionHandlerRegistry::register_frame_delay_handler`.
+//!
+//! 按帧数延迟的事件，用于输入窗口等以帧计时的玩法。
+//! `FrameScheduledEvents` 保存事件以及距离发出还剩的帧数；`tick_frame_scheduled_events_system`
+//! 每帧倒数一次，并在计数归零的那一帧发出事件。桥接层负责调度 `CoreActionDef::EmitEventAfterFrames`
+//! 动作的事件，可以自行调度，也可以通过 `ActionHandlerRegistry::register_frame_delay_handler`
+//! 安装的处理器调度。
+
+use bevy::prelude::*;
+
+use crate::asset::{ActionHandlerRegistry, CoreActionDef};
+use crate::event::FactEvent;
+
+/// Events waiting for their frame, with the number of frames left.
+///
+/// 等待其所在帧的事件，以及剩余帧数。
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FrameScheduledEvents {
+    scheduled: Vec<(u32, FactEvent)>,
+}
+
+impl FrameScheduledEvents {
+    /// Emit `event` on the `frames`-th frame from now. A delay of 0 behaves like 1, emitting on
+    /// the next frame.
+    ///
+    /// 在从现在起的第 `frames` 帧发出 `event`。延迟为 0 时与 1 相同，在下一帧发出。
+    pub fn schedule(&mut self, event: FactEvent, frames: u32) {
+        self.scheduled.push((frames.max(1), event));
+    }
+
+    pub fn len(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+
+    /// Drop every scheduled event.
+    ///
+    /// 丢弃所有已调度的事件。
+    pub fn clear(&mut self) {
+        self.scheduled.clear();
+    }
+}
+
+/// System that counts scheduled events down and emits those whose counter reaches zero,
+/// in the order they were scheduled.
+///
+/// 对已调度事件进行倒数，并按调度顺序发出计数归零的事件的系统。
+pub fn tick_frame_scheduled_events_system(
+    mut scheduled: ResMut<FrameScheduledEvents>,
+    mut writer: MessageWriter<FactEvent>,
+) {
+    if scheduled.is_empty() {
+        return;
+    }
+    let mut remaining = Vec::with_capacity(scheduled.scheduled.len());
+    for (frames, event) in scheduled.scheduled.drain(..) {
+        if frames <= 1 {
+            writer.write(event);
+        } else {
+            remaining.push((frames - 1, event));
+        }
+    }
+    scheduled.scheduled = remaining;
+}
+
+impl ActionHandlerRegistry<CoreActionDef> {
+    /// Handle `EmitEventAfterFrames` actions by scheduling their event in `FrameScheduledEvents`.
+    ///
+    /// 通过在 `FrameScheduledEvents` 中调度事件来处理 `EmitEventAfterFrames` 动作。
+    pub fn register_frame_delay_handler(&mut self) {
+        self.register("EmitEventAfterFrames", |action, _db, commands| {
+            let CoreActionDef::EmitEventAfterFrames { event, frames } = action else {
+                return;
+            };
+            let (event, frames) = (FactEvent::new(event.as_str()), *frames);
+            commands.queue(move |world: &mut World| {
+                world
+                    .resource_mut::<FrameScheduledEvents>()
+                    .schedule(event, frames);
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layered::LayeredFactDatabase;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn frame_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<FrameScheduledEvents>()
+            .init_resource::<LayeredFactDatabase>()
+            .add_message::<FactEvent>()
+            .add_systems(Update, tick_frame_scheduled_events_system);
+        app
+    }
+
+    /// Run one frame and return whether `id` was emitted during it.
+    fn step(app: &mut App, id: &str) -> bool {
+        app.update();
+        app.world()
+            .resource::<Messages<FactEvent>>()
+            .iter_current_update_messages()
+            .any(|event| event.id == id)
+    }
+
+    #[test]
+    fn test_fires_on_exact_frame() {
+        for frames in [1, 2, 5] {
+            let mut app = frame_app();
+            app.world_mut()
+                .resource_mut::<FrameScheduledEvents>()
+                .schedule(FactEvent::new("window_closed"), frames);
+
+            for _ in 1..frames {
+                assert!(!step(&mut app, "window_closed"));
+            }
+            assert!(step(&mut app, "window_closed"), "frame {}", frames);
+            assert!(!step(&mut app, "window_closed"));
+            assert!(app.world().resource::<FrameScheduledEvents>().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_action_handler_schedules_event() {
+        let mut app = frame_app();
+        let mut handlers = ActionHandlerRegistry::<CoreActionDef>::default();
+        handlers.register_frame_delay_handler();
+        let action: CoreActionDef =
+            ron::from_str(r#"EmitEventAfterFrames(event: "parry_window", frames: 3)"#).unwrap();
+        app.world_mut()
+            .run_system_once(
+                move |db: Res<LayeredFactDatabase>, mut commands: Commands| {
+                    handlers.execute(&action, &db, &mut commands);
+                },
+            )
+            .unwrap();
+
+        assert!(!step(&mut app, "parry_window"));
+        assert!(!step(&mut app, "parry_window"));
+        assert!(step(&mut app, "parry_window"));
+    }
+}
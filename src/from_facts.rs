@@ -0,0 +1,214 @@
+//! # from_facts.rs
+//!
+//! Reading several facts into a typed struct at once.
+//! A type implementing `FromFacts` builds itself from a fact reader, failing with a
+//! `FromFactsError` that names the first missing or mistyped fact. With the `derive` feature,
+//! `#[derive(FromFacts)]` implements it for structs whose fields are annotated with
+//! `#[fact("key")]`, so read-heavy systems do not repeat one `get_*` call per field.
+//! Field types convert through `FromFactValue`; conversions are strict, so an `Int` fact does
+//! not read as an `f64` field.
+//!
+//! 一次把多个事实读入类型化结构体。
+//! 实现 `FromFacts` 的类型从事实读取器构建自身，失败时返回指出第一个缺失或类型错误事实的
+//! `FromFactsError`。启用 `derive` 特性后，`#[derive(FromFacts)]` 会为字段标注了
+//! `#[fact("key")]` 的结构体实现它，因此读取密集的系统无需为每个字段重复调用 `get_*`。
+//! 字段类型通过 `FromFactValue` 转换；转换是严格的，因此 `Int` 事实不能读作 `f64` 字段。
+
+use bevy::prelude::*;
+use std::fmt;
+
+use crate::database::{FactReader, FactValue};
+use crate::schema::FactType;
+
+/// Build a value from facts.
+///
+/// 从事实构建值。
+pub trait FromFacts: Sized {
+    fn from_facts(facts: &dyn FactReader) -> Result<Self, FromFactsError>;
+}
+
+/// Why a value could not be built from facts.
+///
+/// 无法从事实构建值的原因。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromFactsError {
+    /// A required fact is not set.
+    ///
+    /// 必需的事实未设置。
+    Missing { key: String },
+
+    /// A fact holds a different type than the field reading it.
+    ///
+    /// 事实持有的类型与读取它的字段不同。
+    WrongType {
+        key: String,
+        expected: FactType,
+        found: FactType,
+    },
+}
+
+impl fmt::Display for FromFactsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromFactsError::Missing { key } => write!(f, "fact '{}' is missing", key),
+            FromFactsError::WrongType {
+                key,
+                expected,
+                found,
+            } => write!(f, "fact '{}' should be {} but is {}", key, expected, found),
+        }
+    }
+}
+
+impl std::error::Error for FromFactsError {}
+
+/// A type a single fact value converts to.
+///
+/// 单个事实值可以转换成的类型。
+pub trait FromFactValue: Sized {
+    /// The fact type this converts from, reported in `FromFactsError::WrongType`.
+    const FACT_TYPE: FactType;
+
+    /// Convert a stored value, or None if it has another type.
+    fn from_fact_value(value: &FactValue) -> Option<Self>;
+
+    /// The value a missing fact reads as, or None if the fact is required.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Read the fact `key` as `T`. Used by `#[derive(FromFacts)]`.
+///
+/// 把事实 `key` 读取为 `T`。供 `#[derive(FromFacts)]` 使用。
+pub fn read_fact<T: FromFactValue>(facts: &dyn FactReader, key: &str) -> Result<T, FromFactsError> {
+    let Some(value) = facts.get_by_str(key) else {
+        return T::from_missing().ok_or_else(|| FromFactsError::Missing {
+            key: key.to_string(),
+        });
+    };
+    T::from_fact_value(value).ok_or_else(|| FromFactsError::WrongType {
+        key: key.to_string(),
+        expected: T::FACT_TYPE,
+        found: FactType::of(value),
+    })
+}
+
+/// Missing facts read as `None`; present facts must still have the right type.
+impl<T: FromFactValue> FromFactValue for Option<T> {
+    const FACT_TYPE: FactType = T::FACT_TYPE;
+
+    fn from_fact_value(value: &FactValue) -> Option<Self> {
+        T::from_fact_value(value).map(Some)
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+macro_rules! impl_from_fact_value {
+    ($($ty:ty => $fact_type:ident, $accessor:ident;)*) => {
+        $(
+            impl FromFactValue for $ty {
+                const FACT_TYPE: FactType = FactType::$fact_type;
+
+                fn from_fact_value(value: &FactValue) -> Option<Self> {
+                    value.$accessor().map(Into::into)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_fact_value! {
+    i64 => Int, as_int;
+    f64 => Float, as_float;
+    bool => Bool, as_bool;
+    String => String, as_string;
+    Vec<String> => StringList, as_string_list;
+    Vec<i64> => IntList, as_int_list;
+    Vec<f64> => FloatList, as_float_list;
+    Vec<bool> => BoolList, as_bool_list;
+    Vec2 => Vec2, as_vec2;
+    Vec3 => Vec3, as_vec3;
+    Entity => EntityRef, as_entity;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layered::LayeredFactDatabase;
+
+    /// What `#[derive(FromFacts)]` generates, written out so the crate can test it directly.
+    #[derive(Debug, PartialEq)]
+    struct PlayerState {
+        hp: i64,
+        name: String,
+        alive: bool,
+        title: Option<String>,
+    }
+
+    impl FromFacts for PlayerState {
+        fn from_facts(facts: &dyn FactReader) -> Result<Self, FromFactsError> {
+            Ok(Self {
+                hp: read_fact(facts, "player:hp")?,
+                name: read_fact(facts, "player:name")?,
+                alive: read_fact(facts, "player:alive")?,
+                title: read_fact(facts, "player:title")?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_read_fact_conversions() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("hp", 10i64);
+        db.set("bag", vec!["key"]);
+        db.set("pos", Vec2::new(1.0, 2.0));
+
+        assert_eq!(read_fact::<i64>(&db, "hp"), Ok(10));
+        assert_eq!(read_fact::<Vec<String>>(&db, "bag"), Ok(vec!["key".into()]));
+        assert_eq!(read_fact::<Vec2>(&db, "pos"), Ok(Vec2::new(1.0, 2.0)));
+        assert_eq!(read_fact::<Option<i64>>(&db, "mp"), Ok(None));
+        assert_eq!(
+            read_fact::<f64>(&db, "hp"),
+            Err(FromFactsError::WrongType {
+                key: "hp".into(),
+                expected: FactType::Float,
+                found: FactType::Int,
+            })
+        );
+        assert_eq!(
+            read_fact::<Option<bool>>(&db, "hp")
+                .unwrap_err()
+                .to_string(),
+            "fact 'hp' should be Bool but is Int"
+        );
+    }
+
+    #[test]
+    fn test_from_facts_reports_the_first_problem() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("player:name", "hero");
+        db.set("player:hp", 7i64);
+
+        assert_eq!(
+            PlayerState::from_facts(&db),
+            Err(FromFactsError::Missing {
+                key: "player:alive".into()
+            })
+        );
+
+        db.set("player:alive", true);
+        assert_eq!(
+            PlayerState::from_facts(&db),
+            Ok(PlayerState {
+                hp: 7,
+                name: "hero".into(),
+                alive: true,
+                title: None,
+            })
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! # interval.rs
+//!
+//! Interval triggers for periodic rules.
+//! A rule declared with `Every(seconds: 5.0)` is triggered by the internal event
+//! `fre:every:5`; `tick_interval_rules_system` emits that event on schedule for as long as a
+//! rule with the trigger is registered and enabled.
+//!
+//! 周期性规则的间隔触发器。
+//! 以 `Every(seconds: 5.0)` 声明的规则由内部事件 `fre:every:5` 触发；
+//! 只要注册表中存在启用的该触发器规则，`tick_interval_rules_system` 就会按时发出该事件。
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::asset::ActionDef;
+use crate::event::FactEvent;
+use crate::layered::LayeredFactDatabase;
+use crate::rule::LayeredRuleRegistry;
+
+/// Prefix of the internal event ids used by interval triggers.
+///
+/// 间隔触发器所用内部事件 ID 的前缀。
+pub const INTERVAL_EVENT_PREFIX: &str = "fre:every:";
+
+/// Default fact that pauses all interval triggers while it is true.
+///
+/// 默认的暂停事实，为 true 时所有间隔触发器暂停。
+pub const DEFAULT_PAUSE_FACT: &str = "fre:paused";
+
+/// Event id that triggers rules running every `seconds`.
+/// Rules sharing the same interval share the same id, and therefore one tick.
+///
+/// 每 `seconds` 秒触发规则的事件 ID。
+/// 间隔相同的规则共享同一个 ID，因此共享同一个计时。
+pub fn interval_event_id(seconds: f32) -> String {
+    format!("{}{}", INTERVAL_EVENT_PREFIX, seconds)
+}
+
+/// Parse the interval of an interval trigger id, if it is one.
+fn parse_interval(event_id: &str) -> Option<f32> {
+    event_id
+        .strip_prefix(INTERVAL_EVENT_PREFIX)?
+        .parse()
+        .ok()
+        .filter(|seconds: &f32| *seconds > 0.0)
+}
+
+/// Time accumulated by each active interval trigger.
+///
+/// 每个活动间隔触发器累计的时间。
+#[derive(Resource, Debug, Clone)]
+pub struct IntervalClock {
+    /// Boolean fact that pauses all intervals while true.
+    ///
+    /// 为 true 时暂停所有间隔的布尔事实。
+    pub pause_fact: String,
+
+    elapsed: HashMap<String, f32>,
+    /// Interval of each enabled interval trigger, refreshed when the registry changes.
+    intervals: HashMap<String, f32>,
+}
+
+impl Default for IntervalClock {
+    fn default() -> Self {
+        Self {
+            pause_fact: DEFAULT_PAUSE_FACT.to_string(),
+            elapsed: HashMap::new(),
+            intervals: HashMap::new(),
+        }
+    }
+}
+
+impl IntervalClock {
+    /// Number of interval triggers currently being tracked.
+    ///
+    /// 当前正在跟踪的间隔触发器数量。
+    pub fn active_intervals(&self) -> usize {
+        self.elapsed.len()
+    }
+}
+
+/// System that emits interval trigger events for the enabled interval rules in the registry.
+/// Each interval emits at most once per frame, and intervals whose rules are gone are dropped.
+/// The registry is only rescanned in frames it was modified in. Does nothing in apps without a
+/// `Time` resource.
+///
+/// 为注册表中启用的间隔规则发出间隔触发事件的系统。
+/// 每个间隔每帧最多发出一次，规则已不存在的间隔会被移除。
+/// 只有在注册表被修改的帧中才会重新扫描注册表。在没有 `Time` 资源的应用中不执行任何操作。
+pub fn tick_interval_rules_system<A: ActionDef>(
+    time: Option<Res<Time>>,
+    registry: Res<LayeredRuleRegistry<A>>,
+    db: Res<LayeredFactDatabase>,
+    mut clock: ResMut<IntervalClock>,
+    mut writer: MessageWriter<FactEvent>,
+) {
+    let refresh = registry.is_changed() || clock.is_added();
+    let clock = &mut *clock;
+    if refresh {
+        clock.intervals = registry
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                Some((
+                    rule.trigger.to_string(),
+                    parse_interval(rule.trigger.as_str())?,
+                ))
+            })
+            .collect();
+        let intervals = &clock.intervals;
+        clock
+            .elapsed
+            .retain(|event_id, _| intervals.contains_key(event_id));
+    }
+    if clock.intervals.is_empty() {
+        return;
+    }
+
+    let Some(time) = time else {
+        return;
+    };
+    if db.get_bool(&clock.pause_fact) == Some(true) {
+        return;
+    }
+
+    let delta = time.delta_secs();
+    for (event_id, &seconds) in &clock.intervals {
+        if !clock.elapsed.contains_key(event_id) {
+            clock.elapsed.insert(event_id.clone(), 0.0);
+        }
+        let Some(elapsed) = clock.elapsed.get_mut(event_id) else {
+            continue;
+        };
+        *elapsed += delta;
+        if *elapsed >= seconds {
+            *elapsed %= seconds;
+            writer.write(FactEvent::new(event_id.as_str()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, FreAsset};
+    use std::time::Duration;
+
+    fn interval_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<IntervalClock>()
+            .add_message::<FactEvent>()
+            .add_systems(Update, tick_interval_rules_system::<CoreActionDef>);
+        app
+    }
+
+    /// Advance simulated time by `seconds`, run one frame, and return the emitted event ids.
+    fn step(app: &mut App, seconds: f32) -> Vec<String> {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        app.update();
+        app.world()
+            .resource::<Messages<FactEvent>>()
+            .iter_current_update_messages()
+            .map(|event| event.id.to_string())
+            .collect()
+    }
+
+    fn register_asset(app: &mut App, fre_data: &str) {
+        let asset: FreAsset = ron::from_str(fre_data).unwrap();
+        let mut registry = app
+            .world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>();
+        asset.register_rules_layered(&mut registry);
+    }
+
+    #[test]
+    fn test_every_emits_on_schedule() {
+        let mut app = interval_app();
+        register_asset(
+            &mut app,
+            r#"(rules: [(id: "decay_hunger", event: Every(seconds: 5.0))])"#,
+        );
+
+        assert!(step(&mut app, 3.0).is_empty());
+        assert_eq!(step(&mut app, 3.0), vec!["fre:every:5"]);
+        assert!(step(&mut app, 3.0).is_empty());
+        assert_eq!(step(&mut app, 1.0), vec!["fre:every:5"]);
+    }
+
+    #[test]
+    fn test_shared_interval_and_short_intervals() {
+        let mut app = interval_app();
+        register_asset(
+            &mut app,
+            r#"(rules: [
+                (id: "a", event: Every(seconds: 1.0)),
+                (id: "b", event: Every(seconds: 1.0)),
+                (id: "fast", event: Every(seconds: 0.01)),
+            ])"#,
+        );
+
+        let mut emitted = step(&mut app, 1.0);
+        emitted.sort();
+        // One tick for the shared interval; the short interval emits once per frame
+        assert_eq!(emitted, vec!["fre:every:0.01", "fre:every:1"]);
+        assert_eq!(
+            app.world().resource::<IntervalClock>().active_intervals(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_pause_and_unregister_stop_ticks() {
+        let mut app = interval_app();
+        register_asset(
+            &mut app,
+            r#"(rules: [(id: "decay_hunger", event: Every(seconds: 1.0))])"#,
+        );
+
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set(DEFAULT_PAUSE_FACT, true);
+        assert!(step(&mut app, 2.0).is_empty());
+
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set(DEFAULT_PAUSE_FACT, false);
+        assert_eq!(step(&mut app, 1.0), vec!["fre:every:1"]);
+
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+            .unregister("decay_hunger");
+        assert!(step(&mut app, 2.0).is_empty());
+        assert_eq!(
+            app.world().resource::<IntervalClock>().active_intervals(),
+            0
+        );
+
+        // Rules registered later are picked up
+        register_asset(
+            &mut app,
+            r#"(rules: [(id: "decay_hunger", event: Every(seconds: 1.0))])"#,
+        );
+        assert_eq!(step(&mut app, 1.0), vec!["fre:every:1"]);
+    }
+}
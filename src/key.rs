@@ -0,0 +1,181 @@
+//! # key.rs
+//!
+//! Interned fact keys. A `FactKey` is a `u32` symbol into a process-wide string interner, so
+//! keys repeated across many rules and events are stored once and compare/hash as integers.
+//!
+//! 驻留的事实键。`FactKey` 是指向进程级字符串驻留池的 `u32` 符号，
+//! 因此在许多规则和事件中重复出现的键只存储一次，并以整数方式比较和哈希。
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+#[cfg(feature = "debug")]
+use bevy::reflect::{Reflect, ReflectDeserialize, ReflectSerialize};
+
+/// Interned fact key.
+///
+/// Construction interns the string in a global table guarded by an `RwLock`, so keys can be
+/// created and resolved from any thread. Interned strings live for the rest of the process;
+/// intern stable identifiers, not unbounded runtime text.
+///
+/// 驻留的事实键。
+///
+/// 构造时会把字符串驻留到由 `RwLock` 保护的全局表中，因此可以在任意线程中创建和解析键。
+/// 驻留的字符串会一直存活到进程结束；请只驻留稳定的标识符，而不是无上限的运行时文本。
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "debug",
+    derive(Reflect),
+    reflect(opaque, Clone, Debug, PartialEq, Hash, Serialize, Deserialize)
+)]
+pub struct FactKey(u32);
+
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<&'static str, u32>,
+    strings: Vec<&'static str>,
+}
+
+fn interner() -> &'static RwLock<Interner> {
+    static INTERNER: OnceLock<RwLock<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(RwLock::default)
+}
+
+impl FactKey {
+    /// Intern a key, returning the existing symbol if the string was seen before.
+    ///
+    /// 驻留一个键；如果该字符串之前出现过，则返回已有的符号。
+    pub fn new(key: &str) -> Self {
+        let lock = interner();
+        if let Some(&symbol) = lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .symbols
+            .get(key)
+        {
+            return Self(symbol);
+        }
+
+        let mut interner = lock.write().unwrap_or_else(PoisonError::into_inner);
+        // Another thread may have interned the key between the two locks
+        if let Some(&symbol) = interner.symbols.get(key) {
+            return Self(symbol);
+        }
+        let symbol = u32::try_from(interner.strings.len()).expect("fact key interner is full");
+        let leaked: &'static str = Box::leak(key.to_owned().into_boxed_str());
+        interner.strings.push(leaked);
+        interner.symbols.insert(leaked, symbol);
+        Self(symbol)
+    }
+
+    /// The key for `key` if it was interned before, without interning it.
+    ///
+    /// 如果 `key` 之前已被驻留则返回其键，不会驻留它。
+    pub fn lookup(key: &str) -> Option<Self> {
+        interner()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .symbols
+            .get(key)
+            .map(|&symbol| Self(symbol))
+    }
+
+    /// Resolve the key back to its string.
+    ///
+    /// 将键解析回字符串。
+    pub fn as_str(&self) -> &'static str {
+        interner()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .strings[self.0 as usize]
+    }
+}
+
+impl From<&str> for FactKey {
+    fn from(key: &str) -> Self {
+        Self::new(key)
+    }
+}
+
+impl From<String> for FactKey {
+    fn from(key: String) -> Self {
+        Self::new(&key)
+    }
+}
+
+impl From<FactKey> for String {
+    fn from(key: FactKey) -> Self {
+        key.as_str().to_string()
+    }
+}
+
+/// Keys serialize as their string, since symbols differ between runs.
+impl Serialize for FactKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for FactKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Ok(Self::new(&key))
+    }
+}
+
+impl fmt::Debug for FactKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FactKey").field(&self.as_str()).finish()
+    }
+}
+
+impl fmt::Display for FactKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{FactDatabase, FactReader};
+
+    #[test]
+    fn test_same_string_same_key() {
+        let a = FactKey::from("player:hp");
+        let b = FactKey::from(String::from("player:hp"));
+        let c = FactKey::new("player:mp");
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // Comparison is a plain integer compare
+        assert_eq!(std::mem::size_of::<FactKey>(), std::mem::size_of::<u32>());
+    }
+
+    #[test]
+    fn test_as_str_round_trip() {
+        let key = FactKey::new("quest:stage");
+        assert_eq!(key.as_str(), "quest:stage");
+        assert_eq!(key.to_string(), "quest:stage");
+        assert_eq!(String::from(key), "quest:stage");
+    }
+
+    #[test]
+    fn test_interning_across_threads() {
+        let handles: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| FactKey::new("shared:key")))
+            .collect();
+        let keys: Vec<FactKey> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert!(keys.iter().all(|key| *key == keys[0]));
+    }
+
+    #[test]
+    fn test_fact_key_lookup() {
+        let key = FactKey::new("gold");
+        let mut db = FactDatabase::new();
+        db.set(key, 10i64);
+        assert_eq!(db.get_by_key(key), Some(&crate::FactValue::Int(10)));
+    }
+}
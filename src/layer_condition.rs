@@ -0,0 +1,244 @@
+//! # layer_condition.rs
+//!
+//! Built-in conditions pinned to one storage layer. Ordinary reads fall back from the local
+//! layer to the global one, so a local shadow hides the persistent value; these conditions read
+//! a single layer through `FactReader::get_in_layer` instead. `global_equals($key, value)` and
+//! `local_equals($key, value)` compare the fact in that layer with a literal, and
+//! `global_exists($key)` / `local_exists($key)` check that the layer stores it.
+//! `in_layer(global, <condition>)` evaluates any other built-in condition, such as
+//! `float($hp) > 2`, against one layer; an inner condition that is not a built-in leaves the
+//! whole condition to the game's evaluator. Readers without layers fail every layer condition.
+//! Like the other built-ins, they are checked by `ConditionEvaluator` before the game's evaluator.
+//!
+//! 固定到单个存储层的内置条件。普通读取会从局部层回退到全局层，因此局部遮蔽会隐藏持久值；
+//! 这些条件改为通过 `FactReader::get_in_layer` 只读取一层。`global_equals($key, value)` 和
+//! `local_equals($key, value)` 把该层中的事实与字面量比较，`global_exists($key)` /
+//! `local_exists($key)` 检查该层是否存储了该事实。`in_layer(global, <condition>)` 针对某一层
+//! 评估任何其他内置条件（例如 `float($hp) > 2`）；内层条件不是内置条件时，整个条件交给游戏的评估器。
+//! 没有分层的读取器会使所有分层条件失败。与其他内置条件一样，它们由 `ConditionEvaluator`
+//! 在游戏评估器之前检查。
+
+use crate::database::{FactLayer, FactReader, FactValue};
+
+/// A parsed built-in condition on one storage layer.
+///
+/// 已解析的针对单个存储层的内置条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerCondition<'a> {
+    /// `global_equals($key, value)` or `local_equals($key, value)`. Quoted values compare as
+    /// strings; unquoted ones also match booleans and numbers by value.
+    ///
+    /// `global_equals($key, value)` 或 `local_equals($key, value)`。带引号的值按字符串比较；
+    /// 不带引号的值也可按值匹配布尔值和数值。
+    Equals(FactLayer, &'a str, &'a str),
+
+    /// `global_exists($key)` or `local_exists($key)`.
+    Exists(FactLayer, &'a str),
+}
+
+impl<'a> LayerCondition<'a> {
+    /// Parse a condition expression, returning None if it is not a layer condition.
+    ///
+    /// 解析条件表达式；如果不是分层条件则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let (name, args) = condition.trim().split_once('(')?;
+        let (layer, kind) = name.trim().split_once('_')?;
+        let layer = layer_name(layer)?;
+        let args = args.strip_suffix(')')?;
+        match kind {
+            "exists" => Some(LayerCondition::Exists(layer, key(args)?)),
+            "equals" => {
+                let (key_arg, value) = args.split_once(',')?;
+                Some(LayerCondition::Equals(layer, key(key_arg)?, value.trim()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the fact in the layer satisfies the condition.
+    ///
+    /// 该层中的事实是否满足条件。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        match *self {
+            LayerCondition::Equals(layer, key, literal) => facts
+                .get_in_layer(layer, key)
+                .is_some_and(|value| literal_equals(value, literal)),
+            LayerCondition::Exists(layer, key) => facts.contains_in_layer(layer, key),
+        }
+    }
+}
+
+/// A parsed `in_layer(global, <condition>)` or `in_layer(local, <condition>)` condition.
+/// `ConditionEvaluator` evaluates the inner condition against a `LayerReader`.
+///
+/// 已解析的 `in_layer(global, <condition>)` 或 `in_layer(local, <condition>)` 条件。
+/// `ConditionEvaluator` 针对 `LayerReader` 评估内层条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InLayer<'a>(pub FactLayer, pub &'a str);
+
+impl<'a> InLayer<'a> {
+    /// Parse a condition expression, returning None if it is not an `in_layer` condition.
+    ///
+    /// 解析条件表达式；如果不是 `in_layer` 条件则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let args = condition
+            .trim()
+            .strip_prefix("in_layer(")?
+            .strip_suffix(')')?;
+        let (layer, inner) = args.split_once(',')?;
+        Some(InLayer(layer_name(layer)?, inner.trim()))
+    }
+}
+
+/// Reads one layer of another reader, so built-in and host conditions can be evaluated
+/// against it.
+pub(crate) struct LayerReader<'a> {
+    facts: &'a dyn FactReader,
+    layer: FactLayer,
+}
+
+impl<'a> LayerReader<'a> {
+    pub(crate) fn new(facts: &'a dyn FactReader, layer: FactLayer) -> Self {
+        Self { facts, layer }
+    }
+}
+
+impl FactReader for LayerReader<'_> {
+    fn get_by_str(&self, key: &str) -> Option<&FactValue> {
+        self.facts.get_in_layer(self.layer, key)
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.facts.contains_in_layer(self.layer, key)
+    }
+}
+
+/// Layer named `global` or `local`.
+fn layer_name(name: &str) -> Option<FactLayer> {
+    match name.trim() {
+        "global" => Some(FactLayer::Global),
+        "local" => Some(FactLayer::Local),
+        _ => None,
+    }
+}
+
+/// Key of `$key`.
+fn key(arg: &str) -> Option<&str> {
+    arg.trim().strip_prefix('$')
+}
+
+/// Whether a fact value equals a literal written in a condition.
+fn literal_equals(value: &FactValue, literal: &str) -> bool {
+    if let Some(text) = literal
+        .strip_prefix('"')
+        .and_then(|literal| literal.strip_suffix('"'))
+    {
+        return value.as_string() == Some(text);
+    }
+    match value {
+        FactValue::String(text) => text == literal,
+        FactValue::Bool(flag) => literal.parse() == Ok(*flag),
+        _ => value
+            .as_number()
+            .zip(literal.parse::<f64>().ok())
+            .is_some_and(|(value, literal)| value == literal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::EnumRegistry;
+    use crate::database::FactDatabase;
+    use crate::layered::LayeredFactDatabase;
+    use crate::systems::ConditionEvaluator;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            LayerCondition::parse("global_equals($completed_once, true)"),
+            Some(LayerCondition::Equals(
+                FactLayer::Global,
+                "completed_once",
+                "true"
+            ))
+        );
+        assert_eq!(
+            LayerCondition::parse("local_exists( $room )"),
+            Some(LayerCondition::Exists(FactLayer::Local, "room"))
+        );
+        assert_eq!(
+            InLayer::parse("in_layer(global, float($hp) > 2)"),
+            Some(InLayer(FactLayer::Global, "float($hp) > 2"))
+        );
+        assert_eq!(LayerCondition::parse("scene_exists($room)"), None);
+        assert_eq!(LayerCondition::parse("global_contains($room, 1)"), None);
+        assert_eq!(InLayer::parse("in_layer(scene, exists($room))"), None);
+    }
+
+    #[test]
+    fn test_layer_conditions_ignore_shadows() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("completed_once", true);
+        db.set_global("hp", 5i64);
+        db.set_global("title", "hero");
+        db.set("completed_once", false);
+        db.set("hp", 1i64);
+        let evaluator = ConditionEvaluator::default();
+        let passes = |condition: &str, facts: &dyn FactReader| {
+            evaluator.evaluate_conditions(&[condition.to_string()], facts, &EnumRegistry::default())
+        };
+
+        assert!(passes("global_equals($completed_once, true)", &db));
+        assert!(passes("local_equals($completed_once, false)", &db));
+        assert!(passes("global_equals($hp, 5.0)", &db));
+        assert!(passes(r#"global_equals($title, "hero")"#, &db));
+        assert!(!passes(r#"global_equals($hp, "5")"#, &db));
+        assert!(passes("local_exists($hp)", &db));
+        assert!(!passes("local_exists($title)", &db));
+        assert!(passes("global_exists($title)", &db));
+        assert!(passes("in_layer(global, float($hp) > 2)", &db));
+        assert!(!passes("in_layer(local, float($hp) > 2)", &db));
+        assert!(!passes("in_layer(local, exists($title))", &db));
+
+        // Readers without layers fail
+        let mut flat = FactDatabase::new();
+        flat.set("hp", 5i64);
+        assert!(!passes("global_equals($hp, 5)", &flat));
+        assert!(!passes("in_layer(global, exists($hp))", &flat));
+    }
+
+    #[test]
+    fn test_in_layer_reads_one_layer_for_host_conditions() {
+        use crate::asset::CoreActionDef;
+        use crate::expr_condition::ExprConditionEvaluator;
+        use crate::rule::Rule;
+
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("hp", 5i64);
+        db.set("hp", -1i64);
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        for (condition, expected) in [
+            ("in_layer(global, $hp > 0)", true),
+            ("in_layer(local, $hp > 0)", false),
+            ("in_layer(local, $hp < 0 && $hp > -2)", true),
+        ] {
+            let mut rule = Rule::<CoreActionDef>::builder("rule", "hit")
+                .condition_expr(condition)
+                .build();
+            let conditions = [condition.to_string()];
+            assert_eq!(
+                evaluator.evaluate_conditions(&conditions, &db, &enums),
+                expected,
+                "{condition}"
+            );
+            assert!(rule.compile_conditions());
+            assert_eq!(
+                evaluator.evaluate(&rule, &db, &enums),
+                expected,
+                "{condition}"
+            );
+        }
+    }
+}
@@ -16,8 +16,50 @@
 //! - **全局层**: 跨游戏状态的持久数据（如玩家名称、存档进度）
 //! - **局部层**: 当前上下文的临时数据（如战斗回合数、房间状态）
 
-use crate::database::{FactDatabase, FactReader, FactValue};
+use crate::database::{FactDatabase, FactLayer, FactReader, FactValue, add_or_warn};
+use crate::schema::FactSchema;
 use bevy::prelude::*;
+use std::collections::HashMap;
+
+mod absent;
+mod aliases;
+mod change_tracking;
+mod changes;
+mod defaults;
+mod dump;
+mod effective;
+mod enforcement;
+mod frame;
+mod freeze;
+mod initial;
+mod lists;
+mod merge;
+mod numeric;
+mod persist;
+mod prefix;
+mod retain;
+mod snapshot;
+mod stack;
+mod toggle;
+mod transaction;
+mod transfer;
+mod view;
+mod watch;
+
+pub use aliases::AliasCycleError;
+use aliases::FactAliases;
+use change_tracking::ChangeMarks;
+pub use change_tracking::{FactChangedEvent, emit_fact_changed_events_system};
+pub use changes::clear_fact_changes_system;
+use enforcement::admitted;
+use frame::FrameStart;
+pub use freeze::FreezePolicy;
+pub use initial::InitialFacts;
+pub use persist::{FactSaveError, GlobalLoadMode};
+pub use snapshot::LayeredFactSnapshot;
+pub use toggle::TogglePolicy;
+pub use view::{ViewFacts, prune_despawned_view_facts_system};
+pub use watch::{FactWatchers, emit_fact_watch_events_system};
 
 #[cfg(feature = "debug")]
 use bevy::reflect::Reflect;
@@ -27,10 +69,13 @@ use bevy::reflect::Reflect;
 /// 具有全局和局部作用域的分层事实数据库。
 ///
 /// # Read Priority
-/// When reading a fact, the local layer is checked first. If not found, the global layer is checked.
+/// When reading a fact, the local layer is checked first, after the tier of the active view if
+/// one is set. If not found, the global layer is checked, and finally the read-only defaults
+/// registered with `set_default`.
 ///
 /// # 读取优先级
-/// 读取事实时，首先检查局部层。如果未找到，则检查全局层。
+/// 读取事实时，首先检查局部层（如果设置了活动视图，则先检查其视图层）。如果未找到，则检查全局层，
+/// 最后检查通过 `set_default` 注册的只读默认值。
 ///
 /// # Write Behavior
 /// - `set` / `set_local`: Write to local layer (default)
@@ -39,18 +84,99 @@ use bevy::reflect::Reflect;
 /// # 写入行为
 /// - `set` / `set_local`: 写入局部层（默认）
 /// - `set_global`: 写入全局层（谨慎使用）
-#[derive(Resource, Default, Debug)]
-#[cfg_attr(feature = "debug", derive(Reflect))]
+#[derive(Resource, Default, Debug, Clone)]
+#[cfg_attr(feature = "debug", derive(Reflect), reflect(Resource))]
 pub struct LayeredFactDatabase {
     /// Global layer: persistent data across game states.
     ///
     /// 全局层：跨游戏状态的持久数据。
     global: FactDatabase,
 
-    /// Local layer: temporary data for current context.
+    /// Local layer: temporary data for current context. While layers are pushed, this is the
+    /// top of the stack.
     ///
-    /// 局部层：当前上下文的临时数据。
+    /// 局部层：当前上下文的临时数据。压入层时，它是栈顶。
     local: FactDatabase,
+
+    /// Local layers buried under `local` by `push_layer`, bottom first.
+    ///
+    /// 被 `push_layer` 压在 `local` 之下的局部层，底层在前。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    buried: Vec<FactDatabase>,
+
+    /// Names of the pushed layers, bottom first.
+    ///
+    /// 已压入层的名称，底层在前。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    pushed_names: Vec<String>,
+
+    /// Read-only fallback values for keys missing from both layers.
+    ///
+    /// 两层中都缺失的键的只读回退值。
+    defaults: FactDatabase,
+
+    /// Per-view tiers keyed by the view's entity, read before the layers by that view.
+    ///
+    /// 以视图实体为键的每视图层，该视图读取时先于各层。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    view: HashMap<Entity, FactDatabase>,
+
+    /// View whose tier is read before the layers by every read; see `set_active_view`.
+    ///
+    /// 每次读取时先于各层读取其视图层的视图；参见 `set_active_view`。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    active_view: Option<Entity>,
+
+    /// Key aliases for renamed facts.
+    ///
+    /// 重命名事实的键别名。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    aliases: FactAliases,
+
+    /// Declared fact types checked on every write.
+    ///
+    /// 每次写入时检查的声明事实类型。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    schema: FactSchema,
+
+    /// How `toggle` handles facts that are not booleans.
+    ///
+    /// `toggle` 如何处理非布尔事实。
+    toggle_policy: TogglePolicy,
+
+    /// Whether writes to the global layer are refused; see `freeze_global`.
+    ///
+    /// 是否拒绝对全局层的写入；参见 `freeze_global`。
+    global_frozen: bool,
+
+    /// What a write to the frozen global layer does.
+    ///
+    /// 写入已冻结全局层时的行为。
+    freeze_policy: FreezePolicy,
+
+    /// Starting values restored by `reset_to_initial`.
+    ///
+    /// `reset_to_initial` 恢复的初始值。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    initial: InitialFacts,
+
+    /// Facts whose changes emit events.
+    ///
+    /// 变化时会发出事件的事实。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    watchers: FactWatchers,
+
+    /// Where `take_changed` resumes, or None while change tracking is off.
+    ///
+    /// `take_changed` 继续的位置；变更跟踪关闭时为 None。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    change_marks: Option<ChangeMarks>,
+
+    /// Values of the keys tracked by `snapshot_frame_start` when the frame started.
+    ///
+    /// 由 `snapshot_frame_start` 跟踪的键在帧开始时的值。
+    #[cfg_attr(feature = "debug", reflect(ignore))]
+    frame_start: FrameStart,
 }
 
 impl LayeredFactDatabase {
@@ -58,24 +184,29 @@ impl LayeredFactDatabase {
     ///
     /// 创建一个新的空分层事实数据库。
     pub fn new() -> Self {
-        Self {
-            global: FactDatabase::new(),
-            local: FactDatabase::new(),
-        }
+        Self::default()
     }
 
     // ========================================================================
-    // Read Operations (Local-first, fallback to Global)
-    // 读取操作（优先局部层，回退到全局层）
+    // Read Operations (Local-first, fallback to Global, then Defaults)
+    // 读取操作（优先局部层，回退到全局层，再回退到默认值）
     // ========================================================================
 
-    /// Get a fact value, checking local layer first, then global.
+    /// Get a fact value, checking the active view's tier first, then local, then global, then
+    /// the defaults.
     ///
-    /// 获取事实值，首先检查局部层，然后检查全局层。
+    /// 获取事实值，首先检查活动视图层，然后检查局部层、全局层，最后检查默认值。
     pub fn get_by_str(&self, key: &str) -> Option<&FactValue> {
-        self.local
-            .get_by_str(key)
+        let key = self.aliases.resolve(key);
+        self.get_active_view(key)
+            .or_else(|| self.get_in_layers(key))
+    }
+
+    /// Local, global, then defaults lookup of an already resolved key.
+    fn get_in_layers(&self, key: &str) -> Option<&FactValue> {
+        self.get_local_by_str(key)
             .or_else(|| self.global.get_by_str(key))
+            .or_else(|| self.defaults.get_by_str(key))
     }
 
     /// Get an integer fact value.
@@ -110,31 +241,39 @@ impl LayeredFactDatabase {
     ///
     /// 获取字符串事实值。
     pub fn get_string(&self, key: &str) -> Option<&str> {
-        // Need to check both layers manually for string references
-        self.local
-            .get_string(key)
-            .or_else(|| self.global.get_string(key))
+        self.get_by_str(key).and_then(|v| v.as_string())
+    }
+
+    /// Get an entity reference fact value.
+    ///
+    /// 获取实体引用事实值。
+    pub fn get_entity(&self, key: &str) -> Option<Entity> {
+        self.get_by_str(key).and_then(|v| v.as_entity())
     }
 
-    /// Check if a fact exists in either layer.
+    /// Check if a fact exists in either layer or the active view's tier. Defaults are not
+    /// counted; see `contains_with_defaults`.
     ///
-    /// 检查事实是否存在于任一层。
+    /// 检查事实是否存在于任一层或活动视图层。不计入默认值；参见 `contains_with_defaults`。
     pub fn contains(&self, key: &str) -> bool {
-        self.local.contains(key) || self.global.contains(key)
+        let key = self.aliases.resolve(key);
+        self.get_active_view(key).is_some()
+            || self.get_local_by_str(key).is_some()
+            || self.global.contains(key)
     }
 
-    /// Check if a fact exists in the local layer only.
+    /// Check if a fact exists in the local layer only (any layer of the local stack).
     ///
-    /// 检查事实是否仅存在于局部层。
+    /// 检查事实是否仅存在于局部层（局部栈中的任一层）。
     pub fn contains_local(&self, key: &str) -> bool {
-        self.local.contains(key)
+        self.get_local_by_str(self.aliases.resolve(key)).is_some()
     }
 
     /// Check if a fact exists in the global layer only.
     ///
     /// 检查事实是否仅存在于全局层。
     pub fn contains_global(&self, key: &str) -> bool {
-        self.global.contains(key)
+        self.global.contains(self.aliases.resolve(key))
     }
 
     // ========================================================================
@@ -146,7 +285,10 @@ impl LayeredFactDatabase {
     ///
     /// 在局部层设置事实值（默认写入目标）。
     pub fn set(&mut self, key: impl Into<String>, value: impl Into<FactValue>) {
-        self.local.set(key, value);
+        let key = self.aliases.resolve_write_owned(key.into());
+        if let Some(value) = admitted(&self.schema, &key, value.into()) {
+            self.local.set(key, value);
+        }
     }
 
     /// Set a fact value in the local layer only if it's different from the current value.
@@ -155,14 +297,16 @@ impl LayeredFactDatabase {
     /// 仅当值与当前值不同时才在局部层设置。
     /// 如果值被更改返回 true，否则返回 false。
     pub fn set_if_changed(&mut self, key: impl Into<String>, value: impl Into<FactValue>) -> bool {
-        self.local.set_if_changed(key, value)
+        let key = self.aliases.resolve_write_owned(key.into());
+        admitted(&self.schema, &key, value.into())
+            .is_some_and(|value| self.local.set_if_changed(key, value))
     }
 
     /// Alias for `set` - explicitly writes to local layer.
     ///
     /// `set` 的别名 - 显式写入局部层。
     pub fn set_local(&mut self, key: impl Into<String>, value: impl Into<FactValue>) {
-        self.local.set(key, value);
+        self.set(key, value);
     }
 
     /// Set a fact value in the global layer.
@@ -171,7 +315,13 @@ impl LayeredFactDatabase {
     /// 在全局层设置事实值。
     /// 谨慎使用 - 仅用于必须跨状态转换持久化的数据。
     pub fn set_global(&mut self, key: impl Into<String>, value: impl Into<FactValue>) {
-        self.global.set(key, value);
+        let key = self.aliases.resolve_write_owned(key.into());
+        if !self.global_writable(&key) {
+            return;
+        }
+        if let Some(value) = admitted(&self.schema, &key, value.into()) {
+            self.global.set(key, value);
+        }
     }
 
     /// Set a fact value in the global layer only if it's different from the current value.
@@ -184,7 +334,10 @@ impl LayeredFactDatabase {
         key: impl Into<String>,
         value: impl Into<FactValue>,
     ) -> bool {
-        self.global.set_if_changed(key, value)
+        let key = self.aliases.resolve_write_owned(key.into());
+        self.global_writable(&key)
+            && admitted(&self.schema, &key, value.into())
+                .is_some_and(|value| self.global.set_if_changed(key, value))
     }
 
     /// Increment an integer fact in the local layer.
@@ -194,146 +347,31 @@ impl LayeredFactDatabase {
     /// 如果事实不存在，将使用增量值创建。
     pub fn increment(&mut self, key: &str, amount: i64) {
         let current = self.get_int(key).unwrap_or(0);
-        self.local.set(key, current + amount);
+        let policy = self.local.int_overflow_policy();
+        let Some(sum) = add_or_warn(policy, key, current, amount) else {
+            return;
+        };
+        let key = self.aliases.resolve_write(key);
+        if let Some(value) = admitted(&self.schema, key, sum.into()) {
+            self.local.set(key, value);
+        }
     }
 
     /// Increment an integer fact in the global layer.
     ///
     /// 在全局层增加整数事实。
     pub fn increment_global(&mut self, key: &str, amount: i64) {
-        let current = self.get_int(key).unwrap_or(0);
-        self.global.set(key, current + amount);
-    }
-
-    /// Add a numeric value to a fact (supports Int and Float).
-    /// If Int + Float, result is Float.
-    ///
-    /// 向事实添加数值（支持 Int 和 Float）。
-    /// 如果 Int + Float，结果为 Float。
-    pub fn add(&mut self, key: &str, amount: f64) {
-        match self.get_by_str(key) {
-            Some(FactValue::Int(i)) => {
-                if amount.fract() == 0.0 {
-                    self.local.set(key, *i + amount as i64);
-                } else {
-                    self.local.set(key, FactValue::Float(*i as f64 + amount));
-                }
-            }
-            Some(FactValue::Float(f)) => {
-                self.local.set(key, FactValue::Float(*f + amount));
-            }
-            _ => {
-                if amount.fract() == 0.0 {
-                    self.local.set(key, amount as i64);
-                } else {
-                    self.local.set(key, FactValue::Float(amount));
-                }
-            }
-        }
-    }
-
-    /// Subtract a numeric value from a fact.
-    ///
-    /// 从事实减去数值。
-    pub fn sub(&mut self, key: &str, amount: f64) {
-        self.add(key, -amount);
-    }
-
-    /// Multiply a fact by a numeric value.
-    ///
-    /// 将事实乘以数值。
-    pub fn mul(&mut self, key: &str, factor: f64) {
-        match self.get_by_str(key) {
-            Some(FactValue::Int(i)) => {
-                let result = *i as f64 * factor;
-                if result.fract() == 0.0 {
-                    self.local.set(key, result as i64);
-                } else {
-                    self.local.set(key, FactValue::Float(result));
-                }
-            }
-            Some(FactValue::Float(f)) => {
-                self.local.set(key, FactValue::Float(*f * factor));
-            }
-            _ => {
-                // No-op if fact doesn't exist
-            }
-        }
-    }
-
-    /// Divide a fact by a numeric value.
-    /// Division by zero sets the fact to 0.
-    ///
-    /// 将事实除以数值。
-    /// 除以零时将事实设为 0。
-    pub fn div(&mut self, key: &str, divisor: f64) {
-        if divisor == 0.0 {
-            self.local.set(key, 0i64);
-            return;
-        }
-        match self.get_by_str(key) {
-            Some(FactValue::Int(i)) => {
-                let result = *i as f64 / divisor;
-                if result.fract() == 0.0 {
-                    self.local.set(key, result as i64);
-                } else {
-                    self.local.set(key, FactValue::Float(result));
-                }
-            }
-            Some(FactValue::Float(f)) => {
-                self.local.set(key, FactValue::Float(*f / divisor));
-            }
-            _ => {
-                // No-op if fact doesn't exist
-            }
-        }
-    }
-
-    /// Apply modulo operation to a fact.
-    ///
-    /// 对事实应用取模运算。
-    pub fn modulo(&mut self, key: &str, divisor: i64) {
-        if divisor == 0 {
+        if !self.global_writable(key) {
             return;
         }
-        if let Some(i) = self.get_int(key) {
-            self.local.set(key, i % divisor);
-        }
-    }
-
-    /// Clamp a fact value between min and max (inclusive).
-    ///
-    /// 将事实值限制在 min 和 max 之间（包含）。
-    pub fn clamp(&mut self, key: &str, min: f64, max: f64) {
-        match self.get_by_str(key) {
-            Some(FactValue::Int(i)) => {
-                let clamped = (*i as f64).clamp(min, max);
-                if clamped.fract() == 0.0 {
-                    self.local.set(key, clamped as i64);
-                } else {
-                    self.local.set(key, FactValue::Float(clamped));
-                }
-            }
-            Some(FactValue::Float(f)) => {
-                self.local.set(key, FactValue::Float(f.clamp(min, max)));
-            }
-            _ => {}
-        }
-    }
-
-    /// Wrap a fact value within a range [min, max).
-    /// When value >= max, it wraps to min. When value < min, it wraps to max - 1.
-    ///
-    /// 将事实值包裹在范围 [min, max) 内。
-    /// 当值 >= max 时，包裹到 min。当值 < min 时，包裹到 max - 1。
-    pub fn wrap(&mut self, key: &str, min: i64, max: i64) {
-        if max <= min {
+        let current = self.get_int(key).unwrap_or(0);
+        let policy = self.global.int_overflow_policy();
+        let Some(sum) = add_or_warn(policy, key, current, amount) else {
             return;
-        }
-        if let Some(i) = self.get_int(key) {
-            let range = max - min;
-            let wrapped = ((i - min) % range + range) % range + min;
-            self.local.set(key, wrapped);
+        };
+        let key = self.aliases.resolve_write(key);
+        if let Some(value) = admitted(&self.schema, key, sum.into()) {
+            self.global.set(key, value);
         }
     }
 
@@ -341,13 +379,17 @@ impl LayeredFactDatabase {
     ///
     /// 从局部层移除事实。
     pub fn remove(&mut self, key: &str) -> Option<FactValue> {
-        self.local.remove(key)
+        self.local.remove(self.aliases.resolve_write(key))
     }
 
     /// Remove a fact from the global layer.
     ///
     /// 从全局层移除事实。
     pub fn remove_global(&mut self, key: &str) -> Option<FactValue> {
+        let key = self.aliases.resolve_write(key);
+        if !self.global_writable(key) {
+            return None;
+        }
         self.global.remove(key)
     }
 
@@ -356,12 +398,13 @@ impl LayeredFactDatabase {
     // 层管理
     // ========================================================================
 
-    /// Clear all facts from the local layer.
+    /// Clear all facts from the local layer, popping any pushed layers.
     /// Call this when transitioning between game states.
     ///
-    /// 清空局部层的所有事实。
+    /// 清空局部层的所有事实，并弹出所有已压入的层。
     /// 在游戏状态转换时调用此方法。
     pub fn clear_local(&mut self) {
+        while self.pop_layer().is_some() {}
         self.local.clear();
     }
 
@@ -371,55 +414,18 @@ impl LayeredFactDatabase {
     /// 清空全局层的所有事实。
     /// 谨慎使用 - 这将移除所有持久数据。
     pub fn clear_global(&mut self) {
-        self.global.clear();
-    }
-
-    /// Clear both layers.
-    ///
-    /// 清空两层。
-    pub fn clear_all(&mut self) {
-        self.local.clear();
-        self.global.clear();
-    }
-
-    /// Promote a fact from local layer to global layer.
-    /// The fact is moved (removed from local, added to global).
-    ///
-    /// 将事实从局部层提升到全局层。
-    /// 事实被移动（从局部层移除，添加到全局层）。
-    pub fn promote_to_global(&mut self, key: &str) -> bool {
-        if let Some(value) = self.local.remove(key) {
-            self.global.set(key, value);
-            true
-        } else {
-            false
-        }
-    }
-
-    /// Copy a fact from local layer to global layer (keeping both copies).
-    ///
-    /// 将事实从局部层复制到全局层（保留两份副本）。
-    pub fn copy_to_global(&mut self, key: &str) -> bool {
-        if let Some(value) = self.local.get_by_str(key).cloned() {
-            self.global.set(key, value);
-            true
-        } else {
-            false
+        if self.global_writable("*") {
+            self.global.clear();
         }
     }
 
-    /// Demote a fact from global layer to local layer.
-    /// The fact is moved (removed from global, added to local).
+    /// Clear both layers and every view tier. A frozen global layer is kept.
     ///
-    /// 将事实从全局层降级到局部层。
-    /// 事实被移动（从全局层移除，添加到局部层）。
-    pub fn demote_to_local(&mut self, key: &str) -> bool {
-        if let Some(value) = self.global.remove(key) {
-            self.local.set(key, value);
-            true
-        } else {
-            false
-        }
+    /// 清空两层以及所有视图层。已冻结的全局层会被保留。
+    pub fn clear_all(&mut self) {
+        self.clear_local();
+        self.clear_global();
+        self.view.clear();
     }
 
     // ========================================================================
@@ -427,16 +433,16 @@ impl LayeredFactDatabase {
     // 直接层访问（用于高级用例）
     // ========================================================================
 
-    /// Get immutable reference to the local layer.
+    /// Get immutable reference to the local layer (the top of the local stack).
     ///
-    /// 获取局部层的不可变引用。
+    /// 获取局部层（局部栈顶）的不可变引用。
     pub fn local(&self) -> &FactDatabase {
         &self.local
     }
 
-    /// Get mutable reference to the local layer.
+    /// Get mutable reference to the local layer (the top of the local stack).
     ///
-    /// 获取局部层的可变引用。
+    /// 获取局部层（局部栈顶）的可变引用。
     pub fn local_mut(&mut self) -> &mut FactDatabase {
         &mut self.local
     }
@@ -448,9 +454,10 @@ impl LayeredFactDatabase {
         &self.global
     }
 
-    /// Get mutable reference to the global layer.
+    /// Get mutable reference to the global layer. Writes through it bypass the schema and the
+    /// freeze.
     ///
-    /// 获取全局层的可变引用。
+    /// 获取全局层的可变引用。通过它的写入会绕过模式和冻结。
     pub fn global_mut(&mut self) -> &mut FactDatabase {
         &mut self.global
     }
@@ -464,14 +471,14 @@ impl LayeredFactDatabase {
     ///
     /// 获取两层中事实的总数。
     pub fn len(&self) -> usize {
-        self.local.len() + self.global.len()
+        self.local_len() + self.global.len()
     }
 
-    /// Get the number of facts in the local layer.
+    /// Get the number of facts in the local layer, summed over the local stack.
     ///
-    /// 获取局部层中事实的数量。
+    /// 获取局部层中事实的数量（局部栈各层之和）。
     pub fn local_len(&self) -> usize {
-        self.local.len()
+        self.local_layers().map(FactDatabase::len).sum()
     }
 
     /// Get the number of facts in the global layer.
@@ -481,9 +488,9 @@ impl LayeredFactDatabase {
         self.global.len()
     }
 
-    /// Iterate over facts in the local layer.
+    /// Iterate over facts in the local layer (the top of the local stack).
     ///
-    /// 迭代局部层中的事实。
+    /// 迭代局部层（局部栈顶）中的事实。
     pub fn iter_local(&self) -> impl Iterator<Item = (&String, &FactValue)> {
         self.local.iter()
     }
@@ -499,19 +506,44 @@ impl LayeredFactDatabase {
     ///
     /// 检查两层是否都为空。
     pub fn is_empty(&self) -> bool {
-        self.local.is_empty() && self.global.is_empty()
+        self.local_layers().all(FactDatabase::is_empty) && self.global.is_empty()
     }
 }
 
 impl FactReader for LayeredFactDatabase {
     fn get_by_str(&self, key: &str) -> Option<&FactValue> {
-        self.local
-            .get_by_str(key)
-            .or_else(|| self.global.get_by_str(key))
+        LayeredFactDatabase::get_by_str(self, key)
     }
 
+    /// Readers see the defaults like any other fact, so `exists($key)` agrees with reads.
     fn contains(&self, key: &str) -> bool {
-        self.local.contains(key) || self.global.contains(key)
+        self.contains_with_defaults(key)
+    }
+
+    fn layer(&self, layer: FactLayer) -> Option<&dyn FactReader> {
+        match layer {
+            FactLayer::Global => Some(&self.global),
+            FactLayer::Local => Some(&self.local),
+        }
+    }
+
+    fn get_in_layer(&self, layer: FactLayer, key: &str) -> Option<&FactValue> {
+        let key = self.aliases.resolve(key);
+        match layer {
+            FactLayer::Global => self.global.get_by_str(key),
+            FactLayer::Local => self.get_local_by_str(key),
+        }
+    }
+
+    fn contains_in_layer(&self, layer: FactLayer, key: &str) -> bool {
+        match layer {
+            FactLayer::Global => self.contains_global(key),
+            FactLayer::Local => self.contains_local(key),
+        }
+    }
+
+    fn changed_since_last_frame(&self, key: &str) -> Option<bool> {
+        LayeredFactDatabase::changed_since_last_frame(self, key)
     }
 }
 
@@ -550,20 +582,6 @@ mod tests {
         assert!(!db.contains_local("global_only"));
     }
 
-    #[test]
-    fn test_promote_to_global() {
-        let mut db = LayeredFactDatabase::new();
-
-        db.set_local("temp_score", 42i64);
-        assert!(db.contains_local("temp_score"));
-        assert!(!db.contains_global("temp_score"));
-
-        db.promote_to_global("temp_score");
-        assert!(!db.contains_local("temp_score"));
-        assert!(db.contains_global("temp_score"));
-        assert_eq!(db.get_int("temp_score"), Some(42));
-    }
-
     #[test]
     fn test_increment_across_layers() {
         let mut db = LayeredFactDatabase::new();
@@ -579,50 +597,6 @@ mod tests {
         assert_eq!(db.global().get_int("counter"), Some(10)); // Global unchanged
     }
 
-    #[test]
-    fn test_copy_to_global() {
-        let mut db = LayeredFactDatabase::new();
-
-        db.set_local("data", "important");
-        assert!(db.copy_to_global("data"));
-
-        // Both layers now have the value
-        assert!(db.contains_local("data"));
-        assert!(db.contains_global("data"));
-        assert_eq!(db.local().get_string("data"), Some("important"));
-        assert_eq!(db.global().get_string("data"), Some("important"));
-    }
-
-    #[test]
-    fn test_copy_to_global_nonexistent() {
-        let mut db = LayeredFactDatabase::new();
-        assert!(!db.copy_to_global("nonexistent"));
-    }
-
-    #[test]
-    fn test_demote_to_local() {
-        let mut db = LayeredFactDatabase::new();
-
-        db.set_global("global_data", 100i64);
-        assert!(db.demote_to_local("global_data"));
-
-        assert!(!db.contains_global("global_data"));
-        assert!(db.contains_local("global_data"));
-        assert_eq!(db.get_int("global_data"), Some(100));
-    }
-
-    #[test]
-    fn test_demote_to_local_nonexistent() {
-        let mut db = LayeredFactDatabase::new();
-        assert!(!db.demote_to_local("nonexistent"));
-    }
-
-    #[test]
-    fn test_promote_to_global_nonexistent() {
-        let mut db = LayeredFactDatabase::new();
-        assert!(!db.promote_to_global("nonexistent"));
-    }
-
     #[test]
     fn test_remove_operations() {
         let mut db = LayeredFactDatabase::new();
@@ -782,4 +756,33 @@ mod tests {
         db.clear_local();
         assert_eq!(db.get_string("player_name"), Some("GlobalPlayer"));
     }
+
+    #[test]
+    fn test_layer_pinned_reads() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("completed_once", true);
+        // A debug tool shadows the persistent value locally
+        db.set_local("completed_once", false);
+
+        let reader: &dyn FactReader = &db;
+        assert_eq!(reader.get_bool("completed_once"), Some(false));
+        assert_eq!(
+            reader.get_in_layer(FactLayer::Global, "completed_once"),
+            Some(&FactValue::Bool(true))
+        );
+        assert_eq!(
+            reader.get_in_layer(FactLayer::Local, "completed_once"),
+            Some(&FactValue::Bool(false))
+        );
+
+        db.set_global("save_slot", 2i64);
+        assert!(db.contains_in_layer(FactLayer::Global, "save_slot"));
+        assert!(!db.contains_in_layer(FactLayer::Local, "save_slot"));
+
+        // Aliases resolve as they do for get_in_layer
+        db.add_alias("slot", "save_slot").unwrap();
+        let reader: &dyn FactReader = &db;
+        assert!(reader.contains_in_layer(FactLayer::Global, "slot"));
+        assert!(!reader.contains_in_layer(FactLayer::Local, "slot"));
+    }
 }
@@ -0,0 +1,133 @@
+//! # absent.rs
+//!
+//! # absent.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Insert-if-missing helpers for `LayeredFactDatabase`. The local variants check both layers
+//! before writing to the local layer, so initializing a fact that already has a global value does
+//! not shadow that value with a local default. The global variants only look at the global layer.
+//! Inserted values are still subject to the schema, so a rejected value is not inserted.
+//!
+//! `LayeredFactDatabase` 的缺失时插入辅助方法。局部版本在写入局部层之前会检查两层，
+//! 因此初始化一个已有全局值的事实时，不会用局部默认值遮蔽该值。全局版本只查看全局层。
+//! 插入的值仍受模式约束，被拒绝的值不会被插入。
+
+use super::{LayeredFactDatabase, admitted};
+use crate::database::FactValue;
+
+impl LayeredFactDatabase {
+    /// Get the effective fact `key`, first setting it to `f()` in the local layer if neither
+    /// layer has it. Returns None only if the schema rejects the new value.
+    ///
+    /// 获取有效事实 `key`；如果两层都没有，先在局部层将其设置为 `f()`。
+    /// 仅当模式拒绝新值时返回 None。
+    pub fn get_or_insert_with(
+        &mut self,
+        key: &str,
+        f: impl FnOnce() -> FactValue,
+    ) -> Option<&FactValue> {
+        if !self.contains(key) {
+            self.set(key, f());
+        }
+        self.get_by_str(key)
+    }
+
+    /// Get the global fact `key`, first setting it to `f()` if the global layer lacks it.
+    /// Returns None only if the schema rejects the new value or the global layer is frozen.
+    ///
+    /// 获取全局事实 `key`；如果全局层没有，先将其设置为 `f()`。
+    /// 仅当模式拒绝新值或全局层已冻结时返回 None。
+    pub fn get_or_insert_global_with(
+        &mut self,
+        key: &str,
+        f: impl FnOnce() -> FactValue,
+    ) -> Option<&FactValue> {
+        if !self.contains_global(key) {
+            self.set_global(key, f());
+        }
+        self.global.get_by_str(self.aliases.resolve(key))
+    }
+
+    /// Set the fact `key` in the local layer only if neither layer has it.
+    /// Returns whether it was set.
+    ///
+    /// 仅当两层都没有事实 `key` 时才在局部层设置。返回是否进行了设置。
+    pub fn set_if_absent(&mut self, key: impl Into<String>, value: impl Into<FactValue>) -> bool {
+        let key = self.aliases.resolve_write_owned(key.into());
+        if self.contains(&key) {
+            return false;
+        }
+        admitted(&self.schema, &key, value.into()).is_some_and(|value| {
+            self.local.set(key, value);
+            true
+        })
+    }
+
+    /// Set the fact `key` in the global layer only if the global layer lacks it.
+    /// Returns whether it was set.
+    ///
+    /// 仅当全局层没有事实 `key` 时才在全局层设置。返回是否进行了设置。
+    pub fn set_global_if_absent(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<FactValue>,
+    ) -> bool {
+        let key = self.aliases.resolve_write_owned(key.into());
+        if self.contains_global(&key) || !self.global_writable(&key) {
+            return false;
+        }
+        admitted(&self.schema, &key, value.into()).is_some_and(|value| {
+            self.global.set(key, value);
+            true
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_defaults_do_not_shadow_global_values() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("difficulty", 3);
+
+        assert!(!db.set_if_absent("difficulty", 1));
+        assert_eq!(
+            db.get_or_insert_with("difficulty", || 1.into()),
+            Some(&FactValue::Int(3))
+        );
+        assert!(!db.contains_local("difficulty"));
+
+        assert!(db.set_if_absent("turn", 0));
+        assert!(db.contains_local("turn"));
+        assert_eq!(
+            db.get_or_insert_with("room", || "hall".into()),
+            Some(&FactValue::from("hall"))
+        );
+        assert!(db.contains_local("room"));
+    }
+
+    #[test]
+    fn test_global_variants_only_check_global_layer() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("volume", 2);
+
+        assert!(db.set_global_if_absent("volume", 5));
+        assert!(!db.set_global_if_absent("volume", 7));
+        assert_eq!(db.global().get_int("volume"), Some(5));
+        assert_eq!(
+            db.get_or_insert_global_with("volume", || unreachable!()),
+            Some(&FactValue::Int(5))
+        );
+        assert_eq!(
+            db.get_or_insert_global_with("lang", || "en".into()),
+            Some(&FactValue::from("en"))
+        );
+        // The local value still shadows the global one on reads
+        assert_eq!(db.get_int("volume"), Some(2));
+    }
+}
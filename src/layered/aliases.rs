@@ -0,0 +1,231 @@
+//! # aliases.rs
+//!
+//! # aliases.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Key aliases for `LayeredFactDatabase`. An alias maps a renamed fact key to its new name, so
+//! old saves and un-migrated content keep working while reads (and, by default, writes) land on
+//! the new key. Each alias logs a warning the first time it is used, to help find stragglers.
+//!
+//! `LayeredFactDatabase` 的键别名。别名把重命名前的事实键映射到新名称，使旧存档和未迁移的
+//! 内容继续工作，而读取（默认也包括写入）会落到新键上。每个别名在首次被使用时记录一条警告，
+//! 以便找出遗漏的旧用法。
+
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use super::LayeredFactDatabase;
+
+/// Error returned when adding an alias would create a cycle in the alias table.
+///
+/// 添加别名会在别名表中形成环时返回的错误。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasCycleError {
+    pub alias: String,
+    pub target: String,
+}
+
+impl fmt::Display for AliasCycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fact alias '{}' -> '{}' would create a cycle",
+            self.alias, self.target
+        )
+    }
+}
+
+impl std::error::Error for AliasCycleError {}
+
+/// Alias table with once-per-alias usage warnings.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FactAliases {
+    map: HashMap<String, String>,
+    /// When true, writes go to the alias key itself instead of its target.
+    direct_writes: bool,
+    /// Aliases that have already been warned about. Shared by clones of the database.
+    warned: Arc<Mutex<HashSet<String>>>,
+}
+
+impl FactAliases {
+    pub(crate) fn insert(&mut self, alias: String, target: String) -> Result<(), AliasCycleError> {
+        if self.leads_to(&target, &alias) {
+            return Err(AliasCycleError { alias, target });
+        }
+        self.map.insert(alias, target);
+        Ok(())
+    }
+
+    /// Whether following the chain from `key` reaches `alias`.
+    fn leads_to<'a>(&'a self, mut key: &'a str, alias: &str) -> bool {
+        loop {
+            if key == alias {
+                return true;
+            }
+            match self.map.get(key) {
+                Some(next) => key = next,
+                None => return false,
+            }
+        }
+    }
+
+    /// Follow the alias chain to the final key, without logging.
+    pub(crate) fn resolve_quiet<'a>(&'a self, key: &'a str) -> &'a str {
+        let mut resolved = key;
+        while let Some(next) = self.map.get(resolved) {
+            resolved = next;
+        }
+        resolved
+    }
+
+    /// Resolve a key for reading, warning the first time each alias is used.
+    pub(crate) fn resolve<'a>(&'a self, key: &'a str) -> &'a str {
+        if self.map.is_empty() {
+            return key;
+        }
+        let resolved = self.resolve_quiet(key);
+        if resolved != key {
+            self.warn_once(key, resolved);
+        }
+        resolved
+    }
+
+    /// Resolve a key for writing, honoring the write policy.
+    pub(crate) fn resolve_write<'a>(&'a self, key: &'a str) -> &'a str {
+        if self.direct_writes {
+            key
+        } else {
+            self.resolve(key)
+        }
+    }
+
+    /// Resolve an owned key for writing, only allocating when an alias applies.
+    pub(crate) fn resolve_write_owned(&self, key: String) -> String {
+        if self.direct_writes || !self.map.contains_key(&key) {
+            return key;
+        }
+        self.resolve(&key).to_string()
+    }
+
+    fn warn_once(&self, alias: &str, target: &str) {
+        let mut warned = self.warned.lock().unwrap_or_else(PoisonError::into_inner);
+        if warned.insert(alias.to_string()) {
+            warn!(
+                "FRE: Fact key '{}' is an alias of '{}'; update content to use the new key",
+                alias, target
+            );
+        }
+    }
+}
+
+impl LayeredFactDatabase {
+    /// Add an alias so `alias` transparently resolves to `target` for reads and writes.
+    /// Aliases may chain, but an alias that would form a cycle is rejected.
+    ///
+    /// 添加别名，使 `alias` 在读写时透明地解析为 `target`。
+    /// 别名可以链式指向，但会形成环的别名将被拒绝。
+    pub fn add_alias(
+        &mut self,
+        alias: impl Into<String>,
+        target: impl Into<String>,
+    ) -> Result<(), AliasCycleError> {
+        self.aliases.insert(alias.into(), target.into())
+    }
+
+    /// Remove an alias, returning its target.
+    ///
+    /// 移除别名，并返回其目标键。
+    pub fn remove_alias(&mut self, alias: &str) -> Option<String> {
+        self.aliases.map.remove(alias)
+    }
+
+    /// Resolve a key through the alias table. Keys without an alias resolve to themselves.
+    ///
+    /// 通过别名表解析键。没有别名的键解析为自身。
+    pub fn resolve_alias<'a>(&'a self, key: &'a str) -> &'a str {
+        self.aliases.resolve_quiet(key)
+    }
+
+    /// Choose whether writes through an alias go to its target (default) or to the alias key.
+    ///
+    /// 选择通过别名的写入是写到目标键（默认）还是写到别名键本身。
+    pub fn set_alias_writes(&mut self, follow_aliases: bool) {
+        self.aliases.direct_writes = !follow_aliases;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asset::EnumRegistry;
+    use crate::database::{FactLayer, FactReader};
+    use crate::expr::evaluate_expr;
+    use crate::expr_condition::ExprConditionEvaluator;
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::FactModification;
+    use crate::systems::ConditionEvaluatorTrait;
+
+    fn aliased_db() -> LayeredFactDatabase {
+        let mut db = LayeredFactDatabase::new();
+        db.add_alias("player_hp", "stats:hp").unwrap();
+        db.set_global("stats:hp", 30i64);
+        db
+    }
+
+    #[test]
+    fn test_alias_read_and_write() {
+        let mut db = aliased_db();
+        assert_eq!(db.get_int("player_hp"), Some(30));
+        assert!(db.contains("player_hp"));
+        assert_eq!(
+            db.get_in_layer(FactLayer::Global, "player_hp"),
+            Some(&crate::database::FactValue::Int(30))
+        );
+
+        db.set("player_hp", 25i64);
+        assert_eq!(db.local().get_int("stats:hp"), Some(25));
+        assert!(!db.local().contains("player_hp"));
+
+        // Writes can be configured to stay on the old key
+        db.set_alias_writes(false);
+        db.set("player_hp", 1i64);
+        assert_eq!(db.local().get_int("player_hp"), Some(1));
+        assert_eq!(db.get_int("player_hp"), Some(25));
+    }
+
+    #[test]
+    fn test_alias_in_modifications_and_expressions() {
+        let mut db = aliased_db();
+        FactModification::Increment("player_hp".to_string(), 5).apply(&mut db);
+        assert_eq!(db.get_int("stats:hp"), Some(35));
+
+        assert_eq!(evaluate_expr("$player_hp * 2", &db), Some(70.0));
+    }
+
+    #[test]
+    fn test_alias_in_condition_evaluation() {
+        let db = aliased_db();
+        let conditions = vec!["$player_hp >= 10".to_string()];
+        assert!(ExprConditionEvaluator.evaluate(&conditions, &db, &EnumRegistry::default()));
+    }
+
+    #[test]
+    fn test_alias_chain_and_cycles() {
+        let mut db = LayeredFactDatabase::new();
+        db.add_alias("hp", "player_hp").unwrap();
+        db.add_alias("player_hp", "stats:hp").unwrap();
+        assert_eq!(db.resolve_alias("hp"), "stats:hp");
+
+        assert!(db.add_alias("stats:hp", "hp").is_err());
+        assert!(db.add_alias("self", "self").is_err());
+        // Re-pointing an existing alias into its own chain is a cycle too
+        assert!(db.add_alias("player_hp", "hp").is_err());
+
+        assert_eq!(db.remove_alias("hp"), Some("player_hp".to_string()));
+        assert_eq!(db.resolve_alias("hp"), "hp");
+    }
+}
@@ -0,0 +1,204 @@
+//! # change_tracking.rs
+//!
+//! # change_tracking.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Opt-in change notifications for code that redraws only when facts change. Unlike the
+//! per-frame `changed_keys`, which `FREPlugin` clears at the end of every frame, the keys
+//! returned by `take_changed` accumulate until the caller drains them, however many frames
+//! pass in between. Nothing extra is recorded on writes: each layer already stamps written keys
+//! with its write generation, so tracking only remembers the generations it last drained at.
+//! Draining also forgets the stamps of keys removed before it, since they have been reported.
+//! While tracking is on, `emit_fact_changed_events_system` drains the keys once per frame and
+//! sends a `FactChangedEvent` for each.
+//!
+//! 为仅在事实变化时才重绘的代码提供的可选变更通知。与 `FREPlugin` 在每帧结束时清除的
+//! 按帧 `changed_keys` 不同，`take_changed` 返回的键会一直累积到调用方取走为止，
+//! 无论中间经过多少帧。写入时不会额外记录任何内容：每一层本就用写入代数标记被写入的键，
+//! 因此跟踪只需记住上次取走时的代数。取走时还会丢弃在此之前被移除的键的标记，因为它们已被报告过。
+//! 跟踪开启时，`emit_fact_changed_events_system`
+//! 每帧取走一次这些键，并为每个键发送一个 `FactChangedEvent`。
+
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use super::LayeredFactDatabase;
+use crate::database::FactDatabase;
+
+/// Write generations of both layers at the last `take_changed`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ChangeMarks {
+    global: u64,
+    local: u64,
+}
+
+/// Message sent for each fact written while change tracking is on.
+///
+/// 变更跟踪开启期间，每个被写入的事实都会发送的消息。
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct FactChangedEvent {
+    pub key: String,
+}
+
+/// Keys of `layer` written after generation `mark`.
+fn changed_since(layer: &FactDatabase, mark: u64) -> impl Iterator<Item = &str> {
+    // A generation below the mark means the layer was replaced through `local_mut` or
+    // `global_mut`, so every key it holds stamps is new
+    let mark = if layer.generation() < mark { 0 } else { mark };
+    layer.iter_changed_since(mark).map(|(key, _)| key)
+}
+
+impl LayeredFactDatabase {
+    fn current_marks(&self) -> ChangeMarks {
+        ChangeMarks {
+            global: self.global.generation(),
+            local: self.local.generation(),
+        }
+    }
+
+    /// Turn change tracking on or off. Tracking starts empty, and turning it off forgets the
+    /// keys not taken yet.
+    ///
+    /// 开启或关闭变更跟踪。跟踪从空开始，关闭时会丢弃尚未取走的键。
+    pub fn set_change_tracking(&mut self, enabled: bool) {
+        if enabled != self.change_tracking() {
+            self.change_marks = enabled.then(|| self.current_marks());
+        }
+    }
+
+    pub fn change_tracking(&self) -> bool {
+        self.change_marks.is_some()
+    }
+
+    /// Keys written in either layer since the last call (set, incremented, removed, ...),
+    /// each once. Always empty while change tracking is off.
+    ///
+    /// 自上次调用以来在任一层中被写入（设置、增量、移除等）的键，每个键一次。
+    /// 变更跟踪关闭时始终为空。
+    pub fn take_changed(&mut self) -> HashSet<String> {
+        let Some(marks) = self.change_marks else {
+            return HashSet::new();
+        };
+        let changed = changed_since(&self.global, marks.global)
+            .chain(changed_since(&self.local, marks.local))
+            .map(str::to_owned)
+            .collect();
+        let marks = self.current_marks();
+        self.global.forget_removals_through(marks.global);
+        self.local.forget_removals_through(marks.local);
+        self.change_marks = Some(marks);
+        changed
+    }
+}
+
+/// System that sends a `FactChangedEvent` for every fact written since the previous frame.
+///
+/// 为自上一帧以来被写入的每个事实发送 `FactChangedEvent` 的系统。
+pub fn emit_fact_changed_events_system(
+    mut db: ResMut<LayeredFactDatabase>,
+    mut writer: MessageWriter<FactChangedEvent>,
+) {
+    if !db.change_tracking() {
+        return;
+    }
+    writer.write_batch(
+        db.take_changed()
+            .into_iter()
+            .map(|key| FactChangedEvent { key }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CoreActionDef, FREPlugin};
+    use bevy::ecs::message::MessageCursor;
+
+    fn sorted(keys: HashSet<String>) -> Vec<String> {
+        let mut keys: Vec<String> = keys.into_iter().collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_changed_keys_accumulate_until_taken() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("untracked", 1i64);
+        assert!(db.take_changed().is_empty());
+
+        db.set_change_tracking(true);
+        db.set("hp", 10i64);
+        db.set_local("room", "cellar");
+        db.set_global("gold", 5i64);
+        db.increment("hp", 1);
+        // Per-frame tracking is cleared independently of `take_changed`
+        db.clear_changes();
+        db.remove("untracked");
+        assert_eq!(
+            sorted(db.take_changed()),
+            ["gold", "hp", "room", "untracked"]
+        );
+        assert!(db.take_changed().is_empty());
+
+        db.increment_global("gold", 1);
+        db.local_mut().set("direct", true);
+        assert_eq!(sorted(db.take_changed()), ["direct", "gold"]);
+
+        db.set_change_tracking(false);
+        db.set("hp", 0i64);
+        db.set_change_tracking(true);
+        assert!(db.take_changed().is_empty());
+    }
+
+    #[test]
+    fn test_taking_changes_forgets_reported_removals() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_change_tracking(true);
+        db.set("hp", 10i64);
+        db.set("buff", true);
+        db.remove("buff");
+        db.remove_global("missing");
+        assert_eq!(db.local().iter_changed_since(0).count(), 2);
+
+        assert_eq!(sorted(db.take_changed()), ["buff", "hp"]);
+        // Only the key still set keeps its stamp, and later reads start after the mark
+        assert_eq!(db.local().iter_changed_since(0).count(), 1);
+        db.set("mp", 3i64);
+        assert_eq!(sorted(db.take_changed()), ["mp"]);
+    }
+
+    #[test]
+    fn test_plugin_sends_changed_messages() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            FREPlugin::<CoreActionDef>::default(),
+        ));
+        {
+            let mut db = app.world_mut().resource_mut::<LayeredFactDatabase>();
+            db.set_change_tracking(true);
+            db.set("score", 3i64);
+        }
+        let mut cursor = MessageCursor::<FactChangedEvent>::default();
+        let mut sent = |app: &App| -> Vec<String> {
+            let messages = app.world().resource::<Messages<FactChangedEvent>>();
+            cursor
+                .read(messages)
+                .map(|message| message.key.clone())
+                .collect()
+        };
+        app.update();
+        assert_eq!(sent(&app), ["score"]);
+
+        // Facts not written again are not sent again
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("lives", 2i64);
+        app.update();
+        assert_eq!(sent(&app), ["lives"]);
+    }
+}
@@ -0,0 +1,119 @@
+//! # changes.rs
+//!
+//! # changes.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Change tracking across both layers of `LayeredFactDatabase`, built on the per-layer tracking
+//! of `FactDatabase`, plus the system `FREPlugin` uses to reset it at the end of every frame.
+//!
+//! 基于 `FactDatabase` 的逐层跟踪，对 `LayeredFactDatabase` 两层的变更跟踪，
+//! 以及 `FREPlugin` 在每帧结束时用于重置跟踪的系统。
+
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use super::LayeredFactDatabase;
+use crate::database::FactDatabase;
+
+impl LayeredFactDatabase {
+    /// Keys changed in either layer since changes were last cleared, each listed once.
+    ///
+    /// 自上次清除变更以来在任一层中发生变化的键，每个键只列出一次。
+    pub fn changed_keys(&self) -> impl Iterator<Item = &str> {
+        let mut seen = HashSet::new();
+        self.local_layers()
+            .chain(std::iter::once(&self.global))
+            .flat_map(FactDatabase::changed_keys)
+            .filter(move |key| seen.insert(*key))
+    }
+
+    /// Whether `key` (or the key it aliases) changed in either layer.
+    ///
+    /// `key`（或其别名指向的键）是否在任一层中发生了变化。
+    pub fn is_changed(&self, key: &str) -> bool {
+        let key = self.aliases.resolve(key);
+        self.local_layers().any(|layer| layer.is_changed(key)) || self.global.is_changed(key)
+    }
+
+    /// Forget the recorded changes of both layers.
+    ///
+    /// 清除两层已记录的变更。
+    pub fn clear_changes(&mut self) {
+        self.local.clear_changes();
+        self.buried.iter_mut().for_each(FactDatabase::clear_changes);
+        self.global.clear_changes();
+    }
+}
+
+/// System that clears change tracking once the frame is over. Frames without fact changes
+/// leave the database untouched, so they do not trip Bevy change detection on it.
+///
+/// 在帧结束时清除变更跟踪的系统。没有事实变化的帧不会触碰数据库，因此不会触发其 Bevy 变更检测。
+pub fn clear_fact_changes_system(mut db: ResMut<LayeredFactDatabase>) {
+    if db.changed_keys().next().is_some() {
+        db.clear_changes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_across_layers() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("gold", 10i64);
+        db.set("gold", 20i64);
+        db.set("room", "cellar");
+        db.local_mut().clear_changes();
+        db.set_global("chapter", 2i64);
+
+        let mut keys: Vec<&str> = db.changed_keys().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["chapter", "gold"]);
+        assert!(db.is_changed("gold"));
+        assert!(!db.is_changed("room"));
+
+        db.set("room", "hall");
+        assert_eq!(db.changed_keys().count(), 3);
+        db.clear_changes();
+        assert_eq!(db.changed_keys().count(), 0);
+    }
+
+    #[test]
+    fn test_plugin_clears_changes_each_frame() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            crate::FREPlugin::<crate::CoreActionDef>::default(),
+        ));
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set("hp", 3i64);
+        assert!(
+            app.world()
+                .resource::<LayeredFactDatabase>()
+                .is_changed("hp")
+        );
+        app.update();
+        assert!(
+            !app.world()
+                .resource::<LayeredFactDatabase>()
+                .is_changed("hp")
+        );
+
+        // A frame without writes leaves the resource unchanged for Bevy change detection
+        let last_changed = |app: &App| {
+            app.world()
+                .resource_ref::<LayeredFactDatabase>()
+                .last_changed()
+        };
+        let before = last_changed(&app);
+        app.update();
+        assert_eq!(last_changed(&app), before);
+    }
+}
@@ -0,0 +1,135 @@
+//! # defaults.rs
+//!
+//! # defaults.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Fallback values for facts that neither layer holds. Tuning values such as `max_party_size`
+//! get a compiled-in default with `set_default`, and content overrides them by setting the key
+//! in the global or local layer. Reads resolve local, then global, then defaults. The defaults
+//! are not a writable layer: `set`, `increment` and the other writes still target local or
+//! global (an increment starts from the default), `clear_all` keeps them, and registering a
+//! default is not recorded as a fact change.
+//!
+//! 两层都不持有的事实的回退值。`max_party_size` 等调优值通过 `set_default` 获得编译期默认值，
+//! 内容通过在全局层或局部层设置该键来覆盖它。读取依次解析局部层、全局层、默认值。
+//! 默认值不是可写层：`set`、`increment` 等写入仍然指向局部层或全局层（增量从默认值开始），
+//! `clear_all` 会保留默认值，注册默认值也不会被记录为事实变更。
+
+use super::{LayeredFactDatabase, admitted};
+use crate::database::{FactDatabase, FactValue};
+
+impl LayeredFactDatabase {
+    /// Register the value `key` reads as while neither layer holds it.
+    ///
+    /// 注册当两层都不持有 `key` 时读取到的值。
+    pub fn set_default(&mut self, key: impl Into<String>, value: impl Into<FactValue>) {
+        let key = self.aliases.resolve_write_owned(key.into());
+        if let Some(value) = admitted(&self.schema, &key, value.into()) {
+            self.defaults.set(key, value);
+        }
+    }
+
+    /// Remove the default of `key`, returning it.
+    ///
+    /// 移除 `key` 的默认值并返回它。
+    pub fn remove_default(&mut self, key: &str) -> Option<FactValue> {
+        self.defaults.remove(self.aliases.resolve_write(key))
+    }
+
+    /// Remove every default.
+    ///
+    /// 移除所有默认值。
+    pub fn clear_defaults(&mut self) {
+        self.defaults.clear();
+    }
+
+    /// The registered defaults.
+    ///
+    /// 已注册的默认值。
+    pub fn defaults(&self) -> &FactDatabase {
+        &self.defaults
+    }
+
+    /// Check if a fact exists in either layer or has a default.
+    ///
+    /// 检查事实是否存在于任一层或具有默认值。
+    pub fn contains_with_defaults(&self, key: &str) -> bool {
+        self.contains(key) || self.defaults.contains(self.aliases.resolve(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::FactReader;
+
+    #[test]
+    fn test_reads_resolve_local_then_global_then_defaults() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_default("max_party_size", 4i64);
+        db.set_default("difficulty", "normal");
+        db.set_default("volume", 0.5);
+        assert_eq!(db.get_int("max_party_size"), Some(4));
+        assert!(!db.contains("max_party_size"));
+        assert!(db.contains_with_defaults("max_party_size"));
+        assert!(FactReader::contains(&db, "max_party_size"));
+        assert_eq!(db.len(), 0);
+
+        db.set_global("max_party_size", 6i64);
+        db.set_global("difficulty", "hard");
+        db.set_local("max_party_size", 2i64);
+        assert_eq!(db.get_int("max_party_size"), Some(2));
+        assert_eq!(db.get_string("difficulty"), Some("hard"));
+        assert_eq!(db.get_float("volume"), Some(0.5));
+
+        db.remove("max_party_size");
+        assert_eq!(db.get_int("max_party_size"), Some(6));
+        db.remove_global("max_party_size");
+        assert_eq!(db.get_int("max_party_size"), Some(4));
+    }
+
+    #[test]
+    fn test_defaults_survive_clear_all() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_default("lives", 3i64);
+        assert!(!db.is_changed("lives"));
+        db.increment("lives", -1);
+        assert_eq!(db.local().get_int("lives"), Some(2));
+        assert_eq!(db.defaults().get_int("lives"), Some(3));
+
+        db.clear_all();
+        assert!(db.is_empty());
+        assert_eq!(db.get_int("lives"), Some(3));
+
+        assert_eq!(db.remove_default("lives"), Some(FactValue::Int(3)));
+        assert_eq!(db.get_int("lives"), None);
+        db.set_default("lives", 5i64);
+        db.clear_defaults();
+        assert!(!db.contains_with_defaults("lives"));
+    }
+
+    #[test]
+    fn test_plugin_registers_defaults() {
+        use crate::{CoreActionDef, FREPlugin};
+        use bevy::prelude::*;
+        use std::collections::HashMap;
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            FREPlugin::<CoreActionDef> {
+                fact_defaults: HashMap::from([("max_party_size".to_string(), FactValue::Int(4))]),
+                ..default()
+            },
+        ));
+        app.update();
+
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert_eq!(db.get_int("max_party_size"), Some(4));
+        assert!(db.is_empty());
+    }
+}
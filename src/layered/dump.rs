@@ -0,0 +1,105 @@
+//! # dump.rs
+//!
+//! # dump.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! A plain-text listing of every layer of a `LayeredFactDatabase` for debug overlays and logs.
+//! Each layer, including any pushed local layers, is listed under its own heading, sorted by key, with values in their `Display`
+//! form. Local facts that hide a global fact of the same key are marked, since reads of such
+//! keys never see the global value.
+//!
+//! `LayeredFactDatabase` 各层的纯文本列表，用于调试叠加层和日志。每一层（包括压入的局部层）列在各自的标题下，
+//! 按键排序，值使用其 `Display` 形式。遮蔽同名全局事实的局部事实会被标记，
+//! 因为读取这些键时永远看不到全局值。
+
+use std::fmt::Write;
+
+use super::LayeredFactDatabase;
+use crate::database::{FactDatabase, FactValue};
+
+/// Marker appended to local facts that shadow a global fact.
+const SHADOW_MARKER: &str = "(shadows global)";
+
+/// A layer's facts sorted by key.
+fn sorted(layer: &FactDatabase) -> Vec<(&String, &FactValue)> {
+    let mut facts: Vec<_> = layer.iter().collect();
+    facts.sort_by(|a, b| a.0.cmp(b.0));
+    facts
+}
+
+impl LayeredFactDatabase {
+    /// Render every layer as text, one `key = value` line per fact, followed by the
+    /// defaults if any are registered.
+    ///
+    /// 把各层渲染为文本，每个事实一行 `key = value`；如果注册了默认值，随后列出默认值。
+    pub fn debug_dump(&self) -> String {
+        let mut dump = String::from("global:\n");
+        for (key, value) in sorted(&self.global) {
+            let _ = writeln!(dump, "  {} = {}", key, value);
+        }
+        // Local layers bottom first, so pushed layers read in the order they were pushed
+        let layers = self.buried.iter().chain(std::iter::once(&self.local));
+        for (depth, (layer, name)) in layers.zip(self.layer_names()).enumerate() {
+            if depth == 0 {
+                dump.push_str("local:\n");
+            } else {
+                let _ = writeln!(dump, "local '{}':", name);
+            }
+            for (key, value) in sorted(layer) {
+                let _ = write!(dump, "  {} = {}", key, value);
+                self.mark_shadowed(&mut dump, key);
+            }
+        }
+        if !self.defaults.is_empty() {
+            dump.push_str("defaults:\n");
+            for (key, value) in sorted(&self.defaults) {
+                let _ = writeln!(dump, "  {} = {}", key, value);
+            }
+        }
+        dump
+    }
+
+    /// Finish a local line, marking it when a global fact with the same key is hidden.
+    fn mark_shadowed(&self, dump: &mut String, key: &str) {
+        if self.global.contains(key) {
+            let _ = write!(dump, " {}", SHADOW_MARKER);
+        }
+        dump.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_dump_marks_shadowed_keys() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("volume", 0.8);
+        db.set_global("name", "hero");
+        db.set("volume", 0.2);
+        db.set("inventory", vec!["key", "map"]);
+
+        assert_eq!(
+            db.debug_dump(),
+            "global:\n  name = hero\n  volume = 0.8\n\
+             local:\n  inventory = [key, map]\n  volume = 0.2 (shadows global)\n"
+        );
+
+        db.push_layer("menu");
+        db.set("cursor", 2i64);
+        assert!(
+            db.debug_dump()
+                .ends_with("(shadows global)\nlocal 'menu':\n  cursor = 2\n")
+        );
+
+        db.set_default("max_party_size", 4i64);
+        assert!(
+            db.debug_dump()
+                .ends_with("defaults:\n  max_party_size = 4\n")
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! # effective.rs
+//!
+//! # effective.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! The effective view of a `LayeredFactDatabase`: every stored key once, with the value a read
+//! would return from the layers. Local facts shadow global ones, and the top of the local stack
+//! shadows the layers buried by `push_layer`, so a save screen or debug overlay can list what
+//! the game currently sees. Defaults and view facts are not stored facts and are not included.
+//!
+//! `LayeredFactDatabase` 的有效视图：每个已存储的键出现一次，值为从各层读取时返回的值。
+//! 局部事实遮蔽全局事实，局部栈顶遮蔽被 `push_layer` 压下的层，
+//! 因此存档界面或调试覆盖层可以列出游戏当前看到的内容。默认值和视图事实不是已存储的事实，不会被包含。
+
+use super::LayeredFactDatabase;
+use crate::database::FactValue;
+
+impl LayeredFactDatabase {
+    /// Iterate over the effective facts: the local facts, top layer first, then the global
+    /// facts, skipping any key shadowed by a layer above.
+    ///
+    /// 迭代有效事实：先是局部事实（栈顶层在前），然后是全局事实，跳过被上方层遮蔽的键。
+    pub fn iter_effective(&self) -> impl Iterator<Item = (&String, &FactValue)> {
+        self.local_layers()
+            .chain(std::iter::once(&self.global))
+            .enumerate()
+            .flat_map(move |(depth, layer)| {
+                layer.iter().filter(move |(key, _)| {
+                    !self
+                        .local_layers()
+                        .take(depth)
+                        .any(|above| above.contains(key))
+                })
+            })
+    }
+
+    /// Number of distinct keys across all layers, unlike `len`, which counts a shadowed key
+    /// once per layer holding it.
+    ///
+    /// 所有层中不同键的数量；与 `len` 不同，`len` 会按持有被遮蔽键的层数重复计数。
+    pub fn effective_len(&self) -> usize {
+        self.iter_effective().count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_shadowed_keys_appear_once() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("gold", 100i64);
+        db.set_global("chapter", 2i64);
+        db.set("gold", 40i64);
+        db.set("room", "cave");
+        db.push_layer("dialogue");
+        db.set("room", "shop");
+        db.set("speaker", "merchant");
+
+        assert_eq!(db.len(), 6);
+        assert_eq!(db.effective_len(), 4);
+        let effective: HashMap<&str, &FactValue> = db
+            .iter_effective()
+            .map(|(key, value)| (key.as_str(), value))
+            .collect();
+        assert_eq!(effective.len(), 4);
+        assert_eq!(effective["gold"], &FactValue::Int(40));
+        assert_eq!(effective["room"], &FactValue::from("shop"));
+        assert_eq!(effective["speaker"], &FactValue::from("merchant"));
+        assert_eq!(effective["chapter"], &FactValue::Int(2));
+
+        db.pop_layer();
+        db.remove("gold");
+        assert_eq!(db.effective_len(), 3);
+        assert_eq!(
+            db.iter_effective().find(|(key, _)| *key == "gold"),
+            Some((&"gold".to_string(), &FactValue::Int(100)))
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! # enforcement.rs
+//!
+//! # enforcement.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Schema enforcement for `LayeredFactDatabase`. Every write through the database's setters and
+//! numeric helpers passes through `admitted`, which applies the installed `FactSchema` policy and
+//! logs the writes it rejects.
+//!
+//! `LayeredFactDatabase` 的模式强制检查。通过数据库的设置方法和数值辅助方法进行的每次写入
+//! 都会经过 `admitted`，它应用已安装的 `FactSchema` 策略，并记录被拒绝的写入。
+
+use bevy::prelude::*;
+
+use super::LayeredFactDatabase;
+use crate::database::FactValue;
+use crate::schema::{FactSchema, SchemaViolation};
+
+/// Apply the schema to a write, logging and dropping values it rejects.
+pub(super) fn admitted(schema: &FactSchema, key: &str, value: FactValue) -> Option<FactValue> {
+    schema
+        .admit(key, value)
+        .map_err(|violation| error!("FRE: Rejected write: {}", violation))
+        .ok()
+}
+
+impl LayeredFactDatabase {
+    /// Write to the local layer under `key` as given, subject to the schema.
+    pub(super) fn write_local(&mut self, key: &str, value: impl Into<FactValue>) {
+        if let Some(value) = admitted(&self.schema, key, value.into()) {
+            self.local.set(key, value);
+        }
+    }
+
+    /// Declared fact types enforced on writes through this database.
+    /// Writes made directly on `local_mut` / `global_mut` bypass the schema.
+    ///
+    /// 通过此数据库写入时强制执行的声明事实类型。
+    /// 直接通过 `local_mut` / `global_mut` 进行的写入会绕过模式。
+    pub fn schema(&self) -> &FactSchema {
+        &self.schema
+    }
+
+    /// Get mutable access to the schema, e.g. to declare types or change the policy.
+    ///
+    /// 获取模式的可变访问，例如声明类型或更改策略。
+    pub fn schema_mut(&mut self) -> &mut FactSchema {
+        &mut self.schema
+    }
+
+    /// Whether the schema would accept a write of `value` under `key`, without writing it.
+    pub(crate) fn check_write(&self, key: &str, value: FactValue) -> Result<(), SchemaViolation> {
+        self.schema
+            .admit(self.aliases.resolve_write(key), value)
+            .map(drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{FactType, SchemaPolicy};
+
+    #[test]
+    fn test_schema_rejects_mismatched_writes() {
+        let mut db = LayeredFactDatabase::new();
+        db.schema_mut().declare("hp", FactType::Int);
+        db.set("hp", 10i64);
+
+        // Enforcement is off by default
+        db.set("hp", "full");
+        assert_eq!(db.get_string("hp"), Some("full"));
+
+        db.schema_mut().set_policy(SchemaPolicy::Reject);
+        db.set("hp", 10i64);
+        db.set("hp", "full");
+        db.set_global("hp", 2.5);
+        assert!(!db.set_if_changed("hp", true));
+        db.add("hp", 0.5);
+        assert_eq!(db.get_int("hp"), Some(10));
+        assert!(!db.contains_global("hp"));
+
+        // Undeclared facts are unconstrained
+        db.set("mood", "calm");
+        assert_eq!(db.get_string("mood"), Some("calm"));
+    }
+
+    #[test]
+    fn test_schema_coerces_writes() {
+        let mut db = LayeredFactDatabase::new();
+        db.schema_mut().set_policy(SchemaPolicy::Coerce);
+        db.schema_mut().declare("hp", FactType::Int);
+        db.schema_mut().declare("speed", FactType::Float);
+
+        db.set("hp", "12");
+        assert_eq!(db.get_int("hp"), Some(12));
+        db.mul("hp", 1.5);
+        assert_eq!(db.get_int("hp"), Some(18));
+        db.div("hp", 4.0);
+        assert_eq!(db.get_int("hp"), Some(5));
+
+        db.set("speed", 3i64);
+        assert_eq!(db.get_float("speed"), Some(3.0));
+
+        // Values with no conversion are still rejected
+        db.set("hp", "lots");
+        assert_eq!(db.get_int("hp"), Some(5));
+    }
+}
@@ -0,0 +1,82 @@
+//! # frame.rs
+//!
+//! # frame.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Frame-granular change detection. `snapshot_frame_start` records the current values of the
+//! keys a game cares about, normally once at the start of each frame, and
+//! `changed_since_last_frame` compares a key's value with that record. Unlike the generic change
+//! tracking, which reports any write since the marks were last cleared, this answers whether
+//! the value is different from what the previous frame ended with, so writing the same value
+//! back does not count. Only tracked keys have an answer.
+//!
+//! 帧粒度的变化检测。`snapshot_frame_start` 记录游戏所关心键的当前值，通常在每帧开始时记录一次，
+//! `changed_since_last_frame` 把键的值与该记录比较。通用变更跟踪报告自上次清除标记以来的任何写入，
+//! 而这里回答的是值是否与上一帧结束时不同，因此写回相同的值不算变化。只有被跟踪的键才有结果。
+
+use std::collections::HashMap;
+
+use super::LayeredFactDatabase;
+use crate::database::FactValue;
+
+/// Tracked keys and their values when the frame started, None for facts that were missing.
+#[derive(Debug, Clone, Default)]
+pub(super) struct FrameStart {
+    values: HashMap<String, Option<FactValue>>,
+}
+
+impl LayeredFactDatabase {
+    /// Record the current values of `keys` as the frame-start snapshot, replacing the previous
+    /// snapshot. Keys left out are no longer tracked.
+    ///
+    /// 把 `keys` 的当前值记录为帧开始快照，替换之前的快照。未列出的键不再被跟踪。
+    pub fn snapshot_frame_start<'a>(&mut self, keys: impl IntoIterator<Item = &'a str>) {
+        let values = keys
+            .into_iter()
+            .map(|key| {
+                let key = self.aliases.resolve(key);
+                (key.to_string(), self.get_by_str(key).cloned())
+            })
+            .collect();
+        self.frame_start = FrameStart { values };
+    }
+
+    /// Whether the value of `key` differs from the frame-start snapshot, with a fact appearing
+    /// or disappearing counting as a change. None if `key` is not tracked.
+    ///
+    /// `key` 的值是否与帧开始快照不同，事实出现或消失也算作变化。如果 `key` 未被跟踪则返回 None。
+    pub fn changed_since_last_frame(&self, key: &str) -> Option<bool> {
+        let key = self.aliases.resolve(key);
+        let previous = self.frame_start.values.get(key)?;
+        Some(previous.as_ref() != self.get_by_str(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_compare_against_snapshot() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("x", 1i64);
+        db.snapshot_frame_start(["x", "y"]);
+        assert_eq!(db.changed_since_last_frame("x"), Some(false));
+        assert_eq!(db.changed_since_last_frame("z"), None);
+
+        // Writing the same value back is not a change; a new fact is
+        db.set("x", 1i64);
+        db.set("y", true);
+        assert_eq!(db.changed_since_last_frame("x"), Some(false));
+        assert_eq!(db.changed_since_last_frame("y"), Some(true));
+
+        db.set("x", 2i64);
+        assert_eq!(db.changed_since_last_frame("x"), Some(true));
+        db.snapshot_frame_start(["x"]);
+        assert_eq!(db.changed_since_last_frame("x"), Some(false));
+        assert_eq!(db.changed_since_last_frame("y"), None);
+    }
+}
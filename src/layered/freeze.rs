@@ -0,0 +1,145 @@
+//! # freeze.rs
+//!
+//! # freeze.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! A write lock on the global layer. While the layer is frozen, every write through the
+//! database's global setters, removals and transfers is refused, so a misbehaving rule cannot
+//! clobber save-critical data during gameplay; a checkpoint system unfreezes the layer, writes,
+//! and freezes it again. What a refused write does is decided by the `FreezePolicy`: methods
+//! that report success return false, None or 0 either way. Local writes are unaffected, and
+//! `global_mut` bypasses the lock like it bypasses the schema.
+//!
+//! 全局层的写锁。全局层被冻结期间，通过数据库的全局设置、移除和转移方法进行的写入都会被拒绝，
+//! 因此行为异常的规则无法在游戏过程中破坏对存档至关重要的数据；检查点系统会先解冻该层、写入，
+//! 然后再次冻结。被拒绝的写入如何处理由 `FreezePolicy` 决定：报告成功与否的方法在任何策略下
+//! 都返回 false、None 或 0。局部写入不受影响，`global_mut` 会像绕过模式一样绕过此锁。
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::LayeredFactDatabase;
+
+/// What a write to the frozen global layer does.
+///
+/// 写入已冻结的全局层时的行为。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "debug", derive(Reflect))]
+pub enum FreezePolicy {
+    /// Leave the layer unchanged and log an error.
+    ///
+    /// 保持该层不变并记录错误。
+    #[default]
+    Error,
+
+    /// Leave the layer unchanged silently.
+    ///
+    /// 静默保持该层不变。
+    Ignore,
+
+    /// Panic, to stop at the offending write while debugging.
+    ///
+    /// 触发 panic，以便调试时停在出错的写入处。
+    Panic,
+}
+
+impl LayeredFactDatabase {
+    /// Refuse writes to the global layer until `unfreeze_global`.
+    ///
+    /// 拒绝对全局层的写入，直到调用 `unfreeze_global`。
+    pub fn freeze_global(&mut self) {
+        self.global_frozen = true;
+    }
+
+    /// Accept writes to the global layer again.
+    ///
+    /// 重新接受对全局层的写入。
+    pub fn unfreeze_global(&mut self) {
+        self.global_frozen = false;
+    }
+
+    pub fn is_global_frozen(&self) -> bool {
+        self.global_frozen
+    }
+
+    pub fn freeze_policy(&self) -> FreezePolicy {
+        self.freeze_policy
+    }
+
+    /// Set what writes to the frozen global layer do (logged as errors by default).
+    ///
+    /// 设置写入已冻结全局层时的行为（默认记录为错误）。
+    pub fn set_freeze_policy(&mut self, policy: FreezePolicy) {
+        self.freeze_policy = policy;
+    }
+
+    /// Whether a write of `key` to the global layer may go ahead, applying the freeze policy
+    /// if it may not.
+    pub(super) fn global_writable(&self, key: &str) -> bool {
+        if !self.global_frozen {
+            return true;
+        }
+        match self.freeze_policy {
+            FreezePolicy::Error => {
+                error!(
+                    "FRE: Rejected write of global fact '{}': layer is frozen",
+                    key
+                );
+            }
+            FreezePolicy::Ignore => {}
+            FreezePolicy::Panic => panic!("FRE: write of global fact '{}' while frozen", key),
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::FactValue;
+
+    #[test]
+    fn test_frozen_global_refuses_writes() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("save_slot", 1i64);
+        db.set("room", "cave");
+        db.freeze_global();
+        assert!(db.is_global_frozen());
+
+        db.set_global("save_slot", 2i64);
+        db.increment_global("save_slot", 5);
+        assert_eq!(db.remove_global("save_slot"), None);
+        assert!(!db.set_global_if_changed("save_slot", 3i64));
+        db.clear_global();
+        assert_eq!(db.global().get_int("save_slot"), Some(1));
+
+        // Local writes still work, and a refused promote keeps the local fact
+        db.set("room", "lake");
+        db.increment("steps", 1);
+        assert_eq!(db.get_string("room"), Some("lake"));
+        assert!(!db.promote_to_global("room"));
+        assert!(!db.copy_to_global("room"));
+        assert!(!db.demote_to_local("save_slot"));
+        assert_eq!(db.local().get_string("room"), Some("lake"));
+        assert!(!db.contains_global("room"));
+
+        db.unfreeze_global();
+        assert!(db.promote_to_global("room"));
+        assert_eq!(
+            db.global().get_by_str("room"),
+            Some(&FactValue::from("lake"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "while frozen")]
+    fn test_panic_policy() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_freeze_policy(FreezePolicy::Panic);
+        db.freeze_global();
+        db.set_global("save_slot", 1i64);
+    }
+}
@@ -0,0 +1,129 @@
+//! # initial.rs
+//!
+//! # initial.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Starting values of declared facts. `FreAsset::register_initial_facts` records the values an
+//! asset declares in `facts`, and `reset_to_initial` (or the `ResetToInitial` modification)
+//! writes a fact's recorded value back, so a rule can restore "hp at scene start" without
+//! repeating the number. The record lives on the database because modifications only see the
+//! database they are applied to.
+//!
+//! 已声明事实的初始值。`FreAsset::register_initial_facts` 记录资源在 `facts` 中声明的值，
+//! `reset_to_initial`（或 `ResetToInitial` 修改）把事实的记录值写回，
+//! 因此规则无需重复数值即可恢复"场景开始时的 hp"。记录保存在数据库上，
+//! 因为修改只能访问它们所应用的数据库。
+
+use std::collections::HashMap;
+
+use super::LayeredFactDatabase;
+use crate::database::FactValue;
+
+/// Recorded starting values of facts, by key.
+///
+/// 按键记录的事实初始值。
+#[derive(Debug, Clone, Default)]
+pub struct InitialFacts {
+    values: HashMap<String, FactValue>,
+}
+
+impl InitialFacts {
+    /// Record the starting value of `key`, replacing any earlier record.
+    ///
+    /// 记录 `key` 的初始值，替换之前的记录。
+    pub fn record(&mut self, key: impl Into<String>, value: impl Into<FactValue>) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&FactValue> {
+        self.values.get(key)
+    }
+
+    pub fn forget(&mut self, key: &str) -> Option<FactValue> {
+        self.values.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl LayeredFactDatabase {
+    pub fn initial_facts(&self) -> &InitialFacts {
+        &self.initial
+    }
+
+    /// Get mutable access to the recorded starting values.
+    ///
+    /// 获取已记录初始值的可变访问。
+    pub fn initial_facts_mut(&mut self) -> &mut InitialFacts {
+        &mut self.initial
+    }
+
+    /// Write the recorded starting value of `key` to the local layer.
+    /// Returns false, leaving the fact unchanged, if no starting value was recorded.
+    ///
+    /// 把 `key` 记录的初始值写入局部层。
+    /// 如果没有记录初始值，则保持事实不变并返回 false。
+    pub fn reset_to_initial(&mut self, key: &str) -> bool {
+        let key = self.aliases.resolve(key).to_string();
+        let Some(value) = self.initial.get(&key).cloned() else {
+            return false;
+        };
+        self.set_local(key, value);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{EnumRegistry, FreAsset};
+    use crate::rule::FactModification;
+
+    #[test]
+    fn test_reset_restores_asset_value() {
+        let asset: FreAsset = ron::from_str(
+            r#"(
+                facts: { "hp": Int(100), "name": String("hero") },
+                rules: [(
+                    id: "respawn",
+                    event: Event("respawn"),
+                    modifications: [ResetToInitial("hp"), ResetToInitial("gold")],
+                )],
+            )"#,
+        )
+        .unwrap();
+        let mut db = LayeredFactDatabase::new();
+        for (key, value) in asset.resolve_facts(&EnumRegistry::default()) {
+            db.set(key, value);
+        }
+        asset.register_initial_facts(&mut db, &EnumRegistry::default());
+        assert_eq!(db.initial_facts().len(), 2);
+
+        db.set("hp", 12i64);
+        db.set("gold", 7i64);
+        for modification in &asset.get_rule_defs()[0].to_rule().modifications {
+            modification.apply(&mut db);
+        }
+        assert_eq!(db.get_int("hp"), Some(100));
+        // Nothing was recorded for gold
+        assert_eq!(db.get_int("gold"), Some(7));
+
+        db.set("name", "villain");
+        FactModification::ResetToInitial("name".to_string()).apply(&mut db);
+        assert_eq!(db.get_string("name"), Some("hero"));
+        assert!(!db.reset_to_initial("gold"));
+    }
+}
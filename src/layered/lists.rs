@@ -0,0 +1,91 @@
+//! # lists.rs
+//!
+//! # lists.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! List helpers for `LayeredFactDatabase`. Like the numeric helpers, they read the effective list
+//! through the layered lookup and write the updated copy to the local layer, so inventories and
+//! tag sets can change one element at a time instead of being replaced.
+//!
+//! `LayeredFactDatabase` 的列表辅助方法。与数值辅助方法一样，它们通过分层查找
+//! 读取有效列表，并把更新后的副本写入局部层，因此物品栏和标签集合可以逐个元素修改，
+//! 而无需整体替换。
+
+use super::LayeredFactDatabase;
+use crate::database::{FactValue, list_with_item, list_without_item};
+
+impl LayeredFactDatabase {
+    /// Push `value` onto the list `key`, creating the list if the fact is absent.
+    /// A fact that is not a list of the value's type is left unchanged with a warning.
+    ///
+    /// 把 `value` 追加到列表 `key`；如果事实不存在则创建列表。
+    /// 不是该值类型列表的事实保持不变并记录警告。
+    pub fn push_to_list(&mut self, key: &str, value: impl Into<FactValue>) {
+        if let Some(list) = list_with_item(key, self.get_by_str(key), value.into()) {
+            self.write_local(key, list);
+        }
+    }
+
+    /// Push `value` onto the string list `key`; see `push_to_list`.
+    ///
+    /// 把 `value` 追加到字符串列表 `key`；参见 `push_to_list`。
+    pub fn append_to_list(&mut self, key: &str, value: impl Into<String>) {
+        self.push_to_list(key, FactValue::String(value.into()));
+    }
+
+    /// Remove the first element equal to `value` from the list `key`.
+    /// Returns false if the fact is missing, not a list of the value's type, or lacks the value.
+    ///
+    /// 从列表 `key` 中移除第一个等于 `value` 的元素。
+    /// 如果事实缺失、不是该值类型的列表或不包含该值，则返回 false。
+    pub fn remove_from_list(&mut self, key: &str, value: impl Into<FactValue>) -> bool {
+        let Some(list) = list_without_item(self.get_by_str(key), &value.into()) else {
+            return false;
+        };
+        self.write_local(key, list);
+        true
+    }
+
+    /// Whether the effective list `key` holds `value`.
+    ///
+    /// 有效列表 `key` 是否包含 `value`。
+    pub fn list_contains(&self, key: &str, value: impl Into<FactValue>) -> bool {
+        self.get_by_str(key)
+            .is_some_and(|list| list.list_contains(&value.into()))
+    }
+
+    /// Number of elements of the effective list `key`, or None if it is missing or not a list.
+    ///
+    /// 有效列表 `key` 的元素数量；如果缺失或不是列表则返回 None。
+    pub fn list_len(&self, key: &str) -> Option<usize> {
+        self.get_by_str(key).and_then(FactValue::list_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_helpers_copy_global_list_into_local_layer() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("party", vec![1i64, 2]);
+        db.push_to_list("party", 3);
+        assert_eq!(db.global().get_by_str("party"), Some(&vec![1i64, 2].into()));
+        assert_eq!(
+            db.local().get_by_str("party"),
+            Some(&vec![1i64, 2, 3].into())
+        );
+        assert!(db.list_contains("party", 3));
+
+        assert!(db.remove_from_list("party", 1));
+        assert_eq!(db.list_len("party"), Some(2));
+
+        db.set("name", "hero");
+        db.push_to_list("name", "s");
+        assert_eq!(db.get_string("name"), Some("hero"));
+    }
+}
@@ -0,0 +1,76 @@
+//! # merge.rs
+//!
+//! # merge.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Layer-targeted merges for `LayeredFactDatabase`. Conflicts are detected against the target
+//! layer alone, and the merged facts are written through the layer's usual setter, so aliases
+//! and the schema apply as they do to any other write.
+//!
+//! `LayeredFactDatabase` 针对特定层的合并。冲突只针对目标层检测，合并的事实通过该层常规的
+//! 设置方法写入，因此别名和模式会像其他写入一样生效。
+
+use super::LayeredFactDatabase;
+use crate::database::{FactDatabase, MergeConflict, MergeError, MergePolicy, plan_merge};
+
+impl LayeredFactDatabase {
+    /// Merge `other` into the local layer; see `FactDatabase::merge`.
+    ///
+    /// 把 `other` 合并到局部层；参见 `FactDatabase::merge`。
+    pub fn merge_into_local(
+        &mut self,
+        other: &FactDatabase,
+        policy: MergePolicy,
+    ) -> Result<Vec<MergeConflict>, MergeError> {
+        let plan = plan_merge(&self.local, other, policy)?;
+        for (key, value) in plan.writes {
+            self.set(key, value);
+        }
+        Ok(plan.conflicts)
+    }
+
+    /// Merge `other` into the global layer; see `FactDatabase::merge`.
+    ///
+    /// 把 `other` 合并到全局层；参见 `FactDatabase::merge`。
+    pub fn merge_into_global(
+        &mut self,
+        other: &FactDatabase,
+        policy: MergePolicy,
+    ) -> Result<Vec<MergeConflict>, MergeError> {
+        let plan = plan_merge(&self.global, other, policy)?;
+        for (key, value) in plan.writes {
+            self.set_global(key, value);
+        }
+        Ok(plan.conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_targets_one_layer() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("hp", 10i64);
+        let mut incoming = FactDatabase::new();
+        incoming.set("hp", 20i64);
+
+        // The local layer has no `hp`, so there is no conflict there
+        assert_eq!(
+            db.merge_into_local(&incoming, MergePolicy::Error),
+            Ok(vec![])
+        );
+        assert_eq!(db.local().get_int("hp"), Some(20));
+        assert_eq!(db.global().get_int("hp"), Some(10));
+
+        let conflicts = db
+            .merge_into_global(&incoming, MergePolicy::KeepExisting)
+            .unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(db.global().get_int("hp"), Some(10));
+    }
+}
@@ -0,0 +1,158 @@
+//! # numeric.rs
+//!
+//! # numeric.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Numeric write helpers for `LayeredFactDatabase`. They read through the layered lookup
+//! (local first, then global) and always write the result to the local layer, preserving
+//! integer results as `Int` (clamped to the `i64` range) and promoting to `Float` when a
+//! fractional part appears.
+//!
+//! `LayeredFactDatabase` 的数值写入辅助方法。它们通过分层查找读取（先局部层后全局层），
+//! 并始终把结果写入局部层；结果为整数时保持 `Int`（限制在 `i64` 范围内），出现小数部分时提升为 `Float`。
+
+use super::LayeredFactDatabase;
+use crate::database::{FactValue, IntOverflowPolicy, add_or_warn, float_to_fact, whole_to_int};
+
+impl LayeredFactDatabase {
+    /// The overflow policy of the global layer. Layered increments and additions use the policy
+    /// of the layer they write to.
+    ///
+    /// 全局层的溢出策略。分层的增量和加法使用其写入层的策略。
+    pub fn int_overflow_policy(&self) -> IntOverflowPolicy {
+        self.global.int_overflow_policy()
+    }
+
+    /// Set how integer increments and additions handle results outside the `i64` range
+    /// (saturating by default) on every layer, including layers pushed later.
+    ///
+    /// 在所有层（包括之后压入的层）上设置整数增量和加法如何处理超出 `i64` 范围的结果（默认饱和）。
+    pub fn set_int_overflow_policy(&mut self, policy: IntOverflowPolicy) {
+        self.global.set_int_overflow_policy(policy);
+        self.local.set_int_overflow_policy(policy);
+        for layer in &mut self.buried {
+            layer.set_int_overflow_policy(policy);
+        }
+    }
+
+    /// Add a numeric value to a fact (supports Int and Float).
+    /// If Int + Float, result is Float.
+    ///
+    /// 向事实添加数值（支持 Int 和 Float）。
+    /// 如果 Int + Float，结果为 Float。
+    pub fn add(&mut self, key: &str, amount: f64) {
+        match self.get_by_str(key) {
+            Some(FactValue::Int(i)) => {
+                let current = *i;
+                let Some(whole) = whole_to_int(amount) else {
+                    self.write_local(key, float_to_fact(current as f64 + amount));
+                    return;
+                };
+                let policy = self.local.int_overflow_policy();
+                if let Some(sum) = add_or_warn(policy, key, current, whole) {
+                    self.write_local(key, sum);
+                }
+            }
+            Some(FactValue::Float(f)) => {
+                self.write_local(key, FactValue::Float(*f + amount));
+            }
+            _ => self.write_local(key, float_to_fact(amount)),
+        }
+    }
+
+    /// Subtract a numeric value from a fact.
+    ///
+    /// 从事实减去数值。
+    pub fn sub(&mut self, key: &str, amount: f64) {
+        self.add(key, -amount);
+    }
+
+    /// Multiply a fact by a numeric value.
+    ///
+    /// 将事实乘以数值。
+    pub fn mul(&mut self, key: &str, factor: f64) {
+        match self.get_by_str(key) {
+            Some(FactValue::Int(i)) => {
+                let result = *i as f64 * factor;
+                self.write_local(key, float_to_fact(result));
+            }
+            Some(FactValue::Float(f)) => {
+                self.write_local(key, FactValue::Float(*f * factor));
+            }
+            _ => {
+                // No-op if fact doesn't exist
+            }
+        }
+    }
+
+    /// Divide a fact by a numeric value.
+    /// Division by zero sets the fact to 0.
+    ///
+    /// 将事实除以数值。
+    /// 除以零时将事实设为 0。
+    pub fn div(&mut self, key: &str, divisor: f64) {
+        if divisor == 0.0 {
+            self.write_local(key, 0i64);
+            return;
+        }
+        match self.get_by_str(key) {
+            Some(FactValue::Int(i)) => {
+                let result = *i as f64 / divisor;
+                self.write_local(key, float_to_fact(result));
+            }
+            Some(FactValue::Float(f)) => {
+                self.write_local(key, FactValue::Float(*f / divisor));
+            }
+            _ => {
+                // No-op if fact doesn't exist
+            }
+        }
+    }
+
+    /// Apply modulo operation to a fact.
+    ///
+    /// 对事实应用取模运算。
+    pub fn modulo(&mut self, key: &str, divisor: i64) {
+        if divisor == 0 {
+            return;
+        }
+        if let Some(i) = self.get_int(key) {
+            self.write_local(key, i % divisor);
+        }
+    }
+
+    /// Clamp a fact value between min and max (inclusive).
+    ///
+    /// 将事实值限制在 min 和 max 之间（包含）。
+    pub fn clamp(&mut self, key: &str, min: f64, max: f64) {
+        match self.get_by_str(key) {
+            Some(FactValue::Int(i)) => {
+                let clamped = (*i as f64).clamp(min, max);
+                self.write_local(key, float_to_fact(clamped));
+            }
+            Some(FactValue::Float(f)) => {
+                self.write_local(key, FactValue::Float(f.clamp(min, max)));
+            }
+            _ => {}
+        }
+    }
+
+    /// Wrap a fact value within a range [min, max).
+    /// When value >= max, it wraps to min. When value < min, it wraps to max - 1.
+    ///
+    /// 将事实值包裹在范围 [min, max) 内。
+    /// 当值 >= max 时，包裹到 min。当值 < min 时，包裹到 max - 1。
+    pub fn wrap(&mut self, key: &str, min: i64, max: i64) {
+        if max <= min {
+            return;
+        }
+        if let Some(i) = self.get_int(key) {
+            let range = max - min;
+            let wrapped = ((i - min) % range + range) % range + min;
+            self.write_local(key, wrapped);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! # persist.rs
+//!
+//! # persist.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Saving the global layer to disk and loading it back, for save games. The layer is written as
+//! the RON produced by `FactDatabase::to_ron_string`. Loading writes the saved facts through
+//! `set_global`, so aliases, the schema and the global freeze apply as they do to any other
+//! global write. `GlobalLoadMode::Merge` keeps the facts a save does not mention, which lets
+//! saves written before a fact was introduced load without losing its current value.
+//!
+//! 把全局层保存到磁盘并重新加载，用于存档。该层以 `FactDatabase::to_ron_string` 生成的 RON
+//! 写出。加载时通过 `set_global` 写入保存的事实，因此别名、模式和全局冻结会像其他全局写入一样生效。
+//! `GlobalLoadMode::Merge` 保留存档中未提及的事实，使在某个事实引入之前写出的存档加载后
+//! 不会丢失该事实的当前值。
+
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::LayeredFactDatabase;
+use crate::database::FactDatabase;
+
+/// How `load_global_from` combines the saved facts with the global layer.
+///
+/// `load_global_from` 如何把保存的事实与全局层结合。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GlobalLoadMode {
+    /// The global layer holds exactly the saved facts afterwards.
+    ///
+    /// 加载后全局层恰好持有保存的事实。
+    #[default]
+    Replace,
+
+    /// Saved facts overwrite the layer's values; facts the save does not mention are kept.
+    ///
+    /// 保存的事实覆盖该层的值；存档中未提及的事实会被保留。
+    Merge,
+}
+
+/// Error produced when saving or loading the global layer.
+///
+/// 保存或加载全局层时产生的错误。
+#[derive(Debug)]
+pub enum FactSaveError {
+    /// The file could not be read or written.
+    ///
+    /// 无法读取或写入文件。
+    Io(std::io::Error),
+
+    /// The facts could not be written as RON.
+    ///
+    /// 无法把事实写为 RON。
+    Serialize(ron::Error),
+
+    /// The file is not a valid save of a fact layer.
+    ///
+    /// 文件不是有效的事实层存档。
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for FactSaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactSaveError::Io(err) => write!(f, "failed to access fact save: {}", err),
+            FactSaveError::Serialize(err) => write!(f, "failed to serialize facts: {}", err),
+            FactSaveError::Parse(err) => write!(f, "failed to parse fact save: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FactSaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FactSaveError::Io(err) => Some(err),
+            FactSaveError::Serialize(err) => Some(err),
+            FactSaveError::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for FactSaveError {
+    fn from(err: std::io::Error) -> Self {
+        FactSaveError::Io(err)
+    }
+}
+
+impl From<ron::Error> for FactSaveError {
+    fn from(err: ron::Error) -> Self {
+        FactSaveError::Serialize(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for FactSaveError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        FactSaveError::Parse(err)
+    }
+}
+
+impl LayeredFactDatabase {
+    /// Write the global layer to `path` as RON, replacing any existing file. The save is
+    /// written to a temporary file next to `path` and renamed over it, so a crash while saving
+    /// leaves the previous save intact.
+    ///
+    /// 把全局层以 RON 格式写入 `path`，替换已有文件。存档先写入 `path` 旁边的临时文件，
+    /// 再重命名覆盖它，因此保存过程中崩溃不会破坏之前的存档。
+    pub fn save_global_to(&self, path: impl AsRef<Path>) -> Result<(), FactSaveError> {
+        let path = path.as_ref();
+        let ron = self.global.to_ron_string()?;
+        let temp = temp_path(path);
+        let result = write_synced(&temp, &ron).and_then(|()| std::fs::rename(&temp, path));
+        if result.is_err() {
+            std::fs::remove_file(&temp).ok();
+        }
+        Ok(result?)
+    }
+
+    /// Load a global layer written by `save_global_to`. Nothing is changed if the file cannot
+    /// be read or parsed.
+    ///
+    /// 加载由 `save_global_to` 写出的全局层。如果文件无法读取或解析，则不做任何更改。
+    pub fn load_global_from(
+        &mut self,
+        path: impl AsRef<Path>,
+        mode: GlobalLoadMode,
+    ) -> Result<(), FactSaveError> {
+        let saved = FactDatabase::from_ron_str(&std::fs::read_to_string(path)?)?;
+        if mode == GlobalLoadMode::Replace {
+            self.clear_global();
+        }
+        for (key, value) in saved.iter() {
+            self.set_global(key.clone(), value.clone());
+        }
+        Ok(())
+    }
+}
+
+/// `path` with `.tmp` appended, in the same directory so the rename stays on one file system.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    PathBuf::from(temp)
+}
+
+/// Write `contents` to `path` and flush it to disk.
+fn write_synced(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::every_variant;
+
+    #[test]
+    fn test_save_and_load_round_trip_every_variant() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("save.ron");
+        let mut db = LayeredFactDatabase::new();
+        for (key, value) in every_variant() {
+            db.set_global(key, value);
+        }
+        db.set("room", "cave");
+        db.save_global_to(&path).unwrap();
+
+        let mut loaded = LayeredFactDatabase::new();
+        loaded.set_global("stale", 1i64);
+        loaded
+            .load_global_from(&path, GlobalLoadMode::Replace)
+            .unwrap();
+
+        assert_eq!(loaded.global_len(), every_variant().len());
+        for (key, value) in every_variant() {
+            assert_eq!(loaded.global().get_by_str(key), Some(&value), "{}", key);
+        }
+        assert!(!loaded.contains("stale"));
+        assert!(!loaded.contains("room"));
+    }
+
+    #[test]
+    fn test_merge_keeps_facts_missing_from_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("save.ron");
+        let mut old_save = LayeredFactDatabase::new();
+        old_save.set_global("gold", 50i64);
+        old_save.save_global_to(&path).unwrap();
+
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("gold", 0i64);
+        db.set_global("new_feature_unlocked", false);
+        db.load_global_from(&path, GlobalLoadMode::Merge).unwrap();
+
+        assert_eq!(db.global().get_int("gold"), Some(50));
+        assert_eq!(db.global().get_bool("new_feature_unlocked"), Some(false));
+    }
+
+    #[test]
+    fn test_save_replaces_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("save.ron");
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("gold", 10i64);
+        db.save_global_to(&path).unwrap();
+        db.set_global("gold", 20i64);
+        db.save_global_to(&path).unwrap();
+
+        let mut loaded = LayeredFactDatabase::new();
+        loaded
+            .load_global_from(&path, GlobalLoadMode::Replace)
+            .unwrap();
+        assert_eq!(loaded.global().get_int("gold"), Some(20));
+        // Only the save itself is left; the temporary file was renamed over it
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // A failed save leaves no temporary file behind
+        let missing_dir = dir.path().join("missing").join("save.ron");
+        assert!(matches!(
+            db.save_global_to(&missing_dir),
+            Err(FactSaveError::Io(_))
+        ));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_errors_leave_layer_unchanged() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("gold", 10i64);
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.ron");
+        let result = db.load_global_from(&missing, GlobalLoadMode::Replace);
+        assert!(matches!(result, Err(FactSaveError::Io(_))));
+
+        let corrupt = dir.path().join("corrupt.ron");
+        std::fs::write(&corrupt, "{ \"gold\": Int(").unwrap();
+        let result = db.load_global_from(&corrupt, GlobalLoadMode::Replace);
+        assert!(matches!(result, Err(FactSaveError::Parse(_))));
+        assert_eq!(db.global().get_int("gold"), Some(10));
+    }
+}
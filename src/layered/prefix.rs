@@ -0,0 +1,122 @@
+//! # prefix.rs
+//!
+//! # prefix.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Namespace queries for `LayeredFactDatabase`, built on the per-layer helpers of
+//! `FactDatabase`. Iteration yields the effective facts, so a local fact hides the global
+//! fact with the same key. Removal targets one layer, which lets a game drop `battle:` state
+//! when combat ends without clearing the unrelated local facts of other systems.
+//! Prefixes match stored keys, not aliases.
+//!
+//! 基于 `FactDatabase` 逐层辅助方法的 `LayeredFactDatabase` 命名空间查询。
+//! 迭代产生有效事实，因此局部事实会遮蔽同键的全局事实。移除只针对单一层，
+//! 使游戏可以在战斗结束时丢弃 `battle:` 状态，而不清空其他系统无关的局部事实。
+//! 前缀匹配存储的键，而不是别名。
+
+use super::LayeredFactDatabase;
+use crate::database::FactValue;
+
+impl LayeredFactDatabase {
+    /// Iterate over the effective facts whose key starts with `prefix`: the local matches,
+    /// top layer first, then the global matches, skipping any key shadowed by a layer above.
+    ///
+    /// 迭代键以 `prefix` 开头的有效事实：先是局部匹配（栈顶层在前），
+    /// 然后是全局匹配，跳过被上方层遮蔽的键。
+    pub fn iter_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a String, &'a FactValue)> {
+        self.local_layers()
+            .chain(std::iter::once(&self.global))
+            .enumerate()
+            .flat_map(move |(depth, layer)| {
+                layer.iter_prefix(prefix).filter(move |(key, _)| {
+                    !self
+                        .local_layers()
+                        .take(depth)
+                        .any(|above| above.contains(key))
+                })
+            })
+    }
+
+    /// Whether any fact in either layer has a key starting with `prefix`.
+    ///
+    /// 任一层中是否有事实的键以 `prefix` 开头。
+    pub fn contains_prefix(&self, prefix: &str) -> bool {
+        self.local_layers()
+            .any(|layer| layer.contains_prefix(prefix))
+            || self.global.contains_prefix(prefix)
+    }
+
+    /// Remove every local fact whose key starts with `prefix`, returning how many were removed.
+    ///
+    /// 移除键以 `prefix` 开头的所有局部事实，并返回移除的数量。
+    pub fn remove_prefix(&mut self, prefix: &str) -> usize {
+        self.local.remove_prefix(prefix)
+    }
+
+    /// Remove every global fact whose key starts with `prefix`, returning how many were removed.
+    ///
+    /// 移除键以 `prefix` 开头的所有全局事实，并返回移除的数量。
+    pub fn remove_prefix_global(&mut self, prefix: &str) -> usize {
+        if !self.global_writable(prefix) {
+            return 0;
+        }
+        self.global.remove_prefix(prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_prefix_queries_respect_shadowing() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("battle:turn", 1i64);
+        db.set_global("battle:round", 2i64);
+        db.set_local("battle:turn", 5i64);
+        db.set_local("battle:phase", "attack");
+        db.set_local("menu:selection", 3i64);
+        db.set_local("battlecry", true);
+
+        let battle: BTreeMap<&String, &FactValue> = db.iter_prefix("battle:").collect();
+        assert_eq!(battle.len(), 3);
+        assert_eq!(battle[&"battle:turn".to_string()], &FactValue::Int(5));
+        assert_eq!(battle[&"battle:round".to_string()], &FactValue::Int(2));
+        assert!(db.contains_prefix("battle:"));
+        assert!(!db.contains_prefix("shop:"));
+
+        // Combat ends: local battle state goes, UI state and global facts stay
+        assert_eq!(db.remove_prefix("battle:"), 2);
+        assert!(!db.local().contains_prefix("battle:"));
+        assert_eq!(db.get_int("menu:selection"), Some(3));
+        assert_eq!(db.get_bool("battlecry"), Some(true));
+        assert_eq!(db.get_int("battle:turn"), Some(1));
+        assert!(db.is_changed("battle:phase"));
+
+        assert_eq!(db.remove_prefix_global("battle:"), 2);
+        assert!(!db.contains_prefix("battle:"));
+        assert_eq!(db.remove_prefix("battle:"), 0);
+    }
+
+    #[test]
+    fn test_prefix_queries_see_pushed_layers() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("menu:depth", 0i64);
+        db.set_local("menu:depth", 1i64);
+        db.set_local("menu:title", "battle");
+        db.push_layer("submenu");
+        db.set_local("menu:depth", 2i64);
+
+        let menu: BTreeMap<&String, &FactValue> = db.iter_prefix("menu:").collect();
+        assert_eq!(menu.len(), 2);
+        assert_eq!(menu[&"menu:depth".to_string()], &FactValue::Int(2));
+        assert!(db.contains_prefix("menu:title"));
+    }
+}
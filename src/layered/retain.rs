@@ -0,0 +1,98 @@
+//! # retain.rs
+//!
+//! # retain.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Per-layer bulk removal for `LayeredFactDatabase`, built on `FactDatabase::retain` and
+//! `remove_many`. Each method targets one layer, like `remove` and `remove_global`. Keys passed
+//! to `remove_many` resolve through aliases; `retain` predicates see the stored keys.
+//!
+//! 基于 `FactDatabase::retain` 和 `remove_many` 的 `LayeredFactDatabase` 逐层批量移除。
+//! 与 `remove` 和 `remove_global` 一样，每个方法只针对一层。传给 `remove_many` 的键会经过
+//! 别名解析；`retain` 的谓词看到的是存储的键。
+
+use super::LayeredFactDatabase;
+use crate::database::FactValue;
+
+impl LayeredFactDatabase {
+    /// Keep only the local facts for which `keep` returns true, returning how many were removed.
+    ///
+    /// 仅保留 `keep` 返回 true 的局部事实，并返回移除的数量。
+    pub fn retain_local(&mut self, keep: impl FnMut(&str, &FactValue) -> bool) -> usize {
+        self.local.retain(keep)
+    }
+
+    /// Keep only the global facts for which `keep` returns true, returning how many were removed.
+    ///
+    /// 仅保留 `keep` 返回 true 的全局事实，并返回移除的数量。
+    pub fn retain_global(&mut self, keep: impl FnMut(&str, &FactValue) -> bool) -> usize {
+        if !self.global_writable("*") {
+            return 0;
+        }
+        self.global.retain(keep)
+    }
+
+    /// Remove every listed key from the local layer, returning how many facts were removed.
+    ///
+    /// 从局部层移除列出的每个键，并返回移除的事实数量。
+    pub fn remove_many<'a>(&mut self, keys: impl IntoIterator<Item = &'a str>) -> usize {
+        let aliases = &self.aliases;
+        self.local
+            .remove_many(keys.into_iter().map(|key| aliases.resolve_write(key)))
+    }
+
+    /// Remove every listed key from the global layer, returning how many facts were removed.
+    ///
+    /// 从全局层移除列出的每个键，并返回移除的事实数量。
+    pub fn remove_many_global<'a>(&mut self, keys: impl IntoIterator<Item = &'a str>) -> usize {
+        if !self.global_writable("*") {
+            return 0;
+        }
+        let aliases = &self.aliases;
+        self.global
+            .remove_many(keys.into_iter().map(|key| aliases.resolve_write(key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retain_targets_one_layer() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("room:visits", 1i64);
+        db.set_global("player:name", "hero");
+        db.set_local("room:visits", 4i64);
+        db.set_local("room:lit", true);
+        db.set_local("room:label", "hall");
+
+        let removed = db.retain_local(|key, value| {
+            !key.starts_with("room:") || matches!(value, FactValue::String(_))
+        });
+        assert_eq!(removed, 2);
+        assert_eq!(db.get_int("room:visits"), Some(1));
+        assert_eq!(db.get_string("room:label"), Some("hall"));
+
+        assert_eq!(db.retain_global(|key, _| !key.starts_with("room:")), 1);
+        assert!(!db.contains("room:visits"));
+        assert_eq!(db.get_string("player:name"), Some("hero"));
+    }
+
+    #[test]
+    fn test_remove_many_resolves_aliases() {
+        let mut db = LayeredFactDatabase::new();
+        db.add_alias("old_gold", "gold").unwrap();
+        db.set_local("gold", 5i64);
+        db.set_local("keys", 2i64);
+        db.set_global("gold", 50i64);
+
+        assert_eq!(db.remove_many(["old_gold", "keys", "missing"]), 2);
+        assert_eq!(db.local_len(), 0);
+        assert_eq!(db.remove_many_global(["old_gold"]), 1);
+        assert!(db.is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+//! # snapshot.rs
+//!
+//! # snapshot.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Snapshots of both layers of a `LayeredFactDatabase`. Each layer is captured and diffed on its
+//! own, so a change reports which layer it happened in, and a local fact shadowing a global one
+//! shows up as an addition to the local layer rather than a modification of the effective value.
+//! `restore` rolls both layers back to a snapshot, such as the state when a battle started so
+//! it can be retried. Only the top of the local stack is captured and restored; layers buried by
+//! `push_layer` are left alone.
+//!
+//! `LayeredFactDatabase` 两层的快照。每一层单独捕获和比较，因此变化会报告它发生在哪一层；
+//! 局部事实遮蔽全局事实时，表现为局部层的新增，而不是有效值的修改。
+//! `restore` 把两层回滚到快照，例如战斗开始时的状态，以便重试战斗。
+//! 只有局部栈顶会被捕获和恢复；被 `push_layer` 压下的层保持不变。
+
+use serde::{Deserialize, Serialize};
+
+use super::LayeredFactDatabase;
+use crate::database::{FactChange, FactLayer, FactSnapshot};
+
+/// Snapshots of the global and local layers taken together.
+///
+/// 同时拍摄的全局层和局部层快照。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayeredFactSnapshot {
+    pub global: FactSnapshot,
+    pub local: FactSnapshot,
+}
+
+impl LayeredFactDatabase {
+    /// Snapshot both layers for a later `diff_all` or `restore`.
+    ///
+    /// 拍摄两层的快照，供之后的 `diff_all` 或 `restore` 使用。
+    pub fn snapshot_all(&self) -> LayeredFactSnapshot {
+        LayeredFactSnapshot {
+            global: self.global.snapshot(),
+            local: self.local.snapshot(),
+        }
+    }
+
+    /// Changes made to each layer since `snapshot` was taken: global changes first, then local
+    /// ones, each sorted by key.
+    ///
+    /// 自 `snapshot` 拍摄以来每一层的变化：先全局后局部，各自按键排序。
+    pub fn diff_all(&self, snapshot: &LayeredFactSnapshot) -> Vec<(FactLayer, FactChange)> {
+        let global = self.global.diff(&snapshot.global);
+        let local = self.local.diff(&snapshot.local);
+        global
+            .into_iter()
+            .map(|change| (FactLayer::Global, change))
+            .chain(local.into_iter().map(|change| (FactLayer::Local, change)))
+            .collect()
+    }
+
+    /// Replace the contents of both layers with those of `snapshot`. A frozen global layer is
+    /// kept, and only the local layer is rolled back.
+    ///
+    /// 用 `snapshot` 的内容替换两层的内容。已冻结的全局层会被保留，只回滚局部层。
+    pub fn restore(&mut self, snapshot: &LayeredFactSnapshot) {
+        if self.global_writable("*") {
+            self.global.restore(&snapshot.global);
+        }
+        self.local.restore(&snapshot.local);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::FactValue;
+
+    #[test]
+    fn test_restore_undoes_every_change() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("gold", 10);
+        db.set_global("potions", 2);
+        db.set("turn", 1);
+        db.set("boss_hp", 300);
+        let start = db.snapshot_all();
+
+        db.set_global("gold", 0);
+        db.remove_global("potions");
+        db.increment("turn", 4);
+        db.set("boss_hp", 120);
+        db.remove("boss_hp");
+        db.set("fled", true);
+        db.restore(&start);
+
+        assert_eq!(db.global().get_int("gold"), Some(10));
+        assert_eq!(db.global().get_int("potions"), Some(2));
+        assert_eq!(db.get_int("turn"), Some(1));
+        assert_eq!(db.get_int("boss_hp"), Some(300));
+        assert!(!db.contains("fled"));
+        assert!(db.diff_all(&start).is_empty());
+
+        // Snapshots survive a save file
+        let saved = ron::to_string(&start).unwrap();
+        assert_eq!(ron::from_str::<LayeredFactSnapshot>(&saved).unwrap(), start);
+    }
+
+    #[test]
+    fn test_diff_all_reports_the_layer_of_each_change() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("gold", 10);
+        db.set("turn", 1);
+        let before = db.snapshot_all();
+
+        db.set_global("gold", 15);
+        db.set("gold", 0);
+        db.remove("turn");
+
+        assert_eq!(
+            db.diff_all(&before),
+            vec![
+                (
+                    FactLayer::Global,
+                    FactChange::Modified {
+                        key: "gold".to_string(),
+                        old: FactValue::Int(10),
+                        new: FactValue::Int(15),
+                    }
+                ),
+                (
+                    FactLayer::Local,
+                    FactChange::Added {
+                        key: "gold".to_string(),
+                        value: FactValue::Int(0),
+                    }
+                ),
+                (
+                    FactLayer::Local,
+                    FactChange::Removed {
+                        key: "turn".to_string(),
+                        value: FactValue::Int(1),
+                    }
+                ),
+            ]
+        );
+    }
+}
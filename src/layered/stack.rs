@@ -0,0 +1,140 @@
+//! # stack.rs
+//!
+//! # stack.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Named overlay layers on top of the local layer. `push_layer` buries the current local layer
+//! under a fresh one, so a submenu opened during a battle writes to its own layer while still
+//! reading the battle's facts; `pop_layer` discards the overlay and the battle layer is visible
+//! again. Reads resolve the stack top-down before falling back to the global layer. Writes,
+//! removals, and `local()` always target the top layer. Popping marks the popped keys as
+//! changed, since their effective values change. `clear_local` treats the whole stack as the
+//! local tier: it pops every pushed layer and clears the base.
+//!
+//! 局部层之上的具名覆盖层。`push_layer` 把当前局部层压在一个新层之下，因此战斗中打开的子菜单
+//! 写入自己的层，同时仍可读取战斗的事实；`pop_layer` 丢弃覆盖层，战斗层重新可见。
+//! 读取先自顶向下解析栈，再回退到全局层。写入、移除和 `local()` 总是针对栈顶层。
+//! 弹出会把被弹出层的键标记为已变化，因为它们的有效值发生了变化。`clear_local`
+//! 把整个栈视为局部层：它弹出所有已压入的层并清空底层。
+
+use super::LayeredFactDatabase;
+use crate::database::{FactDatabase, FactValue};
+
+/// Name reported by `layer_names` for the base local layer.
+const BASE_LAYER_NAME: &str = "local";
+
+impl LayeredFactDatabase {
+    /// Push an empty layer named `name` over the local layer. Until it is popped, local writes
+    /// go to it and local reads check it first.
+    ///
+    /// 在局部层之上压入名为 `name` 的空层。在其被弹出之前，局部写入都写到该层，局部读取也先检查该层。
+    pub fn push_layer(&mut self, name: impl Into<String>) {
+        let below = std::mem::take(&mut self.local);
+        self.local
+            .set_int_overflow_policy(below.int_overflow_policy());
+        self.buried.push(below);
+        self.pushed_names.push(name.into());
+    }
+
+    /// Pop the top pushed layer, returning its facts, so the layer beneath is visible again.
+    /// Returns None when no layer is pushed; the base local layer cannot be popped.
+    ///
+    /// 弹出栈顶的已压入层并返回其事实，使下方的层重新可见。
+    /// 没有压入层时返回 None；底层局部层不能被弹出。
+    pub fn pop_layer(&mut self) -> Option<FactDatabase> {
+        let below = self.buried.pop()?;
+        self.pushed_names.pop();
+        let popped = std::mem::replace(&mut self.local, below);
+        for (key, _) in popped.iter() {
+            self.local.mark_changed(key);
+        }
+        Some(popped)
+    }
+
+    /// Names of the local layers, bottom first, starting with `"local"` for the base layer.
+    ///
+    /// 局部层的名称，底层在前，底层局部层的名称为 `"local"`。
+    pub fn layer_names(&self) -> Vec<&str> {
+        std::iter::once(BASE_LAYER_NAME)
+            .chain(self.pushed_names.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Number of layers pushed over the base local layer.
+    ///
+    /// 压在底层局部层之上的层数。
+    pub fn pushed_layer_count(&self) -> usize {
+        self.buried.len()
+    }
+
+    /// The local layers, top first.
+    pub(super) fn local_layers(&self) -> impl Iterator<Item = &FactDatabase> {
+        std::iter::once(&self.local).chain(self.buried.iter().rev())
+    }
+
+    /// Read `key` (already resolved) from the local stack, top-down.
+    pub(super) fn get_local_by_str(&self, key: &str) -> Option<&FactValue> {
+        self.local_layers().find_map(|layer| layer.get_by_str(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_popping_a_layer_unshadows_the_value_beneath() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("music", "overworld");
+        db.set("music", "battle");
+        db.set("turn", 3i64);
+
+        db.push_layer("submenu");
+        assert_eq!(db.layer_names(), ["local", "submenu"]);
+        assert_eq!(db.get_string("music"), Some("battle"));
+        assert_eq!(db.get_int("turn"), Some(3));
+        db.set("music", "menu");
+        db.set("cursor", 1i64);
+        assert_eq!(db.get_string("music"), Some("menu"));
+        assert!(db.contains_local("turn"));
+        assert_eq!(db.local_len(), 4);
+
+        db.push_layer("confirm");
+        db.remove("music");
+        assert_eq!(db.get_string("music"), Some("menu"));
+        db.clear_changes();
+
+        assert_eq!(db.pop_layer().map(|layer| layer.len()), Some(0));
+        let submenu = db.pop_layer().unwrap();
+        assert_eq!(submenu.get_string("music"), Some("menu"));
+        assert_eq!(db.layer_names(), ["local"]);
+        assert_eq!(db.get_string("music"), Some("battle"));
+        assert_eq!(db.get_int("cursor"), None);
+        assert!(db.is_changed("music") && db.is_changed("cursor"));
+        assert!(!db.is_changed("turn"));
+
+        assert!(db.pop_layer().is_none());
+        db.remove("music");
+        assert_eq!(db.get_string("music"), Some("overworld"));
+    }
+
+    #[test]
+    fn test_clear_local_clears_the_whole_stack() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("gold", 10i64);
+        db.set("battle:turn", 2i64);
+        db.push_layer("inventory");
+        db.set("selected", "potion");
+        db.increment("battle:turn", 1);
+        assert_eq!(db.local().get_int("battle:turn"), Some(3));
+
+        db.clear_local();
+        assert_eq!(db.pushed_layer_count(), 0);
+        assert_eq!(db.local_len(), 0);
+        assert_eq!(db.get_int("gold"), Some(10));
+        assert!(!db.contains("battle:turn") && !db.contains("selected"));
+    }
+}
@@ -0,0 +1,170 @@
+//! # toggle.rs
+//!
+//! # toggle.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Boolean toggling for `LayeredFactDatabase`. A missing fact toggles to `true`; what happens
+//! to a fact that exists but is not a boolean is decided by the database's `TogglePolicy`, so a
+//! stray `Toggle` in a rule does not have to silently overwrite an int or string fact.
+//!
+//! `LayeredFactDatabase` 的布尔切换。缺失的事实切换为 `true`；已存在但不是布尔值的事实如何处理
+//! 由数据库的 `TogglePolicy` 决定，因此规则中误用的 `Toggle` 不必静默覆盖整数或字符串事实。
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::LayeredFactDatabase;
+use crate::database::FactValue;
+
+/// What toggling a fact that is not a boolean does.
+///
+/// 切换非布尔事实时的行为。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "debug", derive(Reflect))]
+pub enum TogglePolicy {
+    /// Treat the value as `false` and store `true`, replacing it.
+    ///
+    /// 把值视为 `false` 并存储 `true`，替换原值。
+    #[default]
+    TreatAsFalse,
+
+    /// Leave the fact unchanged and log an error.
+    ///
+    /// 保持事实不变并记录错误。
+    Error,
+
+    /// Leave the fact unchanged silently.
+    ///
+    /// 静默保持事实不变。
+    Ignore,
+
+    /// Store the negation of the value's truthiness: nonzero numbers and non-empty strings and
+    /// lists become `false`, everything else `true`.
+    ///
+    /// 存储值真值的取反：非零数字以及非空字符串和列表变为 `false`，其他都变为 `true`。
+    Coerce,
+}
+
+/// Whether a non-boolean value counts as true under `TogglePolicy::Coerce`.
+fn truthy(value: &FactValue) -> bool {
+    if let Some(number) = value.as_number() {
+        return number != 0.0;
+    }
+    match value {
+        FactValue::String(s) => !s.is_empty(),
+        FactValue::Null => false,
+        other => other.list_len().is_none_or(|len| len > 0),
+    }
+}
+
+impl LayeredFactDatabase {
+    pub fn toggle_policy(&self) -> TogglePolicy {
+        self.toggle_policy
+    }
+
+    /// Set how `toggle` handles facts that are not booleans (treated as false by default).
+    ///
+    /// 设置 `toggle` 如何处理非布尔事实（默认视为 false）。
+    pub fn set_toggle_policy(&mut self, policy: TogglePolicy) {
+        self.toggle_policy = policy;
+    }
+
+    /// Flip a boolean fact, writing to the local layer. A missing fact becomes `true`.
+    ///
+    /// 翻转布尔事实并写入局部层。缺失的事实变为 `true`。
+    pub fn toggle(&mut self, key: &str) {
+        if let Some(current) = self.toggle_current(key) {
+            self.write_local(key, !current);
+        }
+    }
+
+    /// Flip a boolean fact, writing to the global layer. A missing fact becomes `true`.
+    ///
+    /// 翻转布尔事实并写入全局层。缺失的事实变为 `true`。
+    pub fn toggle_global(&mut self, key: &str) {
+        if let Some(current) = self.toggle_current(key) {
+            self.set_global(key, !current);
+        }
+    }
+
+    /// The value a toggle of `key` flips, or None if the policy leaves the fact alone.
+    fn toggle_current(&self, key: &str) -> Option<bool> {
+        Some(match self.get_by_str(key) {
+            None => false,
+            Some(FactValue::Bool(current)) => *current,
+            Some(other) => match self.toggle_policy {
+                TogglePolicy::TreatAsFalse => false,
+                TogglePolicy::Coerce => truthy(other),
+                TogglePolicy::Ignore => return None,
+                TogglePolicy::Error => {
+                    error!("FRE: Cannot toggle '{}': {:?} is not a boolean", key, other);
+                    return None;
+                }
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::FactModification;
+
+    fn toggled(policy: TogglePolicy, value: FactValue) -> FactValue {
+        let mut db = LayeredFactDatabase::new();
+        db.set_toggle_policy(policy);
+        db.set("fact", value);
+        FactModification::Toggle("fact".to_string()).apply(&mut db);
+        db.get_by_str("fact").unwrap().clone()
+    }
+
+    #[test]
+    fn test_toggle_non_boolean_under_each_policy() {
+        let name = || FactValue::from("hero");
+        let count = || FactValue::Int(3);
+
+        assert_eq!(
+            toggled(TogglePolicy::TreatAsFalse, name()),
+            FactValue::Bool(true)
+        );
+        assert_eq!(
+            toggled(TogglePolicy::TreatAsFalse, count()),
+            FactValue::Bool(true)
+        );
+        assert_eq!(toggled(TogglePolicy::Error, name()), name());
+        assert_eq!(toggled(TogglePolicy::Error, count()), count());
+        assert_eq!(toggled(TogglePolicy::Ignore, name()), name());
+        assert_eq!(toggled(TogglePolicy::Ignore, count()), count());
+        assert_eq!(
+            toggled(TogglePolicy::Coerce, name()),
+            FactValue::Bool(false)
+        );
+        assert_eq!(
+            toggled(TogglePolicy::Coerce, count()),
+            FactValue::Bool(false)
+        );
+        assert_eq!(
+            toggled(TogglePolicy::Coerce, FactValue::Int(0)),
+            FactValue::Bool(true)
+        );
+        assert_eq!(
+            toggled(TogglePolicy::Coerce, "".into()),
+            FactValue::Bool(true)
+        );
+
+        // Booleans flip under every policy
+        for policy in [
+            TogglePolicy::Error,
+            TogglePolicy::Ignore,
+            TogglePolicy::Coerce,
+        ] {
+            assert_eq!(
+                toggled(policy, FactValue::Bool(true)),
+                FactValue::Bool(false)
+            );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! # transaction.rs
+//!
+//! # transaction.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! All-or-nothing batches of local writes. `transaction` snapshots the local layer, runs a
+//! closure against the database, and rolls the local layer back if the closure returns an
+//! error, so a batch whose later write fails validation leaves no partial state behind. Only
+//! the local layer is captured: global writes made inside the closure are kept either way.
+//! Batches that also write the global layer use `transaction_with_global`, which snapshots
+//! both layers.
+//!
+//! 局部写入的全有或全无批处理。`transaction` 先拍摄局部层快照，再对数据库运行闭包；
+//! 如果闭包返回错误则回滚局部层，因此后面的写入未通过验证的批处理不会留下部分状态。
+//! 只有局部层会被捕获：闭包内对全局层的写入无论如何都会保留。
+//! 同时写入全局层的批处理使用 `transaction_with_global`，它会为两层都拍摄快照。
+
+use super::LayeredFactDatabase;
+
+impl LayeredFactDatabase {
+    /// Run `f` against this database, restoring the local layer to its state before the call
+    /// if `f` returns an error. The error is passed through.
+    ///
+    /// 对此数据库运行 `f`；如果 `f` 返回错误，则把局部层恢复到调用前的状态。错误会原样返回。
+    pub fn transaction<E, F>(&mut self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Self) -> Result<(), E>,
+    {
+        let saved = self.local.snapshot();
+        let result = f(self);
+        if result.is_err() {
+            self.local.restore(&saved);
+        }
+        result
+    }
+
+    /// Like `transaction`, but restores the global layer too if `f` returns an error. Copying
+    /// the global layer costs more, so prefer `transaction` when `f` only writes local facts.
+    ///
+    /// 与 `transaction` 相同，但当 `f` 返回错误时也会恢复全局层。复制全局层的开销更大，
+    /// 因此当 `f` 只写入局部事实时请优先使用 `transaction`。
+    pub fn transaction_with_global<E, F>(&mut self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Self) -> Result<(), E>,
+    {
+        let saved = self.global.snapshot();
+        let result = self.transaction(f);
+        if result.is_err() {
+            self.global.restore(&saved);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_transaction_reverts_every_write() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("gold", 10i64);
+        db.set("room", "cave");
+
+        let result = db.transaction(|db| {
+            db.increment("gold", 5);
+            db.set("room", "lake");
+            db.set("visited_lake", true);
+            db.remove("missing");
+            Err("shop closed")
+        });
+        assert_eq!(result, Err("shop closed"));
+        assert_eq!(db.get_int("gold"), Some(10));
+        assert_eq!(db.get_string("room"), Some("cave"));
+        assert!(!db.contains("visited_lake"));
+
+        let result = db.transaction(|db| {
+            db.increment("gold", 5);
+            db.remove("room");
+            Ok::<(), &str>(())
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(db.get_int("gold"), Some(15));
+        assert!(!db.contains("room"));
+    }
+
+    #[test]
+    fn test_global_transaction_reverts_both_layers() {
+        let mut db = LayeredFactDatabase::new();
+        db.set_global("deaths", 1i64);
+        db.set("hp", 0i64);
+
+        let failed = |db: &mut LayeredFactDatabase| {
+            db.increment_global("deaths", 1);
+            db.set_global("last_death", "cave");
+            db.remove("hp");
+            Err("respawn blocked")
+        };
+        assert!(db.transaction_with_global(failed).is_err());
+        assert_eq!(db.global().get_int("deaths"), Some(1));
+        assert!(!db.global().contains("last_death"));
+        assert_eq!(db.get_int("hp"), Some(0));
+
+        // A plain transaction keeps the global writes
+        assert!(db.transaction(failed).is_err());
+        assert_eq!(db.global().get_int("deaths"), Some(2));
+        assert_eq!(db.get_int("hp"), Some(0));
+    }
+}
@@ -0,0 +1,132 @@
+//! # transfer.rs
+//!
+//! # transfer.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Moving and copying single facts between the local and global layers of a
+//! `LayeredFactDatabase`. Each operation returns whether the source layer held the key, so a
+//! caller can tell a no-op from a transfer. While the global layer is frozen nothing is
+//! transferred and every operation returns false, leaving both layers as they were.
+//!
+//! 在 `LayeredFactDatabase` 的局部层和全局层之间移动或复制单个事实。
+//! 每个操作都返回源层是否持有该键，使调用者能够区分空操作与实际转移。
+//! 全局层被冻结期间不会转移任何事实，每个操作都返回 false，两层保持原样。
+
+use super::LayeredFactDatabase;
+
+impl LayeredFactDatabase {
+    /// Promote a fact from local layer to global layer.
+    /// The fact is moved (removed from local, added to global).
+    ///
+    /// 将事实从局部层提升到全局层。
+    /// 事实被移动（从局部层移除，添加到全局层）。
+    pub fn promote_to_global(&mut self, key: &str) -> bool {
+        if !self.global_writable(key) {
+            return false;
+        }
+        if let Some(value) = self.local.remove(key) {
+            self.global.set(key, value);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Copy a fact from local layer to global layer (keeping both copies).
+    ///
+    /// 将事实从局部层复制到全局层（保留两份副本）。
+    pub fn copy_to_global(&mut self, key: &str) -> bool {
+        if !self.global_writable(key) {
+            return false;
+        }
+        if let Some(value) = self.local.get_by_str(key).cloned() {
+            self.global.set(key, value);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Demote a fact from global layer to local layer.
+    /// The fact is moved (removed from global, added to local).
+    ///
+    /// 将事实从全局层降级到局部层。
+    /// 事实被移动（从全局层移除，添加到局部层）。
+    pub fn demote_to_local(&mut self, key: &str) -> bool {
+        if !self.global_writable(key) {
+            return false;
+        }
+        if let Some(value) = self.global.remove(key) {
+            self.local.set(key, value);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promote_to_global() {
+        let mut db = LayeredFactDatabase::new();
+
+        db.set_local("temp_score", 42i64);
+        assert!(db.contains_local("temp_score"));
+        assert!(!db.contains_global("temp_score"));
+
+        db.promote_to_global("temp_score");
+        assert!(!db.contains_local("temp_score"));
+        assert!(db.contains_global("temp_score"));
+        assert_eq!(db.get_int("temp_score"), Some(42));
+    }
+
+    #[test]
+    fn test_copy_to_global() {
+        let mut db = LayeredFactDatabase::new();
+
+        db.set_local("data", "important");
+        assert!(db.copy_to_global("data"));
+
+        // Both layers now have the value
+        assert!(db.contains_local("data"));
+        assert!(db.contains_global("data"));
+        assert_eq!(db.local().get_string("data"), Some("important"));
+        assert_eq!(db.global().get_string("data"), Some("important"));
+    }
+
+    #[test]
+    fn test_copy_to_global_nonexistent() {
+        let mut db = LayeredFactDatabase::new();
+        assert!(!db.copy_to_global("nonexistent"));
+    }
+
+    #[test]
+    fn test_demote_to_local() {
+        let mut db = LayeredFactDatabase::new();
+
+        db.set_global("global_data", 100i64);
+        assert!(db.demote_to_local("global_data"));
+
+        assert!(!db.contains_global("global_data"));
+        assert!(db.contains_local("global_data"));
+        assert_eq!(db.get_int("global_data"), Some(100));
+    }
+
+    #[test]
+    fn test_demote_to_local_nonexistent() {
+        let mut db = LayeredFactDatabase::new();
+        assert!(!db.demote_to_local("nonexistent"));
+    }
+
+    #[test]
+    fn test_promote_to_global_nonexistent() {
+        let mut db = LayeredFactDatabase::new();
+        assert!(!db.promote_to_global("nonexistent"));
+    }
+}
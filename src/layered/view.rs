@@ -0,0 +1,283 @@
+//! # view.rs
+//!
+//! # view.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Per-view fact tiers, the fact counterpart of the View layer of `LayeredRuleRegistry`.
+//! State that belongs to one open view, such as its `selection`, is stored under the view's
+//! `Entity` instead of the shared local layer, so two views never overwrite each other.
+//! `get_for_view` resolves the view's tier, then local, then global, then the defaults, and
+//! `for_view` wraps that resolution as a `FactReader`; `process_rules_system` evaluates the
+//! conditions of rules triggered by an event with an entity through it. `set_active_view`
+//! makes one view's tier take part in every read, for code that only deals with the focused
+//! view; an explicitly named view replaces the active one. View tiers sit outside the layers:
+//! writes, iteration, counts, snapshots and change tracking do not include them.
+//! `prune_despawned_view_facts_system` drops the tier of a despawned view.
+//!
+//! 每个视图的事实层，是 `LayeredRuleRegistry` 视图层在事实上的对应物。
+//! 属于某个打开视图的状态（如其 `selection`）存储在该视图的 `Entity` 下，而不是共享的局部层，
+//! 因此两个视图永远不会互相覆盖。`get_for_view` 依次解析视图层、局部层、全局层和默认值，
+//! `for_view` 把这种解析包装为 `FactReader`；`process_rules_system` 通过它评估由带实体事件触发的规则的条件。
+//! `set_active_view` 让某个视图的层参与每次读取，供只处理当前焦点视图的代码使用；
+//! 显式指定的视图会取代活动视图。视图层位于各层之外：写入、迭代、计数、快照和变更跟踪都不包含它们。
+//! `prune_despawned_view_facts_system` 会丢弃已销毁视图的事实层。
+
+use bevy::ecs::entity::Entities;
+use bevy::prelude::*;
+
+use super::{LayeredFactDatabase, admitted};
+use crate::database::{FactDatabase, FactLayer, FactReader, FactValue};
+
+impl LayeredFactDatabase {
+    /// Set a fact in the tier of `entity`, creating the tier if needed.
+    ///
+    /// 在 `entity` 的视图层中设置事实，必要时创建该层。
+    pub fn set_view(
+        &mut self,
+        entity: Entity,
+        key: impl Into<String>,
+        value: impl Into<FactValue>,
+    ) {
+        let key = self.aliases.resolve_write_owned(key.into());
+        if let Some(value) = admitted(&self.schema, &key, value.into()) {
+            self.view.entry(entity).or_default().set(key, value);
+        }
+    }
+
+    /// Get a fact from the tier of `entity` only.
+    ///
+    /// 仅从 `entity` 的视图层获取事实。
+    pub fn get_view(&self, entity: Entity, key: &str) -> Option<&FactValue> {
+        self.view
+            .get(&entity)?
+            .get_by_str(self.aliases.resolve(key))
+    }
+
+    /// Get a fact as seen by the view `entity`: its tier first, then local, global and the
+    /// defaults.
+    ///
+    /// 获取视图 `entity` 所看到的事实：先查其视图层，然后是局部层、全局层和默认值。
+    pub fn get_for_view(&self, entity: Entity, key: &str) -> Option<&FactValue> {
+        self.get_view(entity, key)
+            .or_else(|| self.get_in_layers(self.aliases.resolve(key)))
+    }
+
+    /// Make the tier of `entity` the first place every read looks, or stop doing so with None.
+    /// Writes still go to the layers; use `set_view` to write to a tier.
+    ///
+    /// 让 `entity` 的视图层成为每次读取首先查找的位置，传入 None 则取消。
+    /// 写入仍然进入各层；写入视图层请使用 `set_view`。
+    pub fn set_active_view(&mut self, entity: Option<Entity>) {
+        self.active_view = entity;
+    }
+
+    pub fn active_view(&self) -> Option<Entity> {
+        self.active_view
+    }
+
+    /// The active view's value for an already resolved key.
+    pub(super) fn get_active_view(&self, key: &str) -> Option<&FactValue> {
+        self.view.get(&self.active_view?)?.get_by_str(key)
+    }
+
+    /// Remove the tier of `entity` with all its facts.
+    ///
+    /// 移除 `entity` 的视图层及其所有事实。
+    pub fn clear_view(&mut self, entity: Entity) {
+        self.view.remove(&entity);
+    }
+
+    /// The fact tier of `entity`, if it has one.
+    ///
+    /// `entity` 的视图事实层（如果有）。
+    pub fn view_facts(&self, entity: Entity) -> Option<&FactDatabase> {
+        self.view.get(&entity)
+    }
+
+    /// Reader resolving facts as `get_for_view` does. Without an entity it reads like the
+    /// database itself, active view included.
+    ///
+    /// 以 `get_for_view` 的方式解析事实的读取器。没有实体时，其读取与数据库本身相同（包括活动视图）。
+    pub fn for_view(&self, entity: Option<Entity>) -> ViewFacts<'_> {
+        ViewFacts { db: self, entity }
+    }
+}
+
+/// Facts as seen by one view: its tier over the layered facts.
+///
+/// 某个视图所看到的事实：其视图层叠加在分层事实之上。
+pub struct ViewFacts<'a> {
+    db: &'a LayeredFactDatabase,
+    entity: Option<Entity>,
+}
+
+impl FactReader for ViewFacts<'_> {
+    fn get_by_str(&self, key: &str) -> Option<&FactValue> {
+        match self.entity {
+            Some(entity) => self.db.get_for_view(entity, key),
+            None => self.db.get_by_str(key),
+        }
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        match self.entity {
+            Some(_) => self.get_by_str(key).is_some(),
+            None => self.db.contains_with_defaults(key),
+        }
+    }
+
+    fn layer(&self, layer: FactLayer) -> Option<&dyn FactReader> {
+        FactReader::layer(self.db, layer)
+    }
+
+    fn get_in_layer(&self, layer: FactLayer, key: &str) -> Option<&FactValue> {
+        FactReader::get_in_layer(self.db, layer, key)
+    }
+
+    fn changed_since_last_frame(&self, key: &str) -> Option<bool> {
+        self.db.changed_since_last_frame(key)
+    }
+}
+
+/// System that drops the fact tiers of views whose entity was despawned.
+///
+/// 丢弃实体已被销毁的视图事实层的系统。
+pub fn prune_despawned_view_facts_system(mut db: ResMut<LayeredFactDatabase>, entities: &Entities) {
+    let despawned: Vec<Entity> = db
+        .view
+        .keys()
+        .copied()
+        .filter(|entity| !entities.contains_spawned(*entity))
+        .collect();
+    for entity in despawned {
+        db.clear_view(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_tiers_are_isolated() {
+        let mut db = LayeredFactDatabase::new();
+        let (inventory, shop) = (Entity::from_bits(1), Entity::from_bits(2));
+        db.set_global("gold", 10i64);
+        db.set("selection", 0i64);
+        db.set_view(inventory, "selection", 3i64);
+        db.set_view(shop, "selection", 5i64);
+
+        assert_eq!(
+            db.get_for_view(inventory, "selection"),
+            Some(&FactValue::Int(3))
+        );
+        assert_eq!(db.get_for_view(shop, "selection"), Some(&FactValue::Int(5)));
+        assert_eq!(db.get_for_view(shop, "gold"), Some(&FactValue::Int(10)));
+        assert_eq!(db.get_view(shop, "gold"), None);
+        assert_eq!(db.get_int("selection"), Some(0));
+
+        let reader = db.for_view(Some(inventory));
+        assert_eq!(reader.get_int("selection"), Some(3));
+        assert!(reader.contains("gold"));
+        assert_eq!(db.for_view(None).get_int("selection"), Some(0));
+
+        db.clear_view(inventory);
+        assert_eq!(
+            db.get_for_view(inventory, "selection"),
+            Some(&FactValue::Int(0))
+        );
+        assert!(db.view_facts(shop).is_some());
+    }
+
+    #[test]
+    fn test_active_view_takes_part_in_reads() {
+        let mut db = LayeredFactDatabase::new();
+        let (inventory, shop) = (Entity::from_bits(1), Entity::from_bits(2));
+        db.set("cursor", 0i64);
+        db.set_view(inventory, "cursor", 3i64);
+        db.set_view(shop, "cursor", 5i64);
+        assert_eq!(db.get_int("cursor"), Some(0));
+
+        db.set_active_view(Some(inventory));
+        assert_eq!(db.active_view(), Some(inventory));
+        assert_eq!(db.get_int("cursor"), Some(3));
+        assert_eq!(FactReader::get_int(&db, "cursor"), Some(3));
+        // A named view replaces the active one
+        assert_eq!(db.get_for_view(shop, "cursor"), Some(&FactValue::Int(5)));
+        assert_eq!(db.for_view(Some(shop)).get_int("cursor"), Some(5));
+        assert_eq!(db.for_view(None).get_int("cursor"), Some(3));
+
+        // Writes still go to the local layer
+        db.set("cursor", 1i64);
+        assert_eq!(db.get_int("cursor"), Some(3));
+        assert_eq!(db.local().get_int("cursor"), Some(1));
+
+        db.set_view(inventory, "filter", "potions");
+        assert!(db.contains("filter"));
+        db.clear_view(inventory);
+        assert!(!db.contains("filter"));
+        assert_eq!(db.get_int("cursor"), Some(1));
+        db.set_active_view(None);
+        assert_eq!(db.get_for_view(shop, "cursor"), Some(&FactValue::Int(5)));
+    }
+
+    #[test]
+    fn test_rules_read_the_triggering_view() {
+        use crate::asset::{CoreActionDef, EnumRegistry};
+        use crate::event::FactEvent;
+        use crate::rule::{FreRng, LayeredRuleRegistry, Rule};
+        use crate::systems::{ConditionEvaluator, LiveSink, PendingFactEvents, process_event};
+
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(
+            Rule::builder("buy", "confirm")
+                .condition_expr("float($selection) > 2.0")
+                .output("bought")
+                .build(),
+        );
+        let mut db = LayeredFactDatabase::new();
+        let (inventory, shop) = (Entity::from_bits(1), Entity::from_bits(2));
+        db.set("selection", 0i64);
+        db.set_view(inventory, "selection", 1i64);
+        db.set_view(shop, "selection", 4i64);
+
+        let mut fires = |event: FactEvent| {
+            let mut pending = PendingFactEvents::default();
+            let mut sink = LiveSink {
+                pending: &mut pending,
+                db: &mut db,
+            };
+            let evaluator = ConditionEvaluator::default();
+            let enums = EnumRegistry::default();
+            let mut rng = FreRng::seeded(0);
+            process_event(&event, &registry, &mut sink, &evaluator, &enums, &mut rng);
+            !pending.events.is_empty()
+        };
+        assert!(fires(FactEvent::with_entity("confirm", shop)));
+        assert!(!fires(FactEvent::with_entity("confirm", inventory)));
+        assert!(!fires(FactEvent::new("confirm")));
+    }
+
+    #[test]
+    fn test_despawned_views_are_pruned() {
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .add_systems(Update, prune_despawned_view_facts_system);
+        let (open, closed) = (
+            app.world_mut().spawn_empty().id(),
+            app.world_mut().spawn_empty().id(),
+        );
+        let mut db = app.world_mut().resource_mut::<LayeredFactDatabase>();
+        db.set_view(open, "selection", 1i64);
+        db.set_view(closed, "selection", 2i64);
+        app.world_mut().despawn(closed);
+        app.update();
+
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert!(db.view_facts(open).is_some());
+        assert!(db.view_facts(closed).is_none());
+    }
+}
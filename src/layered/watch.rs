@@ -0,0 +1,191 @@
+//! # watch.rs
+//!
+//! # watch.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Fact watchers: events emitted when a fact's value changes, without writing a rule for it.
+//! `LayeredFactDatabase::watch(key, event_id)` remembers the fact's effective value; once per
+//! frame `emit_fact_watch_events_system` compares each watched fact with its remembered value
+//! and, when it differs (set to a new value, incremented, removed, ...), queues the event into
+//! `PendingFactEvents` so rules see it on the next frame. The event's data carries the previous
+//! and current values under `old` and `new`; a side where the fact is absent is left out.
+//!
+//! 事实监视器：在事实值变化时发出事件，无需为此编写规则。
+//! `LayeredFactDatabase::watch(key, event_id)` 记住事实的有效值；`emit_fact_watch_events_system`
+//! 每帧把每个被监视的事实与记住的值比较，若不同（设置为新值、增量、移除等），
+//! 就把事件放入 `PendingFactEvents`，使规则在下一帧看到它。事件数据在 `old` 和 `new`
+//! 下携带之前和当前的值；事实不存在的一侧会被省略。
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use super::LayeredFactDatabase;
+use crate::database::FactValue;
+use crate::event::{FactEvent, FactEventId};
+use crate::systems::PendingFactEvents;
+
+/// A watched fact: the event to emit and the value last seen.
+#[derive(Debug, Clone)]
+struct Watcher {
+    event_id: FactEventId,
+    last: Option<FactValue>,
+}
+
+/// Watched facts of a `LayeredFactDatabase`, by key.
+///
+/// `LayeredFactDatabase` 中按键记录的被监视事实。
+#[derive(Debug, Clone, Default)]
+pub struct FactWatchers {
+    watchers: HashMap<String, Watcher>,
+}
+
+impl FactWatchers {
+    pub fn len(&self) -> usize {
+        self.watchers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watchers.is_empty()
+    }
+
+    pub fn is_watched(&self, key: &str) -> bool {
+        self.watchers.contains_key(key)
+    }
+}
+
+impl LayeredFactDatabase {
+    /// Emit `event_id` whenever the effective value of `key` changes, replacing any earlier
+    /// watcher of the key. The current value is the baseline, so watching emits nothing.
+    ///
+    /// 每当 `key` 的有效值变化时发出 `event_id`，替换该键之前的监视器。
+    /// 当前值作为基准，因此开始监视本身不会发出事件。
+    pub fn watch(&mut self, key: impl Into<String>, event_id: impl Into<FactEventId>) {
+        let key = key.into();
+        let last = self.get_by_str(&key).cloned();
+        let watcher = Watcher {
+            event_id: event_id.into(),
+            last,
+        };
+        self.watchers.watchers.insert(key, watcher);
+    }
+
+    pub fn watchers(&self) -> &FactWatchers {
+        &self.watchers
+    }
+
+    /// Stop watching `key`. Returns whether it was watched.
+    ///
+    /// 停止监视 `key`。返回它之前是否被监视。
+    pub fn unwatch(&mut self, key: &str) -> bool {
+        self.watchers.watchers.remove(key).is_some()
+    }
+
+    /// Events for the watched facts whose value changed since the last call.
+    fn take_watch_events(&mut self) -> Vec<FactEvent> {
+        let mut watchers = std::mem::take(&mut self.watchers);
+        let mut events = Vec::new();
+        for (key, watcher) in &mut watchers.watchers {
+            let current = self.get_by_str(key);
+            if current == watcher.last.as_ref() {
+                continue;
+            }
+            let mut event = FactEvent::new(watcher.event_id.clone());
+            if let Some(old) = &watcher.last {
+                event = event.with_data("old", data_text(old));
+            }
+            if let Some(new) = current {
+                event = event.with_data("new", data_text(new));
+            }
+            watcher.last = current.cloned();
+            events.push(event);
+        }
+        self.watchers = watchers;
+        events
+    }
+}
+
+/// Text of a value in event data: scalars as plain text, other values as RON.
+fn data_text(value: &FactValue) -> String {
+    match value {
+        FactValue::Int(value) => value.to_string(),
+        FactValue::Float(value) => value.to_string(),
+        FactValue::Bool(value) => value.to_string(),
+        FactValue::String(value) => value.clone(),
+        other => ron::to_string(other).unwrap_or_default(),
+    }
+}
+
+/// System that queues the events of watched facts that changed.
+///
+/// 为发生变化的被监视事实排队事件的系统。
+pub fn emit_fact_watch_events_system(
+    mut db: ResMut<LayeredFactDatabase>,
+    mut pending: ResMut<PendingFactEvents>,
+) {
+    if db.watchers().is_empty() {
+        return;
+    }
+    pending.events.extend(db.take_watch_events());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{FactModification, LayeredRuleRegistry, Rule};
+    use crate::{CoreActionDef, FREPlugin};
+
+    #[test]
+    fn test_watched_increment_triggers_rule_next_frame() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            FREPlugin::<CoreActionDef>::default(),
+        ));
+        {
+            let mut db = app.world_mut().resource_mut::<LayeredFactDatabase>();
+            db.set("hp", 100i64);
+            db.watch("hp", "hp_changed");
+            db.watch("mp", "mp_changed");
+        }
+        {
+            let mut registry = app
+                .world_mut()
+                .resource_mut::<LayeredRuleRegistry<CoreActionDef>>();
+            registry.register(
+                Rule::builder("take_hit", "hit")
+                    .modify(FactModification::Increment("hp".to_string(), -10))
+                    .build(),
+            );
+            registry.register(
+                Rule::builder("flash_bar", "hp_changed")
+                    .condition_expr(r#"event.data.old == "100""#)
+                    .condition_expr(r#"event.data.new == "90""#)
+                    .modify(FactModification::Increment("flashes".to_string(), 1))
+                    .build(),
+            );
+        }
+
+        app.update();
+        app.world_mut().write_message(FactEvent::new("hit"));
+        app.update();
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert_eq!(db.get_int("hp"), Some(90));
+        assert_eq!(db.get_int("flashes"), None);
+
+        app.update();
+        let db = app.world().resource::<LayeredFactDatabase>();
+        assert_eq!(db.get_int("flashes"), Some(1));
+
+        // Nothing changed since, and mp was never set
+        app.update();
+        let mut db = app.world_mut().resource_mut::<LayeredFactDatabase>();
+        assert_eq!(db.get_int("flashes"), Some(1));
+        assert!(db.unwatch("hp"));
+        assert!(!db.unwatch("hp"));
+        assert_eq!(db.watchers().len(), 1);
+    }
+}
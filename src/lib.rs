@@ -31,29 +31,129 @@
 //! )
 //! ```
 
+mod action_dispatch;
+#[cfg(test)]
+mod alloc_count;
 pub mod asset;
+#[cfg(feature = "autosave")]
+mod autosave;
+mod containment;
+mod cooldown;
+mod crossing;
 mod database;
+mod dead_condition;
+mod debug_hooks;
+mod derived;
+mod entity_count;
 mod event;
+mod event_chain;
 pub mod expr;
+mod expr_condition;
+mod fact_comparison;
+mod float_condition;
+mod frame_change;
+mod frame_delay;
+mod from_facts;
+mod interval;
+mod key;
+mod layer_condition;
 mod layered;
+mod list_aggregate;
+mod named_condition;
+mod presence;
+mod query;
+mod replay;
 mod rule;
+mod schema;
+mod simulation;
+mod spatial;
+#[cfg(feature = "stream")]
+mod stream;
 mod systems;
 
 pub use asset::{
-    ActionDef, ActionEventKind, ActionHandlerRegistry, CoreActionDef, EnumRegistry,
-    FactModificationDef, FactValueDef, FreAsset, FreAssetLoader, LocalFactValue, RuleDef,
-    RuleEventDef, RuleScopeDef,
+    ActionDef, ActionEventKind, ActionHandlerRegistry, ActionHandlerSet, AssetFactLayer,
+    CoreActionDef, EnumRegistry, FactModificationDef, FactValueDef, FreAsset, FreAssetLoader,
+    FreAssetRules, FreLoadError, LocalFactValue, RuleDef, RuleEventDef, RuleScopeDef,
+    UnrepresentableValue, apply_loaded_fre_asset_facts_system, reload_modified_fre_assets_system,
+    validate_fre_file, validate_fre_str,
 };
 
-pub use database::{CombinedFactReader, FactDatabase, FactReader, FactValue};
-pub use event::{FactEvent, FactEventId};
-pub use layered::LayeredFactDatabase;
-pub use rule::{FactModification, LayeredRuleRegistry, Rule, RuleRegistry, RuleScope};
+pub use action_dispatch::ActionPhase;
+#[cfg(feature = "autosave")]
+pub use autosave::{GlobalAutosave, save_global_on_exit_system};
+#[cfg(feature = "derive")]
+pub use bevy_fact_rule_event_derive::FromFacts;
+pub use containment::ContainsCondition;
+pub use cooldown::{RuleCooldownState, prune_rule_cooldowns_system};
+pub use crossing::{
+    CROSSING_EVENT_PREFIX, CrossDirection, ThresholdTracker, crossing_event_id,
+    track_threshold_crossings_system,
+};
+pub use database::{
+    CombinedFactReader, FactChange, FactDatabase, FactHandle, FactLayer, FactReader, FactSnapshot,
+    FactValue, IntOverflowPolicy, MergeConflict, MergeError, MergePolicy,
+};
+pub use dead_condition::{is_trivially_false, is_trivially_true};
+pub use debug_hooks::{DebugAction, RuleDebugHooks, RuleDebugInfo, prune_rule_debug_hooks_system};
+pub use derived::{
+    DerivedFact, DerivedFactCycleError, DerivedFacts, recompute_derived_facts_system,
+};
+pub use entity_count::{
+    ENTITY_COUNT_PREFIX, EntityCountLessThan, EntityCountProviders, entity_count_key,
+    refresh_entity_counts_system,
+};
+#[doc(hidden)]
+pub use event::distinct_ids as __distinct_event_ids;
+pub use event::{
+    EVENT_DATA_PREFIX, EVENT_PAYLOAD_PREFIX, EventDataCondition, FactEvent, FactEventId,
+    PayloadListLenAtLeast,
+};
+pub use event_chain::{DEFAULT_MAX_EVENT_DEPTH, EventChainLimit};
+pub use expr_condition::{ExprConditionEvaluator, ExprLessThan};
+pub use fact_comparison::FactComparison;
+pub use float_condition::FloatCondition;
+pub use frame_change::{ChangedSinceLastFrame, snapshot_frame_start_system};
+pub use frame_delay::{FrameScheduledEvents, tick_frame_scheduled_events_system};
+pub use from_facts::{FromFactValue, FromFacts, FromFactsError, read_fact};
+pub use interval::{
+    DEFAULT_PAUSE_FACT, INTERVAL_EVENT_PREFIX, IntervalClock, interval_event_id,
+    tick_interval_rules_system,
+};
+pub use key::FactKey;
+pub use layer_condition::{InLayer, LayerCondition};
+pub use layered::{
+    AliasCycleError, FactChangedEvent, FactSaveError, FactWatchers, FreezePolicy, GlobalLoadMode,
+    InitialFacts, LayeredFactDatabase, LayeredFactSnapshot, TogglePolicy, ViewFacts,
+    clear_fact_changes_system, emit_fact_changed_events_system, emit_fact_watch_events_system,
+    prune_despawned_view_facts_system,
+};
+pub use list_aggregate::ListAggregateCondition;
+pub use named_condition::{NAMED_CONDITION_PREFIX, NamedConditions};
+pub use presence::PresenceCondition;
+pub use query::{ConditionReport, ConditionResult, FactQuery, FactQueryRegistry};
+pub use replay::replay;
+pub use rule::{
+    CATCH_ALL_TRIGGER, FactModification, FreRng, LayeredRuleRegistry, RegistrySnapshot, Rule,
+    RuleMut, RuleRegistry, RuleRegistryChangeKind, RuleRegistryChanged, RuleScope, RuleSnapshot,
+    SelectPolicy, ViewRulesSnapshot,
+};
+pub use schema::{FactSchema, FactType, SchemaPolicy, SchemaViolation};
+pub use simulation::{
+    ModificationOutcome, RuleMatch, RulePlan, RuleSimulation, SimulationReport, collect_event_plan,
+    simulate_event,
+};
+pub use spatial::DistanceLessThan;
+#[cfg(feature = "stream")]
+pub use stream::{RuleEventStream, RuleFireRecord};
 pub use systems::{ConditionEvaluator, ConditionEvaluatorTrait, PendingFactEvents};
+#[cfg(feature = "debug")]
+pub use systems::{DUMP_RULES_EVENT, dump_rule_registry_system};
 
 use bevy::asset::AssetApp;
 use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
 use bevy::prelude::*;
+use std::collections::HashMap;
 
 /// Convenient re-exports for common usage.
 ///
@@ -83,6 +183,25 @@ pub enum FRESystemSet {
 /// FRE 系统的主插件。
 pub struct FREPlugin<A: ActionDef = CoreActionDef> {
     pub schedule: Option<InternedScheduleLabel>,
+    /// Events deeper than this in a chain of rule outputs are dropped (default 32).
+    ///
+    /// 规则输出链中深度超过此值的事件会被丢弃（默认 32）。
+    pub max_event_depth: u32,
+    /// Re-register the rules of assets tracked by `FreAssetRules` when they are modified
+    /// (default true).
+    ///
+    /// 当 `FreAssetRules` 跟踪的资源被修改时重新注册其规则（默认 true）。
+    pub hot_reload: bool,
+    /// Layer that the declared facts of newly loaded `FreAsset`s are written to; None (the
+    /// default) leaves loading facts to the game.
+    ///
+    /// 新加载的 `FreAsset` 所声明事实写入的层；None（默认）表示由游戏自行加载事实。
+    pub asset_facts_layer: Option<FactLayer>,
+    /// Fallback values registered on the `LayeredFactDatabase` with `set_default` (default
+    /// empty).
+    ///
+    /// 通过 `set_default` 注册到 `LayeredFactDatabase` 的回退值（默认为空）。
+    pub fact_defaults: HashMap<String, FactValue>,
     _marker: std::marker::PhantomData<A>,
 }
 
@@ -90,6 +209,10 @@ impl<A: ActionDef> Default for FREPlugin<A> {
     fn default() -> Self {
         Self {
             schedule: None,
+            max_event_depth: DEFAULT_MAX_EVENT_DEPTH,
+            hot_reload: true,
+            asset_facts_layer: None,
+            fact_defaults: HashMap::new(),
             _marker: std::marker::PhantomData,
         }
     }
@@ -104,9 +227,22 @@ impl<A: ActionDef> Plugin for FREPlugin<A> {
             .init_resource::<EnumRegistry>()
             .init_resource::<PendingFactEvents>()
             .init_resource::<ConditionEvaluator>()
+            .init_resource::<FreRng>()
+            .init_resource::<IntervalClock>()
+            .init_resource::<ThresholdTracker>()
+            .init_resource::<FrameScheduledEvents>()
+            .init_resource::<EntityCountProviders>()
+            .init_resource::<FactQueryRegistry>()
+            .init_resource::<DerivedFacts>()
+            .init_resource::<RuleDebugHooks>()
+            .init_resource::<RuleCooldownState>()
+            .init_resource::<FreAssetRules<A>>()
+            .insert_resource(EventChainLimit::new(self.max_event_depth))
             .init_asset::<FreAsset<A>>()
             .register_asset_loader(FreAssetLoader::<A>::default())
             .add_message::<FactEvent>()
+            .add_message::<RuleRegistryChanged>()
+            .add_message::<FactChangedEvent>()
             .configure_sets(
                 schedule,
                 (FRESystemSet::EmitEvents, FRESystemSet::ProcessRules).chain(),
@@ -115,11 +251,67 @@ impl<A: ActionDef> Plugin for FREPlugin<A> {
                 schedule,
                 (
                     systems::emit_pending_events_system.in_set(FRESystemSet::EmitEvents),
+                    tick_interval_rules_system::<A>.in_set(FRESystemSet::EmitEvents),
+                    track_threshold_crossings_system::<A>.in_set(FRESystemSet::EmitEvents),
+                    tick_frame_scheduled_events_system.in_set(FRESystemSet::EmitEvents),
+                    systems::emit_rule_registry_changes_system::<A>
+                        .in_set(FRESystemSet::EmitEvents),
+                    prune_rule_debug_hooks_system.in_set(FRESystemSet::ProcessRules),
+                    prune_rule_cooldowns_system.in_set(FRESystemSet::ProcessRules),
+                    prune_despawned_view_facts_system.in_set(FRESystemSet::ProcessRules),
+                    refresh_entity_counts_system.in_set(FRESystemSet::ProcessRules),
                     systems::process_rules_system::<A>
                         .run_if(systems::has_fact_events)
                         .in_set(FRESystemSet::ProcessRules),
+                    recompute_derived_facts_system.in_set(FRESystemSet::ProcessRules),
+                    emit_fact_watch_events_system.in_set(FRESystemSet::ProcessRules),
+                    emit_fact_changed_events_system.in_set(FRESystemSet::ProcessRules),
                 )
                     .chain(),
             );
+
+        app.add_systems(First, snapshot_frame_start_system::<A>)
+            .add_systems(Last, clear_fact_changes_system);
+
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<A>>()
+            .set_change_tracking(true);
+        let mut db = app.world_mut().resource_mut::<LayeredFactDatabase>();
+        for (key, value) in &self.fact_defaults {
+            db.set_default(key.clone(), value.clone());
+        }
+
+        if self.hot_reload {
+            app.add_systems(
+                schedule,
+                reload_modified_fre_assets_system::<A>.in_set(FRESystemSet::EmitEvents),
+            );
+        }
+
+        if let Some(layer) = self.asset_facts_layer {
+            app.insert_resource(AssetFactLayer(layer)).add_systems(
+                schedule,
+                apply_loaded_fre_asset_facts_system::<A>.in_set(FRESystemSet::EmitEvents),
+            );
+        }
+
+        #[cfg(feature = "stream")]
+        app.init_resource::<RuleEventStream>();
+
+        #[cfg(feature = "autosave")]
+        app.add_systems(Last, save_global_on_exit_system);
+
+        #[cfg(feature = "debug")]
+        app.register_type::<FactValue>()
+            .register_type::<FactKey>()
+            .register_type::<FactDatabase>()
+            .register_type::<LayeredFactDatabase>()
+            .register_type::<RuleScope>()
+            .register_type::<FactEventId>()
+            .register_type::<FactEvent>()
+            .add_systems(
+                schedule,
+                systems::dump_rule_registry_system::<A>.in_set(FRESystemSet::ProcessRules),
+            );
     }
 }
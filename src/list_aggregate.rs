@@ -0,0 +1,127 @@
+//! # list_aggregate.rs
+//!
+//! Built-in conditions on the aggregate of an integer list fact.
+//! `int_list_sum($stats) > 100` passes when the elements of the `IntList` fact `stats` add up to
+//! more than 100, and `int_list_max($stats) > 40` when its largest element exceeds 40. The values
+//! come from `FactValue::int_list_sum` and `FactValue::int_list_max`, so an empty list sums to 0
+//! and has no maximum. A missing fact, or one that is not an integer list, fails the condition.
+//! Like the other built-ins, they are checked by `ConditionEvaluator` before the game's evaluator.
+//!
+//! 针对整数列表事实聚合值的内置条件。
+//! 当 `IntList` 事实 `stats` 的元素之和大于 100 时，`int_list_sum($stats) > 100` 通过；
+//! 当其最大元素大于 40 时，`int_list_max($stats) > 40` 通过。数值来自 `FactValue::int_list_sum`
+//! 和 `FactValue::int_list_max`，因此空列表之和为 0 且没有最大值。缺失的事实或不是整数列表的事实
+//! 会使条件失败。与其他内置条件一样，它们由 `ConditionEvaluator` 在游戏评估器之前检查。
+
+use crate::database::FactReader;
+
+/// A parsed built-in integer list aggregate condition.
+///
+/// 已解析的内置整数列表聚合条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListAggregateCondition<'a> {
+    /// `int_list_sum($key) > value`
+    SumGreaterThan(&'a str, i64),
+
+    /// `int_list_max($key) > value`; an empty list has no maximum and fails.
+    ///
+    /// `int_list_max($key) > value`；空列表没有最大值，条件失败。
+    MaxGreaterThan(&'a str, i64),
+}
+
+impl<'a> ListAggregateCondition<'a> {
+    /// Parse a condition expression, returning None if it is not a list aggregate condition.
+    ///
+    /// 解析条件表达式；如果不是列表聚合条件则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let condition = condition.trim();
+        if let Some(rest) = condition.strip_prefix("int_list_sum($") {
+            let (key, value) = comparison(rest)?;
+            return Some(ListAggregateCondition::SumGreaterThan(key, value));
+        }
+        let (key, value) = comparison(condition.strip_prefix("int_list_max($")?)?;
+        Some(ListAggregateCondition::MaxGreaterThan(key, value))
+    }
+
+    /// Whether the fact is an integer list whose aggregate exceeds the threshold.
+    ///
+    /// 事实是否为聚合值超过阈值的整数列表。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        match *self {
+            ListAggregateCondition::SumGreaterThan(key, threshold) => facts
+                .get_by_str(key)
+                .and_then(|value| value.int_list_sum())
+                .is_some_and(|sum| sum > threshold),
+            ListAggregateCondition::MaxGreaterThan(key, threshold) => facts
+                .get_by_str(key)
+                .and_then(|value| value.int_list_max())
+                .is_some_and(|max| max > threshold),
+        }
+    }
+}
+
+/// Key and threshold of `key) > value`.
+fn comparison(rest: &str) -> Option<(&str, i64)> {
+    let (key, comparison) = rest.split_once(')')?;
+    let value = comparison.trim().strip_prefix('>')?;
+    Some((key.trim(), value.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::EnumRegistry;
+    use crate::database::FactValue;
+    use crate::layered::LayeredFactDatabase;
+    use crate::systems::ConditionEvaluator;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ListAggregateCondition::parse("int_list_sum($stats) > 100"),
+            Some(ListAggregateCondition::SumGreaterThan("stats", 100))
+        );
+        assert_eq!(
+            ListAggregateCondition::parse("int_list_max($stats)>-1"),
+            Some(ListAggregateCondition::MaxGreaterThan("stats", -1))
+        );
+        assert_eq!(
+            ListAggregateCondition::parse("int_list_sum(stats) > 1"),
+            None
+        );
+        assert_eq!(
+            ListAggregateCondition::parse("int_list_sum($stats) < 1"),
+            None
+        );
+        assert_eq!(
+            ListAggregateCondition::parse("int_list_max($stats) > 1.5"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_aggregates_gate_conditions() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("stats", vec![40i64, 50, 25]);
+        db.set("empty", FactValue::IntList(Vec::new()));
+        db.set("weights", FactValue::FloatList(vec![60.0, 70.0]));
+        let passes = |condition: &str| {
+            ConditionEvaluator::default().evaluate_conditions(
+                &[condition.to_string()],
+                &db,
+                &EnumRegistry::default(),
+            )
+        };
+
+        assert!(passes("int_list_sum($stats) > 100"));
+        assert!(!passes("int_list_sum($stats) > 115"));
+        assert!(passes("int_list_max($stats) > 49"));
+        assert!(!passes("int_list_max($stats) > 50"));
+        // An empty list sums to 0 and has no maximum
+        assert!(passes("int_list_sum($empty) > -1"));
+        assert!(!passes("int_list_max($empty) > -1"));
+        // Missing facts and other list types fail
+        assert!(!passes("int_list_sum($weights) > 0"));
+        assert!(!passes("int_list_max($missing) > 0"));
+    }
+}
@@ -0,0 +1,192 @@
+//! # named_condition.rs
+//!
+//! Reusable condition snippets referenced by name.
+//! A condition written as `@can_act` stands for the condition list registered under `can_act`
+//! in `NamedConditions`, so many rules can share one gate and follow it when it is redefined.
+//! Named conditions may reference other named conditions. A reference to an unknown name, or
+//! one that leads back to itself, is logged and makes the condition list fail.
+//!
+//! 按名称引用的可复用条件片段。
+//! 写作 `@can_act` 的条件代表在 `NamedConditions` 中以 `can_act` 注册的条件列表，
+//! 因此许多规则可以共享同一个门控条件，并在其被重新定义时随之更新。
+//! 具名条件可以引用其他具名条件。引用未知名称或引用回自身的条件会被记录，并使条件列表失败。
+
+use bevy::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Prefix marking a reference to a named condition.
+///
+/// 标记具名条件引用的前缀。
+pub const NAMED_CONDITION_PREFIX: &str = "@";
+
+/// Condition lists registered by name. Owned by `ConditionEvaluator`, which resolves
+/// references before evaluating.
+///
+/// 按名称注册的条件列表。由 `ConditionEvaluator` 持有，并在评估前解析引用。
+#[derive(Debug, Clone, Default)]
+pub struct NamedConditions {
+    conditions: HashMap<String, Vec<String>>,
+}
+
+impl NamedConditions {
+    /// Define (or redefine) a named condition. All conditions in the list must pass.
+    ///
+    /// 定义（或重新定义）具名条件。列表中的所有条件都必须通过。
+    pub fn define<S: Into<String>>(
+        &mut self,
+        name: impl Into<String>,
+        conditions: impl IntoIterator<Item = S>,
+    ) {
+        self.conditions.insert(
+            name.into(),
+            conditions.into_iter().map(Into::into).collect(),
+        );
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Vec<String>> {
+        self.conditions.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.conditions.get(name).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.conditions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Replace every `@name` reference with the conditions it stands for.
+    /// Returns None, after logging a warning, if a name is unknown or forms a cycle.
+    ///
+    /// 把每个 `@name` 引用替换为它代表的条件。
+    /// 如果名称未知或形成环，则记录警告并返回 None。
+    pub fn expand<'a>(&self, conditions: &'a [String]) -> Option<Cow<'a, [String]>> {
+        if !conditions
+            .iter()
+            .any(|condition| reference(condition).is_some())
+        {
+            return Some(Cow::Borrowed(conditions));
+        }
+        let mut expanded = Vec::with_capacity(conditions.len());
+        self.expand_into(conditions, &mut Vec::new(), &mut expanded)?;
+        Some(Cow::Owned(expanded))
+    }
+
+    fn expand_into<'a>(
+        &'a self,
+        conditions: &'a [String],
+        visiting: &mut Vec<&'a str>,
+        expanded: &mut Vec<String>,
+    ) -> Option<()> {
+        for condition in conditions {
+            let Some(name) = reference(condition) else {
+                expanded.push(condition.clone());
+                continue;
+            };
+            if visiting.contains(&name) {
+                warn!("FRE: Named condition '@{}' forms a cycle", name);
+                return None;
+            }
+            let Some(inner) = self.conditions.get(name) else {
+                warn!("FRE: Unknown named condition '@{}'", name);
+                return None;
+            };
+            visiting.push(name);
+            self.expand_into(inner, visiting, expanded)?;
+            visiting.pop();
+        }
+        Some(())
+    }
+}
+
+/// Name referenced by a condition, if it is a named condition reference.
+pub(crate) fn reference(condition: &str) -> Option<&str> {
+    condition
+        .trim()
+        .strip_prefix(NAMED_CONDITION_PREFIX)
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry, FreAsset};
+    use crate::event::FactEvent;
+    use crate::expr_condition::ExprConditionEvaluator;
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::{FreRng, LayeredRuleRegistry};
+    use crate::systems::{ConditionEvaluator, LiveSink, PendingFactEvents, process_event_rules};
+
+    fn fired(registry: &LayeredRuleRegistry, evaluator: &ConditionEvaluator) -> Vec<String> {
+        let event = FactEvent::new("tick");
+        let mut pending = PendingFactEvents::default();
+        let mut db = LayeredFactDatabase::new();
+        db.set("alive", true);
+        db.set("stunned", false);
+        process_event_rules(
+            &event,
+            registry.get_matching_rules_grouped(&event),
+            &mut LiveSink {
+                pending: &mut pending,
+                db: &mut db,
+            },
+            evaluator,
+            &EnumRegistry::default(),
+            &mut FreRng::seeded(0),
+        );
+        pending.events.iter().map(|e| e.id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rules_share_a_named_condition() {
+        let asset: FreAsset = ron::from_str(
+            r#"(
+                named_conditions: {"can_act": ["$alive"]},
+                rules: [
+                    (id: "attack", event: Event("tick"), conditions: ["@can_act"], outputs: ["attacked"], consume_event: false),
+                    (id: "move", event: Event("tick"), conditions: ["@can_act"], outputs: ["moved"], consume_event: false),
+                ],
+            )"#,
+        )
+        .unwrap();
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        asset.register_rules_layered(&mut registry);
+        let mut evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        asset.register_named_conditions(evaluator.named_conditions_mut());
+
+        let mut outputs = fired(&registry, &evaluator);
+        outputs.sort();
+        assert_eq!(outputs, vec!["attacked", "moved"]);
+
+        // Redefining the gate affects both rules
+        evaluator
+            .named_conditions_mut()
+            .define("can_act", ["$alive", "$stunned"]);
+        assert!(fired(&registry, &evaluator).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_and_cyclic_names_fail() {
+        let mut named = NamedConditions::default();
+        named.define("a", ["@b"]);
+        named.define("b", ["x", "@a"]);
+        named.define("c", ["@b_alias"]);
+        named.define("ok", ["x", "y"]);
+
+        let refs = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert!(named.expand(&refs(&["@a"])).is_none());
+        assert!(named.expand(&refs(&["@c"])).is_none());
+        assert_eq!(
+            named.expand(&refs(&["z", "@ok"])).unwrap().as_ref(),
+            refs(&["z", "x", "y"]).as_slice()
+        );
+        // Shared references that are not cycles are fine
+        named.define("twice", ["@ok", "@ok"]);
+        assert_eq!(named.expand(&refs(&["@twice"])).unwrap().len(), 4);
+    }
+}
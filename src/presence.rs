@@ -0,0 +1,106 @@
+//! # presence.rs
+//!
+//! Built-in conditions on whether a fact is present, and whether it was explicitly cleared.
+//! `exists($quest)` passes when the fact `quest` is stored, whatever its value, including
+//! `FactValue::Null`. `is_null($quest)` passes only when the fact is stored as `Null`, so rules
+//! can tell a flag that was cleared apart from one that was never created. Like the other
+//! built-ins, they are checked by `ConditionEvaluator` before the game's evaluator.
+//!
+//! 关于事实是否存在以及是否被显式清空的内置条件。
+//! 只要事实 `quest` 已存储（无论其值为何，包括 `FactValue::Null`），`exists($quest)` 就通过。
+//! `is_null($quest)` 仅在事实存储为 `Null` 时通过，因此规则可以区分被清空的标志和从未创建的标志。
+//! 与其他内置条件一样，它们由 `ConditionEvaluator` 在游戏评估器之前检查。
+
+use crate::database::FactReader;
+
+/// A parsed built-in presence condition.
+///
+/// 已解析的内置存在性条件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresenceCondition<'a> {
+    /// `exists($key)`: the fact is stored, possibly as `Null`.
+    ///
+    /// `exists($key)`：事实已存储，可能为 `Null`。
+    Exists(&'a str),
+
+    /// `is_null($key)`: the fact is stored as `Null`.
+    ///
+    /// `is_null($key)`：事实存储为 `Null`。
+    IsNull(&'a str),
+}
+
+impl<'a> PresenceCondition<'a> {
+    /// Parse a condition expression, returning None if it is not a presence condition.
+    ///
+    /// 解析条件表达式；如果不是存在性条件则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let condition = condition.trim();
+        if let Some(args) = condition.strip_prefix("exists(") {
+            return Some(PresenceCondition::Exists(key(args)?));
+        }
+        let args = condition.strip_prefix("is_null(")?;
+        Some(PresenceCondition::IsNull(key(args)?))
+    }
+
+    /// Whether the facts satisfy the condition.
+    ///
+    /// 事实是否满足条件。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        match *self {
+            PresenceCondition::Exists(key) => facts.contains(key),
+            PresenceCondition::IsNull(key) => facts.get_by_str(key).is_some_and(|v| v.is_null()),
+        }
+    }
+}
+
+/// Key of `$key)`.
+fn key(args: &str) -> Option<&str> {
+    args.strip_suffix(')')?.trim().strip_prefix('$')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{EnumRegistry, FactModificationDef, FactValueDef};
+    use crate::database::FactValue;
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::FactModification;
+    use crate::systems::ConditionEvaluator;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            PresenceCondition::parse("exists($quest)"),
+            Some(PresenceCondition::Exists("quest"))
+        );
+        assert_eq!(
+            PresenceCondition::parse(" is_null( $quest ) "),
+            Some(PresenceCondition::IsNull("quest"))
+        );
+        assert_eq!(PresenceCondition::parse("is_null(quest)"), None);
+    }
+
+    #[test]
+    fn test_cleared_and_missing_facts_differ() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("quest", "started");
+        let modification: FactModificationDef = ron::from_str(r#"SetNull("quest")"#).unwrap();
+        FactModification::from(modification).apply(&mut db);
+        let default: FactValueDef = ron::from_str("Null").unwrap();
+        db.set("rival", FactValue::from(default));
+
+        let evaluator = ConditionEvaluator::default();
+        let passes = |db: &LayeredFactDatabase, condition: &str| {
+            evaluator.evaluate_conditions(&[condition.to_string()], db, &EnumRegistry::default())
+        };
+        assert!(passes(&db, "exists($quest)"));
+        assert!(passes(&db, "is_null($quest)"));
+        assert!(passes(&db, "is_null($rival)"));
+        assert!(!passes(&db, "exists($missing)"));
+        assert!(!passes(&db, "is_null($missing)"));
+        assert_eq!(db.get_bool("quest"), None);
+
+        db.set("quest", true);
+        assert!(!passes(&db, "is_null($quest)"));
+    }
+}
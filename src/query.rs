@@ -0,0 +1,249 @@
+//! # query.rs
+//!
+//! Reusable fact queries for game code outside the rule loop.
+//! A `FactQuery` holds the same condition expressions a rule would, so systems can ask
+//! "would these conditions pass right now?" without duplicating the checks in Rust.
+//! Named queries can be declared in `.fre.ron` assets and looked up in `FactQueryRegistry`.
+//!
+//! 供规则循环之外的游戏代码复用的事实查询。
+//! `FactQuery` 持有与规则相同的条件表达式，因此系统可以询问“这些条件现在是否成立？”，
+//! 而无需在 Rust 中重复这些检查。具名查询可以在 `.fre.ron` 资源中声明，并在 `FactQueryRegistry` 中查找。
+
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::asset::{ActionDef, EnumRegistry, FreAsset};
+use crate::database::FactReader;
+use crate::dead_condition::{is_trivially_false, is_trivially_true};
+use crate::expr::expr_variables;
+use crate::systems::ConditionEvaluator;
+
+/// A set of condition expressions that can be evaluated against any fact reader.
+///
+/// 可以针对任意事实读取器评估的一组条件表达式。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FactQuery {
+    conditions: Vec<String>,
+}
+
+/// Result of evaluating a single condition of a query.
+///
+/// 评估查询中单个条件的结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionResult {
+    pub expression: String,
+    pub passed: bool,
+}
+
+/// Per-condition breakdown of a query evaluation.
+///
+/// 查询评估的逐条件明细。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionReport {
+    /// Whether the query as a whole passed.
+    ///
+    /// 查询整体是否通过。
+    pub passed: bool,
+    pub conditions: Vec<ConditionResult>,
+}
+
+impl ConditionReport {
+    /// Conditions that did not pass.
+    ///
+    /// 未通过的条件。
+    pub fn failed(&self) -> impl Iterator<Item = &ConditionResult> {
+        self.conditions.iter().filter(|result| !result.passed)
+    }
+}
+
+impl FactQuery {
+    /// Create a query from condition expressions, using the same syntax as rule conditions.
+    ///
+    /// 使用与规则条件相同的语法，从条件表达式创建查询。
+    pub fn from_conditions<S: Into<String>>(conditions: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            conditions: conditions.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Parse a query from RON: either a single expression string or a list of them.
+    ///
+    /// 从 RON 解析查询：单个表达式字符串或表达式字符串列表。
+    pub fn parse(ron_str: &str) -> Result<Self, ron::error::SpannedError> {
+        let conditions = match ron::from_str::<Vec<String>>(ron_str) {
+            Ok(conditions) => conditions,
+            Err(list_err) => vec![ron::from_str::<String>(ron_str).map_err(|_| list_err)?],
+        };
+        Ok(Self { conditions })
+    }
+
+    pub fn conditions(&self) -> &[String] {
+        &self.conditions
+    }
+
+    /// Whether all conditions pass. A query without conditions always matches.
+    ///
+    /// 所有条件是否都通过。没有条件的查询总是匹配。
+    pub fn matches(
+        &self,
+        facts: &dyn FactReader,
+        evaluator: &ConditionEvaluator,
+        enums: &EnumRegistry,
+    ) -> bool {
+        evaluator.evaluate_conditions(&self.conditions, facts, enums)
+    }
+
+    /// Whether the query can never match, as far as static analysis can tell
+    /// (see `is_trivially_false`).
+    ///
+    /// 就静态分析所能判断的范围而言，查询是否永远无法匹配（见 `is_trivially_false`）。
+    pub fn is_trivially_false(&self) -> bool {
+        is_trivially_false(&self.conditions)
+    }
+
+    /// Whether the query matches whatever the facts (see `is_trivially_true`).
+    ///
+    /// 无论事实如何，查询是否总是匹配（见 `is_trivially_true`）。
+    pub fn is_trivially_true(&self) -> bool {
+        is_trivially_true(&self.conditions)
+    }
+
+    /// Evaluate each condition on its own and report which ones pass.
+    ///
+    /// 单独评估每个条件，并报告哪些条件通过。
+    pub fn explain(
+        &self,
+        facts: &dyn FactReader,
+        evaluator: &ConditionEvaluator,
+        enums: &EnumRegistry,
+    ) -> ConditionReport {
+        let conditions = self
+            .conditions
+            .iter()
+            .map(|expression| ConditionResult {
+                expression: expression.clone(),
+                passed: evaluator.evaluate_conditions(
+                    std::slice::from_ref(expression),
+                    facts,
+                    enums,
+                ),
+            })
+            .collect();
+        ConditionReport {
+            passed: self.matches(facts, evaluator, enums),
+            conditions,
+        }
+    }
+
+    /// Fact keys referenced as `$key` in the conditions, in order of first appearance.
+    ///
+    /// 条件中以 `$key` 形式引用的事实键，按首次出现的顺序排列。
+    pub fn referenced_keys(&self) -> Vec<&str> {
+        let mut keys = Vec::new();
+        for key in self.conditions.iter().flat_map(|c| expr_variables(c)) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+}
+
+/// Named queries that game code can look up instead of duplicating conditions.
+///
+/// 游戏代码可以按名称查找的查询，避免重复编写条件。
+#[derive(Resource, Debug, Clone, Default)]
+pub struct FactQueryRegistry {
+    queries: HashMap<String, FactQuery>,
+}
+
+impl FactQueryRegistry {
+    /// Register (or replace) a named query.
+    ///
+    /// 注册（或替换）一个具名查询。
+    pub fn register(&mut self, name: impl Into<String>, query: FactQuery) {
+        self.queries.insert(name.into(), query);
+    }
+
+    /// Register every query declared by an asset.
+    ///
+    /// 注册资源中声明的所有查询。
+    pub fn register_asset<A: ActionDef>(&mut self, asset: &FreAsset<A>) {
+        for (name, conditions) in &asset.queries {
+            self.register(name.clone(), FactQuery::from_conditions(conditions.clone()));
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FactQuery> {
+        self.queries.get(name)
+    }
+
+    /// Evaluate a named query. Unknown names do not match.
+    ///
+    /// 评估具名查询。未知名称不匹配。
+    pub fn matches(
+        &self,
+        name: &str,
+        facts: &dyn FactReader,
+        evaluator: &ConditionEvaluator,
+        enums: &EnumRegistry,
+    ) -> bool {
+        self.get(name)
+            .is_some_and(|query| query.matches(facts, evaluator, enums))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr_condition::ExprConditionEvaluator;
+    use crate::layered::LayeredFactDatabase;
+
+    fn shop_db(gold: i64) -> LayeredFactDatabase {
+        let mut db = LayeredFactDatabase::new();
+        db.set("shop_open", true);
+        db.set("gold", gold);
+        db
+    }
+
+    #[test]
+    fn test_parse_and_match() {
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let query = FactQuery::parse(r#"["$shop_open", "$gold >= 100"]"#).unwrap();
+
+        assert!(query.matches(&shop_db(150), &evaluator, &enums));
+        assert!(!query.matches(&shop_db(50), &evaluator, &enums));
+        assert_eq!(query.referenced_keys(), vec!["shop_open", "gold"]);
+
+        let single = FactQuery::parse(r#""$gold >= 10""#).unwrap();
+        assert_eq!(single.conditions(), ["$gold >= 10"]);
+        assert!(FactQuery::parse("[1, 2").is_err());
+    }
+
+    #[test]
+    fn test_explain_reports_failed_conditions() {
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let query = FactQuery::from_conditions(["$shop_open", "$gold >= 100"]);
+        let report = query.explain(&shop_db(50), &evaluator, &EnumRegistry::default());
+
+        assert!(!report.passed);
+        let failed: Vec<_> = report.failed().map(|r| r.expression.as_str()).collect();
+        assert_eq!(failed, vec!["$gold >= 100"]);
+    }
+
+    #[test]
+    fn test_registry_lookup_from_asset() {
+        let asset: FreAsset =
+            ron::from_str(r#"(queries: { "can_afford_upgrade": ["$shop_open", "$gold >= 100"] })"#)
+                .unwrap();
+        let mut registry = FactQueryRegistry::default();
+        registry.register_asset(&asset);
+
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        assert!(registry.matches("can_afford_upgrade", &shop_db(100), &evaluator, &enums));
+        assert!(!registry.matches("can_afford_upgrade", &shop_db(99), &evaluator, &enums));
+        assert!(!registry.matches("missing", &shop_db(100), &evaluator, &enums));
+    }
+}
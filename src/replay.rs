@@ -0,0 +1,155 @@
+//! # replay.rs
+//!
+//! Deterministic replay of a recorded session, for reproducing bugs. `replay` restores the
+//! facts of a `LayeredFactSnapshot`, feeds the recorded external events through the same rule
+//! loop as `process_rules_system`, and returns a snapshot of the final state. Rule outputs in
+//! the log (events with a chain depth above 0) are skipped, since replaying regenerates them.
+//! Each external event is settled before the next one: its outputs are processed a wave at a
+//! time, one wave per frame of a live run, which is the order a live run follows whenever the
+//! recorded events were sent at least a chain's length of frames apart. Random rule selection
+//! draws from a clone of the given generator, so seeding it like the session reproduces the
+//! session's picks. Actions are not run, and cooldowns are not tracked.
+//!
+//! 对记录的会话进行确定性重放，用于复现缺陷。`replay` 恢复 `LayeredFactSnapshot` 中的事实，
+//! 让记录的外部事件经过与 `process_rules_system` 相同的规则循环，并返回最终状态的快照。
+//! 日志中的规则输出（事件链深度大于 0 的事件）会被跳过，因为重放会重新生成它们。
+//! 每个外部事件在下一个事件之前处理完毕：其输出逐波处理，每波对应实际运行中的一帧；
+//! 只要记录的事件之间至少相隔一条事件链长度的帧数，这就是实际运行所遵循的顺序。
+//! 随机规则选择从给定生成器的克隆中抽取，因此按会话的方式设置种子即可复现会话的选择。
+//! 动作不会被执行，冷却也不会被跟踪。
+
+use crate::asset::{ActionDef, EnumRegistry};
+use crate::event::FactEvent;
+use crate::event_chain::EventChainLimit;
+use crate::layered::{LayeredFactDatabase, LayeredFactSnapshot};
+use crate::rule::{FreRng, LayeredRuleRegistry};
+use crate::systems::{ConditionEvaluator, LiveSink, PendingFactEvents, process_event};
+
+/// Replay the external events of `events` against facts restored from `initial`, returning
+/// the final state. The replay runs on a plain database, without a schema, aliases or
+/// defaults, and drops output chains deeper than the default `EventChainLimit`.
+///
+/// 针对从 `initial` 恢复的事实重放 `events` 中的外部事件，并返回最终状态。
+/// 重放在没有模式、别名或默认值的普通数据库上运行，并丢弃深度超过默认 `EventChainLimit` 的输出链。
+pub fn replay<A: ActionDef>(
+    initial: &LayeredFactSnapshot,
+    events: &[FactEvent],
+    registry: &LayeredRuleRegistry<A>,
+    condition_evaluator: &ConditionEvaluator,
+    enum_registry: &EnumRegistry,
+    rng: &FreRng,
+) -> LayeredFactSnapshot {
+    let mut db = LayeredFactDatabase::new();
+    for (key, value) in initial.global.iter() {
+        db.global_mut().set(key.clone(), value.clone());
+    }
+    for (key, value) in initial.local.iter() {
+        db.local_mut().set(key.clone(), value.clone());
+    }
+    let mut rng = rng.clone();
+    let mut limit = EventChainLimit::default();
+
+    for event in events.iter().filter(|event| event.depth == 0) {
+        let mut wave = vec![event.clone()];
+        while !wave.is_empty() {
+            // A fresh queue per wave, as outputs are only deduplicated within a frame
+            let mut pending = PendingFactEvents::default();
+            for event in wave.iter().filter(|event| limit.admits(event)) {
+                let mut sink = LiveSink {
+                    pending: &mut pending,
+                    db: &mut db,
+                };
+                process_event(
+                    event,
+                    registry,
+                    &mut sink,
+                    condition_evaluator,
+                    enum_registry,
+                    &mut rng,
+                );
+            }
+            wave = pending.events;
+        }
+    }
+    db.snapshot_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CoreActionDef;
+    use crate::rule::{FactModification, Rule, SelectPolicy};
+    use crate::{FREPlugin, FRESystemSet};
+    use bevy::prelude::*;
+
+    #[derive(Resource, Default)]
+    struct EventLog(Vec<FactEvent>);
+
+    fn record_events(mut events: MessageReader<FactEvent>, mut log: ResMut<EventLog>) {
+        log.0.extend(events.read().cloned());
+    }
+
+    fn registry() -> LayeredRuleRegistry<CoreActionDef> {
+        let mut registry = LayeredRuleRegistry::new();
+        registry.register(
+            Rule::builder("swing", "attack")
+                .modify(FactModification::Increment("swings".to_string(), 1))
+                .output("hit")
+                .build(),
+        );
+        for (id, damage) in [("graze", 1), ("strike", 5), ("crit", 20)] {
+            registry.register(
+                Rule::builder(id, "hit")
+                    .selection(SelectPolicy::Random)
+                    .modify(FactModification::Increment("damage".to_string(), damage))
+                    .build(),
+            );
+        }
+        registry.register(
+            Rule::builder("rest", "rest")
+                .modify(FactModification::Set("damage".to_string(), 0i64.into()))
+                .build(),
+        );
+        registry
+    }
+
+    #[test]
+    fn test_replay_reproduces_recorded_session() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            FREPlugin::<CoreActionDef>::default(),
+        ))
+        .init_resource::<EventLog>()
+        .insert_resource(registry())
+        .insert_resource(FreRng::seeded(7))
+        .add_systems(Update, record_events.before(FRESystemSet::ProcessRules));
+        app.world_mut()
+            .resource_mut::<LayeredFactDatabase>()
+            .set_global("swings", 10i64);
+        let initial = app.world().resource::<LayeredFactDatabase>().snapshot_all();
+
+        // Each event settles before the next one is sent
+        for id in ["attack", "attack", "rest", "attack", "attack", "attack"] {
+            app.world_mut().write_message(FactEvent::new(id));
+            for _ in 0..3 {
+                app.update();
+            }
+        }
+        let live = app.world().resource::<LayeredFactDatabase>();
+        assert_eq!(live.get_int("swings"), Some(15));
+
+        let log = &app.world().resource::<EventLog>().0;
+        assert!(log.iter().any(|event| event.depth > 0));
+        let replayed = replay(
+            &initial,
+            log,
+            &registry(),
+            &ConditionEvaluator::default(),
+            &EnumRegistry::default(),
+            &FreRng::seeded(7),
+        );
+        assert_eq!(replayed, live.snapshot_all());
+    }
+}
@@ -7,17 +7,36 @@
 //! 规则包含触发器、条件（表达式）、修改和输出。
 
 use crate::asset::{ActionDef, CoreActionDef};
-use crate::database::FactValue;
 use crate::event::{FactEvent, FactEventId};
-use crate::expr;
 use crate::layered::LayeredFactDatabase;
+use crate::schema::SchemaViolation;
+use crate::systems::CompiledConditions;
 use bevy::prelude::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
 
+mod enabled_snapshot;
 mod layered_registry;
+mod modification;
 mod registry;
+mod registry_events;
+mod selection;
+mod snapshot;
 
 pub use layered_registry::LayeredRuleRegistry;
-pub use registry::RuleRegistry;
+pub use modification::FactModification;
+pub use registry::{RuleMut, RuleRegistry};
+pub use registry_events::{RuleRegistryChangeKind, RuleRegistryChanged};
+pub use selection::{FreRng, SelectPolicy};
+pub(crate) use selection::{conflicting_policy, group_policy, resolve_highest_score, select_rule};
+pub use snapshot::{RegistrySnapshot, RuleSnapshot, ViewRulesSnapshot};
+
+/// Trigger of catch-all rules, which are considered for an event only when no rule
+/// registered for that event fired.
+///
+/// 兜底规则的触发器。只有当为某事件注册的规则都没有触发时，才会为该事件考虑兜底规则。
+pub const CATCH_ALL_TRIGGER: &str = "*";
 
 /// Rule scope - determines the lifetime and isolation of rules.
 ///
@@ -25,6 +44,7 @@ pub use registry::RuleRegistry;
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Deserialize, serde::Serialize,
 )]
+#[cfg_attr(feature = "debug", derive(Reflect), reflect(PartialEq, Hash))]
 pub enum RuleScope {
     /// Global rules - persist for the entire application lifetime.
     /// Examples: pause menu, debug commands, achievement triggers.
@@ -53,121 +73,6 @@ pub enum RuleScope {
     View,
 }
 
-/// Modification to apply to the fact database.
-///
-/// 应用于事实数据库的修改。
-#[derive(Clone, Debug)]
-pub enum FactModification {
-    /// Set a fact to a specific value.
-    ///
-    /// 将事实设置为特定值。
-    Set(String, FactValue),
-
-    /// Increment an integer fact by a whole-number value.
-    ///
-    /// 将整数事实增加指定的整数值。
-    Increment(String, i64),
-
-    /// Add a numeric value to a fact.
-    ///
-    /// 向事实添加数值。
-    Add(String, f64),
-
-    /// Subtract a numeric value from a fact.
-    ///
-    /// 从事实减去数值。
-    Sub(String, f64),
-
-    /// Multiply a fact by a numeric value.
-    ///
-    /// 将事实乘以数值。
-    Mul(String, f64),
-
-    /// Divide a fact by a numeric value.
-    ///
-    /// 将事实除以数值。
-    Div(String, f64),
-
-    /// Apply modulo operation to a fact.
-    ///
-    /// 对事实应用取模运算。
-    Mod(String, i64),
-
-    /// Clamp a fact value between min and max.
-    ///
-    /// 将事实值限制在 min 和 max 之间。
-    Clamp(String, f64, f64),
-
-    /// Wrap a fact value within a range [min, max).
-    ///
-    /// 将事实值包裹在范围 [min, max) 内。
-    Wrap(String, i64, i64),
-
-    /// Evaluate an expression and store the result in a fact.
-    ///
-    /// 评估表达式并将结果存储在事实中。
-    Eval(String, String),
-
-    /// Remove a fact.
-    ///
-    /// 移除一个事实。
-    Remove(String),
-
-    /// Toggle a boolean fact.
-    ///
-    /// 切换布尔事实。
-    Toggle(String),
-}
-
-impl FactModification {
-    /// Apply the modification to the layered fact database (local layer by default).
-    ///
-    /// 将修改应用于分层事实数据库（默认为局部层）。
-    pub fn apply(&self, db: &mut LayeredFactDatabase) {
-        match self {
-            FactModification::Set(key, value) => {
-                db.set_local(key.as_str(), value.clone());
-            }
-            FactModification::Increment(key, amount) => {
-                db.increment(key, *amount);
-            }
-            FactModification::Add(key, amount) => {
-                db.add(key, *amount);
-            }
-            FactModification::Sub(key, amount) => {
-                db.sub(key, *amount);
-            }
-            FactModification::Mul(key, factor) => {
-                db.mul(key, *factor);
-            }
-            FactModification::Div(key, divisor) => {
-                db.div(key, *divisor);
-            }
-            FactModification::Mod(key, divisor) => {
-                db.modulo(key, *divisor);
-            }
-            FactModification::Clamp(key, min, max) => {
-                db.clamp(key, *min, *max);
-            }
-            FactModification::Wrap(key, min, max) => {
-                db.wrap(key, *min, *max);
-            }
-            FactModification::Eval(key, expression) => {
-                if let Some(value) = expr::evaluate_expr_to_fact(expression, db) {
-                    db.set_local(key.as_str(), value);
-                }
-            }
-            FactModification::Remove(key) => {
-                db.remove(key);
-            }
-            FactModification::Toggle(key) => {
-                let current = db.get_bool(key).unwrap_or(false);
-                db.set_local(key.as_str(), !current);
-            }
-        }
-    }
-}
-
 /// A rule definition containing trigger, conditions (expressions), modifications, and outputs.
 ///
 /// 包含触发器、条件（表达式）、修改和输出的规则定义。
@@ -202,11 +107,27 @@ pub struct Rule<A: ActionDef = CoreActionDef> {
     /// 应用于事实数据库的修改。
     pub modifications: Vec<FactModification>,
 
+    /// Whether the modifications are applied all or nothing: if the schema rejects one of
+    /// them, the writes of the others are rolled back, in both layers.
+    ///
+    /// 修改是否以全有或全无的方式应用：如果模式拒绝其中一个修改，其他修改在两层中的写入都会被回滚。
+    pub transactional: bool,
+
     /// Events to emit after rule execution.
     ///
     /// 规则执行后要发出的事件。
     pub outputs: Vec<FactEventId>,
 
+    /// Outputs that opt out of inheriting the triggering event's entity and data.
+    ///
+    /// 不继承触发事件实体和数据的输出事件。
+    pub detached_outputs: HashSet<FactEventId>,
+
+    /// Whether outputs also carry the triggering event's data (entity is always carried).
+    ///
+    /// 输出事件是否也携带触发事件的数据（实体总是会被携带）。
+    pub forward_data: bool,
+
     /// Whether this rule is enabled.
     ///
     /// 此规则是否启用。
@@ -226,12 +147,56 @@ pub struct Rule<A: ActionDef = CoreActionDef> {
     /// 如果为 false，继续检查同一优先级组内的规则。
     pub consume_event: bool,
 
+    /// How this rule's priority group chooses among its passing rules.
+    /// All rules sharing a priority should declare the same policy.
+    ///
+    /// 此规则所在优先级组如何在通过的规则中进行选择。
+    /// 同一优先级的所有规则应声明相同的策略。
+    pub selection: SelectPolicy,
+
+    /// Relative weight for `SelectPolicy::WeightedRandom` (default 1.0).
+    ///
+    /// `SelectPolicy::WeightedRandom` 使用的相对权重（默认 1.0）。
+    pub weight: f32,
+
+    /// Expression scoring this rule for `SelectPolicy::HighestScore`, evaluated by `expr`.
+    /// Rules without a score, or whose score cannot be evaluated, rank below scored rules.
+    ///
+    /// `SelectPolicy::HighestScore` 使用的评分表达式，由 `expr` 评估。
+    /// 没有评分或评分无法评估的规则排在有评分的规则之后。
+    pub score_expression: Option<String>,
+
+    /// Minimum time between firings. While on cooldown the rule is skipped as if its
+    /// conditions failed.
+    ///
+    /// 两次触发之间的最短时间。冷却期间规则会被跳过，如同其条件未通过。
+    pub cooldown: Option<Duration>,
+
     /// Actions to execute when this rule fires.
     /// These are game-specific actions that are processed by the bridge layer.
     ///
     /// 当此规则触发时要执行的动作。
     /// 这些是由桥接层处理的游戏特定动作。
     pub actions: Vec<A>,
+
+    /// Where this rule came from (e.g. the asset path), if known.
+    ///
+    /// 此规则的来源（如资源路径），如果已知。
+    pub source: Option<String>,
+
+    /// Labels for toggling related rules together, e.g. `"tutorial"`.
+    ///
+    /// 用于一起切换相关规则的标签，例如 `"tutorial"`。
+    pub tags: Vec<String>,
+
+    /// Local facts owned by this rule, removed by `LayeredRuleRegistry::clear_local_with_facts`
+    /// when the rule's layer is cleared.
+    ///
+    /// 此规则拥有的局部事实；当规则所在层被清空时，由 `LayeredRuleRegistry::clear_local_with_facts` 移除。
+    pub owned_facts: Vec<String>,
+
+    /// Parsed form of `condition_expressions`, set by `compile_conditions`.
+    pub(crate) compiled: Option<Arc<CompiledConditions>>,
 }
 
 impl<A: ActionDef> Rule<A> {
@@ -248,6 +213,59 @@ impl<A: ActionDef> Rule<A> {
     pub fn matches_event(&self, event: &FactEvent) -> bool {
         self.enabled && self.trigger == event.id
     }
+
+    /// Build the event emitted for `output_id` when this rule fires on `trigger`.
+    /// Outputs carry the trigger's entity (and data, if `forward_data` is set) unless detached.
+    ///
+    /// 构建此规则因 `trigger` 触发时为 `output_id` 发出的事件。
+    /// 除非输出被分离，否则输出会携带触发事件的实体（若设置了 `forward_data` 还会携带数据）。
+    pub fn output_event(&self, output_id: &FactEventId, trigger: &FactEvent) -> FactEvent {
+        if self.detached_outputs.contains(output_id) {
+            let mut event = FactEvent::new(output_id.clone());
+            event.depth = trigger.depth;
+            return event;
+        }
+        trigger.derive(output_id.clone(), self.forward_data)
+    }
+
+    /// Parse the conditions once so evaluation skips re-parsing them. Returns whether the rule
+    /// was compiled: rules referencing `@name` conditions stay uncompiled.
+    ///
+    /// 一次性解析条件，使评估时无需重复解析。返回规则是否已编译：
+    /// 引用 `@name` 条件的规则保持未编译。
+    pub fn compile_conditions(&mut self) -> bool {
+        self.compiled = CompiledConditions::compile(&self.condition_expressions).map(Arc::new);
+        self.compiled.is_some()
+    }
+
+    pub fn is_compiled(&self) -> bool {
+        self.compiled.is_some()
+    }
+
+    /// Apply the rule's modifications to `db`. A transactional rule applies them all or none,
+    /// rolling back the global layer too if it writes there, and returns the violation that
+    /// rolled the batch back.
+    pub(crate) fn apply_modifications(
+        &self,
+        db: &mut LayeredFactDatabase,
+    ) -> Result<(), SchemaViolation> {
+        if !self.transactional {
+            for modification in &self.modifications {
+                modification.apply(db);
+            }
+            return Ok(());
+        }
+        let batch = |db: &mut LayeredFactDatabase| {
+            self.modifications
+                .iter()
+                .try_for_each(|modification| modification.try_apply(db))
+        };
+        if self.modifications.iter().any(FactModification::is_global) {
+            db.transaction_with_global(batch)
+        } else {
+            db.transaction(batch)
+        }
+    }
 }
 
 /// Builder for constructing rules.
@@ -259,11 +277,21 @@ pub struct RuleBuilder<A: ActionDef = CoreActionDef> {
     trigger: FactEventId,
     condition_expressions: Vec<String>,
     modifications: Vec<FactModification>,
+    transactional: bool,
     outputs: Vec<FactEventId>,
+    detached_outputs: HashSet<FactEventId>,
+    forward_data: bool,
     enabled: bool,
     priority: i32,
     consume_event: bool,
+    selection: SelectPolicy,
+    weight: f32,
+    score_expression: Option<String>,
+    cooldown: Option<Duration>,
     actions: Vec<A>,
+    source: Option<String>,
+    tags: Vec<String>,
+    owned_facts: Vec<String>,
 }
 
 impl<A: ActionDef> RuleBuilder<A> {
@@ -277,11 +305,21 @@ impl<A: ActionDef> RuleBuilder<A> {
             trigger: trigger.into(),
             condition_expressions: Vec::new(),
             modifications: Vec::new(),
+            transactional: false,
             outputs: Vec::new(),
+            detached_outputs: HashSet::new(),
+            forward_data: false,
             enabled: true,
             priority: 0,
             consume_event: true,
+            selection: SelectPolicy::default(),
+            weight: 1.0,
+            score_expression: None,
+            cooldown: None,
             actions: Vec::new(),
+            source: None,
+            tags: Vec::new(),
+            owned_facts: Vec::new(),
         }
     }
 
@@ -309,6 +347,14 @@ impl<A: ActionDef> RuleBuilder<A> {
         self
     }
 
+    /// Set whether this rule's modifications are applied all or nothing.
+    ///
+    /// 设置此规则的修改是否以全有或全无的方式应用。
+    pub fn transactional(mut self, transactional: bool) -> Self {
+        self.transactional = transactional;
+        self
+    }
+
     /// Add an output event to this rule.
     ///
     /// 向此规则添加输出事件。
@@ -317,6 +363,24 @@ impl<A: ActionDef> RuleBuilder<A> {
         self
     }
 
+    /// Add an output event that does not inherit the triggering event's entity or data.
+    ///
+    /// 添加一个不继承触发事件实体或数据的输出事件。
+    pub fn output_detached(mut self, event_id: impl Into<FactEventId>) -> Self {
+        let event_id = event_id.into();
+        self.detached_outputs.insert(event_id.clone());
+        self.outputs.push(event_id);
+        self
+    }
+
+    /// Set whether outputs carry the triggering event's data.
+    ///
+    /// 设置输出事件是否携带触发事件的数据。
+    pub fn forward_data(mut self, forward: bool) -> Self {
+        self.forward_data = forward;
+        self
+    }
+
     /// Set the priority of this rule.
     ///
     /// 设置此规则的优先级。
@@ -349,6 +413,62 @@ impl<A: ActionDef> RuleBuilder<A> {
         self
     }
 
+    /// Set the selection policy of this rule's priority group.
+    ///
+    /// 设置此规则所在优先级组的选择策略。
+    pub fn selection(mut self, selection: SelectPolicy) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Set the weight used by `SelectPolicy::WeightedRandom`.
+    ///
+    /// 设置 `SelectPolicy::WeightedRandom` 使用的权重。
+    pub fn weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Set the expression used by `SelectPolicy::HighestScore`.
+    ///
+    /// 设置 `SelectPolicy::HighestScore` 使用的评分表达式。
+    pub fn score(mut self, expr: impl Into<String>) -> Self {
+        self.score_expression = Some(expr.into());
+        self
+    }
+
+    /// Set the minimum time between firings of this rule.
+    ///
+    /// 设置此规则两次触发之间的最短时间。
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Set where this rule came from (e.g. an asset path).
+    ///
+    /// 设置此规则的来源（如资源路径）。
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Add a tag to this rule.
+    ///
+    /// 向此规则添加标签。
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Declare a local fact owned by this rule.
+    ///
+    /// 声明此规则拥有的局部事实。
+    pub fn owns_fact(mut self, key: impl Into<String>) -> Self {
+        self.owned_facts.push(key.into());
+        self
+    }
+
     /// Build the rule.
     ///
     /// 构建规则。
@@ -359,11 +479,22 @@ impl<A: ActionDef> RuleBuilder<A> {
             trigger: self.trigger,
             condition_expressions: self.condition_expressions,
             modifications: self.modifications,
+            transactional: self.transactional,
             outputs: self.outputs,
+            detached_outputs: self.detached_outputs,
+            forward_data: self.forward_data,
             enabled: self.enabled,
             priority: self.priority,
             consume_event: self.consume_event,
+            selection: self.selection,
+            weight: self.weight,
+            score_expression: self.score_expression,
+            cooldown: self.cooldown,
             actions: self.actions,
+            source: self.source,
+            tags: self.tags,
+            owned_facts: self.owned_facts,
+            compiled: None,
         }
     }
 }
@@ -372,6 +503,7 @@ impl<A: ActionDef> RuleBuilder<A> {
 mod tests {
     use super::*;
     use crate::asset::CoreActionDef;
+    use crate::database::FactValue;
 
     #[test]
     fn test_rule_builder() {
@@ -386,62 +518,12 @@ mod tests {
             .build();
 
         assert_eq!(rule.id, "test_rule");
-        assert_eq!(rule.trigger.0, "test_event");
+        assert_eq!(rule.trigger, "test_event");
         assert_eq!(rule.priority, 10);
         assert!(rule.enabled);
         assert_eq!(rule.condition_expressions, vec!["$counter == 3"]);
     }
 
-    #[test]
-    fn test_fact_modification_set() {
-        let mut db = LayeredFactDatabase::new();
-        let mod_set = FactModification::Set("key".to_string(), FactValue::Int(42));
-        mod_set.apply(&mut db);
-        assert_eq!(db.get_int("key"), Some(42));
-    }
-
-    #[test]
-    fn test_fact_modification_increment() {
-        let mut db = LayeredFactDatabase::new();
-        db.set("counter", 10i64);
-        let mod_inc = FactModification::Increment("counter".to_string(), 5);
-        mod_inc.apply(&mut db);
-        assert_eq!(db.get_int("counter"), Some(15));
-    }
-
-    #[test]
-    fn test_fact_modification_remove() {
-        let mut db = LayeredFactDatabase::new();
-        db.set("to_remove", 100i64);
-        assert!(db.contains("to_remove"));
-
-        let mod_remove = FactModification::Remove("to_remove".to_string());
-        mod_remove.apply(&mut db);
-        assert!(!db.contains_local("to_remove"));
-    }
-
-    #[test]
-    fn test_fact_modification_toggle() {
-        let mut db = LayeredFactDatabase::new();
-        db.set("flag", false);
-
-        let mod_toggle = FactModification::Toggle("flag".to_string());
-        mod_toggle.apply(&mut db);
-        assert_eq!(db.get_bool("flag"), Some(true));
-
-        mod_toggle.apply(&mut db);
-        assert_eq!(db.get_bool("flag"), Some(false));
-    }
-
-    #[test]
-    fn test_fact_modification_toggle_missing_key() {
-        let mut db = LayeredFactDatabase::new();
-        // Toggle on missing key should default to false, then toggle to true
-        let mod_toggle = FactModification::Toggle("missing".to_string());
-        mod_toggle.apply(&mut db);
-        assert_eq!(db.get_bool("missing"), Some(true));
-    }
-
     #[test]
     fn test_rule_registry_basic() {
         let mut registry = RuleRegistry::<CoreActionDef>::new();
@@ -540,4 +622,71 @@ mod tests {
         // Disabled rules should not match
         assert!(matching.is_empty());
     }
+
+    #[test]
+    fn test_rule_registry_set_enabled_by_trigger() {
+        let mut registry = RuleRegistry::<CoreActionDef>::new();
+        registry.register(Rule::builder("a1", "event_a").build());
+        registry.register(Rule::builder("a2", "event_a").build());
+        registry.register(Rule::builder("b1", "event_b").build());
+
+        let affected = registry.set_enabled_by_trigger(&FactEventId::new("event_a"), false);
+        assert_eq!(affected, 2);
+        assert!(!registry.get("a1").unwrap().enabled);
+        assert!(!registry.get("a2").unwrap().enabled);
+        assert!(registry.get("b1").unwrap().enabled);
+
+        assert_eq!(
+            registry.set_enabled_by_trigger(&FactEventId::new("missing"), false),
+            0
+        );
+    }
+
+    #[test]
+    fn test_rule_registry_set_enabled_by_tag() {
+        let mut registry = RuleRegistry::<CoreActionDef>::new();
+        registry.register(Rule::builder("hint", "tick").tag("tutorial").build());
+        registry.register(
+            Rule::builder("arrow", "tick")
+                .tag("tutorial")
+                .tag("ui")
+                .build(),
+        );
+        registry.register(Rule::builder("hud", "tick").tag("ui").build());
+        registry.register(Rule::builder("spawn", "tick").build());
+
+        assert_eq!(registry.set_enabled_by_tag("tutorial", false), 2);
+        assert!(!registry.get("hint").unwrap().enabled);
+        assert!(!registry.get("arrow").unwrap().enabled);
+        assert!(registry.get("hud").unwrap().enabled);
+        assert!(registry.get("spawn").unwrap().enabled);
+
+        assert_eq!(registry.set_enabled_by_tag("ui", true), 2);
+        assert!(registry.get("arrow").unwrap().enabled);
+        assert!(!registry.get("hint").unwrap().enabled);
+        assert_eq!(registry.set_enabled_by_tag("missing", false), 0);
+    }
+
+    #[test]
+    fn test_output_event_carries_trigger_context() {
+        let entity = Entity::from_bits(42);
+        let trigger = FactEvent::with_entity("hit", entity).with_data("damage", "3");
+
+        let rule = Rule::<CoreActionDef>::builder("on_hit", "hit")
+            .output("damaged")
+            .output_detached("play_sound")
+            .build();
+        let damaged = rule.output_event(&FactEventId::new("damaged"), &trigger);
+        assert_eq!(damaged.entity, Some(entity));
+        assert!(damaged.data().is_none());
+        let sound = rule.output_event(&FactEventId::new("play_sound"), &trigger);
+        assert_eq!(sound.entity, None);
+
+        let forwarding = Rule::<CoreActionDef>::builder("on_hit", "hit")
+            .output("damaged")
+            .forward_data(true)
+            .build();
+        let damaged = forwarding.output_event(&FactEventId::new("damaged"), &trigger);
+        assert_eq!(damaged.get_data("damage"), Some(&"3".to_string()));
+    }
 }
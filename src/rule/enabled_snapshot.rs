@@ -0,0 +1,89 @@
+//! # enabled_snapshot.rs
+//!
+//! # enabled_snapshot.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Capturing and restoring only the `enabled` flags of a `LayeredRuleRegistry`, so a debug menu
+//! that toggles many rules can offer "restore defaults". The snapshot is keyed by rule id across
+//! all layers; restoring goes through the same bulk toggle as the trigger-based helpers, so the
+//! change is reported as `EnabledChanged` messages for the rules that actually flipped.
+//!
+//! 仅捕获和恢复 `LayeredRuleRegistry` 的 `enabled` 标志，使切换大量规则的调试菜单可以提供
+//! "恢复默认"。快照跨所有层按规则 ID 索引；恢复时使用与基于触发器的辅助方法相同的批量切换，
+//! 因此只有真正翻转的规则会以 `EnabledChanged` 消息报告变化。
+
+use std::collections::HashMap;
+
+use super::{ActionDef, LayeredRuleRegistry};
+
+impl<A: ActionDef> LayeredRuleRegistry<A> {
+    /// The `enabled` flag of every rule in every layer, by rule id.
+    ///
+    /// 所有层中每条规则的 `enabled` 标志，按规则 ID 索引。
+    pub fn enabled_snapshot(&self) -> HashMap<String, bool> {
+        self.iter()
+            .map(|rule| (rule.id.clone(), rule.enabled))
+            .collect()
+    }
+
+    /// Restore the `enabled` flags captured by `enabled_snapshot`. Rules missing from the
+    /// snapshot are left unchanged. Returns the number of rules whose flag changed.
+    ///
+    /// 恢复 `enabled_snapshot` 捕获的 `enabled` 标志。快照中缺失的规则保持不变。
+    /// 返回标志发生变化的规则数量。
+    pub fn apply_enabled_snapshot(&mut self, snapshot: &HashMap<String, bool>) -> usize {
+        [true, false]
+            .into_iter()
+            .map(|enabled| {
+                self.set_enabled_where(enabled, None, |rule| {
+                    rule.enabled != enabled && snapshot.get(&rule.id) == Some(&enabled)
+                })
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CoreActionDef;
+    use crate::rule::{Rule, RuleScope};
+    use bevy::prelude::Entity;
+
+    #[test]
+    fn test_enabled_snapshot_restores_toggled_rules() {
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(
+            Rule::builder("pause", "menu")
+                .scope(RuleScope::Global)
+                .build(),
+        );
+        registry.register(Rule::builder("door", "interact").build());
+        registry.register(Rule::builder("trap", "step").enabled(false).build());
+        registry.register_view_rule(Entity::from_bits(42), Rule::builder("cursor", "up").build());
+        let defaults = registry.enabled_snapshot();
+        assert_eq!(defaults.len(), 4);
+
+        registry.set_enabled("pause", false);
+        registry.set_enabled("trap", true);
+        registry.set_enabled("cursor", false);
+        registry.register(Rule::builder("late", "tick").enabled(false).build());
+        registry.set_change_tracking(true);
+
+        assert_eq!(registry.apply_enabled_snapshot(&defaults), 3);
+        for (rule_id, enabled) in [
+            ("pause", true),
+            ("door", true),
+            ("trap", false),
+            ("cursor", true),
+            ("late", false),
+        ] {
+            assert_eq!(registry.get(rule_id).unwrap().enabled, enabled, "{rule_id}");
+        }
+        assert_eq!(registry.take_changes().len(), 2);
+        assert_eq!(registry.apply_enabled_snapshot(&defaults), 0);
+    }
+}
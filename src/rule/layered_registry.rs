@@ -14,11 +14,19 @@
 //! per-view 的规则注册表分开维护，这样调用方就能清理短生命周期的规则，而不影响应当跨场景或
 //! 跨 UI 实例持续存在的规则。
 
+use std::cmp::Reverse;
 use std::collections::{BTreeMap, HashMap};
 
-use bevy::prelude::{Entity, Resource, error, info};
+use bevy::prelude::{Entity, Resource, error, info, warn};
 
-use super::{ActionDef, CoreActionDef, FactEvent, Rule, RuleRegistry, RuleScope};
+use crate::dead_condition::is_trivially_false;
+use crate::layered::LayeredFactDatabase;
+
+use super::snapshot::{RegistrySnapshot, ViewRulesSnapshot, snapshot_rules};
+use super::{
+    ActionDef, CATCH_ALL_TRIGGER, CoreActionDef, FactEvent, FactEventId, Rule, RuleMut,
+    RuleRegistry, RuleRegistryChangeKind, RuleRegistryChanged, RuleScope, conflicting_policy,
+};
 
 /// Layered rule registry that manages rules with different scopes.
 /// Rules are separated into Global, Local, and View layers with different lifecycles.
@@ -29,6 +37,12 @@ pub struct LayeredRuleRegistry<A: ActionDef = CoreActionDef> {
     global: RuleRegistry<A>,
     local: RuleRegistry<A>,
     view: HashMap<Entity, RuleRegistry<A>>,
+    /// Mutations recorded since the last drain, emitted as `RuleRegistryChanged` messages.
+    /// None while change tracking is off.
+    ///
+    /// 自上次取出以来记录的变更，以 `RuleRegistryChanged` 消息的形式发出。
+    /// 变更跟踪关闭时为 None。
+    changes: Option<Vec<RuleRegistryChanged>>,
 }
 
 impl<A: ActionDef> Default for LayeredRuleRegistry<A> {
@@ -37,6 +51,7 @@ impl<A: ActionDef> Default for LayeredRuleRegistry<A> {
             global: RuleRegistry::default(),
             local: RuleRegistry::default(),
             view: HashMap::new(),
+            changes: None,
         }
     }
 }
@@ -49,6 +64,8 @@ impl<A: ActionDef> LayeredRuleRegistry<A> {
     }
 
     pub fn register(&mut self, rule: Rule<A>) {
+        self.warn_on_conflicting_policy(&rule);
+        self.record(RuleRegistryChangeKind::Registered, vec![rule.id.clone()]);
         match rule.scope {
             RuleScope::Global => self.global.register(rule),
             RuleScope::Local => self.local.register(rule),
@@ -65,16 +82,171 @@ impl<A: ActionDef> LayeredRuleRegistry<A> {
     }
 
     pub fn register_view_rule(&mut self, view_entity: Entity, rule: Rule<A>) {
+        self.warn_on_conflicting_policy(&rule);
+        self.record(RuleRegistryChangeKind::Registered, vec![rule.id.clone()]);
         self.view.entry(view_entity).or_default().register(rule);
     }
 
+    /// Warn if `rule` shares its trigger and priority with a rule selecting differently, since
+    /// the group then selects with whichever policy other than `First` comes first.
+    fn warn_on_conflicting_policy(&self, rule: &Rule<A>) {
+        let layers = [&self.global, &self.local].into_iter();
+        let others = layers
+            .chain(self.view.values())
+            .flat_map(|registry| registry.rules_with_trigger(&rule.trigger))
+            .filter(|other| other.id != rule.id)
+            .map(|other| (other.selection, other.priority));
+        if let Some(other) = conflicting_policy(rule.selection, rule.priority, others) {
+            warn!(
+                "FRE: Rule '{}' selects with {:?} but shares priority {} on '{}' with a rule \
+                selecting with {:?}; the group draws from all of them",
+                rule.id, rule.selection, rule.priority, rule.trigger, other
+            );
+        }
+    }
+
+    /// Unregister a rule from whichever layer holds it.
+    ///
+    /// 从持有该规则的层中注销规则。
+    pub fn unregister(&mut self, rule_id: &str) -> Option<Rule<A>> {
+        let rule = self
+            .global
+            .unregister(rule_id)
+            .or_else(|| self.local.unregister(rule_id))
+            .or_else(|| {
+                self.view
+                    .values_mut()
+                    .find_map(|registry| registry.unregister(rule_id))
+            })?;
+        self.record(RuleRegistryChangeKind::Unregistered, vec![rule.id.clone()]);
+        Some(rule)
+    }
+
+    /// Enable or disable a rule in whichever layer holds it.
+    /// Returns true if the rule was found.
+    ///
+    /// 在持有该规则的层中启用或禁用规则。
+    /// 如果找到规则则返回 true。
+    pub fn set_enabled(&mut self, rule_id: &str, enabled: bool) -> bool {
+        let Some(layer) = self.layer_mut(rule_id) else {
+            return false;
+        };
+        layer.set_enabled(rule_id, enabled);
+        self.record(
+            RuleRegistryChangeKind::EnabledChanged { enabled },
+            vec![rule_id.to_string()],
+        );
+        true
+    }
+
+    /// Enable or disable every rule triggered by `trigger` across all layers,
+    /// optionally restricted to a single scope. Returns the number of rules affected.
+    ///
+    /// 在所有层中启用或禁用由 `trigger` 触发的规则，可选地限定为单个作用域。
+    /// 返回受影响的规则数量。
+    pub fn set_enabled_by_trigger(
+        &mut self,
+        trigger: &FactEventId,
+        enabled: bool,
+        scope: Option<RuleScope>,
+    ) -> usize {
+        self.set_enabled_where(enabled, scope, |rule| &rule.trigger == trigger)
+    }
+
+    /// Enable or disable every rule whose trigger starts with `prefix` across all layers,
+    /// optionally restricted to a single scope. Returns the number of rules affected.
+    ///
+    /// 在所有层中启用或禁用触发器以 `prefix` 开头的规则，可选地限定为单个作用域。
+    /// 返回受影响的规则数量。
+    pub fn set_enabled_by_trigger_prefix(
+        &mut self,
+        prefix: &str,
+        enabled: bool,
+        scope: Option<RuleScope>,
+    ) -> usize {
+        self.set_enabled_where(enabled, scope, |rule| {
+            rule.trigger.as_str().starts_with(prefix)
+        })
+    }
+
+    /// Enable or disable every rule tagged `tag` across all layers, optionally restricted to a
+    /// single scope. Returns the number of rules affected.
+    ///
+    /// 在所有层中启用或禁用带有 `tag` 标签的规则，可选地限定为单个作用域。
+    /// 返回受影响的规则数量。
+    pub fn set_enabled_by_tag(
+        &mut self,
+        tag: &str,
+        enabled: bool,
+        scope: Option<RuleScope>,
+    ) -> usize {
+        self.set_enabled_where(enabled, scope, |rule| rule.tags.iter().any(|t| t == tag))
+    }
+
+    pub(super) fn set_enabled_where(
+        &mut self,
+        enabled: bool,
+        scope: Option<RuleScope>,
+        predicate: impl Fn(&Rule<A>) -> bool,
+    ) -> usize {
+        let in_scope = |layer: RuleScope| scope.is_none_or(|scope| scope == layer);
+        let mut rule_ids = Vec::new();
+        if in_scope(RuleScope::Global) {
+            rule_ids.extend(self.global.set_enabled_where(enabled, &predicate));
+        }
+        if in_scope(RuleScope::Local) {
+            rule_ids.extend(self.local.set_enabled_where(enabled, &predicate));
+        }
+        if in_scope(RuleScope::View) {
+            for registry in self.view.values_mut() {
+                rule_ids.extend(registry.set_enabled_where(enabled, &predicate));
+            }
+        }
+
+        let count = rule_ids.len();
+        if count > 0 {
+            self.record(RuleRegistryChangeKind::EnabledChanged { enabled }, rule_ids);
+        }
+        count
+    }
+
     pub fn clear_local(&mut self) {
+        let rule_ids = self.local.iter().map(|rule| rule.id.clone()).collect();
         self.local.clear();
+        self.record(
+            RuleRegistryChangeKind::LayerCleared {
+                scope: RuleScope::Local,
+            },
+            rule_ids,
+        );
         info!("LayeredRuleRegistry: Cleared local layer rules");
     }
 
+    /// Clear the local layer like `clear_local`, also removing the local facts owned by the
+    /// cleared rules from `db`. Returns the number of facts removed.
+    ///
+    /// 像 `clear_local` 一样清空局部层，同时从 `db` 中移除被清空规则所拥有的局部事实。
+    /// 返回移除的事实数量。
+    pub fn clear_local_with_facts(&mut self, db: &mut LayeredFactDatabase) -> usize {
+        let removed = self
+            .local
+            .iter()
+            .flat_map(|rule| &rule.owned_facts)
+            .filter(|key| db.remove(key).is_some())
+            .count();
+        self.clear_local();
+        removed
+    }
+
     pub fn clear_view(&mut self, view_entity: Entity) {
-        if self.view.remove(&view_entity).is_some() {
+        if let Some(registry) = self.view.remove(&view_entity) {
+            let rule_ids = registry.iter().map(|rule| rule.id.clone()).collect();
+            self.record(
+                RuleRegistryChangeKind::LayerCleared {
+                    scope: RuleScope::View,
+                },
+                rule_ids,
+            );
             info!(
                 "LayeredRuleRegistry: Cleared rules for view entity {:?}",
                 view_entity
@@ -82,24 +254,75 @@ impl<A: ActionDef> LayeredRuleRegistry<A> {
         }
     }
 
+    /// Turn change tracking on or off. Tracking is off by default, so a registry nobody drains
+    /// does not accumulate changes; `FREPlugin` turns it on. Turning it off forgets the changes
+    /// not taken yet.
+    ///
+    /// 开启或关闭变更跟踪。默认关闭，因此无人取出时注册表不会累积变更；`FREPlugin` 会开启它。
+    /// 关闭时会丢弃尚未取出的变更。
+    pub fn set_change_tracking(&mut self, enabled: bool) {
+        if enabled != self.change_tracking() {
+            self.changes = enabled.then(Vec::new);
+        }
+    }
+
+    pub fn change_tracking(&self) -> bool {
+        self.changes.is_some()
+    }
+
+    /// Take all changes recorded since the last call. Always empty while change tracking is off.
+    /// The plugin drains these every frame into `RuleRegistryChanged` messages.
+    ///
+    /// 取出自上次调用以来记录的所有变更。变更跟踪关闭时始终为空。
+    /// 插件每帧将其转换为 `RuleRegistryChanged` 消息。
+    pub fn take_changes(&mut self) -> Vec<RuleRegistryChanged> {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Check if there are recorded changes waiting to be drained.
+    ///
+    /// 检查是否有等待取出的变更记录。
+    pub fn has_changes(&self) -> bool {
+        self.changes
+            .as_ref()
+            .is_some_and(|changes| !changes.is_empty())
+    }
+
+    fn record(&mut self, kind: RuleRegistryChangeKind, rule_ids: Vec<String>) {
+        if let Some(changes) = &mut self.changes {
+            changes.push(RuleRegistryChanged::new(kind, rule_ids));
+        }
+    }
+
     pub fn get_matching_rules_grouped(&self, event: &FactEvent) -> Vec<Vec<&Rule<A>>> {
+        self.grouped_where(&event.id, |rule| rule.matches_event(event))
+    }
+
+    /// Enabled catch-all rules (trigger `"*"`), grouped like `get_matching_rules_grouped`.
+    ///
+    /// 已启用的兜底规则（触发器为 `"*"`），分组方式与 `get_matching_rules_grouped` 相同。
+    pub fn get_catch_all_rules_grouped(&self) -> Vec<Vec<&Rule<A>>> {
+        let trigger = FactEventId::from(CATCH_ALL_TRIGGER);
+        self.grouped_where(&trigger, |rule| rule.enabled)
+    }
+
+    /// Rules with `trigger` accepted by `matches`, grouped by descending priority,
+    /// fewer conditions first.
+    fn grouped_where(
+        &self,
+        trigger: &FactEventId,
+        matches: impl Fn(&Rule<A>) -> bool,
+    ) -> Vec<Vec<&Rule<A>>> {
         let mut all_groups: BTreeMap<i32, Vec<&Rule<A>>> = BTreeMap::new();
 
-        for rule in self.global.iter() {
-            if rule.matches_event(event) {
-                all_groups.entry(rule.priority).or_default().push(rule);
-            }
-        }
-        for rule in self.local.iter() {
-            if rule.matches_event(event) {
-                all_groups.entry(rule.priority).or_default().push(rule);
-            }
-        }
-        for rule in self
-            .view
-            .values()
-            .flat_map(|registry| registry.iter())
-            .filter(|rule| rule.matches_event(event))
+        let layers = [&self.global, &self.local].into_iter();
+        for rule in layers
+            .chain(self.view.values())
+            .flat_map(|registry| registry.rules_with_trigger(trigger))
+            .filter(|rule| matches(rule))
         {
             all_groups.entry(rule.priority).or_default().push(rule);
         }
@@ -122,6 +345,25 @@ impl<A: ActionDef> LayeredRuleRegistry<A> {
             .collect()
     }
 
+    /// All rules (enabled or not) grouped by trigger, each group in the order the rules would
+    /// be evaluated: higher priority first, then fewer conditions first.
+    ///
+    /// 按触发器分组的所有规则（无论是否启用），每组按规则的评估顺序排列：
+    /// 优先级高的在前，其次条件少的在前。
+    pub fn rules_by_trigger(&self) -> BTreeMap<FactEventId, Vec<&Rule<A>>> {
+        let mut by_trigger: BTreeMap<FactEventId, Vec<&Rule<A>>> = BTreeMap::new();
+        for rule in self.iter() {
+            by_trigger
+                .entry(rule.trigger.clone())
+                .or_default()
+                .push(rule);
+        }
+        for rules in by_trigger.values_mut() {
+            rules.sort_by_key(|rule| (Reverse(rule.priority), rule.condition_expressions.len()));
+        }
+        by_trigger
+    }
+
     pub fn len(&self) -> usize {
         self.global.len() + self.local.len() + self.view.values().map(|r| r.len()).sum::<usize>()
     }
@@ -137,6 +379,73 @@ impl<A: ActionDef> LayeredRuleRegistry<A> {
             .or_else(|| self.view.values().find_map(|r| r.get(rule_id)))
     }
 
+    /// Mutable access to a rule in whichever layer holds it. A `Modified` change is recorded,
+    /// since the caller may edit the rule.
+    ///
+    /// 在持有该规则的层中可变地访问规则。由于调用方可能编辑规则，会记录一次 `Modified` 变更。
+    pub fn get_mut(&mut self, rule_id: &str) -> Option<RuleMut<'_, A>> {
+        self.layer_mut(rule_id)?;
+        self.record(RuleRegistryChangeKind::Modified, vec![rule_id.to_string()]);
+        self.layer_mut(rule_id)?.get_mut(rule_id)
+    }
+
+    /// Compile the conditions of every rule not yet compiled, in every layer. Called after
+    /// asset rules are registered; rules added or edited later are compiled by the next call.
+    ///
+    /// 编译所有层中尚未编译的规则的条件。在注册资源规则后调用；
+    /// 之后添加或编辑的规则由下一次调用编译。
+    pub fn compile_expressions(&mut self) -> usize {
+        let views: usize = self
+            .view
+            .values_mut()
+            .map(RuleRegistry::compile_expressions)
+            .sum();
+        self.global.compile_expressions() + self.local.compile_expressions() + views
+    }
+
+    /// Rules in every layer whose conditions can never pass, as reported by
+    /// `is_trivially_false`. Useful for linting content after it is loaded.
+    ///
+    /// 所有层中条件永远无法通过的规则（由 `is_trivially_false` 判定）。
+    /// 适合在内容加载后进行检查。
+    pub fn find_dead_conditions(&self) -> Vec<&Rule<A>> {
+        self.iter()
+            .filter(|rule| is_trivially_false(&rule.condition_expressions))
+            .collect()
+    }
+
+    /// The layer holding `rule_id`.
+    fn layer_mut(&mut self, rule_id: &str) -> Option<&mut RuleRegistry<A>> {
+        if self.global.get(rule_id).is_some() {
+            return Some(&mut self.global);
+        }
+        if self.local.get(rule_id).is_some() {
+            return Some(&mut self.local);
+        }
+        self.view.values_mut().find(|r| r.get(rule_id).is_some())
+    }
+
+    /// Export every layer's rules with their runtime state, for debugging snapshots.
+    ///
+    /// 导出每一层的规则及其运行时状态，用于调试快照。
+    pub fn export_snapshot(&self) -> RegistrySnapshot {
+        let mut views: Vec<ViewRulesSnapshot> = self
+            .view
+            .iter()
+            .map(|(entity, registry)| ViewRulesSnapshot {
+                entity: entity.to_bits(),
+                rules: snapshot_rules(registry.iter(), RuleScope::View),
+            })
+            .collect();
+        views.sort_by_key(|view| view.entity);
+
+        RegistrySnapshot {
+            global: snapshot_rules(self.global.iter(), RuleScope::Global),
+            local: snapshot_rules(self.local.iter(), RuleScope::Local),
+            views,
+        }
+    }
+
     pub fn global_iter(&self) -> impl Iterator<Item = &Rule<A>> {
         self.global.iter()
     }
@@ -158,3 +467,321 @@ impl<A: ActionDef> LayeredRuleRegistry<A> {
             .chain(self.view.values().flat_map(|registry| registry.iter()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{FactModification, RegistrySnapshot, RuleRegistryChangeKind};
+
+    #[test]
+    fn test_layered_registry_records_changes() {
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        let view = Entity::from_bits(42);
+
+        // Nothing is recorded until tracking is turned on
+        registry.register(
+            Rule::builder("untracked", "event")
+                .scope(RuleScope::Global)
+                .build(),
+        );
+        assert!(!registry.has_changes());
+        registry.set_change_tracking(true);
+
+        registry.register(Rule::builder("local_rule", "event").build());
+        registry.register_view_rule(view, Rule::builder("view_rule", "event").build());
+        assert!(registry.set_enabled("local_rule", false));
+        assert!(!registry.set_enabled("missing", false));
+        assert!(registry.unregister("local_rule").is_some());
+        registry.register(Rule::builder("other_local", "event").build());
+        registry.get_mut("other_local").unwrap().priority = 3;
+        assert!(registry.get_mut("missing").is_none());
+        registry.clear_local();
+        registry.clear_view(view);
+
+        let changes = registry.take_changes();
+        let kinds: Vec<_> = changes.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                RuleRegistryChangeKind::Registered,
+                RuleRegistryChangeKind::Registered,
+                RuleRegistryChangeKind::EnabledChanged { enabled: false },
+                RuleRegistryChangeKind::Unregistered,
+                RuleRegistryChangeKind::Registered,
+                RuleRegistryChangeKind::Modified,
+                RuleRegistryChangeKind::LayerCleared {
+                    scope: RuleScope::Local
+                },
+                RuleRegistryChangeKind::LayerCleared {
+                    scope: RuleScope::View
+                },
+            ]
+        );
+        assert_eq!(changes[3].rule_ids, vec!["local_rule"]);
+        assert_eq!(changes[5].rule_ids, vec!["other_local"]);
+        assert_eq!(changes[6].rule_ids, vec!["other_local"]);
+        assert_eq!(changes[7].rule_ids, vec!["view_rule"]);
+
+        // Draining empties the log
+        assert!(!registry.has_changes());
+        assert!(registry.take_changes().is_empty());
+    }
+
+    #[test]
+    fn test_clear_local_removes_owned_facts() {
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        let mut db = LayeredFactDatabase::new();
+        registry.register(
+            Rule::builder("puzzle", "lever_pulled")
+                .owns_fact("levers_pulled")
+                .owns_fact("puzzle_solved")
+                .build(),
+        );
+        registry.register(
+            Rule::builder("save_progress", "puzzle_done")
+                .scope(RuleScope::Global)
+                .owns_fact("chapter")
+                .build(),
+        );
+        db.set("levers_pulled", 2i64);
+        db.set("puzzle_solved", false);
+        db.set("room", "crypt");
+        db.set("chapter", 3i64);
+        db.set_global("levers_pulled", 9i64);
+
+        assert_eq!(registry.clear_local_with_facts(&mut db), 2);
+        assert_eq!(registry.local_iter().count(), 0);
+        assert!(!db.contains_local("levers_pulled"));
+        assert!(!db.contains_local("puzzle_solved"));
+        // Unowned facts, facts of global rules and the global layer are kept
+        assert_eq!(db.get_string("room"), Some("crypt"));
+        assert_eq!(db.get_int("chapter"), Some(3));
+        assert_eq!(db.global().get_int("levers_pulled"), Some(9));
+    }
+
+    #[test]
+    fn test_layered_registry_set_enabled_by_trigger_prefix() {
+        use crate::asset::FreAsset;
+
+        let fre_data = r#"
+(
+    rules: [
+        (id: "up", event: ActionEvent(action: "Up", kind: JustPressed)),
+        (id: "confirm", event: ActionEvent(action: "Confirm", kind: JustPressed)),
+        (id: "timer", event: Event("timer_tick")),
+    ],
+)
+"#;
+        let asset: FreAsset = ron::from_str(fre_data).unwrap();
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        asset.register_rules_layered(&mut registry);
+        registry.register(
+            Rule::builder("global_up", "action:up:just_pressed")
+                .scope(RuleScope::Global)
+                .build(),
+        );
+        registry.set_change_tracking(true);
+
+        // Scope filter only touches the global layer
+        let affected =
+            registry.set_enabled_by_trigger_prefix("action:", false, Some(RuleScope::Global));
+        assert_eq!(affected, 1);
+        assert!(!registry.get("global_up").unwrap().enabled);
+        assert!(registry.get("up").unwrap().enabled);
+
+        // No filter spans every layer
+        let affected = registry.set_enabled_by_trigger_prefix("action:", false, None);
+        assert_eq!(affected, 3);
+        assert!(!registry.get("up").unwrap().enabled);
+        assert!(!registry.get("confirm").unwrap().enabled);
+        assert!(registry.get("timer").unwrap().enabled);
+
+        let affected = registry.set_enabled_by_trigger(
+            &FactEventId::new("action:up:just_pressed"),
+            true,
+            None,
+        );
+        assert_eq!(affected, 2);
+        assert!(registry.get("up").unwrap().enabled);
+        assert!(!registry.get("confirm").unwrap().enabled);
+
+        let changes = registry.take_changes();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(
+            changes[2].kind,
+            RuleRegistryChangeKind::EnabledChanged { enabled: true }
+        );
+    }
+
+    #[test]
+    fn test_layered_registry_set_enabled_by_tag() {
+        use crate::asset::FreAsset;
+
+        let asset: FreAsset = ron::from_str(
+            r#"(rules: [
+                (id: "hint", event: Event("tick"), tags: ["tutorial"]),
+                (id: "arrow", event: Event("tick"), tags: ["tutorial", "ui"]),
+                (id: "hud", event: Event("tick"), tags: ["ui"]),
+            ])"#,
+        )
+        .unwrap();
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        asset.register_rules_layered(&mut registry);
+        registry.register(
+            Rule::builder("intro", "start")
+                .scope(RuleScope::Global)
+                .tag("tutorial")
+                .build(),
+        );
+        registry.register_view_rule(
+            Entity::from_bits(7),
+            Rule::builder("menu_hint", "open").tag("tutorial").build(),
+        );
+        registry.set_change_tracking(true);
+
+        assert_eq!(registry.set_enabled_by_tag("tutorial", false, None), 4);
+        for id in ["hint", "arrow", "intro", "menu_hint"] {
+            assert!(!registry.get(id).unwrap().enabled, "{id}");
+        }
+        assert!(registry.get("hud").unwrap().enabled);
+
+        let affected = registry.set_enabled_by_tag("tutorial", true, Some(RuleScope::Global));
+        assert_eq!(affected, 1);
+        assert!(registry.get("intro").unwrap().enabled);
+        assert!(!registry.get("hint").unwrap().enabled);
+        assert_eq!(registry.take_changes().len(), 2);
+    }
+
+    #[test]
+    fn test_rules_by_trigger() {
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(
+            Rule::builder("fallback", "interact")
+                .condition_expr("$a")
+                .condition_expr("$b")
+                .build(),
+        );
+        registry.register(Rule::builder("plain", "interact").build());
+        registry.register(
+            Rule::builder("urgent", "interact")
+                .condition_expr("$c")
+                .priority(10)
+                .scope(RuleScope::Global)
+                .build(),
+        );
+        registry.register(Rule::builder("tick", "tick").enabled(false).build());
+        registry.register_view_rule(
+            Entity::from_bits(3),
+            Rule::builder("menu", "interact").build(),
+        );
+
+        let by_trigger = registry.rules_by_trigger();
+        let ids = |trigger: &str| -> Vec<&str> {
+            by_trigger[&FactEventId::new(trigger)]
+                .iter()
+                .map(|rule| rule.id.as_str())
+                .collect()
+        };
+        assert_eq!(by_trigger.len(), 2);
+        assert_eq!(ids("interact"), vec!["urgent", "plain", "menu", "fallback"]);
+        assert_eq!(ids("tick"), vec!["tick"]);
+
+        // Matches the order the rules would actually be evaluated in
+        let evaluated: Vec<&str> = registry
+            .get_matching_rules(&FactEvent::new("interact"))
+            .iter()
+            .map(|rule| rule.id.as_str())
+            .collect();
+        assert_eq!(evaluated, ids("interact"));
+    }
+
+    #[test]
+    fn test_layered_registry_export_snapshot() {
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(
+            Rule::builder("pause", "pause_pressed")
+                .scope(RuleScope::Global)
+                .output("paused")
+                .build(),
+        );
+        registry.register(
+            Rule::builder("count", "tick")
+                .condition_expr("$counter < 3")
+                .modify(FactModification::Increment("counter".to_string(), 1))
+                .action(CoreActionDef::EmitEvent("counted".to_string()))
+                .priority(5)
+                .source("rules/room.fre.ron")
+                .build(),
+        );
+        registry.register_view_rule(
+            Entity::from_bits(7),
+            Rule::builder("nav", "action:up:just_pressed")
+                .enabled(false)
+                .build(),
+        );
+
+        let snapshot = registry.export_snapshot();
+        let expected = r#"(
+    global: [
+        (
+            id: "pause",
+            trigger: "pause_pressed",
+            conditions: [],
+            modifications: [],
+            outputs: [
+                "paused",
+            ],
+            actions: 0,
+            enabled: true,
+            priority: 0,
+            consume_event: true,
+            scope: Global,
+            source: None,
+        ),
+    ],
+    local: [
+        (
+            id: "count",
+            trigger: "tick",
+            conditions: [
+                "$counter < 3",
+            ],
+            modifications: [
+                "Increment(\"counter\", 1)",
+            ],
+            outputs: [],
+            actions: 1,
+            enabled: true,
+            priority: 5,
+            consume_event: true,
+            scope: Local,
+            source: Some("rules/room.fre.ron"),
+        ),
+    ],
+    views: [
+        (
+            entity: 7,
+            rules: [
+                (
+                    id: "nav",
+                    trigger: "action:up:just_pressed",
+                    conditions: [],
+                    modifications: [],
+                    outputs: [],
+                    actions: 0,
+                    enabled: false,
+                    priority: 0,
+                    consume_event: true,
+                    scope: View,
+                    source: None,
+                ),
+            ],
+        ),
+    ],
+)"#;
+        assert_eq!(snapshot.to_ron_string().unwrap(), expected);
+
+        let parsed: RegistrySnapshot = ron::from_str(expected).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+}
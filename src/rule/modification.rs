@@ -0,0 +1,379 @@
+//! # modification.rs
+//!
+//! # modification.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Defines the runtime fact modifications a rule applies when it fires. Each variant maps
+//! onto one of the `LayeredFactDatabase` write helpers, and writes go to the local layer by
+//! default. The `*Global` variants write to the global layer instead, so data-driven rules can
+//! persist facts across scene changes.
+//!
+//! 定义规则触发时应用的运行时事实修改。每个变体都对应 `LayeredFactDatabase` 的一个
+//! 写入辅助方法，默认写入局部层。`*Global` 变体改为写入全局层，
+//! 因此数据驱动的规则可以跨场景切换持久化事实。
+
+use crate::database::FactValue;
+use crate::expr;
+use crate::layered::LayeredFactDatabase;
+use crate::schema::SchemaViolation;
+
+/// Modification to apply to the fact database.
+///
+/// 应用于事实数据库的修改。
+#[derive(Clone, Debug, PartialEq)]
+pub enum FactModification {
+    /// Set a fact to a specific value.
+    ///
+    /// 将事实设置为特定值。
+    Set(String, FactValue),
+
+    /// Increment an integer fact by a whole-number value.
+    ///
+    /// 将整数事实增加指定的整数值。
+    Increment(String, i64),
+
+    /// Add a numeric value to a fact.
+    ///
+    /// 向事实添加数值。
+    Add(String, f64),
+
+    /// Subtract a numeric value from a fact.
+    ///
+    /// 从事实减去数值。
+    Sub(String, f64),
+
+    /// Multiply a fact by a numeric value.
+    ///
+    /// 将事实乘以数值。
+    Mul(String, f64),
+
+    /// Divide a fact by a numeric value.
+    ///
+    /// 将事实除以数值。
+    Div(String, f64),
+
+    /// Apply modulo operation to a fact.
+    ///
+    /// 对事实应用取模运算。
+    Mod(String, i64),
+
+    /// Clamp a fact value between min and max.
+    ///
+    /// 将事实值限制在 min 和 max 之间。
+    Clamp(String, f64, f64),
+
+    /// Wrap a fact value within a range [min, max).
+    ///
+    /// 将事实值包裹在范围 [min, max) 内。
+    Wrap(String, i64, i64),
+
+    /// Evaluate an expression and store the result in a fact.
+    ///
+    /// 评估表达式并将结果存储在事实中。
+    Eval(String, String),
+
+    /// Remove a fact.
+    ///
+    /// 移除一个事实。
+    Remove(String),
+
+    /// Toggle a boolean fact. Non-boolean facts follow the database's `TogglePolicy`.
+    ///
+    /// 切换布尔事实。非布尔事实遵循数据库的 `TogglePolicy`。
+    Toggle(String),
+
+    /// Mark a fact as explicitly cleared: it still exists, but holds `FactValue::Null`.
+    ///
+    /// 将事实标记为已显式清空：它仍然存在，但持有 `FactValue::Null`。
+    SetNull(String),
+
+    /// Push a value onto a string list fact, creating the list if absent.
+    ///
+    /// 向字符串列表事实追加一个值；如果列表不存在则创建。
+    AppendToList(String, String),
+
+    /// Remove the first matching value from a string list fact.
+    ///
+    /// 从字符串列表事实中移除第一个匹配的值。
+    RemoveFromList(String, String),
+
+    /// Restore a fact's recorded starting value; does nothing if none was recorded.
+    ///
+    /// 恢复事实记录的初始值；如果没有记录则不做任何操作。
+    ResetToInitial(String),
+
+    /// Set a fact to a specific value in the global layer.
+    ///
+    /// 在全局层将事实设置为特定值。
+    SetGlobal(String, FactValue),
+
+    /// Increment an integer fact in the global layer.
+    ///
+    /// 在全局层增加整数事实。
+    IncrementGlobal(String, i64),
+
+    /// Remove a fact from the global layer.
+    ///
+    /// 从全局层移除事实。
+    RemoveGlobal(String),
+
+    /// Toggle a boolean fact, writing to the global layer.
+    ///
+    /// 切换布尔事实并写入全局层。
+    ToggleGlobal(String),
+}
+
+impl FactModification {
+    /// Get the fact key targeted by this modification.
+    ///
+    /// 获取此修改所针对的事实键。
+    pub fn key(&self) -> &str {
+        match self {
+            FactModification::Set(key, _)
+            | FactModification::Increment(key, _)
+            | FactModification::Add(key, _)
+            | FactModification::Sub(key, _)
+            | FactModification::Mul(key, _)
+            | FactModification::Div(key, _)
+            | FactModification::Mod(key, _)
+            | FactModification::Clamp(key, _, _)
+            | FactModification::Wrap(key, _, _)
+            | FactModification::Eval(key, _)
+            | FactModification::Remove(key)
+            | FactModification::Toggle(key)
+            | FactModification::SetNull(key)
+            | FactModification::ResetToInitial(key)
+            | FactModification::AppendToList(key, _)
+            | FactModification::RemoveFromList(key, _)
+            | FactModification::SetGlobal(key, _)
+            | FactModification::IncrementGlobal(key, _)
+            | FactModification::RemoveGlobal(key)
+            | FactModification::ToggleGlobal(key) => key,
+        }
+    }
+
+    /// Whether the modification writes the global layer.
+    ///
+    /// 修改是否写入全局层。
+    pub fn is_global(&self) -> bool {
+        matches!(
+            self,
+            FactModification::SetGlobal(..)
+                | FactModification::IncrementGlobal(..)
+                | FactModification::RemoveGlobal(_)
+                | FactModification::ToggleGlobal(_)
+        )
+    }
+
+    /// Apply the modification to the layered fact database (local layer unless the variant
+    /// targets the global one).
+    ///
+    /// 将修改应用于分层事实数据库（除非变体以全局层为目标，否则为局部层）。
+    pub fn apply(&self, db: &mut LayeredFactDatabase) {
+        match self {
+            FactModification::Set(key, value) => {
+                db.set_local(key.as_str(), value.clone());
+            }
+            FactModification::Increment(key, amount) => {
+                db.increment(key, *amount);
+            }
+            FactModification::Add(key, amount) => {
+                db.add(key, *amount);
+            }
+            FactModification::Sub(key, amount) => {
+                db.sub(key, *amount);
+            }
+            FactModification::Mul(key, factor) => {
+                db.mul(key, *factor);
+            }
+            FactModification::Div(key, divisor) => {
+                db.div(key, *divisor);
+            }
+            FactModification::Mod(key, divisor) => {
+                db.modulo(key, *divisor);
+            }
+            FactModification::Clamp(key, min, max) => {
+                db.clamp(key, *min, *max);
+            }
+            FactModification::Wrap(key, min, max) => {
+                db.wrap(key, *min, *max);
+            }
+            FactModification::Eval(key, expression) => {
+                if let Some(value) = expr::evaluate_expr_to_fact(expression, db) {
+                    db.set_local(key.as_str(), value);
+                }
+            }
+            FactModification::Remove(key) => {
+                db.remove(key);
+            }
+            FactModification::Toggle(key) => {
+                db.toggle(key);
+            }
+            FactModification::SetNull(key) => {
+                db.set_local(key.as_str(), FactValue::Null);
+            }
+            FactModification::AppendToList(key, value) => {
+                db.append_to_list(key, value.as_str());
+            }
+            FactModification::RemoveFromList(key, value) => {
+                db.remove_from_list(key, value.as_str());
+            }
+            FactModification::ResetToInitial(key) => {
+                db.reset_to_initial(key);
+            }
+            FactModification::SetGlobal(key, value) => {
+                db.set_global(key.as_str(), value.clone());
+            }
+            FactModification::IncrementGlobal(key, amount) => {
+                db.increment_global(key, *amount);
+            }
+            FactModification::RemoveGlobal(key) => {
+                db.remove_global(key);
+            }
+            FactModification::ToggleGlobal(key) => {
+                db.toggle_global(key);
+            }
+        }
+    }
+
+    /// Apply the modification unless the schema would reject the value it writes, for rules
+    /// whose modifications run in a transaction. Only writes whose type is known up front are
+    /// checked (`Set`, `Eval`, `Increment`, `Toggle` and their global forms); the other
+    /// variants are applied as by `apply`.
+    ///
+    /// 应用修改，除非模式会拒绝其写入的值；用于修改在事务中运行的规则。
+    /// 只检查类型可预先确定的写入（`Set`、`Eval`、`Increment`、`Toggle` 及其全局形式）；
+    /// 其他变体按 `apply` 的方式应用。
+    pub fn try_apply(&self, db: &mut LayeredFactDatabase) -> Result<(), SchemaViolation> {
+        if let Some(value) = self.written_value(db) {
+            db.check_write(self.key(), value)?;
+        }
+        self.apply(db);
+        Ok(())
+    }
+
+    /// A value of the type this modification writes, where that type is known up front.
+    fn written_value(&self, db: &LayeredFactDatabase) -> Option<FactValue> {
+        match self {
+            FactModification::Set(_, value) | FactModification::SetGlobal(_, value) => {
+                Some(value.clone())
+            }
+            FactModification::Eval(_, expression) => expr::evaluate_expr_to_fact(expression, db),
+            FactModification::Increment(_, amount)
+            | FactModification::IncrementGlobal(_, amount) => Some(FactValue::Int(*amount)),
+            FactModification::Toggle(_) | FactModification::ToggleGlobal(_) => {
+                Some(FactValue::Bool(true))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::FactReader;
+
+    #[test]
+    fn test_fact_modification_set() {
+        let mut db = LayeredFactDatabase::new();
+        let mod_set = FactModification::Set("key".to_string(), FactValue::Int(42));
+        mod_set.apply(&mut db);
+        assert_eq!(db.get_int("key"), Some(42));
+    }
+
+    #[test]
+    fn test_fact_modification_increment() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("counter", 10i64);
+        let mod_inc = FactModification::Increment("counter".to_string(), 5);
+        mod_inc.apply(&mut db);
+        assert_eq!(db.get_int("counter"), Some(15));
+    }
+
+    #[test]
+    fn test_fact_modification_remove() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("to_remove", 100i64);
+        assert!(db.contains("to_remove"));
+
+        let mod_remove = FactModification::Remove("to_remove".to_string());
+        mod_remove.apply(&mut db);
+        assert!(!db.contains_local("to_remove"));
+    }
+
+    #[test]
+    fn test_fact_modification_toggle() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("flag", false);
+
+        let mod_toggle = FactModification::Toggle("flag".to_string());
+        mod_toggle.apply(&mut db);
+        assert_eq!(db.get_bool("flag"), Some(true));
+
+        mod_toggle.apply(&mut db);
+        assert_eq!(db.get_bool("flag"), Some(false));
+    }
+
+    #[test]
+    fn test_fact_modification_toggle_missing_key() {
+        let mut db = LayeredFactDatabase::new();
+        // Toggle on missing key should default to false, then toggle to true
+        let mod_toggle = FactModification::Toggle("missing".to_string());
+        mod_toggle.apply(&mut db);
+        assert_eq!(db.get_bool("missing"), Some(true));
+    }
+
+    #[test]
+    fn test_global_modifications_target_global_layer() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("deaths", 0i64);
+        db.set_global("tutorial_done", true);
+
+        FactModification::SetGlobal("deaths".to_string(), FactValue::Int(1)).apply(&mut db);
+        FactModification::IncrementGlobal("runs".to_string(), 2).apply(&mut db);
+        FactModification::ToggleGlobal("tutorial_done".to_string()).apply(&mut db);
+        assert_eq!(db.global().get_int("deaths"), Some(1));
+        assert_eq!(db.local().get_int("deaths"), Some(0));
+        assert_eq!(db.global().get_int("runs"), Some(2));
+        assert_eq!(db.global().get_bool("tutorial_done"), Some(false));
+        assert!(!db.contains_local("runs"));
+
+        FactModification::RemoveGlobal("deaths".to_string()).apply(&mut db);
+        assert!(!db.contains_global("deaths"));
+        assert!(db.contains_local("deaths"));
+
+        // Global facts outlive the scene
+        db.clear_local();
+        assert_eq!(db.get_int("runs"), Some(2));
+    }
+
+    #[test]
+    fn test_fact_modification_list_append_and_remove() {
+        let mut db = LayeredFactDatabase::new();
+        let append = |value: &str| FactModification::AppendToList("bag".to_string(), value.into());
+        let remove =
+            |value: &str| FactModification::RemoveFromList("bag".to_string(), value.into());
+
+        // Appending to a missing key creates the list
+        append("potion").apply(&mut db);
+        assert_eq!(db.get_string_list("bag"), Some(&["potion".to_string()][..]));
+
+        append("key").apply(&mut db);
+        append("potion").apply(&mut db);
+        remove("potion").apply(&mut db);
+        assert_eq!(
+            db.get_string_list("bag"),
+            Some(&["key".to_string(), "potion".to_string()][..])
+        );
+
+        // Removing an element that is not present is a no-op
+        remove("sword").apply(&mut db);
+        assert_eq!(db.get_string_list("bag").map(<[String]>::len), Some(2));
+        FactModification::RemoveFromList("missing".to_string(), "x".to_string()).apply(&mut db);
+        assert!(!db.contains("missing"));
+    }
+}
@@ -7,17 +7,18 @@
 //! ## 模块概述
 //!
 //! Contains the core in-memory registry for FRE rules. It stores rules by id, maintains
-//! a priority-sorted cache for fast event matching, and exposes the operations needed to register,
-//! remove, enable, and iterate rules at runtime.
+//! a priority-sorted cache and a trigger index for fast event matching, and exposes the
+//! operations needed to register, remove, enable, and iterate rules at runtime.
 //!
-//! 包含 FRE 规则的核心内存注册表。它按 id 存储规则，维护一个按优先级排序的缓存以便
-//! 快速匹配事件，并提供运行时注册、移除、启用和遍历规则所需的操作。
+//! 包含 FRE 规则的核心内存注册表。它按 id 存储规则，维护一个按优先级排序的缓存和
+//! 一个触发器索引以便快速匹配事件，并提供运行时注册、移除、启用和遍历规则所需的操作。
 
 use std::collections::{BTreeMap, HashMap};
+use std::ops::{Deref, DerefMut};
 
 use bevy::prelude::Resource;
 
-use super::{ActionDef, CoreActionDef, FactEvent, Rule};
+use super::{ActionDef, CoreActionDef, FactEvent, FactEventId, Rule};
 
 fn compare_by_priority<A: ActionDef>(a: &Rule<A>, b: &Rule<A>) -> std::cmp::Ordering {
     b.priority.cmp(&a.priority).then_with(|| {
@@ -37,6 +38,10 @@ pub struct RuleRegistry<A: ActionDef = CoreActionDef> {
     /// 按优先级排序的规则（缓存）。
     sorted_rules: Vec<String>,
     dirty: bool,
+    /// Rule ids by trigger, so matching an event only looks at the rules it can trigger.
+    ///
+    /// 按触发器索引的规则 ID，使匹配事件时只查看它能触发的规则。
+    by_trigger: HashMap<FactEventId, Vec<String>>,
 }
 
 impl<A: ActionDef> Default for RuleRegistry<A> {
@@ -45,6 +50,7 @@ impl<A: ActionDef> Default for RuleRegistry<A> {
             rules: HashMap::new(),
             sorted_rules: Vec::new(),
             dirty: false,
+            by_trigger: HashMap::new(),
         }
     }
 }
@@ -53,32 +59,73 @@ impl<A: ActionDef> Resource for RuleRegistry<A> {}
 
 impl<A: ActionDef> RuleRegistry<A> {
     pub fn new() -> Self {
-        Self {
-            rules: HashMap::new(),
-            sorted_rules: Vec::new(),
-            dirty: false,
-        }
+        Self::default()
     }
 
     pub fn register(&mut self, rule: Rule<A>) {
-        self.rules.insert(rule.id.clone(), rule);
+        let id = rule.id.clone();
+        let trigger = rule.trigger.clone();
+        if let Some(replaced) = self.rules.insert(id.clone(), rule) {
+            self.unindex(&replaced);
+        }
+        self.by_trigger.entry(trigger).or_default().push(id);
         self.dirty = true;
     }
 
     pub fn unregister(&mut self, rule_id: &str) -> Option<Rule<A>> {
-        let rule = self.rules.remove(rule_id);
-        if rule.is_some() {
-            self.dirty = true;
-        }
-        rule
+        let rule = self.rules.remove(rule_id)?;
+        self.unindex(&rule);
+        self.dirty = true;
+        Some(rule)
+    }
+
+    fn unindex(&mut self, rule: &Rule<A>) {
+        unindex(&mut self.by_trigger, &rule.trigger, &rule.id);
     }
 
     pub fn get(&self, rule_id: &str) -> Option<&Rule<A>> {
         self.rules.get(rule_id)
     }
 
-    pub fn get_mut(&mut self, rule_id: &str) -> Option<&mut Rule<A>> {
-        self.rules.get_mut(rule_id)
+    /// Mutable access to a rule. The rule's compiled conditions are dropped, since the caller
+    /// may change them; `compile_expressions` compiles it again. The trigger index and the
+    /// priority order are brought up to date when the returned guard is dropped.
+    ///
+    /// 规则的可变访问。由于调用方可能修改条件，规则的已编译条件会被丢弃；
+    /// `compile_expressions` 会重新编译它。返回的守卫被丢弃时会更新触发器索引和优先级顺序。
+    pub fn get_mut(&mut self, rule_id: &str) -> Option<RuleMut<'_, A>> {
+        let rule = self.rules.get_mut(rule_id)?;
+        rule.compiled = None;
+        Some(RuleMut {
+            indexed_trigger: rule.trigger.clone(),
+            rule,
+            by_trigger: &mut self.by_trigger,
+            dirty: &mut self.dirty,
+        })
+    }
+
+    /// Compile the conditions of every rule not yet compiled, returning how many were compiled.
+    ///
+    /// 编译所有尚未编译的规则的条件，返回编译的数量。
+    pub fn compile_expressions(&mut self) -> usize {
+        self.rules
+            .values_mut()
+            .filter(|rule| !rule.is_compiled())
+            .map(Rule::compile_conditions)
+            .filter(|&compiled| compiled)
+            .count()
+    }
+
+    /// Rules whose trigger is `trigger`, enabled or not.
+    ///
+    /// 触发器为 `trigger` 的规则（无论是否启用）。
+    pub fn rules_with_trigger(&self, trigger: &FactEventId) -> Vec<&Rule<A>> {
+        self.by_trigger
+            .get(trigger)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.rules.get(id))
+            .collect()
     }
 
     pub fn set_enabled(&mut self, rule_id: &str, enabled: bool) {
@@ -87,10 +134,54 @@ impl<A: ActionDef> RuleRegistry<A> {
         }
     }
 
+    /// Enable or disable every rule triggered by `trigger`.
+    /// Returns the number of rules affected. Bulk toggles are transient and simply flip `enabled`.
+    ///
+    /// 启用或禁用由 `trigger` 触发的所有规则。
+    /// 返回受影响的规则数量。批量切换是临时的，仅修改 `enabled`。
+    pub fn set_enabled_by_trigger(&mut self, trigger: &FactEventId, enabled: bool) -> usize {
+        self.set_enabled_where(enabled, |rule| &rule.trigger == trigger)
+            .len()
+    }
+
+    /// Enable or disable every rule whose trigger starts with `prefix` (e.g. `"action:"`).
+    /// Returns the number of rules affected.
+    ///
+    /// 启用或禁用触发器以 `prefix` 开头（如 `"action:"`）的所有规则。
+    /// 返回受影响的规则数量。
+    pub fn set_enabled_by_trigger_prefix(&mut self, prefix: &str, enabled: bool) -> usize {
+        self.set_enabled_where(enabled, |rule| rule.trigger.as_str().starts_with(prefix))
+            .len()
+    }
+
+    /// Enable or disable every rule tagged `tag`. Returns the number of rules affected.
+    ///
+    /// 启用或禁用带有 `tag` 标签的所有规则。返回受影响的规则数量。
+    pub fn set_enabled_by_tag(&mut self, tag: &str, enabled: bool) -> usize {
+        self.set_enabled_where(enabled, |rule| rule.tags.iter().any(|t| t == tag))
+            .len()
+    }
+
+    /// Set `enabled` on every rule matching `predicate`, returning the affected rule ids.
+    pub(crate) fn set_enabled_where(
+        &mut self,
+        enabled: bool,
+        predicate: impl Fn(&Rule<A>) -> bool,
+    ) -> Vec<String> {
+        self.rules
+            .values_mut()
+            .filter(|rule| predicate(rule))
+            .map(|rule| {
+                rule.enabled = enabled;
+                rule.id.clone()
+            })
+            .collect()
+    }
+
     pub fn get_matching_rules_grouped(&self, event: &FactEvent) -> Vec<Vec<&Rule<A>>> {
         let mut groups: BTreeMap<i32, Vec<&Rule<A>>> = BTreeMap::new();
 
-        for rule in self.rules.values() {
+        for rule in self.rules_with_trigger(&event.id) {
             if rule.matches_event(event) {
                 groups.entry(rule.priority).or_default().push(rule);
             }
@@ -133,9 +224,138 @@ impl<A: ActionDef> RuleRegistry<A> {
         self.rules.clear();
         self.sorted_rules.clear();
         self.dirty = false;
+        self.by_trigger.clear();
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &Rule<A>> {
         self.rules.values()
     }
 }
+
+/// Remove `id` from the rules indexed under `trigger`.
+fn unindex(by_trigger: &mut HashMap<FactEventId, Vec<String>>, trigger: &FactEventId, id: &str) {
+    let Some(ids) = by_trigger.get_mut(trigger) else {
+        return;
+    };
+    ids.retain(|indexed| indexed != id);
+    if ids.is_empty() {
+        by_trigger.remove(trigger);
+    }
+}
+
+/// A rule borrowed mutably from a `RuleRegistry`. Dropping it re-indexes the rule under its
+/// new trigger if the trigger was changed, and marks the priority order for re-sorting.
+///
+/// 从 `RuleRegistry` 可变借用的规则。丢弃时，如果触发器被修改，会按新触发器重新索引该规则，
+/// 并标记优先级顺序需要重新排序。
+pub struct RuleMut<'a, A: ActionDef> {
+    rule: &'a mut Rule<A>,
+    /// The trigger the rule is indexed under.
+    indexed_trigger: FactEventId,
+    by_trigger: &'a mut HashMap<FactEventId, Vec<String>>,
+    dirty: &'a mut bool,
+}
+
+impl<A: ActionDef> Deref for RuleMut<'_, A> {
+    type Target = Rule<A>;
+
+    fn deref(&self) -> &Rule<A> {
+        self.rule
+    }
+}
+
+impl<A: ActionDef> DerefMut for RuleMut<'_, A> {
+    fn deref_mut(&mut self) -> &mut Rule<A> {
+        self.rule
+    }
+}
+
+impl<A: ActionDef> Drop for RuleMut<'_, A> {
+    fn drop(&mut self) {
+        *self.dirty = true;
+        if self.rule.trigger == self.indexed_trigger {
+            return;
+        }
+        unindex(self.by_trigger, &self.indexed_trigger, &self.rule.id);
+        self.by_trigger
+            .entry(self.rule.trigger.clone())
+            .or_default()
+            .push(self.rule.id.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{LayeredRuleRegistry, RuleScope};
+    use bevy::prelude::Entity;
+
+    #[test]
+    fn test_trigger_index_with_many_rules() {
+        let mut registry: LayeredRuleRegistry = LayeredRuleRegistry::new();
+        let view = Entity::from_raw_u32(1).unwrap();
+        for i in 0..1000 {
+            let mut builder = Rule::builder(format!("rule_{i}"), format!("event_{}", i % 50))
+                .priority(i % 3)
+                .enabled(i % 7 != 0);
+            for _ in 0..i % 4 {
+                builder = builder.condition_expr("true");
+            }
+            match i % 3 {
+                0 => registry.register(builder.scope(RuleScope::Global).build()),
+                1 => registry.register(builder.build()),
+                _ => registry.register_view_rule(view, builder.build()),
+            }
+        }
+
+        let groups = registry.get_matching_rules_grouped(&FactEvent::new("event_7"));
+        let expected = (0..1000).filter(|i| i % 50 == 7 && i % 7 != 0).count();
+        assert_eq!(groups.iter().map(Vec::len).sum::<usize>(), expected);
+        for (group, priority) in groups.iter().zip([2, 1, 0]) {
+            assert!(group.iter().all(|rule| rule.priority == priority));
+            assert!(group.iter().all(|rule| rule.trigger == "event_7"));
+            assert!(
+                group
+                    .windows(2)
+                    .all(|w| w[0].condition_expressions.len() <= w[1].condition_expressions.len())
+            );
+        }
+
+        // Unregistering and retriggering a rule keeps the index in sync
+        registry.unregister("rule_57");
+        registry.get_mut("rule_1").unwrap().trigger = FactEventId::from("event_7");
+        let matching = registry.get_matching_rules(&FactEvent::new("event_7"));
+        assert_eq!(matching.len(), expected);
+        assert!(matching.iter().any(|rule| rule.id == "rule_1"));
+        assert!(matching.iter().all(|rule| rule.id != "rule_57"));
+        assert!(
+            registry
+                .get_matching_rules(&FactEvent::new("event_1"))
+                .len()
+                < 20
+        );
+    }
+
+    #[test]
+    fn test_edits_through_get_mut_update_the_index_and_order() {
+        let mut registry = RuleRegistry::<CoreActionDef>::new();
+        for (id, priority) in [("low", 0), ("high", 5)] {
+            registry.register(Rule::builder(id, "tick").priority(priority).build());
+        }
+        let order = |registry: &mut RuleRegistry| -> Vec<String> {
+            let event = FactEvent::new("tick");
+            let rules = registry.get_matching_rules(&event);
+            rules.into_iter().map(|rule| rule.id.clone()).collect()
+        };
+        assert_eq!(order(&mut registry), ["high", "low"]);
+
+        registry.get_mut("low").unwrap().priority = 10;
+        assert_eq!(order(&mut registry), ["low", "high"]);
+
+        registry.get_mut("high").unwrap().trigger = FactEventId::from("tock");
+        assert_eq!(order(&mut registry), ["low"]);
+        let tock = registry.rules_with_trigger(&FactEventId::from("tock"));
+        assert_eq!(tock.len(), 1);
+        assert_eq!(tock[0].id, "high");
+    }
+}
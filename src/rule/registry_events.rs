@@ -0,0 +1,72 @@
+//! # registry_events.rs
+//!
+//! # registry_events.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Describes the change notifications produced by the layered rule registry. While change
+//! tracking is on, mutations are recorded on the registry itself and later drained by a plugin
+//! system into Bevy messages, so inspectors can refresh cached rule lists without polling the
+//! whole registry.
+//!
+//! 描述分层规则注册表产生的变更通知。开启变更跟踪时，变更先记录在注册表内部，随后由插件系统取出并
+//! 转换为 Bevy 消息，这样检查器无需轮询整个注册表即可刷新缓存的规则列表。
+
+use bevy::prelude::Message;
+
+use super::RuleScope;
+
+/// The kind of mutation applied to a rule registry.
+///
+/// 应用于规则注册表的变更类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleRegistryChangeKind {
+    /// Rules were registered (or replaced by id).
+    ///
+    /// 规则被注册（或按 id 替换）。
+    Registered,
+
+    /// Rules were unregistered.
+    ///
+    /// 规则被注销。
+    Unregistered,
+
+    /// Rules had their `enabled` flag set.
+    ///
+    /// 规则的 `enabled` 标志被设置。
+    EnabledChanged { enabled: bool },
+
+    /// A rule was borrowed through `get_mut` and may have been edited in place.
+    ///
+    /// 规则通过 `get_mut` 被借用，可能已被原地编辑。
+    Modified,
+
+    /// A whole layer was cleared.
+    ///
+    /// 整个层被清空。
+    LayerCleared { scope: RuleScope },
+}
+
+/// Message emitted whenever the `LayeredRuleRegistry` is mutated.
+///
+/// 每当 `LayeredRuleRegistry` 被修改时发出的消息。
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct RuleRegistryChanged {
+    /// What kind of change happened.
+    ///
+    /// 发生的变更类型。
+    pub kind: RuleRegistryChangeKind,
+
+    /// Ids of the rules affected by the change.
+    ///
+    /// 受此变更影响的规则 ID。
+    pub rule_ids: Vec<String>,
+}
+
+impl RuleRegistryChanged {
+    pub fn new(kind: RuleRegistryChangeKind, rule_ids: Vec<String>) -> Self {
+        Self { kind, rule_ids }
+    }
+}
@@ -0,0 +1,514 @@
+//! # selection.rs
+//!
+//! # selection.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Selection policies for priority groups. By default every passing rule in a group fires in
+//! order until one consumes the event; a group that opts into `Random` or `WeightedRandom`
+//! instead fires exactly one of its passing rules, drawn from the seedable `FreRng` resource.
+//! `HighestScore` rules compete across priorities: those matching an event are gathered into one
+//! group, and only its passing rule with the highest `score_expression` fires, at its own priority.
+//!
+//! 优先级组的选择策略。默认情况下，组内每条通过的规则按顺序触发，直到某条规则消费事件；
+//! 选择 `Random` 或 `WeightedRandom` 的组则从通过的规则中只触发一条，
+//! 由可设定种子的 `FreRng` 资源抽取。`HighestScore` 规则跨优先级竞争：
+//! 匹配事件的此类规则被归入同一组，只有组内 `score_expression` 最高的通过规则会在其自身优先级触发。
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::Rule;
+use crate::asset::ActionDef;
+use crate::database::FactReader;
+use crate::expr::evaluate_expr;
+
+/// How a priority group chooses among its passing rules.
+///
+/// 优先级组如何在通过的规则中进行选择。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SelectPolicy {
+    /// Fire passing rules in order (current behavior).
+    ///
+    /// 按顺序触发通过的规则（默认行为）。
+    #[default]
+    First,
+
+    /// Fire one passing rule chosen uniformly at random.
+    ///
+    /// 从通过的规则中均匀随机地选择一条触发。
+    Random,
+
+    /// Fire one passing rule chosen at random, proportionally to each rule's `weight`.
+    ///
+    /// 按每条规则的 `weight` 比例随机选择一条通过的规则触发。
+    WeightedRandom,
+
+    /// Compete with every `HighestScore` rule matching the event, across priorities; only the
+    /// passing rule with the highest `score_expression` fires. Ties go to the higher priority,
+    /// then to the lexicographically smaller id.
+    ///
+    /// 与匹配事件的所有 `HighestScore` 规则跨优先级竞争；只有 `score_expression` 最高的
+    /// 通过规则会触发。平分时优先级高者胜出，其次 ID 字典序较小者胜出。
+    HighestScore,
+}
+
+/// Seedable random source for rule selection.
+/// Insert `FreRng::seeded` to make random selection deterministic (e.g. in tests or replays).
+///
+/// 用于规则选择的可设定种子的随机源。
+/// 插入 `FreRng::seeded` 可使随机选择具有确定性（例如在测试或回放中）。
+#[derive(Resource, Debug, Clone)]
+pub struct FreRng(fastrand::Rng);
+
+impl Default for FreRng {
+    fn default() -> Self {
+        Self(fastrand::Rng::new())
+    }
+}
+
+impl FreRng {
+    /// Create a generator with a fixed seed.
+    ///
+    /// 使用固定种子创建生成器。
+    pub fn seeded(seed: u64) -> Self {
+        Self(fastrand::Rng::with_seed(seed))
+    }
+
+    /// Re-seed the generator.
+    ///
+    /// 重新设定生成器的种子。
+    pub fn reseed(&mut self, seed: u64) {
+        self.0.seed(seed);
+    }
+}
+
+/// The policy of a priority group: the first policy other than `First` declared by its rules.
+/// Only the group built by `resolve_highest_score` holds `HighestScore` rules. Groups mixing
+/// policies are reported by `conflicting_policy`.
+pub(crate) fn group_policy<A: ActionDef>(group: &[&Rule<A>]) -> SelectPolicy {
+    group
+        .iter()
+        .map(|rule| rule.selection)
+        .find(|policy| *policy != SelectPolicy::First)
+        .unwrap_or_default()
+}
+
+/// The policy of the first of `others` that shares `priority` with a rule selecting with
+/// `policy` but selects differently, making `First` rules candidates of a random draw or
+/// splitting a group between two draws. `HighestScore` rules form their own group and never
+/// conflict.
+pub(crate) fn conflicting_policy(
+    policy: SelectPolicy,
+    priority: i32,
+    others: impl IntoIterator<Item = (SelectPolicy, i32)>,
+) -> Option<SelectPolicy> {
+    if policy == SelectPolicy::HighestScore {
+        return None;
+    }
+    others
+        .into_iter()
+        .find(|&(other, other_priority)| {
+            other_priority == priority && other != policy && other != SelectPolicy::HighestScore
+        })
+        .map(|(other, _)| other)
+}
+
+fn score<A: ActionDef>(rule: &Rule<A>, facts: &dyn FactReader) -> f64 {
+    rule.score_expression
+        .as_deref()
+        .and_then(|expr| evaluate_expr(expr, facts))
+        .unwrap_or(f64::NEG_INFINITY)
+}
+
+/// The rule with the highest score. Ties go to the higher priority, then the smaller id.
+fn best_scored<'a, A: ActionDef>(
+    rules: impl Iterator<Item = &'a Rule<A>>,
+    facts: &dyn FactReader,
+) -> Option<&'a Rule<A>> {
+    rules
+        .map(|rule| (score(rule, facts), rule))
+        .max_by(|(score_a, a), (score_b, b)| {
+            score_a
+                .total_cmp(score_b)
+                .then(a.priority.cmp(&b.priority))
+                .then(b.id.cmp(&a.id))
+        })
+        .map(|(_, rule)| rule)
+}
+
+/// A priority group, with whether each of its rules passes if that is already known.
+pub(crate) type CheckedGroup<'a, A> = (Vec<&'a Rule<A>>, Option<Vec<bool>>);
+
+/// Gather the `HighestScore` rules of `groups` into one group, placed ahead of the other rules
+/// sharing the priority of the best-scoring one that `passes`, or of the first of them if none
+/// passes. The gathered group carries the results of `passes` for its rules.
+pub(crate) fn resolve_highest_score<'a, A: ActionDef>(
+    groups: Vec<Vec<&'a Rule<A>>>,
+    facts: &dyn FactReader,
+    passes: impl Fn(&Rule<A>) -> bool,
+) -> Vec<CheckedGroup<'a, A>> {
+    let is_scored = |rule: &&Rule<A>| rule.selection == SelectPolicy::HighestScore;
+    let scored: Vec<&Rule<A>> = groups.iter().flatten().copied().filter(is_scored).collect();
+    if scored.is_empty() {
+        return groups.into_iter().map(|group| (group, None)).collect();
+    }
+    let passed: Vec<bool> = scored.iter().map(|rule| passes(rule)).collect();
+    let passing = scored.iter().zip(&passed).filter(|(_, passed)| **passed);
+    let priority = best_scored(passing.map(|(rule, _)| *rule), facts)
+        .unwrap_or(scored[0])
+        .priority;
+
+    let mut groups: Vec<CheckedGroup<A>> = groups
+        .into_iter()
+        .map(|group| group.into_iter().filter(|rule| !is_scored(rule)).collect())
+        .filter(|group: &Vec<&Rule<A>>| !group.is_empty())
+        .map(|group| (group, None))
+        .collect();
+    let at = groups
+        .iter()
+        .position(|(group, _)| group[0].priority <= priority)
+        .unwrap_or(groups.len());
+    groups.insert(at, (scored, Some(passed)));
+    groups
+}
+
+/// Select one rule from the passing candidates according to `policy`.
+pub(crate) fn select_rule<'a, A: ActionDef>(
+    policy: SelectPolicy,
+    candidates: &[&'a Rule<A>],
+    facts: &dyn FactReader,
+    rng: &mut FreRng,
+) -> Option<&'a Rule<A>> {
+    if candidates.is_empty() {
+        return None;
+    }
+    if policy == SelectPolicy::HighestScore {
+        return best_scored(candidates.iter().copied(), facts);
+    }
+    let total: f32 = candidates.iter().map(|rule| rule.weight.max(0.0)).sum();
+    if policy != SelectPolicy::WeightedRandom || total <= 0.0 {
+        return Some(candidates[rng.0.usize(..candidates.len())]);
+    }
+
+    let mut roll = rng.0.f32() * total;
+    for rule in candidates {
+        roll -= rule.weight.max(0.0);
+        if roll < 0.0 {
+            return Some(rule);
+        }
+    }
+    candidates.last().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry};
+    use crate::event::{FactEvent, FactEventId};
+    use crate::expr_condition::ExprConditionEvaluator;
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::LayeredRuleRegistry;
+    use crate::systems::{ConditionEvaluator, LiveSink, PendingFactEvents, process_event_rules};
+    use std::collections::HashMap;
+
+    fn bark_registry(weights: &[f32], policy: SelectPolicy) -> LayeredRuleRegistry<CoreActionDef> {
+        let mut registry = LayeredRuleRegistry::new();
+        for (idx, weight) in weights.iter().enumerate() {
+            registry.register(
+                Rule::builder(format!("bark_{}", idx), "idle")
+                    .selection(policy)
+                    .weight(*weight)
+                    .output(format!("say_{}", idx))
+                    .build(),
+            );
+        }
+        registry
+    }
+
+    fn count_picks(
+        registry: &LayeredRuleRegistry<CoreActionDef>,
+        rng: &mut FreRng,
+        rounds: usize,
+    ) -> HashMap<FactEventId, usize> {
+        let mut db = LayeredFactDatabase::new();
+        let mut pending = PendingFactEvents::default();
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let event = FactEvent::new("idle");
+        let mut counts = HashMap::new();
+        for _ in 0..rounds {
+            process_event_rules(
+                &event,
+                registry.get_matching_rules_grouped(&event),
+                &mut LiveSink {
+                    pending: &mut pending,
+                    db: &mut db,
+                },
+                &evaluator,
+                &EnumRegistry::default(),
+                rng,
+            );
+            for output in pending.events.drain(..) {
+                *counts.entry(output.id).or_insert(0) += 1;
+            }
+            pending.clear_tracking();
+        }
+        counts
+    }
+
+    #[test]
+    fn test_first_policy_is_deterministic() {
+        let registry = bark_registry(&[1.0; 5], SelectPolicy::First);
+        let counts = count_picks(&registry, &mut FreRng::seeded(7), 20);
+        assert_eq!(counts.len(), 1);
+    }
+
+    #[test]
+    fn test_random_policy_picks_one_rule_per_event() {
+        let registry = bark_registry(&[1.0; 5], SelectPolicy::Random);
+        let counts = count_picks(&registry, &mut FreRng::seeded(7), 200);
+        assert_eq!(counts.values().sum::<usize>(), 200);
+        assert_eq!(counts.len(), 5, "{:?}", counts);
+
+        // The same seed reproduces the same draws
+        let again = count_picks(&registry, &mut FreRng::seeded(7), 200);
+        assert_eq!(counts, again);
+    }
+
+    #[test]
+    fn test_weighted_random_respects_weights() {
+        let registry = bark_registry(&[9.0, 1.0, 0.0], SelectPolicy::WeightedRandom);
+        let counts = count_picks(&registry, &mut FreRng::seeded(42), 1000);
+        let heavy = counts.get(&FactEventId::new("say_0")).copied().unwrap_or(0);
+        let light = counts.get(&FactEventId::new("say_1")).copied().unwrap_or(0);
+        assert!((820..=980).contains(&heavy), "{:?}", counts);
+        assert!(light > 0);
+        assert!(!counts.contains_key(&FactEventId::new("say_2")));
+    }
+
+    #[test]
+    fn test_only_passing_rules_are_drawn() {
+        let mut registry = bark_registry(&[1.0, 1.0], SelectPolicy::Random);
+        registry.register(
+            Rule::builder("bark_blocked", "idle")
+                .selection(SelectPolicy::Random)
+                .condition_expr("$never")
+                .output("say_blocked")
+                .build(),
+        );
+        let counts = count_picks(&registry, &mut FreRng::seeded(3), 100);
+        assert_eq!(counts.values().sum::<usize>(), 100);
+        assert!(!counts.contains_key(&FactEventId::new("say_blocked")));
+    }
+
+    fn fire_once(
+        registry: &LayeredRuleRegistry<CoreActionDef>,
+        db: &mut LayeredFactDatabase,
+    ) -> Vec<FactEventId> {
+        let mut pending = PendingFactEvents::default();
+        let event = FactEvent::new("think");
+        process_event_rules(
+            &event,
+            registry.get_matching_rules_grouped(&event),
+            &mut LiveSink {
+                pending: &mut pending,
+                db,
+            },
+            &ConditionEvaluator::new(ExprConditionEvaluator),
+            &EnumRegistry::default(),
+            &mut FreRng::seeded(1),
+        );
+        pending.events.into_iter().map(|event| event.id).collect()
+    }
+
+    fn utility_rule(id: &str, score: &str, priority: i32) -> Rule<CoreActionDef> {
+        Rule::builder(id, "think")
+            .selection(SelectPolicy::HighestScore)
+            .score(score)
+            .priority(priority)
+            .output(format!("do_{}", id))
+            .build()
+    }
+
+    #[test]
+    fn test_highest_score_wins() {
+        let mut registry = LayeredRuleRegistry::new();
+        registry.register(utility_rule("attack", "$enemy_near * 10", 0));
+        registry.register(utility_rule("heal", "100 - $hp", 0));
+        registry.register(utility_rule("flee", "(30 - $hp) * 5", 1));
+        let mut db = LayeredFactDatabase::new();
+        db.set("enemy_near", 1i64);
+
+        db.set("hp", 85i64);
+        assert_eq!(
+            fire_once(&registry, &mut db),
+            vec![FactEventId::new("do_heal")]
+        );
+        db.set("hp", 95i64);
+        assert_eq!(
+            fire_once(&registry, &mut db),
+            vec![FactEventId::new("do_attack")]
+        );
+        db.set("hp", 20i64);
+        assert_eq!(
+            fire_once(&registry, &mut db),
+            vec![FactEventId::new("do_heal")]
+        );
+        db.set("hp", 5i64);
+        assert_eq!(
+            fire_once(&registry, &mut db),
+            vec![FactEventId::new("do_flee")]
+        );
+
+        // A failing rule does not compete, however high its score
+        registry.register(
+            Rule::builder("blocked", "think")
+                .selection(SelectPolicy::HighestScore)
+                .score("1000")
+                .condition_expr("$never")
+                .output("do_blocked")
+                .build(),
+        );
+        assert_eq!(
+            fire_once(&registry, &mut db),
+            vec![FactEventId::new("do_flee")]
+        );
+    }
+
+    #[test]
+    fn test_every_candidate_is_reported() {
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let db = LayeredFactDatabase::new();
+        let outcomes = |registry: &LayeredRuleRegistry<CoreActionDef>, event: &str| {
+            let explained = registry.explain(
+                &FactEvent::new(event),
+                &db,
+                &evaluator,
+                &enums,
+                &FreRng::seeded(5),
+                None,
+            );
+            explained
+                .into_iter()
+                .map(|rule| (rule.rule_id, rule.condition_passed, rule.fired))
+                .collect::<Vec<_>>()
+        };
+
+        let mut registry = bark_registry(&[1.0, 1.0], SelectPolicy::Random);
+        registry.register(
+            Rule::builder("bark_blocked", "idle")
+                .selection(SelectPolicy::Random)
+                .condition_expr("$never")
+                .build(),
+        );
+        let barks = outcomes(&registry, "idle");
+        assert_eq!(barks.len(), 3);
+        assert_eq!(barks.iter().filter(|(_, passed, _)| *passed).count(), 2);
+        assert_eq!(barks.iter().filter(|(_, _, fired)| *fired).count(), 1);
+        assert!(barks.contains(&("bark_blocked".to_string(), false, false)));
+
+        registry.register(utility_rule("attack", "10", 0));
+        registry.register(utility_rule("heal", "20", 1));
+        registry.register(
+            Rule::builder("blocked", "think")
+                .selection(SelectPolicy::HighestScore)
+                .score("1000")
+                .condition_expr("$never")
+                .build(),
+        );
+        let mut thoughts = outcomes(&registry, "think");
+        thoughts.sort();
+        assert_eq!(
+            thoughts,
+            vec![
+                ("attack".to_string(), true, false),
+                ("blocked".to_string(), false, false),
+                ("heal".to_string(), true, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highest_score_ties_break_by_priority_then_id() {
+        let mut registry = LayeredRuleRegistry::new();
+        registry.register(utility_rule("b_wait", "5", 0));
+        registry.register(utility_rule("a_wait", "5", 0));
+        registry.register(utility_rule("patrol", "2 + 3", 0));
+        let mut db = LayeredFactDatabase::new();
+        for _ in 0..5 {
+            assert_eq!(
+                fire_once(&registry, &mut db),
+                vec![FactEventId::new("do_a_wait")]
+            );
+        }
+
+        registry.register(utility_rule("z_guard", "5", 2));
+        assert_eq!(
+            fire_once(&registry, &mut db),
+            vec![FactEventId::new("do_z_guard")]
+        );
+    }
+
+    #[test]
+    fn test_highest_score_conditions_are_checked_once() {
+        use crate::systems::ConditionEvaluatorTrait;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Counting(Arc<AtomicUsize>);
+        impl ConditionEvaluatorTrait for Counting {
+            fn evaluate(
+                &self,
+                conditions: &[String],
+                _: &dyn FactReader,
+                _: &EnumRegistry,
+            ) -> bool {
+                self.0.fetch_add(conditions.len(), Ordering::Relaxed);
+                true
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let evaluator = ConditionEvaluator::new(Counting(calls.clone()));
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        for (id, priority) in [("attack", 0), ("heal", 1)] {
+            registry.register(
+                Rule::builder(id, "think")
+                    .selection(SelectPolicy::HighestScore)
+                    .score("1")
+                    .condition_expr("$ready")
+                    .priority(priority)
+                    .build(),
+            );
+        }
+        let mut db = LayeredFactDatabase::new();
+        let mut fire = |registry: &LayeredRuleRegistry<CoreActionDef>| {
+            let event = FactEvent::new("think");
+            process_event_rules(
+                &event,
+                registry.get_matching_rules_grouped(&event),
+                &mut LiveSink {
+                    pending: &mut PendingFactEvents::default(),
+                    db: &mut db,
+                },
+                &evaluator,
+                &EnumRegistry::default(),
+                &mut FreRng::seeded(1),
+            );
+            calls.swap(0, Ordering::Relaxed)
+        };
+        assert_eq!(fire(&registry), 2);
+
+        // A rule firing first may change the facts, so the candidates are checked again
+        registry.register(
+            Rule::builder("alert", "think")
+                .priority(5)
+                .consume_event(false)
+                .build(),
+        );
+        assert_eq!(fire(&registry), 4);
+    }
+}
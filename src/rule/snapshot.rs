@@ -0,0 +1,118 @@
+//! # snapshot.rs
+//!
+//! # snapshot.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Captures the live contents of a `LayeredRuleRegistry` as plain serializable data. Each
+//! layer is exported as a list of rule-def-shaped entries with their runtime state (enabled flag,
+//! priority, scope, source asset), so bug reports can include exactly which rules were live.
+//!
+//! 把 `LayeredRuleRegistry` 的实时内容捕获为可序列化的普通数据。每一层都被导出为
+//! 一组与规则定义形状一致的条目，并附带运行时状态（启用标志、优先级、作用域、来源资源），
+//! 这样错误报告就能准确包含当时生效的规则。
+
+use serde::{Deserialize, Serialize};
+
+use super::{ActionDef, Rule, RuleScope};
+
+/// Serializable view of a single live rule.
+///
+/// 单条实时规则的可序列化视图。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleSnapshot {
+    pub id: String,
+    pub trigger: String,
+    pub conditions: Vec<String>,
+    /// Modifications in their debug form.
+    ///
+    /// 调试形式的修改。
+    pub modifications: Vec<String>,
+    pub outputs: Vec<String>,
+    /// Number of actions attached to the rule.
+    ///
+    /// 规则附带的动作数量。
+    pub actions: usize,
+    pub enabled: bool,
+    pub priority: i32,
+    pub consume_event: bool,
+    /// The layer the rule is registered in. Exported snapshots take it from the layer, so it
+    /// holds even for rules registered in a layer other than their `scope` field names.
+    ///
+    /// 规则注册所在的层。导出的快照从层中获取该值，因此即使规则注册所在的层与其 `scope`
+    /// 字段不同，该值也是准确的。
+    pub scope: RuleScope,
+    pub source: Option<String>,
+}
+
+impl<A: ActionDef> From<&Rule<A>> for RuleSnapshot {
+    fn from(rule: &Rule<A>) -> Self {
+        Self {
+            id: rule.id.clone(),
+            trigger: rule.trigger.to_string(),
+            conditions: rule.condition_expressions.clone(),
+            modifications: rule
+                .modifications
+                .iter()
+                .map(|modification| format!("{:?}", modification))
+                .collect(),
+            outputs: rule.outputs.iter().map(ToString::to_string).collect(),
+            actions: rule.actions.len(),
+            enabled: rule.enabled,
+            priority: rule.priority,
+            consume_event: rule.consume_event,
+            scope: rule.scope,
+            source: rule.source.clone(),
+        }
+    }
+}
+
+/// Rules registered for one view entity.
+///
+/// 为某个视图实体注册的规则。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewRulesSnapshot {
+    /// The view entity, as `Entity::to_bits`.
+    ///
+    /// 视图实体，以 `Entity::to_bits` 表示。
+    pub entity: u64,
+    pub rules: Vec<RuleSnapshot>,
+}
+
+/// Serializable snapshot of every layer of a `LayeredRuleRegistry`.
+/// Rules are sorted by id and views by entity, so snapshots of equal registries compare equal.
+///
+/// `LayeredRuleRegistry` 所有层的可序列化快照。
+/// 规则按 id 排序，视图按实体排序，因此相同注册表的快照比较结果相等。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RegistrySnapshot {
+    pub global: Vec<RuleSnapshot>,
+    pub local: Vec<RuleSnapshot>,
+    pub views: Vec<ViewRulesSnapshot>,
+}
+
+impl RegistrySnapshot {
+    /// Render the snapshot as pretty-printed RON.
+    ///
+    /// 将快照渲染为格式化的 RON。
+    pub fn to_ron_string(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+}
+
+/// Snapshot the rules of the layer for `scope`, sorted by id.
+pub(crate) fn snapshot_rules<'a, A: ActionDef>(
+    rules: impl Iterator<Item = &'a Rule<A>>,
+    scope: RuleScope,
+) -> Vec<RuleSnapshot> {
+    let mut snapshots: Vec<RuleSnapshot> = rules
+        .map(|rule| RuleSnapshot {
+            scope,
+            ..RuleSnapshot::from(rule)
+        })
+        .collect();
+    snapshots.sort_by(|a, b| a.id.cmp(&b.id));
+    snapshots
+}
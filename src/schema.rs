@@ -0,0 +1,255 @@
+//! # schema.rs
+//!
+//! Declared fact types with optional runtime enforcement.
+//! A `FactSchema` maps fact keys to a `FactType`. Installed on a `LayeredFactDatabase`, it checks
+//! every write to a declared key according to its `SchemaPolicy`: writes of the wrong type are
+//! logged and rejected, or converted when a lossless-enough coercion exists.
+//! Keys without a declaration are unconstrained.
+//!
+//! 声明的事实类型，以及可选的运行时强制检查。
+//! `FactSchema` 把事实键映射到 `FactType`。安装到 `LayeredFactDatabase` 后，
+//! 它会按 `SchemaPolicy` 检查对已声明键的每次写入：类型错误的写入会被记录并拒绝，
+//! 或者在存在合理转换时被转换。未声明的键不受约束。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::database::FactValue;
+
+/// The type a fact is declared to hold.
+///
+/// 事实被声明持有的类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FactType {
+    Int,
+    Float,
+    Bool,
+    String,
+    StringList,
+    IntList,
+    FloatList,
+    BoolList,
+    Vec2,
+    Vec3,
+    EntityRef,
+    Null,
+}
+
+impl FactType {
+    /// The type of a runtime value.
+    ///
+    /// 运行时值的类型。
+    pub fn of(value: &FactValue) -> Self {
+        match value {
+            FactValue::Int(_) => FactType::Int,
+            FactValue::Float(_) => FactType::Float,
+            FactValue::Bool(_) => FactType::Bool,
+            FactValue::String(_) => FactType::String,
+            FactValue::StringList(_) => FactType::StringList,
+            FactValue::IntList(_) => FactType::IntList,
+            FactValue::FloatList(_) => FactType::FloatList,
+            FactValue::BoolList(_) => FactType::BoolList,
+            FactValue::Vec2(_) => FactType::Vec2,
+            FactValue::Vec3(_) => FactType::Vec3,
+            FactValue::EntityRef(_) => FactType::EntityRef,
+            FactValue::Null => FactType::Null,
+        }
+    }
+
+    /// Whether the type is `Int` or `Float`.
+    ///
+    /// 类型是否为 `Int` 或 `Float`。
+    pub fn is_numeric(self) -> bool {
+        matches!(self, FactType::Int | FactType::Float)
+    }
+
+    /// Convert a value to this type, if a sensible conversion exists.
+    /// Floats round to the nearest integer; strings are parsed; scalars format as strings.
+    ///
+    /// 如果存在合理的转换，则把值转换为此类型。
+    /// 浮点数四舍五入为整数；字符串会被解析；标量会格式化为字符串。
+    pub fn coerce(self, value: FactValue) -> Option<FactValue> {
+        if FactType::of(&value) == self {
+            return Some(value);
+        }
+        match (self, value) {
+            (FactType::Int, FactValue::Float(v)) if v.is_finite() => {
+                Some(FactValue::Int(v.round() as i64))
+            }
+            (FactType::Int, FactValue::Bool(v)) => Some(FactValue::Int(v as i64)),
+            (FactType::Int, FactValue::String(v)) => v.trim().parse().ok().map(FactValue::Int),
+            (FactType::Float, FactValue::Int(v)) => Some(FactValue::Float(v as f64)),
+            (FactType::Float, FactValue::String(v)) => v.trim().parse().ok().map(FactValue::Float),
+            (FactType::Bool, FactValue::Int(v)) => Some(FactValue::Bool(v != 0)),
+            (FactType::Bool, FactValue::String(v)) => v.trim().parse().ok().map(FactValue::Bool),
+            (FactType::String, FactValue::Int(v)) => Some(FactValue::String(v.to_string())),
+            (FactType::String, FactValue::Float(v)) => Some(FactValue::String(v.to_string())),
+            (FactType::String, FactValue::Bool(v)) => Some(FactValue::String(v.to_string())),
+            (FactType::FloatList, FactValue::IntList(v)) => Some(FactValue::FloatList(
+                v.into_iter().map(|i| i as f64).collect(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FactType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// What happens to a write whose value does not match the declared type.
+///
+/// 值与声明类型不匹配的写入会如何处理。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SchemaPolicy {
+    /// Declarations are informational only; every write is accepted.
+    ///
+    /// 声明仅供参考；接受所有写入。
+    #[default]
+    Off,
+
+    /// Mismatched writes are logged as errors and dropped.
+    ///
+    /// 不匹配的写入被记录为错误并丢弃。
+    Reject,
+
+    /// Mismatched writes are converted to the declared type; unconvertible ones are rejected.
+    ///
+    /// 不匹配的写入被转换为声明类型；无法转换的写入被拒绝。
+    Coerce,
+}
+
+/// A write that violated the schema.
+///
+/// 违反模式的写入。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    pub key: String,
+    pub expected: FactType,
+    pub found: FactType,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fact '{}' is declared as {} but was given {}",
+            self.key, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for SchemaViolation {}
+
+/// Declared fact types and the policy used to enforce them.
+///
+/// 声明的事实类型以及强制执行它们的策略。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FactSchema {
+    types: HashMap<String, FactType>,
+    policy: SchemaPolicy,
+}
+
+impl FactSchema {
+    pub fn new(policy: SchemaPolicy) -> Self {
+        Self {
+            types: HashMap::new(),
+            policy,
+        }
+    }
+
+    /// Declare (or redeclare) the type of a fact.
+    ///
+    /// 声明（或重新声明）事实的类型。
+    pub fn declare(&mut self, key: impl Into<String>, fact_type: FactType) {
+        self.types.insert(key.into(), fact_type);
+    }
+
+    /// Declared type of a fact, or None if it is unconstrained.
+    ///
+    /// 事实的声明类型；未受约束时返回 None。
+    pub fn type_of(&self, key: &str) -> Option<FactType> {
+        self.types.get(key).copied()
+    }
+
+    pub fn policy(&self) -> SchemaPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: SchemaPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Check a value against the declared type of `key`, ignoring the policy.
+    /// `Null` fits every declared type, so typed facts can still be cleared.
+    ///
+    /// 针对 `key` 的声明类型检查值，忽略策略。
+    /// `Null` 适配所有声明类型，因此带类型的事实仍然可以被清空。
+    pub fn check(&self, key: &str, value: &FactValue) -> Result<(), SchemaViolation> {
+        match self.type_of(key) {
+            Some(expected) if !value.is_null() && expected != FactType::of(value) => {
+                Err(SchemaViolation {
+                    key: key.to_string(),
+                    expected,
+                    found: FactType::of(value),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the policy to a write: returns the value to store, or the violation that rejected it.
+    ///
+    /// 对写入应用策略：返回要存储的值，或导致拒绝的违规信息。
+    pub fn admit(&self, key: &str, value: FactValue) -> Result<FactValue, SchemaViolation> {
+        if self.policy == SchemaPolicy::Off {
+            return Ok(value);
+        }
+        let Err(violation) = self.check(key, &value) else {
+            return Ok(value);
+        };
+        if self.policy == SchemaPolicy::Coerce {
+            return violation.expected.coerce(value).ok_or(violation);
+        }
+        Err(violation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hp_schema(policy: SchemaPolicy) -> FactSchema {
+        let mut schema = FactSchema::new(policy);
+        schema.declare("hp", FactType::Int);
+        schema.declare("name", FactType::String);
+        schema
+    }
+
+    #[test]
+    fn test_policies() {
+        let off = hp_schema(SchemaPolicy::Off);
+        assert_eq!(off.admit("hp", "full".into()), Ok(FactValue::from("full")));
+
+        let reject = hp_schema(SchemaPolicy::Reject);
+        let violation = reject.admit("hp", "full".into()).unwrap_err();
+        assert_eq!(violation.expected, FactType::Int);
+        assert_eq!(violation.found, FactType::String);
+        assert_eq!(reject.admit("hp", 3i64.into()), Ok(FactValue::Int(3)));
+        // Undeclared keys are unconstrained
+        assert_eq!(reject.admit("mood", 1.5.into()), Ok(FactValue::Float(1.5)));
+
+        let coerce = hp_schema(SchemaPolicy::Coerce);
+        assert_eq!(coerce.admit("hp", "42".into()), Ok(FactValue::Int(42)));
+        assert_eq!(coerce.admit("hp", 2.6.into()), Ok(FactValue::Int(3)));
+        assert_eq!(coerce.admit("name", 7i64.into()), Ok(FactValue::from("7")));
+        assert!(coerce.admit("hp", "full".into()).is_err());
+    }
+}
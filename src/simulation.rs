@@ -0,0 +1,679 @@
+//! # simulation.rs
+//!
+//! Dry-run simulation of an event against the rule registry.
+//! Runs the same matching and condition logic as `process_rules_system`, but applies
+//! modifications to a cloned database and reports what would happen instead of committing it.
+//! `LayeredRuleRegistry::explain` is the lightest form: which rules would fire, for debugging
+//! overlays.
+//!
+//! 针对规则注册表对事件进行试运行模拟。
+//! 使用与 `process_rules_system` 相同的匹配和条件逻辑，但将修改应用于克隆的数据库，
+//! 并报告将会发生的事情而不实际提交。`LayeredRuleRegistry::explain` 是最轻量的形式：
+//! 仅报告哪些规则会触发，供调试叠加层使用。
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use crate::asset::{ActionDef, EnumRegistry};
+use crate::cooldown::{CooldownSink, RuleCooldownState};
+use crate::database::FactValue;
+use crate::event::{FactEvent, FactEventId};
+use crate::layered::LayeredFactDatabase;
+use crate::rule::{FactModification, FreRng, LayeredRuleRegistry, Rule};
+use crate::systems::{ConditionEvaluator, RuleSink, process_event};
+
+mod explain;
+
+pub use explain::RuleMatch;
+
+/// Run `event` through `sink`, skipping rules on cooldown when `cooldowns` holds the state and
+/// the current time. The state is only read, and `rng` is cloned, so nothing outside the sink
+/// changes.
+fn dry_run<A: ActionDef, S: RuleSink<A>>(
+    event: &FactEvent,
+    registry: &LayeredRuleRegistry<A>,
+    sink: S,
+    condition_evaluator: &ConditionEvaluator,
+    enum_registry: &EnumRegistry,
+    rng: &FreRng,
+    cooldowns: Option<(&RuleCooldownState, Duration)>,
+) -> S {
+    let rng = &mut rng.clone();
+    let Some((state, now)) = cooldowns else {
+        let mut sink = sink;
+        process_event(
+            event,
+            registry,
+            &mut sink,
+            condition_evaluator,
+            enum_registry,
+            rng,
+        );
+        return sink;
+    };
+    let mut sink = CooldownSink::read_only(sink, state, now);
+    process_event(
+        event,
+        registry,
+        &mut sink,
+        condition_evaluator,
+        enum_registry,
+        rng,
+    );
+    sink.into_inner()
+}
+
+/// Before/after values of a single simulated modification.
+///
+/// 单个模拟修改的前后值。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModificationOutcome {
+    /// The modification that was applied.
+    ///
+    /// 被应用的修改。
+    pub modification: FactModification,
+
+    /// Value of the targeted fact before the modification.
+    ///
+    /// 修改前目标事实的值。
+    pub before: Option<FactValue>,
+
+    /// Value of the targeted fact after the modification.
+    ///
+    /// 修改后目标事实的值。
+    pub after: Option<FactValue>,
+}
+
+/// Simulated outcome for one rule considered while processing the event.
+///
+/// 处理事件时所考虑的单条规则的模拟结果。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleSimulation {
+    pub rule_id: String,
+    pub priority: i32,
+
+    /// Whether the rule's conditions passed.
+    ///
+    /// 规则条件是否通过。
+    pub condition_passed: bool,
+
+    /// Whether the rule would fire. A passing rule in a random or `HighestScore` group only
+    /// fires if it is the one selected.
+    ///
+    /// 规则是否会触发。随机或 `HighestScore` 组中通过的规则只有被选中时才会触发。
+    pub fired: bool,
+
+    /// Modifications that would be applied, in order.
+    ///
+    /// 将按顺序应用的修改。
+    pub modifications: Vec<ModificationOutcome>,
+
+    /// Output events that would be queued.
+    ///
+    /// 将被排队的输出事件。
+    pub outputs: Vec<FactEventId>,
+
+    /// Action types the rule would execute. Actions are reported, never run.
+    ///
+    /// 规则将执行的动作类型。动作只会被报告，不会被执行。
+    pub actions: Vec<String>,
+
+    /// Whether firing this rule consumes the event.
+    ///
+    /// 触发此规则是否会消费事件。
+    pub consumes_event: bool,
+}
+
+/// Report produced by `simulate_event`.
+///
+/// `simulate_event` 生成的报告。
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub event_id: FactEventId,
+
+    /// Rules in the order they were considered. Rules skipped because an earlier
+    /// rule consumed the event are not listed.
+    ///
+    /// 按考虑顺序排列的规则。因先前规则消费了事件而被跳过的规则不会列出。
+    pub rules: Vec<RuleSimulation>,
+
+    /// Whether a firing rule consumed the event.
+    ///
+    /// 是否有触发的规则消费了该事件。
+    pub consumed: bool,
+
+    /// State of the database after all simulated modifications.
+    ///
+    /// 所有模拟修改后的数据库状态。
+    pub resulting_db: LayeredFactDatabase,
+}
+
+impl SimulationReport {
+    /// Rules that would fire.
+    ///
+    /// 将触发的规则。
+    pub fn fired_rules(&self) -> impl Iterator<Item = &RuleSimulation> {
+        self.rules.iter().filter(|rule| rule.fired)
+    }
+
+    /// All output events that would be queued, in order.
+    ///
+    /// 将被排队的所有输出事件（按顺序）。
+    pub fn queued_outputs(&self) -> impl Iterator<Item = &FactEventId> {
+        self.fired_rules().flat_map(|rule| rule.outputs.iter())
+    }
+}
+
+struct SimulationSink {
+    rules: Vec<RuleSimulation>,
+    db: LayeredFactDatabase,
+}
+
+impl SimulationSink {
+    fn current(&mut self) -> Option<&mut RuleSimulation> {
+        self.rules.last_mut()
+    }
+}
+
+impl<A: ActionDef> RuleSink<A> for SimulationSink {
+    fn facts(&self) -> &LayeredFactDatabase {
+        &self.db
+    }
+
+    fn condition_checked(&mut self, rule: &Rule<A>, _event: &FactEvent, passed: bool) {
+        self.rules.push(RuleSimulation {
+            rule_id: rule.id.clone(),
+            priority: rule.priority,
+            condition_passed: passed,
+            fired: false,
+            modifications: Vec::new(),
+            outputs: Vec::new(),
+            actions: Vec::new(),
+            consumes_event: false,
+        });
+    }
+
+    fn rule_firing(&mut self, rule: &Rule<A>, _event: &FactEvent) {
+        if let Some(current) = self.current() {
+            current.fired = true;
+            current.actions = rule
+                .actions
+                .iter()
+                .map(|action| action.action_type().to_string())
+                .collect();
+            current.consumes_event = rule.consume_event;
+        }
+    }
+
+    fn apply_modification(&mut self, _rule: &Rule<A>, modification: &FactModification) {
+        let before = self.db.get_by_str(modification.key()).cloned();
+        modification.apply(&mut self.db);
+        let after = self.db.get_by_str(modification.key()).cloned();
+        if let Some(current) = self.current() {
+            current.modifications.push(ModificationOutcome {
+                modification: modification.clone(),
+                before,
+                after,
+            });
+        }
+    }
+
+    fn apply_modifications(&mut self, rule: &Rule<A>) {
+        if !rule.transactional {
+            for modification in &rule.modifications {
+                RuleSink::<A>::apply_modification(self, rule, modification);
+            }
+            return;
+        }
+        // A rolled back batch reports every outcome unchanged, as a real run would leave it
+        let before: Vec<_> = rule
+            .modifications
+            .iter()
+            .map(|modification| self.db.get_by_str(modification.key()).cloned())
+            .collect();
+        let _ = rule.apply_modifications(&mut self.db);
+        let outcomes: Vec<_> = rule
+            .modifications
+            .iter()
+            .zip(before)
+            .map(|(modification, before)| ModificationOutcome {
+                modification: modification.clone(),
+                before,
+                after: self.db.get_by_str(modification.key()).cloned(),
+            })
+            .collect();
+        if let Some(current) = self.current() {
+            current.modifications = outcomes;
+        }
+    }
+
+    fn queue_output(&mut self, _rule: &Rule<A>, event: FactEvent) {
+        if let Some(current) = self.current() {
+            current.outputs.push(event.id);
+        }
+    }
+}
+
+/// Simulate processing `event` against the registry without mutating anything.
+/// Modifications are applied to a clone of `db`; the real database and pending queue are untouched.
+/// Random rule selection draws from a clone of `rng`, so the report predicts what a real run
+/// with the same generator state would pick. Pass the `RuleCooldownState` and the current
+/// `Time::elapsed` as `cooldowns` to skip rules on cooldown as a real run would; the state is
+/// only read.
+///
+/// 在不修改任何状态的情况下模拟针对注册表处理 `event`。
+/// 修改会应用到 `db` 的克隆上；真实数据库和待处理队列不受影响。
+/// 随机规则选择从 `rng` 的克隆中抽取，因此报告能预测相同生成器状态下实际运行的选择结果。
+/// 将 `RuleCooldownState` 与当前的 `Time::elapsed` 作为 `cooldowns` 传入，即可像实际运行一样
+/// 跳过冷却中的规则；该状态只会被读取。
+pub fn simulate_event<A: ActionDef>(
+    event: &FactEvent,
+    db: &LayeredFactDatabase,
+    registry: &LayeredRuleRegistry<A>,
+    condition_evaluator: &ConditionEvaluator,
+    enum_registry: &EnumRegistry,
+    rng: &FreRng,
+    cooldowns: Option<(&RuleCooldownState, Duration)>,
+) -> SimulationReport {
+    let sink = dry_run(
+        event,
+        registry,
+        SimulationSink {
+            rules: Vec::new(),
+            db: db.clone(),
+        },
+        condition_evaluator,
+        enum_registry,
+        rng,
+        cooldowns,
+    );
+
+    SimulationReport {
+        event_id: event.id.clone(),
+        consumed: sink.rules.iter().any(|rule| rule.consumes_event),
+        rules: sink.rules,
+        resulting_db: sink.db,
+    }
+}
+
+/// A rule that would fire, with the effects it would have, collected without applying them.
+///
+/// 将会触发的规则及其将产生的效果，收集时不会实际应用。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RulePlan {
+    pub rule_id: String,
+
+    /// Modifications the rule would apply, in order. Empty for a transactional rule whose
+    /// batch would be rolled back.
+    ///
+    /// 规则将按顺序应用的修改。若事务性规则的修改批次将被回滚，则为空。
+    pub modifications: Vec<FactModification>,
+
+    /// Output events the rule would queue.
+    ///
+    /// 规则将排队的输出事件。
+    pub outputs: Vec<FactEventId>,
+}
+
+/// Sink that records firing rules, applying their modifications to a scratch copy of the
+/// database made on the first write.
+struct PlanSink<'a> {
+    db: Cow<'a, LayeredFactDatabase>,
+    plan: Vec<RulePlan>,
+}
+
+impl<A: ActionDef> RuleSink<A> for PlanSink<'_> {
+    fn facts(&self) -> &LayeredFactDatabase {
+        &self.db
+    }
+
+    fn rule_firing(&mut self, rule: &Rule<A>, _event: &FactEvent) {
+        self.plan.push(RulePlan {
+            rule_id: rule.id.clone(),
+            modifications: Vec::new(),
+            outputs: Vec::new(),
+        });
+    }
+
+    fn apply_modification(&mut self, _rule: &Rule<A>, modification: &FactModification) {
+        modification.apply(self.db.to_mut());
+        if let Some(current) = self.plan.last_mut() {
+            current.modifications.push(modification.clone());
+        }
+    }
+
+    fn apply_modifications(&mut self, rule: &Rule<A>) {
+        if !rule.transactional {
+            for modification in &rule.modifications {
+                RuleSink::<A>::apply_modification(self, rule, modification);
+            }
+            return;
+        }
+        if rule.modifications.is_empty() || rule.apply_modifications(self.db.to_mut()).is_err() {
+            return;
+        }
+        if let Some(current) = self.plan.last_mut() {
+            current.modifications = rule.modifications.clone();
+        }
+    }
+
+    fn queue_output(&mut self, _rule: &Rule<A>, event: FactEvent) {
+        if let Some(current) = self.plan.last_mut() {
+            current.outputs.push(event.id);
+        }
+    }
+}
+
+/// Collect the ordered plan of rules that `event` would fire, in collect-only mode.
+/// As in `simulate_event`, each planned rule's modifications are applied to a scratch copy of
+/// `db` before the next rule's conditions are evaluated, so a rule enabled by an earlier one is
+/// planned too. The copy is only made once a rule modifies something, and is discarded.
+/// `cooldowns` works as for `simulate_event`.
+///
+/// 以仅收集模式收集 `event` 将触发的规则的有序计划。
+/// 与 `simulate_event` 相同，每条计划规则的修改都会先应用到 `db` 的临时副本上，再评估下一条规则的条件，
+/// 因此由先前规则启用的规则也会被计划。副本仅在有规则修改事实时才会创建，并在之后丢弃。
+/// `cooldowns` 的作用与 `simulate_event` 相同。
+pub fn collect_event_plan<A: ActionDef>(
+    event: &FactEvent,
+    db: &LayeredFactDatabase,
+    registry: &LayeredRuleRegistry<A>,
+    condition_evaluator: &ConditionEvaluator,
+    enum_registry: &EnumRegistry,
+    rng: &FreRng,
+    cooldowns: Option<(&RuleCooldownState, Duration)>,
+) -> Vec<RulePlan> {
+    let sink = PlanSink {
+        db: Cow::Borrowed(db),
+        plan: Vec::new(),
+    };
+    dry_run(
+        event,
+        registry,
+        sink,
+        condition_evaluator,
+        enum_registry,
+        rng,
+        cooldowns,
+    )
+    .plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::CoreActionDef;
+    use crate::expr_condition::ExprConditionEvaluator;
+    use crate::systems::{LiveSink, PendingFactEvents};
+
+    pub(super) fn setup() -> (LayeredFactDatabase, LayeredRuleRegistry<CoreActionDef>) {
+        let mut db = LayeredFactDatabase::new();
+        db.set("counter", 1i64);
+        db.set("unlocked", true);
+
+        let mut registry = LayeredRuleRegistry::new();
+        registry.register(
+            Rule::builder("locked", "confirm")
+                .priority(10)
+                .condition_expr("$door_open")
+                .output("door_entered")
+                .build(),
+        );
+        registry.register(
+            Rule::builder("count", "confirm")
+                .priority(5)
+                .condition_expr("$unlocked")
+                .modify(FactModification::Increment("counter".to_string(), 2))
+                .output("counted")
+                .action(CoreActionDef::Log {
+                    message: "counted".to_string(),
+                })
+                .build(),
+        );
+        registry.register(Rule::builder("unreached", "confirm").priority(1).build());
+        (db, registry)
+    }
+
+    #[test]
+    fn test_simulation_matches_real_run() {
+        let (db, registry) = setup();
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let event = FactEvent::new("confirm");
+
+        let rng = FreRng::seeded(0);
+        let report = simulate_event(&event, &db, &registry, &evaluator, &enums, &rng, None);
+
+        // The real database is untouched by the simulation
+        assert_eq!(db.get_int("counter"), Some(1));
+
+        let mut real_db = db.clone();
+        let mut pending = PendingFactEvents::default();
+        process_event(
+            &event,
+            &registry,
+            &mut LiveSink {
+                pending: &mut pending,
+                db: &mut real_db,
+            },
+            &evaluator,
+            &enums,
+            &mut rng.clone(),
+        );
+
+        assert_eq!(
+            report.resulting_db.get_int("counter"),
+            real_db.get_int("counter")
+        );
+        let real_outputs: Vec<_> = pending.events.iter().map(|e| e.id.clone()).collect();
+        let simulated_outputs: Vec<_> = report.queued_outputs().cloned().collect();
+        assert_eq!(simulated_outputs, real_outputs);
+    }
+
+    #[test]
+    fn test_simulation_report_details() {
+        let (db, registry) = setup();
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let report = simulate_event(
+            &FactEvent::new("confirm"),
+            &db,
+            &registry,
+            &evaluator,
+            &EnumRegistry::default(),
+            &FreRng::default(),
+            None,
+        );
+
+        // "unreached" is never considered because "count" consumes the event
+        let ids: Vec<_> = report.rules.iter().map(|r| r.rule_id.as_str()).collect();
+        assert_eq!(ids, vec!["locked", "count"]);
+        assert!(report.consumed);
+
+        let locked = &report.rules[0];
+        assert!(!locked.condition_passed);
+        assert!(locked.outputs.is_empty());
+
+        let count = &report.rules[1];
+        assert!(count.condition_passed);
+        assert_eq!(count.modifications[0].before, Some(FactValue::Int(1)));
+        assert_eq!(count.modifications[0].after, Some(FactValue::Int(3)));
+        assert_eq!(count.outputs, vec![FactEventId::new("counted")]);
+        assert_eq!(count.actions, vec!["Log"]);
+    }
+
+    #[test]
+    fn test_collected_plan_matches_real_run() {
+        let (db, registry) = setup();
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let event = FactEvent::new("confirm");
+        let rng = FreRng::seeded(0);
+
+        let plan = collect_event_plan(&event, &db, &registry, &evaluator, &enums, &rng, None);
+        assert_eq!(
+            plan,
+            vec![RulePlan {
+                rule_id: "count".to_string(),
+                modifications: vec![FactModification::Increment("counter".to_string(), 2)],
+                outputs: vec![FactEventId::new("counted")],
+            }]
+        );
+        assert_eq!(db.get_int("counter"), Some(1));
+
+        let mut real_db = db.clone();
+        let mut pending = PendingFactEvents::default();
+        process_event(
+            &event,
+            &registry,
+            &mut LiveSink {
+                pending: &mut pending,
+                db: &mut real_db,
+            },
+            &evaluator,
+            &enums,
+            &mut rng.clone(),
+        );
+
+        let mut planned_db = db.clone();
+        for modification in plan.iter().flat_map(|rule| &rule.modifications) {
+            modification.apply(&mut planned_db);
+        }
+        assert_eq!(planned_db.get_int("counter"), real_db.get_int("counter"));
+        let real_outputs: Vec<_> = pending.events.iter().map(|e| e.id.clone()).collect();
+        let planned_outputs: Vec<_> = plan.iter().flat_map(|rule| rule.outputs.clone()).collect();
+        assert_eq!(planned_outputs, real_outputs);
+    }
+
+    #[test]
+    fn test_plan_includes_rules_enabled_by_earlier_ones() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("has_key", true);
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(
+            Rule::builder("unlock", "use")
+                .priority(10)
+                .condition_expr("$has_key")
+                .consume_event(false)
+                .modify(FactModification::Set("door_open".to_string(), true.into()))
+                .build(),
+        );
+        registry.register(
+            Rule::builder("enter", "use")
+                .priority(5)
+                .condition_expr("$door_open")
+                .output("entered")
+                .build(),
+        );
+
+        let plan = collect_event_plan(
+            &FactEvent::new("use"),
+            &db,
+            &registry,
+            &ConditionEvaluator::new(ExprConditionEvaluator),
+            &EnumRegistry::default(),
+            &FreRng::default(),
+            None,
+        );
+        let fired: Vec<_> = plan.iter().map(|rule| rule.rule_id.as_str()).collect();
+        assert_eq!(fired, ["unlock", "enter"]);
+        assert!(!db.contains("door_open"));
+    }
+
+    #[test]
+    fn test_dry_runs_skip_rules_on_cooldown() {
+        let (mut db, mut registry) = setup();
+        registry.register(
+            Rule::builder("count", "confirm")
+                .priority(5)
+                .condition_expr("$unlocked")
+                .cooldown(Duration::from_secs(1))
+                .build(),
+        );
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let event = FactEvent::new("confirm");
+        let rng = FreRng::default();
+
+        let mut state = RuleCooldownState::default();
+        process_event(
+            &event,
+            &registry,
+            &mut CooldownSink::new(
+                LiveSink {
+                    pending: &mut PendingFactEvents::default(),
+                    db: &mut db,
+                },
+                &mut state,
+                Duration::ZERO,
+            ),
+            &evaluator,
+            &enums,
+            &mut rng.clone(),
+        );
+        let fired = |at: u64| {
+            let cooldowns = Some((&state, Duration::from_millis(at)));
+            let plan =
+                collect_event_plan(&event, &db, &registry, &evaluator, &enums, &rng, cooldowns);
+            let report =
+                simulate_event(&event, &db, &registry, &evaluator, &enums, &rng, cooldowns);
+            let explained = registry.explain(&event, &db, &evaluator, &enums, &rng, cooldowns);
+            let planned: Vec<_> = plan.into_iter().map(|rule| rule.rule_id).collect();
+            let simulated: Vec<_> = report.fired_rules().map(|r| r.rule_id.clone()).collect();
+            let matched: Vec<_> = explained
+                .into_iter()
+                .filter(|rule| rule.fired)
+                .map(|rule| rule.rule_id)
+                .collect();
+            assert_eq!(planned, simulated);
+            assert_eq!(planned, matched);
+            planned
+        };
+
+        // While "count" cools down the event falls through to "unreached"
+        assert_eq!(fired(500), ["unreached"]);
+        assert_eq!(fired(1500), ["count"]);
+        assert_eq!(state.last_fired("count"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_dry_runs_roll_back_rejected_transactional_batch() {
+        use crate::schema::{FactType, SchemaPolicy};
+
+        let mut db = LayeredFactDatabase::new();
+        db.schema_mut().declare("hp", FactType::Int);
+        db.schema_mut().set_policy(SchemaPolicy::Reject);
+        db.set("gold", 10i64);
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(
+            Rule::builder("buy", "buy")
+                .priority(1)
+                .transactional(true)
+                .consume_event(false)
+                .modify(FactModification::Increment("gold".to_string(), -5))
+                .modify(FactModification::Set("hp".to_string(), "full".into()))
+                .build(),
+        );
+        // Only fires if the rejected batch leaked its first write
+        registry.register(
+            Rule::builder("spent", "buy")
+                .condition_expr("float($gold) < 10.0")
+                .build(),
+        );
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let (event, rng) = (FactEvent::new("buy"), FreRng::seeded(0));
+
+        let report = simulate_event(&event, &db, &registry, &evaluator, &enums, &rng, None);
+        assert_eq!(report.resulting_db.get_int("gold"), Some(10));
+        assert!(!report.resulting_db.contains("hp"));
+        let buy = &report.rules[0];
+        assert_eq!(buy.modifications[0].before, Some(FactValue::Int(10)));
+        assert_eq!(buy.modifications[0].after, Some(FactValue::Int(10)));
+        let fired: Vec<_> = report.fired_rules().map(|r| r.rule_id.as_str()).collect();
+        assert_eq!(fired, ["buy"]);
+
+        let plan = collect_event_plan(&event, &db, &registry, &evaluator, &enums, &rng, None);
+        assert_eq!(plan.len(), 1);
+        assert!(plan[0].modifications.is_empty());
+    }
+}
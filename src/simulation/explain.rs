@@ -0,0 +1,183 @@
+//! # explain.rs
+//!
+//! # explain.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! `LayeredRuleRegistry::explain`, the lightest dry run: it reports which rules an event would
+//! consider and which of them would fire, ignoring every effect, for debugging overlays.
+//!
+//! `LayeredRuleRegistry::explain`，最轻量的试运行：报告事件将考虑哪些规则以及其中哪些会触发，
+//! 忽略所有效果，供调试叠加层使用。
+
+use std::time::Duration;
+
+use super::dry_run;
+use crate::asset::{ActionDef, EnumRegistry};
+use crate::cooldown::RuleCooldownState;
+use crate::event::FactEvent;
+use crate::layered::LayeredFactDatabase;
+use crate::rule::{FactModification, FreRng, LayeredRuleRegistry, Rule};
+use crate::systems::{ConditionEvaluator, RuleSink};
+
+/// How one rule considered for an event fared, as reported by `LayeredRuleRegistry::explain`.
+///
+/// 由 `LayeredRuleRegistry::explain` 报告的、处理事件时所考虑的单条规则的结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    pub rule_id: String,
+    pub priority: i32,
+
+    /// Whether the rule's conditions passed.
+    ///
+    /// 规则条件是否通过。
+    pub condition_passed: bool,
+
+    /// Whether the rule would fire. A passing rule in a random or `HighestScore` group only
+    /// fires if it is the one selected.
+    ///
+    /// 规则是否会触发。随机或 `HighestScore` 组中通过的规则只有被选中时才会触发。
+    pub fired: bool,
+
+    /// Whether firing this rule would consume the event, ending the processing.
+    ///
+    /// 触发此规则是否会消费事件并结束处理。
+    pub would_consume: bool,
+}
+
+/// Sink that reports every considered rule and ignores all effects.
+struct ExplainSink<'a> {
+    db: &'a LayeredFactDatabase,
+    matches: Vec<RuleMatch>,
+}
+
+impl<A: ActionDef> RuleSink<A> for ExplainSink<'_> {
+    fn facts(&self) -> &LayeredFactDatabase {
+        self.db
+    }
+
+    fn condition_checked(&mut self, rule: &Rule<A>, _event: &FactEvent, passed: bool) {
+        self.matches.push(RuleMatch {
+            rule_id: rule.id.clone(),
+            priority: rule.priority,
+            condition_passed: passed,
+            fired: false,
+            would_consume: false,
+        });
+    }
+
+    fn rule_firing(&mut self, rule: &Rule<A>, _event: &FactEvent) {
+        if let Some(current) = self.matches.last_mut() {
+            current.fired = true;
+            current.would_consume = rule.consume_event;
+        }
+    }
+
+    fn apply_modification(&mut self, _rule: &Rule<A>, _modification: &FactModification) {}
+
+    fn queue_output(&mut self, _rule: &Rule<A>, _event: FactEvent) {}
+}
+
+impl<A: ActionDef> LayeredRuleRegistry<A> {
+    /// Report the rules `event` would consider, in the order `process_rules_system` considers
+    /// them, and which of them would fire, without touching any state. Rules after the one
+    /// that would consume the event are not listed. Every condition is evaluated against `db` as
+    /// it is, ignoring the modifications of earlier rules, and random rule selection draws from
+    /// a clone of `rng`. `cooldowns` works as for `simulate_event`.
+    ///
+    /// 报告 `event` 将考虑的规则（按 `process_rules_system` 考虑它们的顺序）以及其中哪些会触发，
+    /// 且不修改任何状态。将消费事件的规则之后的规则不会列出。所有条件都针对原样的 `db` 评估，
+    /// 忽略先前规则的修改，随机规则选择从 `rng` 的克隆中抽取。`cooldowns` 的作用与 `simulate_event` 相同。
+    pub fn explain(
+        &self,
+        event: &FactEvent,
+        db: &LayeredFactDatabase,
+        condition_evaluator: &ConditionEvaluator,
+        enum_registry: &EnumRegistry,
+        rng: &FreRng,
+        cooldowns: Option<(&RuleCooldownState, Duration)>,
+    ) -> Vec<RuleMatch> {
+        let sink = ExplainSink {
+            db,
+            matches: Vec::new(),
+        };
+        dry_run(
+            event,
+            self,
+            sink,
+            condition_evaluator,
+            enum_registry,
+            rng,
+            cooldowns,
+        )
+        .matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::FactEventId;
+    use crate::expr_condition::ExprConditionEvaluator;
+    use crate::simulation::tests::setup;
+    use crate::systems::{LiveSink, PendingFactEvents, process_event};
+
+    #[test]
+    fn test_explain_matches_real_run() {
+        let (mut db, mut registry) = setup();
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let event = FactEvent::new("confirm");
+        let rng = FreRng::seeded(0);
+
+        // "unreached" is never considered because "count" consumes the event
+        let explained = registry.explain(&event, &db, &evaluator, &enums, &rng, None);
+        let considered: Vec<_> = explained
+            .iter()
+            .map(|rule| (rule.rule_id.as_str(), rule.priority, rule.condition_passed))
+            .collect();
+        assert_eq!(considered, vec![("locked", 10, false), ("count", 5, true)]);
+        assert!(explained[1].would_consume);
+        assert_eq!(db.get_int("counter"), Some(1));
+
+        // Without consumption, every passing rule fires in the reported order
+        registry.register(
+            Rule::builder("opened", "confirm")
+                .priority(10)
+                .condition_expr("$unlocked")
+                .consume_event(false)
+                .output("opened")
+                .build(),
+        );
+        let explained = registry.explain(&event, &db, &evaluator, &enums, &rng, None);
+        let mut pending = PendingFactEvents::default();
+        process_event(
+            &event,
+            &registry,
+            &mut LiveSink {
+                pending: &mut pending,
+                db: &mut db,
+            },
+            &evaluator,
+            &enums,
+            &mut rng.clone(),
+        );
+
+        let fired: Vec<_> = explained
+            .iter()
+            .filter(|rule| rule.fired)
+            .map(|rule| rule.rule_id.as_str())
+            .collect();
+        assert_eq!(fired, vec!["opened", "count"]);
+        let real_outputs: Vec<_> = pending.events.iter().map(|e| e.id.clone()).collect();
+        assert_eq!(
+            real_outputs,
+            vec![FactEventId::new("opened"), FactEventId::new("counted")]
+        );
+        assert!(!explained[1].would_consume);
+        assert!(explained[2].would_consume);
+        assert!(explained.iter().all(|rule| rule.rule_id != "unreached"));
+    }
+}
@@ -0,0 +1,115 @@
+//! # spatial.rs
+//!
+//! Built-in conditions on vector facts.
+//! A rule condition written as `distance($a, $b) < 5.0` passes when the facts `a` and `b` are
+//! vectors of the same dimension that lie closer than the given distance. Like the
+//! `event.data.*` conditions, it is checked by `ConditionEvaluator` itself, so spatial rules
+//! work without teaching the game's evaluator about vectors.
+//!
+//! 针对向量事实的内置条件。
+//! 写作 `distance($a, $b) < 5.0` 的规则条件在事实 `a` 和 `b` 是相同维度的向量且距离小于给定值时通过。
+//! 与 `event.data.*` 条件一样，它由 `ConditionEvaluator` 自身检查，
+//! 因此空间规则无需让游戏的评估器理解向量即可工作。
+
+use crate::database::FactReader;
+
+/// `distance($a, $b) < max`: two vector facts are closer than `max`.
+///
+/// `distance($a, $b) < max`：两个向量事实的距离小于 `max`。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceLessThan<'a> {
+    pub a: &'a str,
+    pub b: &'a str,
+    pub max: f32,
+}
+
+impl<'a> DistanceLessThan<'a> {
+    /// Parse a condition expression, returning None if it is not a distance condition.
+    ///
+    /// 解析条件表达式；如果不是距离条件则返回 None。
+    pub fn parse(condition: &'a str) -> Option<Self> {
+        let rest = condition.trim().strip_prefix("distance(")?;
+        let (args, comparison) = rest.split_once(')')?;
+        let (a, b) = args.split_once(',')?;
+        let max = comparison.trim().strip_prefix('<')?.trim().parse().ok()?;
+        Some(Self {
+            a: a.trim().strip_prefix('$')?,
+            b: b.trim().strip_prefix('$')?,
+            max,
+        })
+    }
+
+    /// Whether both facts are vectors of the same dimension closer than `max`.
+    /// Missing or non-vector facts never match.
+    ///
+    /// 两个事实是否为相同维度且距离小于 `max` 的向量。缺失或非向量的事实永远不匹配。
+    pub fn matches(&self, facts: &dyn FactReader) -> bool {
+        let (Some(a), Some(b)) = (facts.get_by_str(self.a), facts.get_by_str(self.b)) else {
+            return false;
+        };
+        a.distance(b).is_some_and(|distance| distance < self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry, FreAsset};
+    use crate::event::FactEvent;
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::{FreRng, LayeredRuleRegistry};
+    use crate::systems::{ConditionEvaluator, LiveSink, PendingFactEvents, process_event_rules};
+    use bevy::math::Vec2;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            DistanceLessThan::parse("distance($player, $enemy) < 5.0"),
+            Some(DistanceLessThan {
+                a: "player",
+                b: "enemy",
+                max: 5.0
+            })
+        );
+        assert_eq!(DistanceLessThan::parse("distance(player, enemy) < 5"), None);
+        assert_eq!(DistanceLessThan::parse("$hp < 5"), None);
+    }
+
+    #[test]
+    fn test_distance_rule_between_vector_facts() {
+        let asset: FreAsset = ron::from_str(
+            r#"(
+                facts: {"player": Vec2((0.0, 0.0)), "enemy": Vec2((3.0, 4.0))},
+                rules: [(id: "alert", event: Event("tick"), conditions: ["distance($player, $enemy) < 6.0"], outputs: ["alerted"])],
+            )"#,
+        )
+        .unwrap();
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        asset.register_rules_layered(&mut registry);
+        let mut db = LayeredFactDatabase::new();
+        for (key, value) in asset.resolve_facts(&EnumRegistry::default()) {
+            db.set(key, value);
+        }
+
+        let fires = |db: &mut LayeredFactDatabase| {
+            let event = FactEvent::new("tick");
+            let mut pending = PendingFactEvents::default();
+            process_event_rules(
+                &event,
+                registry.get_matching_rules_grouped(&event),
+                &mut LiveSink {
+                    pending: &mut pending,
+                    db,
+                },
+                &ConditionEvaluator::default(),
+                &EnumRegistry::default(),
+                &mut FreRng::seeded(0),
+            );
+            !pending.events.is_empty()
+        };
+
+        assert!(fires(&mut db));
+        db.set("enemy", Vec2::new(30.0, 40.0));
+        assert!(!fires(&mut db));
+    }
+}
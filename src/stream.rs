@@ -0,0 +1,195 @@
+//! # stream.rs
+//!
+//! A channel of rule-fire notifications for external tools (`stream` feature).
+//! Connecting `RuleEventStream` returns the receiving end of a `std::sync::mpsc` channel;
+//! while connected, `process_rules_system` sends a `RuleFireRecord` for every rule that fires.
+//! The tool drains the receiver on its own thread. Dropping the receiver disconnects the stream
+//! on the next send, after which rule processing no longer records fired rules.
+//!
+//! 面向外部工具的规则触发通知通道（`stream` 特性）。
+//! 连接 `RuleEventStream` 会返回 `std::sync::mpsc` 通道的接收端；连接期间，
+//! `process_rules_system` 会为每条触发的规则发送一条 `RuleFireRecord`。
+//! 工具在自己的线程上读取接收端。丢弃接收端后，流会在下一次发送时断开，
+//! 此后规则处理不再记录触发的规则。
+
+use bevy::prelude::*;
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use crate::action_dispatch::ActionPhase;
+use crate::asset::ActionDef;
+use crate::event::{FactEvent, FactEventId};
+use crate::layered::LayeredFactDatabase;
+use crate::rule::{FactModification, Rule};
+use crate::systems::RuleSink;
+
+/// One rule firing.
+///
+/// 一次规则触发。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleFireRecord {
+    pub rule_id: String,
+    pub event_id: FactEventId,
+
+    /// Value of Bevy's `FrameCount` when the rule fired (0 without `FrameCountPlugin`).
+    ///
+    /// 规则触发时 Bevy `FrameCount` 的值（没有 `FrameCountPlugin` 时为 0）。
+    pub frame: u32,
+}
+
+/// Optional sender of rule-fire notifications.
+///
+/// 可选的规则触发通知发送端。
+#[derive(Resource, Debug, Default)]
+pub struct RuleEventStream {
+    sender: Option<Sender<RuleFireRecord>>,
+}
+
+impl RuleEventStream {
+    /// Start streaming, returning the receiver. Replaces any previous connection.
+    ///
+    /// 开始推送并返回接收端。会替换之前的连接。
+    pub fn connect(&mut self) -> Receiver<RuleFireRecord> {
+        let (sender, receiver) = channel();
+        self.sender = Some(sender);
+        receiver
+    }
+
+    pub fn disconnect(&mut self) {
+        self.sender = None;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    /// Send a record for each fired rule, disconnecting if the receiver was dropped.
+    pub(crate) fn send_fired(&mut self, event: &FactEvent, fired: Vec<String>, frame: u32) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        for rule_id in fired {
+            let record = RuleFireRecord {
+                rule_id,
+                event_id: event.id.clone(),
+                frame,
+            };
+            if sender.send(record).is_err() {
+                self.sender = None;
+                return;
+            }
+        }
+    }
+}
+
+/// Sink wrapper that records the ids of the rules that fire through it.
+pub(crate) struct FiredRules<S> {
+    pub(crate) inner: S,
+    pub(crate) fired: Vec<String>,
+}
+
+impl<S> FiredRules<S> {
+    pub(crate) fn new(inner: S) -> Self {
+        Self {
+            inner,
+            fired: Vec::new(),
+        }
+    }
+}
+
+impl<A: ActionDef, S: RuleSink<A>> RuleSink<A> for FiredRules<S> {
+    fn facts(&self) -> &LayeredFactDatabase {
+        self.inner.facts()
+    }
+
+    fn can_fire(&self, rule: &Rule<A>) -> bool {
+        self.inner.can_fire(rule)
+    }
+
+    fn condition_checked(&mut self, rule: &Rule<A>, event: &FactEvent, passed: bool) {
+        self.inner.condition_checked(rule, event, passed);
+    }
+
+    fn rule_firing(&mut self, rule: &Rule<A>, event: &FactEvent) {
+        self.fired.push(rule.id.clone());
+        self.inner.rule_firing(rule, event);
+    }
+
+    fn run_actions(&mut self, rule: &Rule<A>, phase: ActionPhase) {
+        self.inner.run_actions(rule, phase);
+    }
+
+    fn apply_modification(&mut self, rule: &Rule<A>, modification: &FactModification) {
+        self.inner.apply_modification(rule, modification);
+    }
+
+    fn apply_modifications(&mut self, rule: &Rule<A>) {
+        self.inner.apply_modifications(rule);
+    }
+
+    fn queue_output(&mut self, rule: &Rule<A>, event: FactEvent) {
+        self.inner.queue_output(rule, event);
+    }
+
+    fn rule_finished(&mut self, rule: &Rule<A>) {
+        self.inner.rule_finished(rule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{CoreActionDef, EnumRegistry};
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::{FreRng, LayeredRuleRegistry, Rule};
+    use crate::systems::{ConditionEvaluator, PendingFactEvents, process_rules_system};
+    use bevy::diagnostic::FrameCount;
+
+    fn stream_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<PendingFactEvents>()
+            .init_resource::<ConditionEvaluator>()
+            .init_resource::<EnumRegistry>()
+            .init_resource::<FreRng>()
+            .init_resource::<RuleEventStream>()
+            .insert_resource(FrameCount(7))
+            .add_message::<FactEvent>()
+            .add_systems(Update, process_rules_system::<CoreActionDef>);
+        let mut registry = app
+            .world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>();
+        registry.register(Rule::builder("log_hit", "hit").consume_event(false).build());
+        registry.register(Rule::builder("count_hit", "hit").build());
+        registry.register(
+            Rule::builder("never", "hit")
+                .condition_expr("float($missing) > 0")
+                .build(),
+        );
+        app
+    }
+
+    #[test]
+    fn test_fired_rules_reach_the_receiver() {
+        let mut app = stream_app();
+        let receiver = app.world_mut().resource_mut::<RuleEventStream>().connect();
+        app.world_mut().write_message(FactEvent::new("hit"));
+        app.world_mut().write_message(FactEvent::new("miss"));
+        app.update();
+
+        let mut records: Vec<RuleFireRecord> = receiver.try_iter().collect();
+        records.sort_by(|a, b| a.rule_id.cmp(&b.rule_id));
+        let expected = |rule_id: &str| RuleFireRecord {
+            rule_id: rule_id.to_string(),
+            event_id: FactEventId::new("hit"),
+            frame: 7,
+        };
+        assert_eq!(records, vec![expected("count_hit"), expected("log_hit")]);
+
+        // Dropping the receiver disconnects the stream on the next send
+        drop(receiver);
+        app.world_mut().write_message(FactEvent::new("hit"));
+        app.update();
+        assert!(!app.world().resource::<RuleEventStream>().is_connected());
+    }
+}
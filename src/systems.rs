@@ -4,13 +4,29 @@
 //!
 //! FRE 循环处理的核心系统。
 
-use crate::asset::{ActionDef, EnumRegistry};
-use crate::database::FactReader;
+use crate::action_dispatch::{ActionPhase, ActionSink};
+use crate::asset::{ActionDef, ActionHandlerRegistry, EnumRegistry};
+use crate::cooldown::{CooldownSink, RuleCooldownState};
+use crate::debug_hooks::{DebugSink, RuleDebugHooks};
 use crate::event::FactEvent;
+use crate::event_chain::EventChainLimit;
 use crate::layered::LayeredFactDatabase;
-use crate::rule::{LayeredRuleRegistry, Rule};
+use crate::rule::{
+    CATCH_ALL_TRIGGER, FactModification, FreRng, LayeredRuleRegistry, Rule, RuleRegistryChanged,
+    SelectPolicy, group_policy, resolve_highest_score, select_rule,
+};
+#[cfg(feature = "stream")]
+use crate::stream::{FiredRules, RuleEventStream};
+#[cfg(feature = "stream")]
+use bevy::diagnostic::FrameCount;
 use bevy::prelude::*;
-use std::sync::Arc;
+use std::time::Duration;
+
+mod compiled;
+mod conditions;
+
+pub(crate) use compiled::CompiledConditions;
+pub use conditions::{ConditionEvaluator, ConditionEvaluatorTrait};
 
 /// Resource to queue output events between systems.
 /// Provides deduplication to prevent duplicate events from multiple rule processors.
@@ -20,24 +36,34 @@ use std::sync::Arc;
 #[derive(Resource, Default)]
 pub struct PendingFactEvents {
     pub events: Vec<FactEvent>,
-    /// Track rule IDs that have already emitted outputs this frame to avoid duplicates.
+    /// Outputs each rule has already emitted this frame, to avoid duplicates.
     ///
-    /// 跟踪本帧已发出 outputs 的规则 ID，以避免重复。
-    emitted_by_rule: std::collections::HashSet<String>,
+    /// 每条规则本帧已发出的 outputs，以避免重复。
+    emitted_by_rule: std::collections::HashMap<String, Vec<FactEvent>>,
 }
 
 impl PendingFactEvents {
-    /// Queue an output event from a rule, with deduplication.
-    /// Returns true if the event was queued, false if it was already queued by this rule.
+    /// Queue an output event from a rule, with deduplication, one level deeper in its chain.
+    /// Returns true if the event was queued, false if this rule already queued the same event:
+    /// same id, entity, and data. Outputs for different entities are all queued.
     ///
-    /// 从规则排队输出事件，带去重。
-    /// 如果事件被排队返回 true，如果此规则已排队过则返回 false。
-    pub fn queue_output(&mut self, rule_id: &str, event: FactEvent) -> bool {
-        let key = format!("{}:{}", rule_id, event.id.0);
-        if self.emitted_by_rule.contains(&key) {
-            return false;
-        }
-        self.emitted_by_rule.insert(key);
+    /// 从规则排队输出事件，带去重，并把其事件链深度加一。
+    /// 如果事件被排队返回 true；如果此规则已排队过相同的事件（相同的 ID、实体和数据）则返回 false。
+    /// 针对不同实体的输出都会被排队。
+    pub fn queue_output(&mut self, rule_id: &str, mut event: FactEvent) -> bool {
+        let same_output = |queued: &FactEvent| {
+            queued.id == event.id
+                && queued.entity == event.entity
+                && queued.data() == event.data()
+                && queued.typed_data() == event.typed_data()
+        };
+        let emitted = match self.emitted_by_rule.get_mut(rule_id) {
+            Some(emitted) if emitted.iter().any(same_output) => return false,
+            Some(emitted) => emitted,
+            None => self.emitted_by_rule.entry(rule_id.to_string()).or_default(),
+        };
+        emitted.push(event.clone());
+        event.depth = event.depth.saturating_add(1);
         self.events.push(event);
         true
     }
@@ -52,88 +78,11 @@ impl PendingFactEvents {
     }
 }
 
-/// Trait for evaluating rule condition expressions.
-/// Implement this to provide custom condition evaluation logic.
-///
-/// 用于评估规则条件表达式的 trait。
-/// 实现此 trait 以提供自定义条件评估逻辑。
-pub trait ConditionEvaluatorTrait: Send + Sync + 'static {
-    /// Evaluate all condition expressions for a rule.
-    /// Returns true if all conditions pass or if there are no conditions.
-    ///
-    /// 评估规则的所有条件表达式。
-    /// 如果所有条件都通过或没有条件，返回 true。
-    fn evaluate(&self, conditions: &[String], facts: &dyn FactReader, enums: &EnumRegistry)
-    -> bool;
-}
-
-/// Default condition evaluator that always returns true (matches "Always" behavior).
-///
-/// 默认条件评估器，始终返回 true（匹配 "Always" 行为）。
-#[derive(Default)]
-pub struct DefaultConditionEvaluator;
-
-impl ConditionEvaluatorTrait for DefaultConditionEvaluator {
-    fn evaluate(
-        &self,
-        _conditions: &[String],
-        _facts: &dyn FactReader,
-        _enums: &EnumRegistry,
-    ) -> bool {
-        // Default evaluator keeps FRE bootstrappable before a game installs
-        // its own expression-aware evaluator.
-        true
-    }
-}
-
-/// Resource that holds the condition evaluator function.
-/// Games should replace this with their own evaluator that understands their expression syntax.
-///
-/// 持有条件评估器函数的资源。
-/// 游戏应该用自己的评估器替换它，以理解其表达式语法。
-#[derive(Resource)]
-pub struct ConditionEvaluator {
-    evaluator: Arc<dyn ConditionEvaluatorTrait>,
-}
-
-impl Default for ConditionEvaluator {
-    fn default() -> Self {
-        Self {
-            evaluator: Arc::new(DefaultConditionEvaluator),
-        }
-    }
-}
-
-impl ConditionEvaluator {
-    /// Create a new condition evaluator with a custom implementation.
-    ///
-    /// 使用自定义实现创建新的条件评估器。
-    pub fn new<T: ConditionEvaluatorTrait>(evaluator: T) -> Self {
-        Self {
-            evaluator: Arc::new(evaluator),
-        }
-    }
-
-    /// Evaluate conditions for a rule.
-    ///
-    /// 评估规则的条件。
-    pub fn evaluate<A: ActionDef>(
-        &self,
-        rule: &Rule<A>,
-        facts: &dyn FactReader,
-        enums: &EnumRegistry,
-    ) -> bool {
-        if rule.condition_expressions.is_empty() {
-            return true; // No conditions = always match
-        }
-        self.evaluator
-            .evaluate(&rule.condition_expressions, facts, enums)
-    }
-}
-
 /// Main system for processing the FRE loop using LayeredFactDatabase and LayeredRuleRegistry:
 /// Listen to Events -> Find matching Rules (grouped by priority) -> Check Fact conditions
 /// -> Execute Actions/Modifications -> Queue output Events
+/// Actions run through `ActionHandlerRegistry`, before or after the modifications per its
+/// `ActionPhase`.
 ///
 /// Priority and matching rules:
 /// 1. Rules are grouped by priority (higher priority groups checked first)
@@ -144,6 +93,7 @@ impl ConditionEvaluator {
 /// 使用 LayeredFactDatabase 和 LayeredRuleRegistry 处理 FRE 循环的主系统：
 /// 监听事件 -> 查找匹配规则（按优先级分组）-> 检查事实条件
 /// -> 执行动作/修改 -> 排队输出事件
+/// 动作通过 `ActionHandlerRegistry` 执行，按其 `ActionPhase` 在修改之前或之后运行。
 ///
 /// 优先级和匹配规则：
 /// 1. 规则按优先级分组（高优先级组先检查）
@@ -157,59 +107,376 @@ pub fn process_rules_system<A: ActionDef>(
     mut pending_events: ResMut<PendingFactEvents>,
     condition_evaluator: Res<ConditionEvaluator>,
     enum_registry: Res<EnumRegistry>,
+    mut rng: ResMut<FreRng>,
+    mut debug_hooks: Option<ResMut<RuleDebugHooks>>,
+    mut cooldowns: Option<ResMut<RuleCooldownState>>,
+    time: Option<Res<Time>>,
+    mut chain_limit: Option<ResMut<EventChainLimit>>,
+    handlers: Option<Res<ActionHandlerRegistry<A>>>,
+    mut commands: Commands,
+    #[cfg(feature = "stream")] mut stream: Option<ResMut<RuleEventStream>>,
+    #[cfg(feature = "stream")] frame: Option<Res<FrameCount>>,
 ) {
     let events_to_process: Vec<FactEvent> = events.read().cloned().collect();
+    let now = time.map(|time| time.elapsed());
+    #[cfg(feature = "stream")]
+    let frame = frame.map_or(0, |frame| frame.0);
 
     for event in events_to_process {
-        let rule_groups = registry.get_matching_rules_grouped(&event);
-        process_event_rules(
+        if let Some(limit) = chain_limit.as_deref_mut()
+            && !limit.admits(&event)
+        {
+            continue;
+        }
+        let live = LiveSink {
+            pending: &mut pending_events,
+            db: &mut layered_db,
+        };
+        let handlers = handlers.as_deref();
+        let cooldowns = cooldowns.as_deref_mut().zip(now);
+        #[cfg(feature = "stream")]
+        let stream = stream
+            .as_deref_mut()
+            .filter(|stream| stream.is_connected())
+            .map(|stream| (stream, frame));
+        let hooks = debug_hooks.as_deref_mut().filter(|hooks| !hooks.is_empty());
+        let Some(hooks) = hooks else {
+            process_live_event(
+                &event,
+                &registry,
+                ActionSink::new(live, handlers, &mut commands),
+                &condition_evaluator,
+                &enum_registry,
+                &mut rng,
+                cooldowns,
+                #[cfg(feature = "stream")]
+                stream,
+            );
+            continue;
+        };
+        let sink = DebugSink::new(live, hooks, &condition_evaluator, &enum_registry);
+        process_live_event(
             &event,
-            rule_groups,
-            &mut layered_db,
-            &mut pending_events,
+            &registry,
+            ActionSink::new(sink, handlers, &mut commands),
             &condition_evaluator,
             &enum_registry,
+            &mut rng,
+            cooldowns,
+            #[cfg(feature = "stream")]
+            stream,
         );
     }
 }
 
-/// Process a single event against prioritized rule groups.
-fn process_event_rules<A: ActionDef>(
+/// Process an event for real, skipping rules on cooldown when `cooldowns` holds the state
+/// and the current time.
+fn process_live_event<A: ActionDef>(
     event: &FactEvent,
-    rule_groups: Vec<Vec<&Rule<A>>>,
-    layered_db: &mut LayeredFactDatabase,
-    pending_events: &mut PendingFactEvents,
+    registry: &LayeredRuleRegistry<A>,
+    sink: impl RuleSink<A>,
     condition_evaluator: &ConditionEvaluator,
     enum_registry: &EnumRegistry,
+    rng: &mut FreRng,
+    cooldowns: Option<(&mut RuleCooldownState, Duration)>,
+    #[cfg(feature = "stream")] stream: Option<(&mut RuleEventStream, u32)>,
 ) {
-    'outer: for group in rule_groups {
-        for rule in group {
-            if !condition_evaluator.evaluate(rule, layered_db, enum_registry) {
-                trace!("FRE: Rule '{}' skipped - conditions not met", rule.id);
-                continue;
-            }
+    let Some((state, now)) = cooldowns else {
+        return process_streamed_event(
+            event,
+            registry,
+            sink,
+            condition_evaluator,
+            enum_registry,
+            rng,
+            #[cfg(feature = "stream")]
+            stream,
+        );
+    };
+    process_streamed_event(
+        event,
+        registry,
+        CooldownSink::new(sink, state, now),
+        condition_evaluator,
+        enum_registry,
+        rng,
+        #[cfg(feature = "stream")]
+        stream,
+    );
+}
 
-            info!(
-                "FRE: Rule '{}' triggered by event '{}' (priority: {}, conditions: {})",
-                rule.id,
-                event.id.0,
-                rule.priority,
-                rule.condition_expressions.len()
+/// Process an event, sending the rules that fired to a connected `RuleEventStream`.
+fn process_streamed_event<A: ActionDef>(
+    event: &FactEvent,
+    registry: &LayeredRuleRegistry<A>,
+    mut sink: impl RuleSink<A>,
+    condition_evaluator: &ConditionEvaluator,
+    enum_registry: &EnumRegistry,
+    rng: &mut FreRng,
+    #[cfg(feature = "stream")] stream: Option<(&mut RuleEventStream, u32)>,
+) {
+    #[cfg(feature = "stream")]
+    if let Some((stream, frame)) = stream {
+        let mut recording = FiredRules::new(sink);
+        process_event(
+            event,
+            registry,
+            &mut recording,
+            condition_evaluator,
+            enum_registry,
+            rng,
+        );
+        stream.send_fired(event, recording.fired, frame);
+        return;
+    }
+    process_event(
+        event,
+        registry,
+        &mut sink,
+        condition_evaluator,
+        enum_registry,
+        rng,
+    );
+}
+
+/// Receives the effects of processing an event against prioritized rule groups, and owns the
+/// facts the rules are evaluated against. `LiveSink` commits to the real database and pending
+/// queue; dry runs and plans provide their own sinks, which may never touch the database.
+///
+/// 接收针对按优先级分组的规则处理事件时产生的效果，并持有规则评估所用的事实。
+/// `LiveSink` 提交到真实的数据库和待处理队列；试运行和计划收集提供自己的接收者，
+/// 它们可以完全不修改数据库。
+pub(crate) trait RuleSink<A: ActionDef> {
+    /// Facts that conditions are evaluated against.
+    fn facts(&self) -> &LayeredFactDatabase;
+
+    /// Whether a matching rule may fire at all. Rules that may not are treated as if their
+    /// conditions failed.
+    fn can_fire(&self, _rule: &Rule<A>) -> bool {
+        true
+    }
+
+    /// Called after a rule's conditions were evaluated. A passing rule in a group with a
+    /// random or `HighestScore` policy only fires if it is the one selected.
+    fn condition_checked(&mut self, _rule: &Rule<A>, _event: &FactEvent, _passed: bool) {}
+
+    /// Called when a passing rule is about to fire, before its actions run.
+    fn rule_firing(&mut self, _rule: &Rule<A>, _event: &FactEvent) {}
+
+    /// Called just before and just after a firing rule's modifications are handled, so the
+    /// rule's actions can run in the configured phase.
+    fn run_actions(&mut self, _rule: &Rule<A>, _phase: ActionPhase) {}
+
+    /// Handle one of a firing rule's modifications.
+    fn apply_modification(&mut self, rule: &Rule<A>, modification: &FactModification);
+
+    /// Handle all of a firing rule's modifications, one at a time by default. Sinks wrapping
+    /// another sink forward this, so transactional rules reach the live sink as a batch.
+    fn apply_modifications(&mut self, rule: &Rule<A>) {
+        for modification in &rule.modifications {
+            self.apply_modification(rule, modification);
+        }
+    }
+
+    /// Queue an output event of a firing rule.
+    fn queue_output(&mut self, rule: &Rule<A>, event: FactEvent);
+
+    /// Called once a considered rule is done, whether or not it fired.
+    fn rule_finished(&mut self, _rule: &Rule<A>) {}
+}
+
+/// Sink used by `process_rules_system`: applies modifications and queues outputs for real.
+pub(crate) struct LiveSink<'a> {
+    pub(crate) pending: &'a mut PendingFactEvents,
+    pub(crate) db: &'a mut LayeredFactDatabase,
+}
+
+impl<A: ActionDef> RuleSink<A> for LiveSink<'_> {
+    fn facts(&self) -> &LayeredFactDatabase {
+        self.db
+    }
+
+    fn condition_checked(&mut self, rule: &Rule<A>, _event: &FactEvent, passed: bool) {
+        if !passed {
+            trace!("FRE: Rule '{}' skipped - conditions not met", rule.id);
+        }
+    }
+
+    fn rule_firing(&mut self, rule: &Rule<A>, event: &FactEvent) {
+        info!(
+            "FRE: Rule '{}' triggered by event '{}' (priority: {}, conditions: {})",
+            rule.id,
+            event.id,
+            rule.priority,
+            rule.condition_expressions.len()
+        );
+    }
+
+    fn apply_modification(&mut self, _rule: &Rule<A>, modification: &FactModification) {
+        modification.apply(self.db);
+    }
+
+    fn apply_modifications(&mut self, rule: &Rule<A>) {
+        if let Err(violation) = rule.apply_modifications(self.db) {
+            error!(
+                "FRE: Rule '{}' modifications rolled back: {}",
+                rule.id, violation
             );
+        }
+    }
 
-            for modification in &rule.modifications {
-                modification.apply(layered_db);
-            }
+    fn queue_output(&mut self, rule: &Rule<A>, event: FactEvent) {
+        self.pending.queue_output(&rule.id, event);
+    }
+}
 
-            for output_id in &rule.outputs {
-                pending_events.queue_output(&rule.id, FactEvent::new(output_id.clone()));
+/// Process a single event against the registry: the rules matching it first, then the
+/// catch-all rules if none of those fired.
+pub(crate) fn process_event<A: ActionDef>(
+    event: &FactEvent,
+    registry: &LayeredRuleRegistry<A>,
+    sink: &mut impl RuleSink<A>,
+    condition_evaluator: &ConditionEvaluator,
+    enum_registry: &EnumRegistry,
+    rng: &mut FreRng,
+) {
+    let rule_groups = registry.get_matching_rules_grouped(event);
+    let fired = process_event_rules(
+        event,
+        rule_groups,
+        sink,
+        condition_evaluator,
+        enum_registry,
+        rng,
+    );
+    if !fired && event.id != CATCH_ALL_TRIGGER {
+        process_event_rules(
+            event,
+            registry.get_catch_all_rules_grouped(),
+            sink,
+            condition_evaluator,
+            enum_registry,
+            rng,
+        );
+    }
+}
+
+/// Process a single event against prioritized rule groups, returning whether any rule fired.
+/// Groups with a random `SelectPolicy`, and the group gathering the `HighestScore` rules, fire
+/// a single rule selected from their passing rules; every rule of such a group is still
+/// reported to the sink, in order.
+pub(crate) fn process_event_rules<A: ActionDef>(
+    event: &FactEvent,
+    rule_groups: Vec<Vec<&Rule<A>>>,
+    sink: &mut impl RuleSink<A>,
+    condition_evaluator: &ConditionEvaluator,
+    enum_registry: &EnumRegistry,
+    rng: &mut FreRng,
+) -> bool {
+    // Rules triggered on a view's entity read that view's facts first
+    let ready = &*sink;
+    let facts = ready.facts().for_view(event.entity);
+    let rule_groups = resolve_highest_score(rule_groups, &facts, |rule| {
+        ready.can_fire(rule)
+            && condition_evaluator.evaluate_for_event(rule, event, &facts, enum_registry)
+    });
+    let mut fired = false;
+    'outer: for (group, passed) in rule_groups {
+        let policy = group_policy(&group);
+        if policy != SelectPolicy::First {
+            // Results checked up front still hold as long as no rule has fired since
+            let passed = passed.filter(|_| !fired);
+            let (group_fired, consumed) = process_selected_group(
+                event,
+                group,
+                passed,
+                policy,
+                sink,
+                condition_evaluator,
+                enum_registry,
+                rng,
+            );
+            fired |= group_fired;
+            if consumed {
+                break;
             }
+            continue;
+        }
 
-            if rule.consume_event {
+        for rule in group {
+            let passed = sink.can_fire(rule)
+                && condition_evaluator.evaluate_for_event(
+                    rule,
+                    event,
+                    &sink.facts().for_view(event.entity),
+                    enum_registry,
+                );
+            sink.condition_checked(rule, event, passed);
+            fired |= passed;
+            let consumed = passed && fire_rule(event, rule, sink);
+            sink.rule_finished(rule);
+            if consumed {
                 break 'outer;
             }
         }
     }
+    fired
+}
+
+/// Check every rule of a group with a selecting `policy`, unless whether they pass is already
+/// known, then fire the one selected from those that passed. Returns whether a rule fired and
+/// whether it consumed the event.
+fn process_selected_group<A: ActionDef>(
+    event: &FactEvent,
+    group: Vec<&Rule<A>>,
+    passed: Option<Vec<bool>>,
+    policy: SelectPolicy,
+    sink: &mut impl RuleSink<A>,
+    condition_evaluator: &ConditionEvaluator,
+    enum_registry: &EnumRegistry,
+    rng: &mut FreRng,
+) -> (bool, bool) {
+    let facts = sink.facts().for_view(event.entity);
+    let passed: Vec<bool> = passed.unwrap_or_else(|| {
+        group
+            .iter()
+            .map(|rule| {
+                sink.can_fire(rule)
+                    && condition_evaluator.evaluate_for_event(rule, event, &facts, enum_registry)
+            })
+            .collect()
+    });
+    let passing: Vec<&Rule<A>> = group
+        .iter()
+        .zip(&passed)
+        .filter_map(|(rule, passed)| passed.then_some(*rule))
+        .collect();
+    let selected = select_rule(policy, &passing, &facts, rng);
+
+    let mut consumed = false;
+    for (rule, passed) in group.into_iter().zip(passed) {
+        sink.condition_checked(rule, event, passed);
+        if selected.is_some_and(|selected| std::ptr::eq(selected, rule)) {
+            consumed = fire_rule(event, rule, sink);
+        }
+        sink.rule_finished(rule);
+    }
+    (selected.is_some(), consumed)
+}
+
+/// Hand a passing rule's actions, modifications and outputs to the sink.
+/// Returns whether the rule consumes the event.
+fn fire_rule<A: ActionDef>(event: &FactEvent, rule: &Rule<A>, sink: &mut impl RuleSink<A>) -> bool {
+    sink.rule_firing(rule, event);
+    sink.run_actions(rule, ActionPhase::BeforeModifications);
+    sink.apply_modifications(rule);
+    sink.run_actions(rule, ActionPhase::AfterModifications);
+
+    for output_id in &rule.outputs {
+        sink.queue_output(rule, rule.output_event(output_id, event));
+    }
+
+    rule.consume_event
 }
 
 /// System to emit pending events from the previous frame.
@@ -226,6 +493,47 @@ pub fn emit_pending_events_system(
     pending_events.clear_tracking();
 }
 
+/// System to emit `RuleRegistryChanged` messages for registry mutations recorded since last frame.
+///
+/// 为上一帧以来记录的注册表变更发出 `RuleRegistryChanged` 消息的系统。
+pub fn emit_rule_registry_changes_system<A: ActionDef>(
+    mut registry: ResMut<LayeredRuleRegistry<A>>,
+    mut change_writer: MessageWriter<RuleRegistryChanged>,
+) {
+    if !registry.has_changes() {
+        return;
+    }
+    change_writer.write_batch(registry.take_changes());
+}
+
+/// Event ID that requests a RON dump of the rule registry to the log.
+///
+/// 请求将规则注册表以 RON 形式输出到日志的事件 ID。
+#[cfg(feature = "debug")]
+pub const DUMP_RULES_EVENT: &str = "fre:dump_rules";
+
+/// Debug system that logs a `RegistrySnapshot` whenever `DUMP_RULES_EVENT` is received.
+///
+/// 每当收到 `DUMP_RULES_EVENT` 时记录 `RegistrySnapshot` 的调试系统。
+#[cfg(feature = "debug")]
+pub fn dump_rule_registry_system<A: ActionDef>(
+    mut events: MessageReader<FactEvent>,
+    registry: Res<LayeredRuleRegistry<A>>,
+) {
+    let requested = events
+        .read()
+        .filter(|event| event.id == DUMP_RULES_EVENT)
+        .count()
+        > 0;
+    if !requested {
+        return;
+    }
+    match registry.export_snapshot().to_ron_string() {
+        Ok(ron) => info!("FRE: Rule registry snapshot:\n{}", ron),
+        Err(err) => error!("FRE: Failed to serialize rule registry snapshot: {}", err),
+    }
+}
+
 /// Run condition: returns true if there are events to process.
 /// 运行条件：如果有事件需要处理则返回 true。
 pub fn has_fact_events(events: MessageReader<FactEvent>) -> bool {
@@ -237,7 +545,7 @@ mod tests {
     use super::*;
     use crate::asset::CoreActionDef;
     use crate::database::FactValue;
-    use crate::rule::{FactModification, Rule, RuleRegistry};
+    use crate::rule::{Rule, RuleRegistry, RuleRegistryChangeKind};
 
     #[test]
     fn test_rule_registry_matching() {
@@ -273,4 +581,209 @@ mod tests {
         FactModification::Toggle("flag".to_string()).apply(&mut db);
         assert_eq!(db.get_bool("flag"), Some(false));
     }
+
+    #[test]
+    fn test_registry_changes_emitted_as_messages() {
+        let mut app = App::new();
+        app.init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .add_message::<RuleRegistryChanged>()
+            .add_systems(Update, emit_rule_registry_changes_system::<CoreActionDef>);
+
+        {
+            let mut registry = app
+                .world_mut()
+                .resource_mut::<LayeredRuleRegistry<CoreActionDef>>();
+            registry.set_change_tracking(true);
+            registry.register(Rule::builder("rule1", "event").build());
+            registry.set_enabled("rule1", false);
+        }
+        app.update();
+
+        let messages = app.world().resource::<Messages<RuleRegistryChanged>>();
+        let emitted: Vec<_> = messages.iter_current_update_messages().collect();
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(emitted[0].kind, RuleRegistryChangeKind::Registered);
+        assert_eq!(
+            emitted[1].kind,
+            RuleRegistryChangeKind::EnabledChanged { enabled: false }
+        );
+        assert_eq!(emitted[1].rule_ids, vec!["rule1"]);
+    }
+
+    #[derive(Resource, Default)]
+    struct AliveTargets(Vec<Entity>);
+
+    /// Downstream consumer that records `damaged` targets that are still alive.
+    fn record_alive_targets(
+        mut events: MessageReader<FactEvent>,
+        entities: Query<Entity>,
+        mut alive: ResMut<AliveTargets>,
+    ) {
+        let damaged = events.read().filter(|event| event.id == "damaged");
+        alive.0.extend(
+            damaged
+                .filter_map(|event| event.entity)
+                .filter(|entity| entities.contains(*entity)),
+        );
+    }
+
+    #[test]
+    fn test_outputs_propagate_trigger_entity() {
+        let mut app = App::new();
+        app.init_resource::<LayeredFactDatabase>()
+            .init_resource::<LayeredRuleRegistry<CoreActionDef>>()
+            .init_resource::<PendingFactEvents>()
+            .init_resource::<ConditionEvaluator>()
+            .init_resource::<EnumRegistry>()
+            .init_resource::<FreRng>()
+            .init_resource::<AliveTargets>()
+            .add_message::<FactEvent>()
+            .add_systems(
+                Update,
+                (
+                    emit_pending_events_system,
+                    process_rules_system::<CoreActionDef>,
+                    record_alive_targets,
+                )
+                    .chain(),
+            );
+        app.world_mut()
+            .resource_mut::<LayeredRuleRegistry<CoreActionDef>>()
+            .register(Rule::builder("on_hit", "hit").output("damaged").build());
+
+        let target = app.world_mut().spawn_empty().id();
+        app.world_mut()
+            .write_message(FactEvent::with_entity("hit", target));
+        app.update();
+        // The output is emitted on the next frame
+        app.update();
+
+        assert_eq!(app.world().resource::<AliveTargets>().0, vec![target]);
+
+        // Hits on two entities in one frame produce an output for each
+        let other = app.world_mut().spawn_empty().id();
+        app.world_mut()
+            .write_message(FactEvent::with_entity("hit", target));
+        app.world_mut()
+            .write_message(FactEvent::with_entity("hit", other));
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world().resource::<AliveTargets>().0,
+            vec![target, target, other]
+        );
+    }
+
+    #[test]
+    fn test_identical_outputs_queued_once_per_rule() {
+        let mut pending = PendingFactEvents::default();
+        let entity = Entity::from_bits(7);
+        assert!(pending.queue_output("a", FactEvent::with_entity("hit", entity)));
+        assert!(!pending.queue_output("a", FactEvent::with_entity("hit", entity)));
+        assert!(pending.queue_output("a", FactEvent::new("hit")));
+        assert!(pending.queue_output("a", FactEvent::new("hit").with_data("damage", "3")));
+        assert!(pending.queue_output("b", FactEvent::new("hit")));
+
+        pending.clear_tracking();
+        assert!(pending.queue_output("a", FactEvent::new("hit")));
+        assert_eq!(pending.events.len(), 5);
+    }
+
+    #[test]
+    fn test_transactional_rule_rolls_back_rejected_batch() {
+        use crate::schema::{FactType, SchemaPolicy};
+
+        let mut db = LayeredFactDatabase::new();
+        db.schema_mut().declare("hp", FactType::Int);
+        db.schema_mut().set_policy(SchemaPolicy::Reject);
+        db.set("gold", 10i64);
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        for (id, transactional) in [("buy", true), ("buy_loose", false)] {
+            registry.register(
+                Rule::builder(id, id)
+                    .transactional(transactional)
+                    .modify(FactModification::Increment("gold".to_string(), -5))
+                    .modify(FactModification::Set("item".to_string(), "potion".into()))
+                    .modify(FactModification::Set("hp".to_string(), "full".into()))
+                    .build(),
+            );
+        }
+        registry.register(
+            Rule::builder("die", "die")
+                .transactional(true)
+                .modify(FactModification::IncrementGlobal("deaths".to_string(), 1))
+                .modify(FactModification::Set("hp".to_string(), "none".into()))
+                .build(),
+        );
+        let fire = |id: &str, db: &mut LayeredFactDatabase| {
+            process_event(
+                &FactEvent::new(id),
+                &registry,
+                &mut LiveSink {
+                    pending: &mut PendingFactEvents::default(),
+                    db,
+                },
+                &ConditionEvaluator::default(),
+                &EnumRegistry::default(),
+                &mut FreRng::seeded(0),
+            );
+        };
+
+        fire("buy", &mut db);
+        assert_eq!(db.get_int("gold"), Some(10));
+        assert!(!db.contains("item"));
+        assert!(!db.contains("hp"));
+
+        // Without a transaction the writes before the rejected one stick
+        fire("buy_loose", &mut db);
+        assert_eq!(db.get_int("gold"), Some(5));
+        assert_eq!(db.get_string("item"), Some("potion"));
+        assert!(!db.contains("hp"));
+
+        // Global writes are rolled back with the rest of the batch
+        fire("die", &mut db);
+        assert!(!db.global().contains("deaths"));
+    }
+
+    #[test]
+    fn test_catch_all_rule_only_fires_for_unhandled_events() {
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(Rule::builder("on_jump", "jump").output("jumped").build());
+        registry.register(
+            Rule::builder("gated", "dash")
+                .condition_expr("float($stamina) > 1.0")
+                .output("dashed")
+                .build(),
+        );
+        registry.register(
+            Rule::builder("log_unhandled", CATCH_ALL_TRIGGER)
+                .output("unhandled")
+                .build(),
+        );
+
+        let outputs = |id: &str| {
+            let mut pending = PendingFactEvents::default();
+            process_event(
+                &FactEvent::new(id),
+                &registry,
+                &mut LiveSink {
+                    pending: &mut pending,
+                    db: &mut LayeredFactDatabase::new(),
+                },
+                &ConditionEvaluator::default(),
+                &EnumRegistry::default(),
+                &mut FreRng::seeded(0),
+            );
+            pending
+                .events
+                .iter()
+                .map(|e| e.id.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(outputs("jump"), vec!["jumped"]);
+        assert_eq!(outputs("unknown"), vec!["unhandled"]);
+        // A matching rule whose conditions fail did not handle the event
+        assert_eq!(outputs("dash"), vec!["unhandled"]);
+    }
 }
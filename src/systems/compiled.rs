@@ -0,0 +1,360 @@
+//! # compiled.rs
+//!
+//! # compiled.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Precompiled rule conditions. Evaluating a rule from its strings parses every condition
+//! against each built-in syntax on every evaluation and copies the host conditions into a new
+//! list. Compiling parses the conditions once into `CompiledCondition`s, stored on the rule,
+//! and collects the host conditions up front, so evaluation only runs the parsed matchers and
+//! hands a ready slice to the game's evaluator. Host conditions stay strings: their syntax
+//! belongs to the game. Rules referencing `@name` conditions are not compiled, since named
+//! conditions can be redefined at any time.
+//!
+//! 预编译的规则条件。从字符串评估规则时，每次评估都要针对每种内置语法解析所有条件，
+//! 并把宿主条件复制到新列表中。编译会把条件一次性解析为存储在规则上的 `CompiledCondition`，
+//! 并预先收集宿主条件，因此评估只需运行已解析的匹配器，并把现成的切片交给游戏的评估器。
+//! 宿主条件仍为字符串：其语法属于游戏。引用 `@name` 条件的规则不会被编译，
+//! 因为具名条件可以随时被重新定义。
+
+use crate::containment::ContainsCondition;
+use crate::database::FactLayer;
+use crate::database::FactReader;
+use crate::entity_count::EntityCountLessThan;
+use crate::event::{EventDataCondition, FactEvent, PayloadListLenAtLeast};
+use crate::expr_condition::ExprLessThan;
+use crate::fact_comparison::FactComparison;
+use crate::float_condition::FloatCondition;
+use crate::frame_change::ChangedSinceLastFrame;
+use crate::layer_condition::{InLayer, LayerCondition, LayerReader};
+use crate::list_aggregate::ListAggregateCondition;
+use crate::named_condition::reference;
+use crate::presence::PresenceCondition;
+use crate::spatial::DistanceLessThan;
+
+/// A built-in condition parsed into owned form.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CompiledCondition {
+    DataExists(String),
+    DataEquals(String, String),
+    PayloadListLenAtLeast(String, usize),
+    ListContains(String, String),
+    StringContains(String, String),
+    Exists(String),
+    IsNull(String),
+    FloatAbove(String, f64),
+    FloatAtLeast(String, f64),
+    FloatBelow(String, f64),
+    FloatAtMost(String, f64),
+    FloatInRange(String, f64, f64),
+    FactGreaterThan(String, String),
+    FactEquals(String, String),
+    ExprBelow(ExprLessThan),
+    ChangedSinceLastFrame(String),
+    IntListSumAbove(String, i64),
+    IntListMaxAbove(String, i64),
+    EntityCountBelow(String, usize),
+    LayerEquals(FactLayer, String, String),
+    LayerExists(FactLayer, String),
+    InLayer(FactLayer, Box<CompiledCondition>),
+    Distance(String, String, f32),
+}
+
+impl CompiledCondition {
+    /// Parse a built-in condition, returning None if it is for the host evaluator. This is the
+    /// only place the built-in syntaxes are dispatched, for compiled rules and string rules alike.
+    pub(crate) fn compile(condition: &str) -> Option<Self> {
+        if let Some(data) = EventDataCondition::parse(condition) {
+            return Some(match data {
+                EventDataCondition::Exists(key) => Self::DataExists(key.into()),
+                EventDataCondition::Equals(key, value) => {
+                    Self::DataEquals(key.into(), value.into())
+                }
+            });
+        }
+        if let Some(PayloadListLenAtLeast(key, min)) = PayloadListLenAtLeast::parse(condition) {
+            return Some(Self::PayloadListLenAtLeast(key.into(), min));
+        }
+        if let Some(contains) = ContainsCondition::parse(condition) {
+            return Some(match contains {
+                ContainsCondition::ListContains(k, v) => Self::ListContains(k.into(), v.into()),
+                ContainsCondition::StringContains(k, v) => Self::StringContains(k.into(), v.into()),
+            });
+        }
+        if let Some(presence) = PresenceCondition::parse(condition) {
+            return Some(match presence {
+                PresenceCondition::Exists(key) => Self::Exists(key.into()),
+                PresenceCondition::IsNull(key) => Self::IsNull(key.into()),
+            });
+        }
+        if let Some(float) = FloatCondition::parse(condition) {
+            return Some(match float {
+                FloatCondition::GreaterThan(key, value) => Self::FloatAbove(key.into(), value),
+                FloatCondition::GreaterOrEqual(key, value) => Self::FloatAtLeast(key.into(), value),
+                FloatCondition::LessThan(key, value) => Self::FloatBelow(key.into(), value),
+                FloatCondition::LessOrEqual(key, value) => Self::FloatAtMost(key.into(), value),
+                FloatCondition::InRange(key, min, max) => Self::FloatInRange(key.into(), min, max),
+            });
+        }
+        if let Some(comparison) = FactComparison::parse(condition) {
+            return Some(match comparison {
+                FactComparison::GreaterThan(a, b) => Self::FactGreaterThan(a.into(), b.into()),
+                FactComparison::Equals(a, b) => Self::FactEquals(a.into(), b.into()),
+            });
+        }
+        if let Some(expr_condition) = ExprLessThan::parse(condition) {
+            return Some(Self::ExprBelow(expr_condition));
+        }
+        if let Some(frame_change) = ChangedSinceLastFrame::parse(condition) {
+            return Some(Self::ChangedSinceLastFrame(frame_change.0.into()));
+        }
+        if let Some(aggregate) = ListAggregateCondition::parse(condition) {
+            return Some(match aggregate {
+                ListAggregateCondition::SumGreaterThan(key, value) => {
+                    Self::IntListSumAbove(key.into(), value)
+                }
+                ListAggregateCondition::MaxGreaterThan(key, value) => {
+                    Self::IntListMaxAbove(key.into(), value)
+                }
+            });
+        }
+        if let Some(entity_count) = EntityCountLessThan::parse(condition) {
+            return Some(Self::EntityCountBelow(
+                entity_count.name.into(),
+                entity_count.limit,
+            ));
+        }
+        if let Some(layer_condition) = LayerCondition::parse(condition) {
+            return Some(match layer_condition {
+                LayerCondition::Equals(layer, key, value) => {
+                    Self::LayerEquals(layer, key.into(), value.into())
+                }
+                LayerCondition::Exists(layer, key) => Self::LayerExists(layer, key.into()),
+            });
+        }
+        if let Some(InLayer(layer, inner)) = InLayer::parse(condition) {
+            return Some(Self::InLayer(layer, Box::new(Self::compile(inner)?)));
+        }
+        let distance = DistanceLessThan::parse(condition)?;
+        Some(Self::Distance(
+            distance.a.into(),
+            distance.b.into(),
+            distance.max,
+        ))
+    }
+
+    /// Whether the condition passes for `event` (None outside of an event) and `facts`.
+    pub(crate) fn matches(&self, event: Option<&FactEvent>, facts: &dyn FactReader) -> bool {
+        match self {
+            Self::DataExists(key) => {
+                event.is_some_and(|e| EventDataCondition::Exists(key).matches(e))
+            }
+            Self::DataEquals(key, value) => {
+                event.is_some_and(|e| EventDataCondition::Equals(key, value).matches(e))
+            }
+            Self::PayloadListLenAtLeast(key, min) => {
+                event.is_some_and(|e| PayloadListLenAtLeast(key, *min).matches(e))
+            }
+            Self::ListContains(key, v) => ContainsCondition::ListContains(key, v).matches(facts),
+            Self::StringContains(key, v) => {
+                ContainsCondition::StringContains(key, v).matches(facts)
+            }
+            Self::Exists(key) => PresenceCondition::Exists(key).matches(facts),
+            Self::IsNull(key) => PresenceCondition::IsNull(key).matches(facts),
+            Self::FloatAbove(key, value) => FloatCondition::GreaterThan(key, *value).matches(facts),
+            Self::FloatAtLeast(key, value) => {
+                FloatCondition::GreaterOrEqual(key, *value).matches(facts)
+            }
+            Self::FloatBelow(key, value) => FloatCondition::LessThan(key, *value).matches(facts),
+            Self::FloatAtMost(key, value) => {
+                FloatCondition::LessOrEqual(key, *value).matches(facts)
+            }
+            Self::FloatInRange(key, min, max) => {
+                FloatCondition::InRange(key, *min, *max).matches(facts)
+            }
+            Self::FactGreaterThan(a, b) => FactComparison::GreaterThan(a, b).matches(facts),
+            Self::FactEquals(a, b) => FactComparison::Equals(a, b).matches(facts),
+            Self::ExprBelow(expr_condition) => expr_condition.matches(facts),
+            Self::ChangedSinceLastFrame(key) => ChangedSinceLastFrame(key).matches(facts),
+            Self::IntListSumAbove(key, value) => {
+                ListAggregateCondition::SumGreaterThan(key, *value).matches(facts)
+            }
+            Self::IntListMaxAbove(key, value) => {
+                ListAggregateCondition::MaxGreaterThan(key, *value).matches(facts)
+            }
+            Self::EntityCountBelow(name, limit) => EntityCountLessThan {
+                name,
+                limit: *limit,
+            }
+            .matches(facts),
+            Self::LayerEquals(layer, key, value) => {
+                LayerCondition::Equals(*layer, key, value).matches(facts)
+            }
+            Self::LayerExists(layer, key) => LayerCondition::Exists(*layer, key).matches(facts),
+            Self::InLayer(layer, inner) => inner.matches(event, &LayerReader::new(facts, *layer)),
+            Self::Distance(a, b, max) => DistanceLessThan { a, b, max: *max }.matches(facts),
+        }
+    }
+}
+
+/// A rule's conditions, split into parsed built-ins and host conditions.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CompiledConditions {
+    builtins: Vec<CompiledCondition>,
+    pub(crate) host: Vec<String>,
+    /// Host conditions wrapped in `in_layer`, evaluated against that layer alone.
+    pub(crate) layered_host: Vec<(FactLayer, String)>,
+}
+
+impl CompiledConditions {
+    /// Compile a rule's conditions; None if any of them references a named condition.
+    pub(crate) fn compile(conditions: &[String]) -> Option<Self> {
+        let mut compiled = Self {
+            builtins: Vec::new(),
+            host: Vec::new(),
+            layered_host: Vec::new(),
+        };
+        for condition in conditions {
+            if reference(condition).is_some() {
+                return None;
+            }
+            if let Some(builtin) = CompiledCondition::compile(condition) {
+                compiled.builtins.push(builtin);
+            } else if let Some(InLayer(layer, inner)) = InLayer::parse(condition) {
+                compiled.layered_host.push((layer, inner.to_string()));
+            } else {
+                compiled.host.push(condition.clone());
+            }
+        }
+        Some(compiled)
+    }
+
+    /// Whether every built-in condition passes; the host conditions are left to the caller.
+    pub(crate) fn builtins_pass(&self, event: Option<&FactEvent>, facts: &dyn FactReader) -> bool {
+        self.builtins
+            .iter()
+            .all(|condition| condition.matches(event, facts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asset::{CoreActionDef, EnumRegistry};
+    use crate::database::FactValue;
+    use crate::event::FactEvent;
+    use crate::expr_condition::ExprConditionEvaluator;
+    use crate::layered::LayeredFactDatabase;
+    use crate::rule::{LayeredRuleRegistry, Rule};
+    use crate::systems::ConditionEvaluator;
+
+    fn rule(conditions: &[&str]) -> Rule<CoreActionDef> {
+        conditions
+            .iter()
+            .fold(Rule::builder("rule", "hit"), |builder, condition| {
+                builder.condition_expr(*condition)
+            })
+            .build()
+    }
+
+    #[test]
+    fn test_compiled_and_uncompiled_evaluation_agree() {
+        let mut db = LayeredFactDatabase::new();
+        db.set("hp", 3.0);
+        db.set("name", "hero");
+        db.set("inventory", FactValue::StringList(vec!["key".to_string()]));
+        db.set("a", FactValue::Vec2([0.0, 0.0]));
+        db.set("b", FactValue::Vec2([3.0, 4.0]));
+        db.set("cleared", FactValue::Null);
+        db.set("stats", vec![40i64, 50, 25]);
+        db.set_global("hp", 8.0);
+        let evaluator = ConditionEvaluator::new(ExprConditionEvaluator);
+        let enums = EnumRegistry::default();
+        let events = [
+            FactEvent::new("hit"),
+            FactEvent::new("hit").with_data("kind", "fire"),
+        ];
+
+        let cases: &[&[&str]] = &[
+            &[],
+            &["float($hp) > 2", "$hp > 0"],
+            &["float($hp) in 0..2", "$hp > 0"],
+            &["float($hp) < 5", "$hp > 100"],
+            &["float($hp) >= 2", "float($hp) <= 2"],
+            &["event.data.kind == \"fire\""],
+            &["event.data.kind"],
+            &[
+                r#"list_contains($inventory, "key")"#,
+                r#"string_contains($name, "er")"#,
+            ],
+            &[r#"list_contains($inventory, "sword")"#],
+            &["exists($cleared)", "is_null($cleared)", "exists($hp)"],
+            &["is_null($missing)"],
+            &["distance($a, $b) < 6", "$hp > 0"],
+            &["distance($a, $b) < 5"],
+            &["compare($hp > $hp)", "$hp > 0"],
+            &["compare($name == $name)"],
+            &["expr($hp * 2) < 7", "$hp > 0"],
+            &["expr($hp - $missing) < 7"],
+            &["int_list_sum($stats) > 100", "int_list_max($stats) > 49"],
+            &["int_list_max($stats) > 50", "$hp > 0"],
+            &["global_equals($hp, 8)", "local_exists($hp)", "$hp > 0"],
+            &["global_exists($name)"],
+            &["entity_count(enemies) < 5", "$hp > 0"],
+            &[
+                "in_layer(global, float($hp) > 5)",
+                "in_layer(local, exists($name))",
+            ],
+            &["in_layer(local, float($hp) > 5)"],
+            &["in_layer(global, $hp > 0)"],
+        ];
+        for conditions in cases {
+            let plain = rule(conditions);
+            let mut compiled = plain.clone();
+            assert!(compiled.compile_conditions());
+            for event in &events {
+                assert_eq!(
+                    evaluator.evaluate_for_event(&compiled, event, &db, &enums),
+                    evaluator.evaluate_for_event(&plain, event, &db, &enums),
+                    "{conditions:?} with {event:?}"
+                );
+            }
+            assert_eq!(
+                evaluator.evaluate(&compiled, &db, &enums),
+                evaluator.evaluate(&plain, &db, &enums),
+                "{conditions:?} without an event"
+            );
+        }
+
+        // Named condition references are left to the string path
+        assert!(!rule(&["@alive"]).compile_conditions());
+    }
+
+    #[test]
+    fn test_edited_rules_are_recompiled() {
+        let mut registry = LayeredRuleRegistry::<CoreActionDef>::new();
+        registry.register(rule(&["float($hp) > 2"]));
+        assert_eq!(registry.compile_expressions(), 1);
+        assert!(registry.get("rule").unwrap().is_compiled());
+        assert_eq!(registry.compile_expressions(), 0);
+
+        let mut db = LayeredFactDatabase::new();
+        db.set("hp", 1.0);
+        let evaluator = ConditionEvaluator::default();
+        let enums = EnumRegistry::default();
+        let passes = |registry: &LayeredRuleRegistry<CoreActionDef>, db: &LayeredFactDatabase| {
+            evaluator.evaluate(registry.get("rule").unwrap(), db, &enums)
+        };
+        assert!(!passes(&registry, &db));
+
+        registry.get_mut("rule").unwrap().condition_expressions = vec!["float($hp) < 2".into()];
+        assert!(!registry.get("rule").unwrap().is_compiled());
+        assert!(passes(&registry, &db));
+
+        assert_eq!(registry.compile_expressions(), 1);
+        assert!(registry.get("rule").unwrap().is_compiled());
+        assert!(passes(&registry, &db));
+    }
+}
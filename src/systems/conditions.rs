@@ -0,0 +1,278 @@
+//! # conditions.rs
+//!
+//! # conditions.rs 文件
+//!
+//! ## Module Overview
+//!
+//! ## 模块概述
+//!
+//! Condition evaluation for rules. `ConditionEvaluatorTrait` is the extension point games
+//! implement, and the `ConditionEvaluator` resource wraps it: it expands `@name` references and
+//! resolves the crate's built-in conditions before handing the rest to the game's evaluator.
+//!
+//! 规则的条件评估。`ConditionEvaluatorTrait` 是游戏实现的扩展点，`ConditionEvaluator`
+//! 资源包装了它：它先展开 `@name` 引用并解析 crate 的内置条件，再把其余条件交给游戏的评估器。
+
+use super::compiled::CompiledCondition;
+use crate::asset::{ActionDef, EnumRegistry};
+use crate::database::{FactLayer, FactReader};
+use crate::event::FactEvent;
+use crate::layer_condition::{InLayer, LayerReader};
+use crate::named_condition::NamedConditions;
+use crate::rule::Rule;
+use bevy::prelude::*;
+use std::sync::Arc;
+
+/// Trait for evaluating rule condition expressions.
+/// Implement this to provide custom condition evaluation logic.
+///
+/// 用于评估规则条件表达式的 trait。
+/// 实现此 trait 以提供自定义条件评估逻辑。
+pub trait ConditionEvaluatorTrait: Send + Sync + 'static {
+    /// Evaluate all condition expressions for a rule.
+    /// Returns true if all conditions pass or if there are no conditions.
+    ///
+    /// 评估规则的所有条件表达式。
+    /// 如果所有条件都通过或没有条件，返回 true。
+    fn evaluate(&self, conditions: &[String], facts: &dyn FactReader, enums: &EnumRegistry)
+    -> bool;
+
+    /// Evaluate condition expressions for a rule triggered by `event`.
+    /// Override this to let conditions read the event; the default ignores it.
+    /// Built-in conditions (`event.data.*`, `*_contains(...)`, `float(...)`, `compare(...)`,
+    /// `distance(...)`) are resolved before this is called.
+    ///
+    /// 评估由 `event` 触发的规则的条件表达式。
+    /// 重写此方法可让条件读取事件；默认实现忽略事件。
+    /// 内置条件（`event.data.*`、`*_contains(...)`、`float(...)`、`compare(...)`、`distance(...)`）
+    /// 会在调用此方法之前被解析。
+    fn evaluate_for_event(
+        &self,
+        conditions: &[String],
+        _event: &FactEvent,
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        self.evaluate(conditions, facts, enums)
+    }
+}
+
+/// Default condition evaluator that always returns true (matches "Always" behavior).
+///
+/// 默认条件评估器，始终返回 true（匹配 "Always" 行为）。
+#[derive(Default)]
+pub struct DefaultConditionEvaluator;
+
+impl ConditionEvaluatorTrait for DefaultConditionEvaluator {
+    fn evaluate(
+        &self,
+        _conditions: &[String],
+        _facts: &dyn FactReader,
+        _enums: &EnumRegistry,
+    ) -> bool {
+        // Default evaluator keeps FRE bootstrappable before a game installs
+        // its own expression-aware evaluator.
+        true
+    }
+}
+
+/// Resource that holds the condition evaluator function.
+/// Games should replace this with their own evaluator that understands their expression syntax.
+///
+/// 持有条件评估器函数的资源。
+/// 游戏应该用自己的评估器替换它，以理解其表达式语法。
+#[derive(Resource)]
+pub struct ConditionEvaluator {
+    evaluator: Arc<dyn ConditionEvaluatorTrait>,
+    named: NamedConditions,
+}
+
+impl Default for ConditionEvaluator {
+    fn default() -> Self {
+        Self {
+            evaluator: Arc::new(DefaultConditionEvaluator),
+            named: NamedConditions::default(),
+        }
+    }
+}
+
+impl ConditionEvaluator {
+    /// Create a new condition evaluator with a custom implementation.
+    ///
+    /// 使用自定义实现创建新的条件评估器。
+    pub fn new<T: ConditionEvaluatorTrait>(evaluator: T) -> Self {
+        Self {
+            evaluator: Arc::new(evaluator),
+            named: NamedConditions::default(),
+        }
+    }
+
+    /// Named conditions that `@name` references resolve to.
+    ///
+    /// `@name` 引用解析到的具名条件。
+    pub fn named_conditions(&self) -> &NamedConditions {
+        &self.named
+    }
+
+    pub fn named_conditions_mut(&mut self) -> &mut NamedConditions {
+        &mut self.named
+    }
+
+    /// Evaluate conditions for a rule.
+    ///
+    /// 评估规则的条件。
+    pub fn evaluate<A: ActionDef>(
+        &self,
+        rule: &Rule<A>,
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        self.resolve_rule(rule, None, facts, enums)
+    }
+
+    /// Evaluate a list of condition expressions outside of a rule. Built-in conditions are
+    /// resolved as on the event path, except that `event.data.*` conditions fail without an event.
+    ///
+    /// 在规则之外评估一组条件表达式。内置条件的解析方式与事件路径相同，
+    /// 但没有事件时 `event.data.*` 条件不通过。
+    pub fn evaluate_conditions(
+        &self,
+        conditions: &[String],
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        self.resolve(conditions, None, facts, enums)
+    }
+
+    /// Evaluate a rule's conditions against the event that triggered it.
+    ///
+    /// 针对触发规则的事件评估规则的条件。
+    pub fn evaluate_for_event<A: ActionDef>(
+        &self,
+        rule: &Rule<A>,
+        event: &FactEvent,
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        self.resolve_rule(rule, Some(event), facts, enums)
+    }
+
+    /// Evaluate condition expressions against an event. `@name` references are expanded first;
+    /// built-in `event.data.*`, `event.payload.*`, `*_contains(...)`, `float(...)`, `compare(...)`
+    /// and `distance(...)` conditions are checked here; the rest go to the evaluator's
+    /// `evaluate_for_event`.
+    ///
+    /// 针对事件评估条件表达式。首先展开 `@name` 引用；内置的 `event.data.*`、`event.payload.*`、`*_contains(...)`、
+    /// `float(...)`、`compare(...)` 和 `distance(...)` 条件在此检查；
+    /// 其余条件交给评估器的 `evaluate_for_event`。
+    pub fn evaluate_conditions_for_event(
+        &self,
+        conditions: &[String],
+        event: &FactEvent,
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        self.resolve(conditions, Some(event), facts, enums)
+    }
+
+    /// Evaluate a rule, using its compiled conditions if it has them.
+    fn resolve_rule<A: ActionDef>(
+        &self,
+        rule: &Rule<A>,
+        event: Option<&FactEvent>,
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        match &rule.compiled {
+            Some(compiled) => {
+                compiled.builtins_pass(event, facts)
+                    && compiled.layered_host.iter().all(|(layer, inner)| {
+                        let inner = std::slice::from_ref(inner);
+                        self.evaluate_host_in_layer(inner, *layer, event, facts, enums)
+                    })
+                    && self.evaluate_host(&compiled.host, event, facts, enums)
+            }
+            None => self.resolve(&rule.condition_expressions, event, facts, enums),
+        }
+    }
+
+    fn resolve(
+        &self,
+        conditions: &[String],
+        event: Option<&FactEvent>,
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        if conditions.is_empty() {
+            return true; // No conditions = always match
+        }
+        let Some(conditions) = self.named.expand(conditions) else {
+            return false;
+        };
+        let mut host = Vec::new();
+        let mut layered_host = Vec::new();
+        for condition in conditions.iter() {
+            match builtin_result(condition, event, facts) {
+                Some(false) => return false,
+                Some(true) => {}
+                None => match InLayer::parse(condition) {
+                    Some(InLayer(layer, inner)) => layered_host.push((layer, inner)),
+                    None => host.push(condition),
+                },
+            }
+        }
+        let layered_pass = layered_host.into_iter().all(|(layer, inner)| {
+            let inner = [inner.to_string()];
+            self.evaluate_host_in_layer(&inner, layer, event, facts, enums)
+        });
+        if !layered_pass {
+            return false;
+        }
+        if host.len() == conditions.len() {
+            return self.evaluate_host(&conditions, event, facts, enums);
+        }
+        let host: Vec<String> = host.into_iter().cloned().collect();
+        self.evaluate_host(&host, event, facts, enums)
+    }
+
+    /// Evaluate host conditions wrapped in `in_layer` against that layer alone.
+    fn evaluate_host_in_layer(
+        &self,
+        conditions: &[String],
+        layer: FactLayer,
+        event: Option<&FactEvent>,
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        self.evaluate_host(conditions, event, &LayerReader::new(facts, layer), enums)
+    }
+
+    fn evaluate_host(
+        &self,
+        conditions: &[String],
+        event: Option<&FactEvent>,
+        facts: &dyn FactReader,
+        enums: &EnumRegistry,
+    ) -> bool {
+        if conditions.is_empty() {
+            return true;
+        }
+        match event {
+            Some(event) => self
+                .evaluator
+                .evaluate_for_event(conditions, event, facts, enums),
+            None => self.evaluator.evaluate(conditions, facts, enums),
+        }
+    }
+}
+
+/// Result of a built-in condition, or None if the condition is for the host evaluator. Rules
+/// evaluated from their strings go through the same parsers as compiled rules, so the two
+/// always agree.
+fn builtin_result(
+    condition: &str,
+    event: Option<&FactEvent>,
+    facts: &dyn FactReader,
+) -> Option<bool> {
+    CompiledCondition::compile(condition).map(|compiled| compiled.matches(event, facts))
+}